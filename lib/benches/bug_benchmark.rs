@@ -195,7 +195,7 @@ fn transfer_energy_from_food_to_bug(c: &mut Criterion) {
             b.iter(|| {
                 black_box(benchmark_internals::transfer_energy_from_food_to_bug(
                     &mut environment,
-                    512,
+                    benchmark_internals::food_id(512),
                     &mut bug.borrow_mut(),
                     noneg_float(0.00001),
                 ))
@@ -223,7 +223,7 @@ fn transfer_energy_from_food_to_bug(c: &mut Criterion) {
             b.iter(|| {
                 black_box(benchmark_internals::transfer_energy_from_food_to_bug(
                     &mut environment,
-                    8192,
+                    benchmark_internals::food_id(8192),
                     &mut bug.borrow_mut(),
                     noneg_float(0.00001),
                 ))
@@ -257,7 +257,7 @@ fn transfer_energy_from_food_to_bug(c: &mut Criterion) {
             b.iter(|| {
                 black_box(benchmark_internals::transfer_energy_from_food_to_bug(
                     &mut environment,
-                    8192,
+                    benchmark_internals::food_id(8192),
                     &mut bug.borrow_mut(),
                     noneg_float(0.00001),
                 ))
@@ -266,11 +266,129 @@ fn transfer_energy_from_food_to_bug(c: &mut Criterion) {
     }
 }
 
+/// Bugs are looked up by id through the id index, so lookup time grows neither with population
+/// nor with number of bugs in a chunk
+fn find_bug_by_id(c: &mut Criterion) {
+    let mut rng: Pcg64 = Seeder::from(&[0xff]).make_rng();
+    let the_beginning_of_times = FakeTime::default();
+
+    for (name, bugs_count, range) in [
+        ("find_bug_by_id (small)", 1024, -10000. ..10000.),
+        ("find_bug_by_id (big, far)", 65536, -10000. ..10000.),
+        ("find_bug_by_id (big, dense)", 16384, -50. ..50.),
+    ] {
+        let environment = Environment::new(
+            the_beginning_of_times.clone(),
+            vec![],
+            vec![],
+            BugCreateInfo::generate_vec(
+                &mut rng,
+                1. ..1.01,
+                range.clone(),
+                range,
+                0. ..(PI * 2.),
+                bugs_count,
+            ),
+        );
+
+        c.bench_function(name, |b| {
+            b.iter(|| black_box(environment.find_bug_by_id(bugs_count / 2).is_some()))
+        });
+    }
+}
+
+/// Victim of bite is looked up by id, so crowd around attacker does not slow it down
+fn bite(c: &mut Criterion) {
+    let mut rng: Pcg64 = Seeder::from(&[0xff]).make_rng();
+    let the_beginning_of_times = FakeTime::default();
+
+    for (name, bugs_count) in [("bite (small)", 1024), ("bite (big, dense)", 16384)] {
+        let environment = Environment::new(
+            the_beginning_of_times.clone(),
+            vec![],
+            vec![],
+            BugCreateInfo::generate_vec(
+                &mut rng,
+                1. ..1.01,
+                -1. ..1.,
+                -1. ..1.,
+                0. ..(PI * 2.),
+                bugs_count,
+            ),
+        );
+
+        let attacker = benchmark_internals::find_bug_by_id(&environment, 0).unwrap();
+
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                black_box(benchmark_internals::bite(
+                    &environment,
+                    &mut attacker.borrow_mut(),
+                    bugs_count - 1,
+                    noneg_float(0.00001),
+                ))
+            })
+        });
+    }
+}
+
+fn circular_traverse_iter(c: &mut Criterion) {
+    let environment = Environment::<FakeTime>::new(FakeTime::default(), vec![], vec![], vec![]);
+
+    for (name, range) in [
+        ("circular_traverse_iter (small)", 100.),
+        ("circular_traverse_iter (big)", 2000.),
+    ] {
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                black_box(
+                    environment
+                        .food_chunks_circular_traverse_iter((10., 10.).into(), noneg_float(range))
+                        .count(),
+                )
+            })
+        });
+    }
+}
+
+/// Nuking a small area must not touch bugs of the whole world
+fn irradiate_area(c: &mut Criterion) {
+    let mut rng: Pcg64 = Seeder::from(&[0xff]).make_rng();
+    let the_beginning_of_times = FakeTime::default();
+
+    for (name, bugs_count, range) in [
+        ("irradiate_area (small)", 1024, -500. ..500.),
+        ("irradiate_area (big, far)", 65536, -20000. ..20000.),
+    ] {
+        let mut environment = Environment::new(
+            the_beginning_of_times.clone(),
+            vec![],
+            vec![],
+            BugCreateInfo::generate_vec(
+                &mut rng,
+                1. ..1.01,
+                range.clone(),
+                range,
+                0. ..(PI * 2.),
+                bugs_count,
+            ),
+        );
+
+        c.bench_function(name, |b| {
+            b.iter(|| environment.irradiate_area((0., 0.).into(), noneg_float(200.), &mut rng))
+        });
+    }
+}
+
 criterion_group!(
     benches,
     find_nearest_food,
     find_nearest_bug,
     transfer_energy_from_food_to_bug,
+    find_bug_by_id,
+    bite,
+    circular_traverse_iter,
+    irradiate_area,
 );
 criterion_main!(benches);
 
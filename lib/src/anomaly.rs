@@ -0,0 +1,255 @@
+use std::{collections::BTreeMap, fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    brain::MAX_FEEDING_RATE, bug::Bug, environment::Environment, nutrient::NUTRIENT_COUNT,
+    utils::Float,
+};
+
+/// Gains smaller than that are never reported (rounding of energy transfers)
+const ENERGY_TOLERANCE: Float = 1e-6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyDetectorConfig {
+    /// Iterations between two checks
+    pub period: usize,
+    /// Bug which has not moved for that many checks in a row, yet had offspring meanwhile, is
+    /// suspected to exploit reproduction
+    pub frozen_checks: usize,
+    /// Entities with coordinate further from origin than that are considered lost
+    pub max_coordinate: Float,
+}
+
+impl Default for AnomalyDetectorConfig {
+    fn default() -> Self {
+        Self {
+            period: 300,
+            frozen_checks: 6,
+            max_coordinate: 1_000_000.,
+        }
+    }
+}
+
+/// Suspicious dynamics which usually mean that simulation rules are exploited or numbers went
+/// wrong
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    /// Bug stood still (not buried nor paralyzed) for `checks` checks, yet had offspring
+    FrozenButBreeding {
+        bug_id: usize,
+        checks: usize,
+        offspring: usize,
+    },
+    /// Energy stored by bug grew by `gain` while eating and parental feeding could give at
+    /// most `limit`
+    EnergyGainExceedsIntake {
+        bug_id: usize,
+        gain: Float,
+        limit: Float,
+    },
+    OutOfBounds {
+        bug_id: usize,
+        position: (Float, Float),
+    },
+    /// NaN or infinity in `field` of bug (or of food if `bug_id` is None)
+    NotFinite {
+        bug_id: Option<usize>,
+        field: &'static str,
+    },
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anomaly::FrozenButBreeding {
+                bug_id,
+                checks,
+                offspring,
+            } => write!(
+                f,
+                "bug {} had {} offspring without moving for {} checks",
+                bug_id, offspring, checks
+            ),
+            Anomaly::EnergyGainExceedsIntake {
+                bug_id,
+                gain,
+                limit,
+            } => write!(
+                f,
+                "bug {} gained {} energy while intake allows at most {}",
+                bug_id, gain, limit
+            ),
+            Anomaly::OutOfBounds { bug_id, position } => {
+                write!(f, "bug {} is out of bounds at {:?}", bug_id, position)
+            }
+            Anomaly::NotFinite {
+                bug_id: Some(bug_id),
+                field,
+            } => write!(f, "{} of bug {} is not finite", field, bug_id),
+            Anomaly::NotFinite {
+                bug_id: None,
+                field,
+            } => write!(f, "{} of food is not finite", field),
+        }
+    }
+}
+
+/// What bug looked like at the previous check
+struct Track {
+    position: (Float, Float),
+    still_checks: usize,
+    /// Offspring count when bug stopped moving
+    offspring_when_stopped: usize,
+    /// Energy store, stomach and baby charge
+    stored_energy: Float,
+    consumed_energy: Float,
+    /// Every bug is reported once, so a stuck state does not flood notifications
+    flagged: bool,
+}
+
+/// Periodically scans environment with cheap heuristics. Bugs are tracked between checks by id
+pub struct AnomalyDetector {
+    config: AnomalyDetectorConfig,
+    elapsed: Duration,
+    tracks: BTreeMap<usize, Track>,
+    food_flagged: bool,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyDetectorConfig) -> Self {
+        Self {
+            config,
+            elapsed: Duration::ZERO,
+            tracks: Default::default(),
+            food_flagged: false,
+        }
+    }
+
+    pub fn config(&self) -> &AnomalyDetectorConfig {
+        &self.config
+    }
+
+    /// Called after every iteration. Returns anomalies found since the previous check (empty
+    /// between checks)
+    pub(crate) fn proceed<T>(&mut self, env: &Environment<T>, dt: Duration) -> Vec<Anomaly> {
+        self.elapsed += dt;
+        if env.iteration() % self.config.period.max(1) != 0 {
+            return vec![];
+        }
+        let elapsed = std::mem::take(&mut self.elapsed);
+        // a parent gives at most that much to its child between two checks
+        let feeding_limit = MAX_FEEDING_RATE * elapsed.as_secs_f64();
+
+        let mut anomalies: Vec<Anomaly> = Default::default();
+        let mut tracks: BTreeMap<usize, Track> = Default::default();
+        for bug in env.bugs() {
+            let mut found = check_state(&bug, self.config.max_coordinate);
+            let position = (*bug.position().x(), *bug.position().y());
+            let stored_energy =
+                (bug.energy_level() + bug.stomach_level() + bug.baby_charge_level()).unwrap();
+            let consumed_energy = bug.consumed_energy().unwrap();
+            let track = match self.tracks.remove(&bug.id()) {
+                Some(previous) => {
+                    let still = previous.position == position && !bug.buried() && !bug.paralyzed();
+                    let still_checks = if still { previous.still_checks + 1 } else { 0 };
+                    let offspring_when_stopped = if still {
+                        previous.offspring_when_stopped
+                    } else {
+                        bug.offspring_count()
+                    };
+                    let offspring = bug.offspring_count() - offspring_when_stopped;
+                    if still_checks >= self.config.frozen_checks && offspring > 0 {
+                        found.push(Anomaly::FrozenButBreeding {
+                            bug_id: bug.id(),
+                            checks: still_checks,
+                            offspring,
+                        });
+                    }
+
+                    let gain = stored_energy - previous.stored_energy;
+                    let limit = (consumed_energy - previous.consumed_energy)
+                        * NUTRIENT_COUNT as Float
+                        + feeding_limit;
+                    if gain > limit + ENERGY_TOLERANCE {
+                        found.push(Anomaly::EnergyGainExceedsIntake {
+                            bug_id: bug.id(),
+                            gain,
+                            limit,
+                        });
+                    }
+
+                    Track {
+                        position,
+                        still_checks,
+                        offspring_when_stopped,
+                        stored_energy,
+                        consumed_energy,
+                        flagged: previous.flagged,
+                    }
+                }
+                None => Track {
+                    position,
+                    still_checks: 0,
+                    offspring_when_stopped: bug.offspring_count(),
+                    stored_energy,
+                    consumed_energy,
+                    flagged: false,
+                },
+            };
+            let flagged = track.flagged || !found.is_empty();
+            if !track.flagged {
+                anomalies.extend(found);
+            }
+            tracks.insert(bug.id(), Track { flagged, ..track });
+        }
+        // dead bugs are forgotten
+        self.tracks = tracks;
+
+        if !self.food_flagged {
+            if let Some(field) = env.food().find_map(|food| {
+                if !food.position().x().is_finite() || !food.position().y().is_finite() {
+                    Some("position")
+                } else if !food.energy().unwrap().is_finite() {
+                    Some("energy")
+                } else {
+                    None
+                }
+            }) {
+                self.food_flagged = true;
+                anomalies.push(Anomaly::NotFinite {
+                    bug_id: None,
+                    field,
+                });
+            }
+        }
+        anomalies
+    }
+}
+
+/// Anomalies visible in a single snapshot of bug
+fn check_state<T>(bug: &Bug<T>, max_coordinate: Float) -> Vec<Anomaly> {
+    let fields: [(&'static str, Float); 7] = [
+        ("x", *bug.position().x()),
+        ("y", *bug.position().y()),
+        ("rotation", bug.rotation().radians()),
+        ("energy", bug.energy_level().unwrap()),
+        ("stomach", bug.stomach_level().unwrap()),
+        ("baby charge", bug.baby_charge_level().unwrap()),
+        ("heat", bug.heat_level().unwrap()),
+    ];
+    if let Some((field, _)) = fields.iter().find(|(_, value)| !value.is_finite()) {
+        return vec![Anomaly::NotFinite {
+            bug_id: Some(bug.id()),
+            field: *field,
+        }];
+    }
+    let position = (*bug.position().x(), *bug.position().y());
+    if position.0.abs() > max_coordinate || position.1.abs() > max_coordinate {
+        return vec![Anomaly::OutOfBounds {
+            bug_id: bug.id(),
+            position,
+        }];
+    }
+    vec![]
+}
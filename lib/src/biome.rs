@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    environment::CHUNK_SIZE,
+    math::{noneg_float, NoNeg, Point},
+    utils::Float,
+};
+
+/// Width and height of biome cell (one cell per chunk)
+pub const BIOME_CELL_SIZE: Float = CHUNK_SIZE as Float;
+
+/// Part of generated chunks which are hazardous
+const HAZARDOUS_CHUNK_PROBABILITY: f64 = 0.1;
+
+/// Properties of ground in a chunk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Biome {
+    /// Multiplier of energy of food growing in the chunk
+    pub fertility: NoNeg<Float>,
+    /// Multiplier of movement cost in the chunk
+    pub friction: NoNeg<Float>,
+    /// Energy drained per second from each unit of bug size staying in the chunk
+    pub hazard: NoNeg<Float>,
+}
+
+impl Default for Biome {
+    fn default() -> Self {
+        Self {
+            fertility: noneg_float(1.),
+            friction: noneg_float(1.),
+            hazard: noneg_float(0.),
+        }
+    }
+}
+
+impl Biome {
+    pub fn generate<R: RngCore>(rng: &mut R) -> Self {
+        Self {
+            fertility: NoNeg::wrap(rng.gen_range(0.5..2.)).unwrap(),
+            friction: NoNeg::wrap(rng.gen_range(0.5..2.)).unwrap(),
+            hazard: if rng.gen_bool(HAZARDOUS_CHUNK_PROBABILITY) {
+                NoNeg::wrap(rng.gen_range(0.01..0.1)).unwrap()
+            } else {
+                noneg_float(0.)
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChunkBiome {
+    x: isize,
+    y: isize,
+    biome: Biome,
+}
+
+/// Biomes of chunks. Chunks without explicit biome have default one (neutral ground)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<ChunkBiome>", into = "Vec<ChunkBiome>")]
+pub struct BiomeMap {
+    chunks: BTreeMap<(isize, isize), Biome>,
+}
+
+impl From<Vec<ChunkBiome>> for BiomeMap {
+    fn from(value: Vec<ChunkBiome>) -> Self {
+        Self {
+            chunks: value
+                .into_iter()
+                .map(|chunk| ((chunk.x, chunk.y), chunk.biome))
+                .collect(),
+        }
+    }
+}
+
+impl From<BiomeMap> for Vec<ChunkBiome> {
+    fn from(value: BiomeMap) -> Self {
+        value
+            .chunks
+            .into_iter()
+            .map(|((x, y), biome)| ChunkBiome { x, y, biome })
+            .collect()
+    }
+}
+
+impl BiomeMap {
+    fn cell_of(position: Point<Float>) -> (isize, isize) {
+        (
+            (position.x() / BIOME_CELL_SIZE).floor() as isize,
+            (position.y() / BIOME_CELL_SIZE).floor() as isize,
+        )
+    }
+
+    /// Random biome for every cell covering rect from `(x0, y0)` to `(x1, y1)`
+    pub fn generate<R: RngCore>(
+        rng: &mut R,
+        (x0, y0): (Float, Float),
+        (x1, y1): (Float, Float),
+    ) -> Self {
+        let (cx0, cy0) = Self::cell_of((x0, y0).into());
+        let (cx1, cy1) = Self::cell_of((x1, y1).into());
+        let mut chunks = BTreeMap::new();
+        for y in cy0..=cy1 {
+            for x in cx0..=cx1 {
+                chunks.insert((x, y), Biome::generate(rng));
+            }
+        }
+        Self { chunks }
+    }
+
+    pub fn biome_at(&self, position: Point<Float>) -> Biome {
+        self.chunks
+            .get(&Self::cell_of(position))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set_biome(&mut self, cell: (isize, isize), biome: Biome) {
+        self.chunks.insert(cell, biome);
+    }
+
+    /// Indices (in units of `BIOME_CELL_SIZE`) and biomes of all cells with explicit biome
+    pub fn cells(&self) -> impl Iterator<Item = ((isize, isize), &Biome)> + '_ {
+        self.chunks.iter().map(|(cell, biome)| (*cell, biome))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::math::noneg_float;
+
+    use super::{Biome, BiomeMap, BIOME_CELL_SIZE};
+
+    #[test]
+    fn biomes_cover_generated_rect_and_survive_serialization() {
+        let mut rng = Pcg64::seed_from_u64(0);
+        let map = BiomeMap::generate(
+            &mut rng,
+            (-BIOME_CELL_SIZE * 4., -BIOME_CELL_SIZE * 4.),
+            (BIOME_CELL_SIZE * 4. - 1., BIOME_CELL_SIZE * 4. - 1.),
+        );
+        assert_eq!(map.cells().count(), 64);
+        assert!(map.cells().any(|(_, biome)| biome.hazard > noneg_float(0.)));
+        assert_eq!(
+            map.biome_at((BIOME_CELL_SIZE * 10., 0.).into()),
+            Biome::default()
+        );
+
+        let json = serde_json::to_string(&map).unwrap();
+        let restored: BiomeMap = serde_json::from_str(&json).unwrap();
+        assert!(map.cells().eq(restored.cells()));
+    }
+}
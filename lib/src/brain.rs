@@ -1,13 +1,547 @@
 use crate::{
-    math::{self, clamp_into_range, noneg_float, Angle, DeltaAngle, NoNeg},
+    chromo_utils::{
+        BRAIN_ACTUATOR_GENES, BRAIN_EXTRA_HIDDEN_GENES, BRAIN_EXTRA_WEIGHT_GENES,
+        BRAIN_FLOCK_INPUT_GENES, BRAIN_GENES, BRAIN_HIDDEN_SIZE_GENES, BRAIN_MEMORY_INPUT_GENES,
+        BRAIN_MEMORY_OUTPUT_GENES, BRAIN_NEIGHBOR_INPUT_GENES, BRAIN_ODOR_INPUT_GENES,
+        BRAIN_RETINA_INPUT_GENES, BRAIN_SECOND_EXTRA_WEIGHT_GENES, BRAIN_SIGNAL_INPUT_GENES,
+        BRAIN_SIGNAL_OUTPUT_GENES, BRAIN_STING_GENES, BRAIN_TERRITORY_INPUT_GENES,
+        BRAIN_THIRD_EXTRA_WEIGHT_GENES, BRAIN_TOUCH_INPUT_GENES, BRAIN_WALL_INPUT_GENES,
+    },
+    math::{self, clamp_into_range, noneg_float, Angle, DeltaAngle, NoNeg, Zero},
+    nutrient::Nutrients,
+    odor::OdorInfo,
+    perceptron::{Perceptron, PerceptronLayer},
+    pheromone::PheromoneInfo,
     range::Range,
+    sound::HearingInfo,
     utils::{Color, Float, RequiredToBeInRange as _},
+    water::WaterInfo,
 };
 use chromosome::Chromosome;
-use simple_neural_net::{normalizers, Arr, Layer as _, PerceptronLayer};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use simple_neural_net::normalizers;
 use std::f64::consts::PI;
 
-simple_neural_net::compose_layers!(Net, 16, 8, 8);
+/// Number of neurons in input layer
+pub const INPUT_SIZE: usize = 51;
+
+/// Number of neurons in output layer
+pub const OUTPUT_SIZE: usize = 16;
+
+/// Max absolute value of `Output::velocity`
+pub const MAX_VELOCITY: Float = 10.;
+
+/// Bounds of number of neurons in hidden layer (it is set by `BRAIN_HIDDEN_SIZE_GENES`)
+pub const MIN_HIDDEN_SIZE: usize = 4;
+pub const MAX_HIDDEN_SIZE: usize = 16;
+
+/// Hidden neurons whose weights are stored in `BRAIN_GENES` and in the other genes appended
+/// for new inputs and outputs. Genomes saved before hidden layer became evolvable have exactly
+/// that many
+const BASE_HIDDEN_SIZE: usize = 8;
+
+/// Number of memory cells. Each is written by an output of the net (starting from
+/// `MEMORY_OUTPUT_START`) and is read by an input (starting from `MEMORY_INPUT_START`)
+/// on the next tick
+pub const MEMORY_SIZE: usize = 2;
+
+/// Index of the first memory input neuron
+pub const MEMORY_INPUT_START: usize = 24;
+
+/// Index of the first memory output neuron
+pub const MEMORY_OUTPUT_START: usize = 10;
+
+/// Index of the first of discrete actuator outputs (eat, attack, emit pheromone, feed
+/// offspring). Their weights of all hidden neurons are stored in `BRAIN_ACTUATOR_GENES`
+const ACTUATOR_OUTPUT_START: usize = MEMORY_OUTPUT_START + MEMORY_SIZE;
+
+/// Genes of each actuator output: weights of all possible hidden neurons and bias
+const ACTUATOR_GENES: usize = MAX_HIDDEN_SIZE + 1;
+
+/// Inputs whose weights of hidden neurons beyond `BASE_HIDDEN_SIZE` are stored in
+/// `BRAIN_EXTRA_HIDDEN_GENES` (all inputs before neighbor ones)
+const EXTRA_HIDDEN_INPUT_SIZE: usize = NEIGHBOR_INPUT_START;
+
+/// Genes of each hidden neuron beyond `BASE_HIDDEN_SIZE` in `BRAIN_EXTRA_HIDDEN_GENES`:
+/// weights of inputs before neighbor ones, bias and its weights in all outputs before actuators
+const EXTRA_HIDDEN_NEURON_GENES: usize = EXTRA_HIDDEN_INPUT_SIZE + 1 + ACTUATOR_OUTPUT_START;
+
+/// Inputs whose weights are stored in `BRAIN_GENES` (the rest are in `BRAIN_EXTRA_WEIGHT_GENES`,
+/// `BRAIN_SECOND_EXTRA_WEIGHT_GENES`, `BRAIN_THIRD_EXTRA_WEIGHT_GENES`,
+/// `BRAIN_SIGNAL_INPUT_GENES`, `BRAIN_MEMORY_INPUT_GENES`, `BRAIN_NEIGHBOR_INPUT_GENES`,
+/// `BRAIN_RETINA_INPUT_GENES`, `BRAIN_ODOR_INPUT_GENES`, `BRAIN_TOUCH_INPUT_GENES`,
+/// `BRAIN_TERRITORY_INPUT_GENES`, `BRAIN_FLOCK_INPUT_GENES` and `BRAIN_WALL_INPUT_GENES`)
+const BASE_INPUT_SIZE: usize = 16;
+
+/// Inputs whose weights are stored in `BRAIN_EXTRA_WEIGHT_GENES`
+const EXTRA_INPUT_SIZE: usize = 3;
+
+/// Inputs whose weights are stored in `BRAIN_SECOND_EXTRA_WEIGHT_GENES`
+const SECOND_EXTRA_INPUT_SIZE: usize = 1;
+
+/// Inputs whose weights are stored in `BRAIN_THIRD_EXTRA_WEIGHT_GENES`
+const THIRD_EXTRA_INPUT_SIZE: usize = 3;
+
+/// Inputs whose weights are stored in `BRAIN_SIGNAL_INPUT_GENES`
+const SIGNAL_INPUT_SIZE: usize = 1;
+
+/// Inputs whose weights are stored in `BRAIN_MEMORY_INPUT_GENES`
+const MEMORY_INPUT_SIZE: usize = MEMORY_SIZE;
+
+/// Index of the first input whose weights are stored in `BRAIN_NEIGHBOR_INPUT_GENES`
+const NEIGHBOR_INPUT_START: usize = MEMORY_INPUT_START + MEMORY_INPUT_SIZE;
+
+/// Inputs whose weights are stored in `BRAIN_NEIGHBOR_INPUT_GENES`
+const NEIGHBOR_INPUT_SIZE: usize = 4 * FARTHER_NEIGHBOR_COUNT;
+
+/// Number of food items and bugs seen besides the nearest ones which brain is told about
+pub const FARTHER_NEIGHBOR_COUNT: usize = 2;
+
+/// Index of the first input whose weights are stored in `BRAIN_RETINA_INPUT_GENES`
+const RETINA_INPUT_START: usize = NEIGHBOR_INPUT_START + NEIGHBOR_INPUT_SIZE;
+
+/// Inputs whose weights are stored in `BRAIN_RETINA_INPUT_GENES`
+const RETINA_INPUT_SIZE: usize = 2 * RETINA_SECTORS;
+
+/// Index of the first input whose weights are stored in `BRAIN_ODOR_INPUT_GENES`
+const ODOR_INPUT_START: usize = RETINA_INPUT_START + RETINA_INPUT_SIZE;
+
+/// Inputs whose weights are stored in `BRAIN_ODOR_INPUT_GENES`
+const ODOR_INPUT_SIZE: usize = 2;
+
+/// Index of the first input whose weights are stored in `BRAIN_TOUCH_INPUT_GENES`
+const TOUCH_INPUT_START: usize = ODOR_INPUT_START + ODOR_INPUT_SIZE;
+
+/// Inputs whose weights are stored in `BRAIN_TOUCH_INPUT_GENES`
+const TOUCH_INPUT_SIZE: usize = 2;
+
+/// Index of the first input whose weights are stored in `BRAIN_TERRITORY_INPUT_GENES`
+const TERRITORY_INPUT_START: usize = TOUCH_INPUT_START + TOUCH_INPUT_SIZE;
+
+/// Inputs whose weights are stored in `BRAIN_TERRITORY_INPUT_GENES`
+const TERRITORY_INPUT_SIZE: usize = 1;
+
+/// Index of the first input whose weights are stored in `BRAIN_FLOCK_INPUT_GENES`
+const FLOCK_INPUT_START: usize = TERRITORY_INPUT_START + TERRITORY_INPUT_SIZE;
+
+/// Inputs whose weights are stored in `BRAIN_FLOCK_INPUT_GENES`
+const FLOCK_INPUT_SIZE: usize = 3;
+
+/// Index of the first input whose weights are stored in `BRAIN_WALL_INPUT_GENES`
+const WALL_INPUT_START: usize = FLOCK_INPUT_START + FLOCK_INPUT_SIZE;
+
+/// Inputs whose weights are stored in `BRAIN_WALL_INPUT_GENES`
+const WALL_INPUT_SIZE: usize = INPUT_SIZE - WALL_INPUT_START;
+
+/// Number of equal sectors vision arc is divided into in `VisionMode::Retina`
+pub const RETINA_SECTORS: usize = 4;
+
+/// Meaning of each neuron of input layer. Reserved slots are always zero:
+/// a new sense must claim one of them (rename it and add a setter to `InputVector`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputSlot {
+    /// Energy level / energy capacity
+    EnergyRatio,
+    /// Distance to nearest food / vision range (1 if no food is seen)
+    FoodProximity,
+    /// Direction to nearest food relative to own rotation
+    FoodDirection,
+    FoodRelativeRadius,
+    Age,
+    /// Distance to nearest bug / vision range (1 if no bug is seen)
+    BugProximity,
+    /// Direction to nearest bug relative to own rotation
+    BugDirection,
+    BugAlpha,
+    BugRed,
+    BugGreen,
+    BugBlue,
+    BugRelativeRadius,
+    /// Baby charge level / baby charge capacity
+    BabyChargeRatio,
+    /// Pheromone concentration `c` at own position mapped to `c / (c + 1)`
+    PheromoneConcentration,
+    /// Direction of pheromone gradient relative to own rotation (0 if field is flat)
+    PheromoneDirection,
+    /// Intensity of the loudest sound heard
+    HearingIntensity,
+    /// Direction to the loudest sound relative to own rotation (0 if nothing is heard)
+    HearingDirection,
+    /// Frequency of the loudest sound (0 if nothing is heard)
+    HearingFrequency,
+    /// 1 if bug is in water, 0 otherwise
+    InWater,
+    /// 1 at the shore, decreasing to 0 further from it (0 if there is no water)
+    ShoreProximity,
+    /// Share of protein in nearest food (0 if no food is seen)
+    FoodProtein,
+    /// Share of carbohydrates in nearest food (0 if no food is seen)
+    FoodCarbs,
+    /// Share of fat in nearest food (0 if no food is seen)
+    FoodFat,
+    /// Brightness of glow of nearest bug (0 if no bug is seen)
+    BugSignal,
+    /// Memory cells written by own brain on the previous tick
+    Memory0,
+    Memory1,
+    /// Distance to the 2nd nearest food / vision range (1 if it is not seen)
+    Food2Proximity,
+    /// Direction to the 2nd nearest food relative to own rotation (0 if it is not seen)
+    Food2Direction,
+    Food3Proximity,
+    Food3Direction,
+    /// Distance to the 2nd nearest bug / vision range (1 if it is not seen)
+    Bug2Proximity,
+    /// Direction to the 2nd nearest bug relative to own rotation (0 if it is not seen)
+    Bug2Direction,
+    Bug3Proximity,
+    Bug3Direction,
+    /// Distance to the nearest object in retina sector / vision range (1 if sector is empty or
+    /// retina is off)
+    Sector0Proximity,
+    /// Kind of the nearest object in retina sector: -1 for food, 1 for bug, 0 if sector is
+    /// empty or retina is off
+    Sector0Object,
+    Sector1Proximity,
+    Sector1Object,
+    Sector2Proximity,
+    Sector2Object,
+    Sector3Proximity,
+    Sector3Object,
+    /// Food odor intensity `i` at own position mapped to `i / (i + 1)`
+    OdorIntensity,
+    /// Direction of odor gradient relative to own rotation (0 if field is flat)
+    OdorDirection,
+    /// Kind of object overlapping own body: -1 for food, 1 for bug, 0 if nothing is touched
+    TouchObject,
+    /// Direction to touched object relative to own rotation (0 if nothing is touched)
+    TouchDirection,
+    /// Strength `s` of foreign territory marker at own position mapped to `s / (s + 1)`
+    ForeignTerritory,
+    /// Number of bugs seen `n` mapped to `n / (n + 1)`
+    FlockSize,
+    /// Mean heading of bugs seen relative to own rotation (0 if nothing is seen)
+    FlockHeading,
+    /// Speed of mean velocity of bugs seen relative to `MAX_VELOCITY` (at most 1)
+    FlockSpeed,
+    /// Distance to wall ahead relative to vision range (1 if no wall is seen)
+    WallProximity,
+}
+
+impl InputSlot {
+    /// All slots in order of input neurons
+    pub const ALL: [InputSlot; INPUT_SIZE] = [
+        Self::EnergyRatio,
+        Self::FoodProximity,
+        Self::FoodDirection,
+        Self::FoodRelativeRadius,
+        Self::Age,
+        Self::BugProximity,
+        Self::BugDirection,
+        Self::BugAlpha,
+        Self::BugRed,
+        Self::BugGreen,
+        Self::BugBlue,
+        Self::BugRelativeRadius,
+        Self::BabyChargeRatio,
+        Self::PheromoneConcentration,
+        Self::PheromoneDirection,
+        Self::HearingIntensity,
+        Self::HearingDirection,
+        Self::HearingFrequency,
+        Self::InWater,
+        Self::ShoreProximity,
+        Self::FoodProtein,
+        Self::FoodCarbs,
+        Self::FoodFat,
+        Self::BugSignal,
+        Self::Memory0,
+        Self::Memory1,
+        Self::Food2Proximity,
+        Self::Food2Direction,
+        Self::Food3Proximity,
+        Self::Food3Direction,
+        Self::Bug2Proximity,
+        Self::Bug2Direction,
+        Self::Bug3Proximity,
+        Self::Bug3Direction,
+        Self::Sector0Proximity,
+        Self::Sector0Object,
+        Self::Sector1Proximity,
+        Self::Sector1Object,
+        Self::Sector2Proximity,
+        Self::Sector2Object,
+        Self::Sector3Proximity,
+        Self::Sector3Object,
+        Self::OdorIntensity,
+        Self::OdorDirection,
+        Self::TouchObject,
+        Self::TouchDirection,
+        Self::ForeignTerritory,
+        Self::FlockSize,
+        Self::FlockHeading,
+        Self::FlockSpeed,
+        Self::WallProximity,
+    ];
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// All slots are claimed now. A new sense needs a new slot and weight genes for it
+    pub const fn is_reserved(self) -> bool {
+        false
+    }
+
+    /// Short label displayed next to input neuron
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::EnergyRatio => "E/C",
+            Self::FoodProximity => "FP/",
+            Self::FoodDirection => "R-F",
+            Self::FoodRelativeRadius => "FS",
+            Self::Age => "A",
+            Self::BugProximity => "BP/",
+            Self::BugDirection => "R-B",
+            Self::BugAlpha => "a",
+            Self::BugRed => "r",
+            Self::BugGreen => "g",
+            Self::BugBlue => "b",
+            Self::BugRelativeRadius => "BS",
+            Self::BabyChargeRatio => "B/C",
+            Self::PheromoneConcentration => "Ph",
+            Self::PheromoneDirection => "R-P",
+            Self::HearingIntensity => "H",
+            Self::HearingDirection => "R-H",
+            Self::HearingFrequency => "Hf",
+            Self::InWater => "W",
+            Self::ShoreProximity => "Sh",
+            Self::FoodProtein => "Fp",
+            Self::FoodCarbs => "Fc",
+            Self::FoodFat => "Ff",
+            Self::BugSignal => "BSg",
+            Self::Memory0 => "M0",
+            Self::Memory1 => "M1",
+            Self::Food2Proximity => "F2P",
+            Self::Food2Direction => "R-F2",
+            Self::Food3Proximity => "F3P",
+            Self::Food3Direction => "R-F3",
+            Self::Bug2Proximity => "B2P",
+            Self::Bug2Direction => "R-B2",
+            Self::Bug3Proximity => "B3P",
+            Self::Bug3Direction => "R-B3",
+            Self::Sector0Proximity => "S0P",
+            Self::Sector0Object => "S0O",
+            Self::Sector1Proximity => "S1P",
+            Self::Sector1Object => "S1O",
+            Self::Sector2Proximity => "S2P",
+            Self::Sector2Object => "S2O",
+            Self::Sector3Proximity => "S3P",
+            Self::Sector3Object => "S3O",
+            Self::OdorIntensity => "Od",
+            Self::OdorDirection => "R-Od",
+            Self::TouchObject => "T",
+            Self::TouchDirection => "R-T",
+            Self::ForeignTerritory => "Ter",
+            Self::FlockSize => "Fl",
+            Self::FlockHeading => "R-Fl",
+            Self::FlockSpeed => "V-Fl",
+            Self::WallProximity => "W",
+        }
+    }
+}
+
+const _: () = {
+    let mut i = 0;
+    while i < INPUT_SIZE {
+        assert!(
+            InputSlot::ALL[i].index() == i,
+            "InputSlot::ALL must list slots in order of declaration"
+        );
+        i += 1;
+    }
+    assert!(
+        BRAIN_EXTRA_WEIGHT_GENES.end - BRAIN_EXTRA_WEIGHT_GENES.start == 8 * EXTRA_INPUT_SIZE,
+        "BRAIN_EXTRA_WEIGHT_GENES must hold weights of `EXTRA_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BRAIN_SECOND_EXTRA_WEIGHT_GENES.end - BRAIN_SECOND_EXTRA_WEIGHT_GENES.start
+            == 8 * SECOND_EXTRA_INPUT_SIZE,
+        "BRAIN_SECOND_EXTRA_WEIGHT_GENES must hold weights of `SECOND_EXTRA_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BRAIN_THIRD_EXTRA_WEIGHT_GENES.end - BRAIN_THIRD_EXTRA_WEIGHT_GENES.start
+            == 8 * THIRD_EXTRA_INPUT_SIZE,
+        "BRAIN_THIRD_EXTRA_WEIGHT_GENES must hold weights of `THIRD_EXTRA_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BRAIN_SIGNAL_INPUT_GENES.end - BRAIN_SIGNAL_INPUT_GENES.start == 8 * SIGNAL_INPUT_SIZE,
+        "BRAIN_SIGNAL_INPUT_GENES must hold weights of `SIGNAL_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BRAIN_MEMORY_INPUT_GENES.end - BRAIN_MEMORY_INPUT_GENES.start == 8 * MEMORY_INPUT_SIZE,
+        "BRAIN_MEMORY_INPUT_GENES must hold weights of `MEMORY_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BASE_INPUT_SIZE
+            + EXTRA_INPUT_SIZE
+            + SECOND_EXTRA_INPUT_SIZE
+            + THIRD_EXTRA_INPUT_SIZE
+            + SIGNAL_INPUT_SIZE
+            == MEMORY_INPUT_START,
+        "memory inputs must follow the ones stored in other genes"
+    );
+    assert!(
+        BRAIN_NEIGHBOR_INPUT_GENES.end - BRAIN_NEIGHBOR_INPUT_GENES.start
+            == MAX_HIDDEN_SIZE * NEIGHBOR_INPUT_SIZE,
+        "BRAIN_NEIGHBOR_INPUT_GENES must hold weights of `NEIGHBOR_INPUT_SIZE` inputs"
+    );
+    assert!(
+        RETINA_INPUT_SIZE == 2 * RETINA_SECTORS,
+        "each retina sector needs distance and object kind inputs"
+    );
+    assert!(
+        BRAIN_RETINA_INPUT_GENES.end - BRAIN_RETINA_INPUT_GENES.start
+            == MAX_HIDDEN_SIZE * RETINA_INPUT_SIZE,
+        "BRAIN_RETINA_INPUT_GENES must hold weights of `RETINA_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BRAIN_ODOR_INPUT_GENES.end - BRAIN_ODOR_INPUT_GENES.start
+            == MAX_HIDDEN_SIZE * ODOR_INPUT_SIZE,
+        "BRAIN_ODOR_INPUT_GENES must hold weights of `ODOR_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BRAIN_TOUCH_INPUT_GENES.end - BRAIN_TOUCH_INPUT_GENES.start
+            == MAX_HIDDEN_SIZE * TOUCH_INPUT_SIZE,
+        "BRAIN_TOUCH_INPUT_GENES must hold weights of `TOUCH_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BRAIN_TERRITORY_INPUT_GENES.end - BRAIN_TERRITORY_INPUT_GENES.start
+            == MAX_HIDDEN_SIZE * TERRITORY_INPUT_SIZE,
+        "BRAIN_TERRITORY_INPUT_GENES must hold weights of `TERRITORY_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BRAIN_FLOCK_INPUT_GENES.end - BRAIN_FLOCK_INPUT_GENES.start
+            == MAX_HIDDEN_SIZE * FLOCK_INPUT_SIZE,
+        "BRAIN_FLOCK_INPUT_GENES must hold weights of `FLOCK_INPUT_SIZE` inputs"
+    );
+    assert!(
+        BRAIN_WALL_INPUT_GENES.end - BRAIN_WALL_INPUT_GENES.start
+            == MAX_HIDDEN_SIZE * WALL_INPUT_SIZE,
+        "BRAIN_WALL_INPUT_GENES must hold weights of all the remaining inputs"
+    );
+    assert!(
+        BRAIN_MEMORY_OUTPUT_GENES.end - BRAIN_MEMORY_OUTPUT_GENES.start == (8 + 1) * MEMORY_SIZE,
+        "BRAIN_MEMORY_OUTPUT_GENES must hold weights and biases of all memory outputs"
+    );
+    assert!(
+        BRAIN_EXTRA_HIDDEN_GENES.end - BRAIN_EXTRA_HIDDEN_GENES.start
+            == (MAX_HIDDEN_SIZE - BASE_HIDDEN_SIZE) * EXTRA_HIDDEN_NEURON_GENES,
+        "BRAIN_EXTRA_HIDDEN_GENES must hold all hidden neurons beyond `BASE_HIDDEN_SIZE`"
+    );
+    assert!(
+        BRAIN_ACTUATOR_GENES.end - BRAIN_ACTUATOR_GENES.start
+            == (OUTPUT_SIZE - ACTUATOR_OUTPUT_START) * ACTUATOR_GENES,
+        "BRAIN_ACTUATOR_GENES must hold all the remaining outputs"
+    );
+};
+
+/// Builder of input layer activations. Every active slot must be set exactly once,
+/// reserved slots stay zero
+#[derive(Debug, Clone, Default)]
+pub struct InputVector {
+    values: [Float; INPUT_SIZE],
+    assigned: [bool; INPUT_SIZE],
+}
+
+macro_rules! input_setters {
+    ($($name:ident => $slot:ident),* $(,)?) => {
+        impl InputVector {
+            $(
+                pub fn $name(self, value: Float) -> Self {
+                    self.set(InputSlot::$slot, value)
+                }
+            )*
+        }
+    };
+}
+
+input_setters! {
+    energy_ratio => EnergyRatio,
+    food_proximity => FoodProximity,
+    food_direction => FoodDirection,
+    food_relative_radius => FoodRelativeRadius,
+    age => Age,
+    bug_proximity => BugProximity,
+    bug_direction => BugDirection,
+    bug_alpha => BugAlpha,
+    bug_red => BugRed,
+    bug_green => BugGreen,
+    bug_blue => BugBlue,
+    bug_relative_radius => BugRelativeRadius,
+    baby_charge_ratio => BabyChargeRatio,
+    pheromone_concentration => PheromoneConcentration,
+    pheromone_direction => PheromoneDirection,
+    hearing_intensity => HearingIntensity,
+    hearing_direction => HearingDirection,
+    hearing_frequency => HearingFrequency,
+    in_water => InWater,
+    shore_proximity => ShoreProximity,
+    food_protein => FoodProtein,
+    food_carbs => FoodCarbs,
+    food_fat => FoodFat,
+    bug_signal => BugSignal,
+    memory0 => Memory0,
+    memory1 => Memory1,
+    food2_proximity => Food2Proximity,
+    food2_direction => Food2Direction,
+    food3_proximity => Food3Proximity,
+    food3_direction => Food3Direction,
+    bug2_proximity => Bug2Proximity,
+    bug2_direction => Bug2Direction,
+    bug3_proximity => Bug3Proximity,
+    bug3_direction => Bug3Direction,
+    sector0_proximity => Sector0Proximity,
+    sector0_object => Sector0Object,
+    sector1_proximity => Sector1Proximity,
+    sector1_object => Sector1Object,
+    sector2_proximity => Sector2Proximity,
+    sector2_object => Sector2Object,
+    sector3_proximity => Sector3Proximity,
+    sector3_object => Sector3Object,
+    odor_intensity => OdorIntensity,
+    odor_direction => OdorDirection,
+    touch_object => TouchObject,
+    touch_direction => TouchDirection,
+    foreign_territory => ForeignTerritory,
+    flock_size => FlockSize,
+    flock_heading => FlockHeading,
+    flock_speed => FlockSpeed,
+    wall_proximity => WallProximity,
+}
+
+impl InputVector {
+    fn set(mut self, slot: InputSlot, value: Float) -> Self {
+        let i = slot.index();
+        assert!(!slot.is_reserved(), "input slot {:?} is reserved", slot);
+        assert!(!self.assigned[i], "input slot {:?} is set twice", slot);
+        self.values[i] = value;
+        self.assigned[i] = true;
+        self
+    }
+
+    pub fn build(self) -> [Float; INPUT_SIZE] {
+        for slot in InputSlot::ALL {
+            assert!(
+                slot.is_reserved() || self.assigned[slot.index()],
+                "input slot {:?} is not set",
+                slot
+            );
+        }
+        self.values
+    }
+}
 
 fn angle_to_activation(a: Angle<Float>) -> Float {
     math::fit_into_range(a.radians(), 0. ..PI * 2., -1. ..1.).unwrap()
@@ -17,6 +551,15 @@ fn activation_to_angle(a: Float) -> Angle<Float> {
     Angle::from_radians(math::fit_into_range_inclusive(a, -1. ..=1., 0. ..=PI * 2.).unwrap())
 }
 
+/// -1 for food, 1 for bug, 0 for nothing
+fn seen_object_to_activation(object: Option<SeenObject>) -> Float {
+    match object {
+        Some(SeenObject::Food) => -1.,
+        Some(SeenObject::Bug) => 1.,
+        None => 0.,
+    }
+}
+
 fn delta_angle_to_activation(a: DeltaAngle<Float>) -> Float {
     math::fit_into_range(a.radians(), (-PI * 2.)..PI * 2., -1. ..1.).unwrap()
 }
@@ -43,29 +586,77 @@ fn relative_radius_to_activation(relative_radius: NoNeg<Float>) -> Float {
 }
 
 const MAX_RELATIVE_RADIUS: NoNeg<Float> = noneg_float(64.);
+const MAX_PHEROMONE_EMISSION_RATE: Float = 1.;
+pub(crate) const MAX_FEEDING_RATE: Float = 1.;
 const MIN_RELATIVE_RADIUS: NoNeg<Float> = noneg_float(0.);
 
 #[derive(Clone)]
 pub struct Brain {
-    net: Net<Float>,
+    /// Input layer to hidden layer
+    l0: PerceptronLayer,
+    /// Hidden layer to output layer
+    l1: PerceptronLayer,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FoodInfo {
     pub dst: NoNeg<Float>,
     pub direction: Angle<Float>,
     pub relative_radius: NoNeg<Float>,
+    #[serde(default)]
+    pub nutrients: Nutrients,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BugInfo {
     pub dst: NoNeg<Float>,
     pub direction: Angle<Float>,
     pub color: Color,
     pub relative_radius: NoNeg<Float>,
+    /// Brightness of glow (in range 0..=1)
+    #[serde(default)]
+    pub signal: Float,
 }
 
-#[derive(Debug, Clone)]
+/// Food item or bug seen besides the nearest one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeighborInfo {
+    pub dst: NoNeg<Float>,
+    pub direction: Angle<Float>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SeenObject {
+    Food,
+    Bug,
+}
+
+/// Nearest object seen in a retina sector
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RetinaHit {
+    pub object: SeenObject,
+    /// Effective distance for bugs (see `Bug::visibility`)
+    pub dst: NoNeg<Float>,
+}
+
+/// Summary of all bugs seen (not only of the nearest ones)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlockInfo {
+    pub count: usize,
+    /// Direction of the sum of headings (None if nothing is seen or headings cancel out)
+    pub heading: Option<Angle<Float>>,
+    /// Length of mean velocity vector
+    pub speed: Float,
+}
+
+/// Object overlapping body circle of bug (the deepest one if there are several)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Contact {
+    pub object: SeenObject,
+    pub direction: Angle<Float>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Input {
     pub energy_level: NoNeg<Float>,
     pub energy_capacity: NoNeg<Float>,
@@ -76,6 +667,39 @@ pub struct Input {
     pub vision_range: NoNeg<Float>,
     pub nearest_food: Option<FoodInfo>,
     pub nearest_bug: Option<BugInfo>,
+    #[serde(default)]
+    pub pheromone: PheromoneInfo,
+    #[serde(default)]
+    pub hearing: HearingInfo,
+    #[serde(default)]
+    pub water: WaterInfo,
+    /// Memory written by brain on the previous tick
+    #[serde(default)]
+    pub memory: [Float; MEMORY_SIZE],
+    /// Food items seen besides the nearest one sorted from near to far (at most
+    /// `FARTHER_NEIGHBOR_COUNT`)
+    #[serde(default)]
+    pub farther_food: Vec<NeighborInfo>,
+    /// Bugs seen besides the nearest one sorted from near to far (at most
+    /// `FARTHER_NEIGHBOR_COUNT`)
+    #[serde(default)]
+    pub farther_bugs: Vec<NeighborInfo>,
+    /// Nearest object of each retina sector, ordered from `rotation - vision_half_arc` to
+    /// `rotation + vision_half_arc`. None unless environment uses `VisionMode::Retina`
+    #[serde(default)]
+    pub retina: Option<[Option<RetinaHit>; RETINA_SECTORS]>,
+    #[serde(default)]
+    pub odor: OdorInfo,
+    #[serde(default)]
+    pub touch: Option<Contact>,
+    /// Strength of territory marker of another bug at own position (0 if there is none)
+    #[serde(default = "Zero::zero")]
+    pub foreign_territory: NoNeg<Float>,
+    #[serde(default)]
+    pub flock: FlockInfo,
+    /// Distance to the edge of bounded world along own rotation (None if it is not seen)
+    #[serde(default)]
+    pub wall_distance: Option<NoNeg<Float>>,
 }
 
 #[derive(Debug, Clone)]
@@ -88,82 +712,350 @@ pub struct Output {
     pub rotation_velocity: DeltaAngle<NoNeg<Float>>,
     /// energy per second
     pub baby_charging_rate: NoNeg<Float>,
+    /// stay buried (or burrow) if bug is able to dig
+    pub burrow: bool,
+    /// pheromone deposited at own position per second
+    pub pheromone_emission_rate: NoNeg<Float>,
+    /// loudness of sound pulse (0 - stay silent)
+    pub sound_loudness: NoNeg<Float>,
+    /// energy per second given to own offspring within eat range
+    pub feeding_rate: NoNeg<Float>,
+    /// inject venom into the nearest bug within eat range
+    pub sting: bool,
+    /// brightness of glow seen by other bugs (in range 0..=1)
+    pub signal: NoNeg<Float>,
+    /// fed back into brain on the next tick (each cell in range -1..=1)
+    pub memory: [Float; MEMORY_SIZE],
+    /// eat food within eat range
+    pub eat: bool,
+    /// bite off energy of the nearest bug within eat range
+    pub attack: bool,
+    /// deposit pheromone with `pheromone_emission_rate`
+    pub emit_pheromone: bool,
+    /// feed offspring with `feeding_rate`
+    pub feed_offspring: bool,
+}
+
+/// Input of brain together with its response. Used to catch silent behavioral changes of
+/// input normalization and of the net itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrainTestVector {
+    pub input: Input,
+    /// Normalized input (activations of input layer)
+    pub input_activations: [Float; INPUT_SIZE],
+    /// Activations of output layer
+    pub output_activations: [Float; OUTPUT_SIZE],
 }
 
 pub(crate) struct VerboseOutput {
     pub output: Output,
-    pub activations: ([Float; 16], [Float; 8], [Float; 8]),
+    pub activations: ([Float; INPUT_SIZE], Vec<Float>, [Float; OUTPUT_SIZE]),
 }
 
-impl From<Input> for [Float; 16] {
+impl From<Input> for [Float; INPUT_SIZE] {
     fn from(value: Input) -> Self {
-        [
-            (value.energy_level / value.energy_capacity).unwrap(),
-            value
-                .nearest_food
-                .as_ref()
+        let food = value.nearest_food.as_ref();
+        let bug = value.nearest_bug.as_ref();
+        let proximity = |x: Option<&NeighborInfo>| {
+            x.map(|x| (x.dst / value.vision_range).unwrap())
+                .unwrap_or(1.)
+        };
+        let direction = |x: Option<&NeighborInfo>| {
+            x.map(|x| delta_angle_to_activation(x.direction.signed_distance(value.rotation)))
+                .unwrap_or(0.)
+        };
+        let sector = |i: usize| value.retina.as_ref().and_then(|retina| retina[i].as_ref());
+        let sector_proximity = |i: usize| {
+            sector(i)
                 .map(|x| (x.dst / value.vision_range).unwrap())
-                .unwrap_or(1.),
-            value
-                .nearest_food
-                .as_ref()
-                .map(|d| delta_angle_to_activation(d.direction.signed_distance(value.rotation)))
-                .unwrap_or(0.),
-            value
-                .nearest_food
-                .map(|x| relative_radius_to_activation(x.relative_radius))
-                .unwrap_or(1.),
-            value.age.unwrap(),
-            value
-                .nearest_bug
-                .as_ref()
-                .map(|p| (p.dst / value.vision_range).unwrap())
-                .unwrap_or(1.),
-            value
-                .nearest_bug
-                .as_ref()
-                .map(|d| delta_angle_to_activation(d.direction.signed_distance(value.rotation)))
+                .unwrap_or(1.)
+        };
+        let sector_object = |i: usize| seen_object_to_activation(sector(i).map(|x| x.object));
+        InputVector::default()
+            .energy_ratio((value.energy_level / value.energy_capacity).unwrap())
+            .food_proximity(
+                food.map(|x| (x.dst / value.vision_range).unwrap())
+                    .unwrap_or(1.),
+            )
+            .food_direction(
+                food.map(|x| {
+                    delta_angle_to_activation(x.direction.signed_distance(value.rotation))
+                })
                 .unwrap_or(0.),
-            value.nearest_bug.as_ref().map(|x| x.color.a).unwrap_or(0.),
-            value.nearest_bug.as_ref().map(|x| x.color.r).unwrap_or(0.),
-            value.nearest_bug.as_ref().map(|x| x.color.g).unwrap_or(0.),
-            value.nearest_bug.as_ref().map(|x| x.color.b).unwrap_or(0.),
-            value
-                .nearest_bug
-                .map(|x| relative_radius_to_activation(x.relative_radius))
-                .unwrap_or(1.),
-            value.baby_charge_level.unwrap() / value.baby_charge_capacity.unwrap(),
-            0.,
-            0.,
-            0.,
-        ]
-        .required_to_be_in_range(-1. ..=1.)
-        .unwrap()
-    }
-}
-
-impl From<Arr<Float, 8>> for Output {
-    fn from(value: Arr<Float, 8>) -> Self {
+            )
+            .food_relative_radius(
+                food.map(|x| relative_radius_to_activation(x.relative_radius))
+                    .unwrap_or(1.),
+            )
+            .age(value.age.unwrap())
+            .bug_proximity(
+                bug.map(|x| (x.dst / value.vision_range).unwrap())
+                    .unwrap_or(1.),
+            )
+            .bug_direction(
+                bug.map(|x| delta_angle_to_activation(x.direction.signed_distance(value.rotation)))
+                    .unwrap_or(0.),
+            )
+            .bug_alpha(bug.map(|x| x.color.a).unwrap_or(0.))
+            .bug_red(bug.map(|x| x.color.r).unwrap_or(0.))
+            .bug_green(bug.map(|x| x.color.g).unwrap_or(0.))
+            .bug_blue(bug.map(|x| x.color.b).unwrap_or(0.))
+            .bug_relative_radius(
+                bug.map(|x| relative_radius_to_activation(x.relative_radius))
+                    .unwrap_or(1.),
+            )
+            .baby_charge_ratio(
+                value.baby_charge_level.unwrap() / value.baby_charge_capacity.unwrap(),
+            )
+            .pheromone_concentration({
+                let c = value.pheromone.concentration.unwrap();
+                c / (c + 1.)
+            })
+            .pheromone_direction(
+                value
+                    .pheromone
+                    .gradient_direction
+                    .map(|x| delta_angle_to_activation(x.signed_distance(value.rotation)))
+                    .unwrap_or(0.),
+            )
+            .hearing_intensity(value.hearing.intensity.unwrap())
+            .hearing_direction(
+                value
+                    .hearing
+                    .direction
+                    .map(|x| delta_angle_to_activation(x.signed_distance(value.rotation)))
+                    .unwrap_or(0.),
+            )
+            .hearing_frequency(value.hearing.frequency)
+            .in_water(if value.water.in_water { 1. } else { 0. })
+            .shore_proximity(value.water.shore_proximity())
+            .food_protein(food.map(|x| x.nutrients.protein).unwrap_or(0.))
+            .food_carbs(food.map(|x| x.nutrients.carbs).unwrap_or(0.))
+            .food_fat(food.map(|x| x.nutrients.fat).unwrap_or(0.))
+            .bug_signal(bug.map(|x| x.signal).unwrap_or(0.))
+            .memory0(value.memory[0])
+            .memory1(value.memory[1])
+            .food2_proximity(proximity(value.farther_food.get(0)))
+            .food2_direction(direction(value.farther_food.get(0)))
+            .food3_proximity(proximity(value.farther_food.get(1)))
+            .food3_direction(direction(value.farther_food.get(1)))
+            .bug2_proximity(proximity(value.farther_bugs.get(0)))
+            .bug2_direction(direction(value.farther_bugs.get(0)))
+            .bug3_proximity(proximity(value.farther_bugs.get(1)))
+            .bug3_direction(direction(value.farther_bugs.get(1)))
+            .sector0_proximity(sector_proximity(0))
+            .sector0_object(sector_object(0))
+            .sector1_proximity(sector_proximity(1))
+            .sector1_object(sector_object(1))
+            .sector2_proximity(sector_proximity(2))
+            .sector2_object(sector_object(2))
+            .sector3_proximity(sector_proximity(3))
+            .sector3_object(sector_object(3))
+            .odor_intensity({
+                let i = value.odor.intensity.unwrap();
+                i / (i + 1.)
+            })
+            .odor_direction(
+                value
+                    .odor
+                    .gradient_direction
+                    .map(|x| delta_angle_to_activation(x.signed_distance(value.rotation)))
+                    .unwrap_or(0.),
+            )
+            .touch_object(seen_object_to_activation(value.touch.map(|x| x.object)))
+            .touch_direction(
+                value
+                    .touch
+                    .map(|x| delta_angle_to_activation(x.direction.signed_distance(value.rotation)))
+                    .unwrap_or(0.),
+            )
+            .foreign_territory({
+                let s = value.foreign_territory.unwrap();
+                s / (s + 1.)
+            })
+            .flock_size(value.flock.count as Float / (value.flock.count as Float + 1.))
+            .flock_heading(
+                value
+                    .flock
+                    .heading
+                    .map(|x| delta_angle_to_activation(x.signed_distance(value.rotation)))
+                    .unwrap_or(0.),
+            )
+            .flock_speed((value.flock.speed / MAX_VELOCITY).min(1.))
+            .wall_proximity(
+                value
+                    .wall_distance
+                    .map(|x| (x / value.vision_range).unwrap().min(1.))
+                    .unwrap_or(1.),
+            )
+            .build()
+            .required_to_be_in_range(-1. ..=1.)
+            .unwrap()
+    }
+}
+
+impl Input {
+    /// Arbitrary input which is valid for the brain (all ratios are in range 0..=1)
+    pub fn random<R: RngCore>(rng: &mut R) -> Self {
+        let noneg = |value: Float| NoNeg::wrap(value).unwrap();
+        let energy_capacity = rng.gen_range(1. ..100.);
+        let baby_charge_capacity = rng.gen_range(1. ..100.);
+        let vision_range = rng.gen_range(1. ..200.);
+        let random_neighbors = |rng: &mut R| -> Vec<NeighborInfo> {
+            (0..rng.gen_range(0..=FARTHER_NEIGHBOR_COUNT))
+                .map(|_| NeighborInfo {
+                    dst: noneg(rng.gen_range(0. ..=vision_range)),
+                    direction: Angle::from_radians(rng.gen_range(0. ..PI * 2.)),
+                })
+                .collect()
+        };
+        let nearest_food = rng.gen_bool(0.5).then(|| FoodInfo {
+            dst: noneg(rng.gen_range(0. ..=vision_range)),
+            direction: Angle::from_radians(rng.gen_range(0. ..PI * 2.)),
+            relative_radius: noneg(rng.gen_range(0. ..MAX_RELATIVE_RADIUS.unwrap())),
+            nutrients: Nutrients::new(
+                rng.gen_range(0. ..=1.),
+                rng.gen_range(0. ..=1.),
+                rng.gen_range(0. ..=1.),
+            ),
+        });
+        let nearest_bug = rng.gen_bool(0.5).then(|| BugInfo {
+            dst: noneg(rng.gen_range(0. ..=vision_range)),
+            direction: Angle::from_radians(rng.gen_range(0. ..PI * 2.)),
+            color: Color {
+                a: rng.gen_range(0. ..=1.),
+                r: rng.gen_range(0. ..=1.),
+                g: rng.gen_range(0. ..=1.),
+                b: rng.gen_range(0. ..=1.),
+            },
+            relative_radius: noneg(rng.gen_range(0. ..MAX_RELATIVE_RADIUS.unwrap())),
+            signal: rng.gen_range(0. ..=1.),
+        });
+        Self {
+            energy_level: noneg(rng.gen_range(0. ..=energy_capacity)),
+            energy_capacity: noneg(energy_capacity),
+            rotation: Angle::from_radians(rng.gen_range(0. ..PI * 2.)),
+            age: noneg(rng.gen_range(0. ..=1.)),
+            baby_charge_level: noneg(rng.gen_range(0. ..=baby_charge_capacity)),
+            baby_charge_capacity: noneg(baby_charge_capacity),
+            vision_range: noneg(vision_range),
+            nearest_food,
+            nearest_bug,
+            pheromone: PheromoneInfo {
+                concentration: noneg(rng.gen_range(0. ..10.)),
+                gradient_direction: rng
+                    .gen_bool(0.5)
+                    .then(|| Angle::from_radians(rng.gen_range(0. ..PI * 2.))),
+            },
+            hearing: if rng.gen_bool(0.5) {
+                HearingInfo {
+                    intensity: noneg(rng.gen_range(0. ..=1.)),
+                    direction: Some(Angle::from_radians(rng.gen_range(0. ..PI * 2.))),
+                    frequency: rng.gen_range(0. ..=1.),
+                }
+            } else {
+                HearingInfo::default()
+            },
+            water: if rng.gen_bool(0.5) {
+                WaterInfo {
+                    in_water: rng.gen_bool(0.5),
+                    shore_distance: Some(noneg(rng.gen_range(0. ..1000.))),
+                }
+            } else {
+                WaterInfo::default()
+            },
+            memory: [rng.gen_range(-1. ..=1.), rng.gen_range(-1. ..=1.)],
+            farther_food: random_neighbors(rng),
+            farther_bugs: random_neighbors(rng),
+            retina: rng.gen_bool(0.5).then(|| {
+                std::array::from_fn(|_| {
+                    rng.gen_bool(0.5).then(|| RetinaHit {
+                        object: if rng.gen_bool(0.5) {
+                            SeenObject::Food
+                        } else {
+                            SeenObject::Bug
+                        },
+                        dst: noneg(rng.gen_range(0. ..=vision_range)),
+                    })
+                })
+            }),
+            odor: OdorInfo {
+                intensity: noneg(rng.gen_range(0. ..10.)),
+                gradient_direction: rng
+                    .gen_bool(0.5)
+                    .then(|| Angle::from_radians(rng.gen_range(0. ..PI * 2.))),
+            },
+            touch: rng.gen_bool(0.5).then(|| Contact {
+                object: if rng.gen_bool(0.5) {
+                    SeenObject::Food
+                } else {
+                    SeenObject::Bug
+                },
+                direction: Angle::from_radians(rng.gen_range(0. ..PI * 2.)),
+            }),
+            foreign_territory: noneg(rng.gen_range(0. ..10.)),
+            flock: FlockInfo {
+                count: rng.gen_range(0..10),
+                heading: rng
+                    .gen_bool(0.5)
+                    .then(|| Angle::from_radians(rng.gen_range(0. ..PI * 2.))),
+                speed: rng.gen_range(0. ..MAX_VELOCITY * 2.),
+            },
+            wall_distance: rng
+                .gen_bool(0.5)
+                .then(|| noneg(rng.gen_range(0. ..vision_range))),
+        }
+    }
+}
+
+impl From<[Float; OUTPUT_SIZE]> for Output {
+    fn from(value: [Float; OUTPUT_SIZE]) -> Self {
         Self {
-            velocity: value[0] * 10.,
+            velocity: value[0] * MAX_VELOCITY,
             relative_desired_rotation: activation_to_delta_angle(value[1]),
             rotation_velocity: activation_to_noneg_delta_angle(value[2]),
             baby_charging_rate: NoNeg::wrap(
                 math::fit_into_range_inclusive(value[3].abs(), 0. ..=1., 0. ..=10.).unwrap(),
             )
             .unwrap(),
+            burrow: value[4] > 0.5,
+            pheromone_emission_rate: NoNeg::wrap(
+                math::fit_into_range_inclusive(
+                    value[5].abs(),
+                    0. ..=1.,
+                    0. ..=MAX_PHEROMONE_EMISSION_RATE,
+                )
+                .unwrap(),
+            )
+            .unwrap(),
+            sound_loudness: NoNeg::wrap(value[6].clamp(0., 1.)).unwrap(),
+            feeding_rate: NoNeg::wrap(value[7].clamp(0., 1.) * MAX_FEEDING_RATE).unwrap(),
+            sting: value[8] > 0.5,
+            signal: NoNeg::wrap(value[9].clamp(0., 1.)).unwrap(),
+            memory: std::array::from_fn(|i| value[MEMORY_OUTPUT_START + i].clamp(-1., 1.)),
+            // actions which used to be automatic are switched off only by a strong negative
+            // activation, so brains without actuator genes (zero activation) keep doing them
+            eat: value[12] > -0.5,
+            attack: value[13] > 0.5,
+            emit_pheromone: value[14] > -0.5,
+            feed_offspring: value[15] > -0.5,
         }
     }
 }
 
 impl Brain {
-    pub fn layers(
-        &self,
-    ) -> (
-        &PerceptronLayer<Float, 16, 8>,
-        &PerceptronLayer<Float, 8, 8>,
-    ) {
-        (&self.net.l0, &self.net.l1)
+    pub fn layers(&self) -> (&PerceptronLayer, &PerceptronLayer) {
+        (&self.l0, &self.l1)
+    }
+
+    /// Number of neurons in hidden layer (in range `MIN_HIDDEN_SIZE..=MAX_HIDDEN_SIZE`)
+    pub fn hidden_size(&self) -> usize {
+        self.l0.output_size()
+    }
+
+    /// Brain built from brain genes of a bug genome
+    pub fn from_genome(chromosome: &Chromosome<Float>) -> Self {
+        Self::new(chromosome, BRAIN_GENES)
     }
 
     pub(crate) fn new<R: Into<Range<usize>>>(chromosome: &Chromosome<Float>, range: R) -> Self {
@@ -177,46 +1069,399 @@ impl Brain {
         let l0b_genes = &genes[192..200];
         let l1b_genes = &genes[200..208];
 
-        let net: Net<f64> = Net::new(
-            [
-                (l0w_genes[000..016].try_into().unwrap(), l0b_genes[0]).into(),
-                (l0w_genes[016..032].try_into().unwrap(), l0b_genes[1]).into(),
-                (l0w_genes[032..048].try_into().unwrap(), l0b_genes[2]).into(),
-                (l0w_genes[048..064].try_into().unwrap(), l0b_genes[3]).into(),
-                (l0w_genes[064..080].try_into().unwrap(), l0b_genes[4]).into(),
-                (l0w_genes[080..096].try_into().unwrap(), l0b_genes[5]).into(),
-                (l0w_genes[096..112].try_into().unwrap(), l0b_genes[6]).into(),
-                (l0w_genes[112..128].try_into().unwrap(), l0b_genes[7]).into(),
-            ]
-            .into(),
-            [
-                (l1w_genes[00..08].try_into().unwrap(), l1b_genes[0]).into(),
-                (l1w_genes[08..16].try_into().unwrap(), l1b_genes[1]).into(),
-                (l1w_genes[16..24].try_into().unwrap(), l1b_genes[2]).into(),
-                (l1w_genes[24..32].try_into().unwrap(), l1b_genes[3]).into(),
-                (l1w_genes[32..40].try_into().unwrap(), l1b_genes[4]).into(),
-                (l1w_genes[40..48].try_into().unwrap(), l1b_genes[5]).into(),
-                (l1w_genes[48..56].try_into().unwrap(), l1b_genes[6]).into(),
-                (l1w_genes[56..64].try_into().unwrap(), l1b_genes[7]).into(),
-            ]
-            .into(),
+        let l0w_extra_genes = &chromosome.genes[BRAIN_EXTRA_WEIGHT_GENES];
+        let l0w_second_extra_genes = &chromosome.genes[BRAIN_SECOND_EXTRA_WEIGHT_GENES];
+        // genomes saved before these genes were added ignore the inputs
+        let l0w_third_extra_genes = chromosome
+            .genes
+            .get(BRAIN_THIRD_EXTRA_WEIGHT_GENES)
+            .unwrap_or(&[0.; 8 * THIRD_EXTRA_INPUT_SIZE]);
+        let l0w_signal_genes = chromosome
+            .genes
+            .get(BRAIN_SIGNAL_INPUT_GENES)
+            .unwrap_or(&[0.; 8 * SIGNAL_INPUT_SIZE]);
+        let l0w_memory_genes = chromosome
+            .genes
+            .get(BRAIN_MEMORY_INPUT_GENES)
+            .unwrap_or(&[0.; 8 * MEMORY_INPUT_SIZE]);
+        let l0w_neighbor_genes = chromosome
+            .genes
+            .get(BRAIN_NEIGHBOR_INPUT_GENES)
+            .unwrap_or(&[0.; MAX_HIDDEN_SIZE * NEIGHBOR_INPUT_SIZE]);
+        let l0w_retina_genes = chromosome
+            .genes
+            .get(BRAIN_RETINA_INPUT_GENES)
+            .unwrap_or(&[0.; MAX_HIDDEN_SIZE * RETINA_INPUT_SIZE]);
+        let l0w_odor_genes = chromosome
+            .genes
+            .get(BRAIN_ODOR_INPUT_GENES)
+            .unwrap_or(&[0.; MAX_HIDDEN_SIZE * ODOR_INPUT_SIZE]);
+        let l0w_touch_genes = chromosome
+            .genes
+            .get(BRAIN_TOUCH_INPUT_GENES)
+            .unwrap_or(&[0.; MAX_HIDDEN_SIZE * TOUCH_INPUT_SIZE]);
+        let l0w_territory_genes = chromosome
+            .genes
+            .get(BRAIN_TERRITORY_INPUT_GENES)
+            .unwrap_or(&[0.; MAX_HIDDEN_SIZE * TERRITORY_INPUT_SIZE]);
+        let l0w_flock_genes = chromosome
+            .genes
+            .get(BRAIN_FLOCK_INPUT_GENES)
+            .unwrap_or(&[0.; MAX_HIDDEN_SIZE * FLOCK_INPUT_SIZE]);
+        let l0w_wall_genes = chromosome
+            .genes
+            .get(BRAIN_WALL_INPUT_GENES)
+            .unwrap_or(&[0.; MAX_HIDDEN_SIZE * WALL_INPUT_SIZE]);
+        let l0_weights = |j: usize| -> [Float; INPUT_SIZE] {
+            let mut weights = [0.; INPUT_SIZE];
+            let (base, rest) = weights.split_at_mut(BASE_INPUT_SIZE);
+            let (extra, rest) = rest.split_at_mut(EXTRA_INPUT_SIZE);
+            let (second_extra, rest) = rest.split_at_mut(SECOND_EXTRA_INPUT_SIZE);
+            let (third_extra, rest) = rest.split_at_mut(THIRD_EXTRA_INPUT_SIZE);
+            let (signal, rest) = rest.split_at_mut(SIGNAL_INPUT_SIZE);
+            let (memory, _) = rest.split_at_mut(MEMORY_INPUT_SIZE);
+            base.copy_from_slice(&l0w_genes[j * BASE_INPUT_SIZE..(j + 1) * BASE_INPUT_SIZE]);
+            extra.copy_from_slice(
+                &l0w_extra_genes[j * EXTRA_INPUT_SIZE..(j + 1) * EXTRA_INPUT_SIZE],
+            );
+            second_extra.copy_from_slice(
+                &l0w_second_extra_genes
+                    [j * SECOND_EXTRA_INPUT_SIZE..(j + 1) * SECOND_EXTRA_INPUT_SIZE],
+            );
+            third_extra.copy_from_slice(
+                &l0w_third_extra_genes
+                    [j * THIRD_EXTRA_INPUT_SIZE..(j + 1) * THIRD_EXTRA_INPUT_SIZE],
+            );
+            signal.copy_from_slice(
+                &l0w_signal_genes[j * SIGNAL_INPUT_SIZE..(j + 1) * SIGNAL_INPUT_SIZE],
+            );
+            memory.copy_from_slice(
+                &l0w_memory_genes[j * MEMORY_INPUT_SIZE..(j + 1) * MEMORY_INPUT_SIZE],
+            );
+            weights
+        };
+        // genomes saved before the sting output was added never sting
+        let l1_sting_genes = chromosome
+            .genes
+            .get(BRAIN_STING_GENES)
+            .unwrap_or(&[0.; 8 + 1]);
+        let l1_signal_genes = chromosome
+            .genes
+            .get(BRAIN_SIGNAL_OUTPUT_GENES)
+            .unwrap_or(&[0.; 8 + 1]);
+        let l1_memory_genes = chromosome
+            .genes
+            .get(BRAIN_MEMORY_OUTPUT_GENES)
+            .unwrap_or(&[0.; (8 + 1) * MEMORY_SIZE]);
+
+        // genomes saved before hidden layer became evolvable have the base width
+        let hidden_size = chromosome
+            .genes
+            .get(BRAIN_HIDDEN_SIZE_GENES.start)
+            .map(|gene| {
+                (BASE_HIDDEN_SIZE as Float + gene * 8.)
+                    .round()
+                    .clamp(MIN_HIDDEN_SIZE as Float, MAX_HIDDEN_SIZE as Float)
+                    as usize
+            })
+            .unwrap_or(BASE_HIDDEN_SIZE);
+        let extra_hidden_genes = chromosome
+            .genes
+            .get(BRAIN_EXTRA_HIDDEN_GENES)
+            .unwrap_or(&[0.; (MAX_HIDDEN_SIZE - BASE_HIDDEN_SIZE) * EXTRA_HIDDEN_NEURON_GENES]);
+        let actuator_genes = chromosome
+            .genes
+            .get(BRAIN_ACTUATOR_GENES)
+            .unwrap_or(&[0.; (OUTPUT_SIZE - ACTUATOR_OUTPUT_START) * ACTUATOR_GENES]);
+        let extra_hidden = |h: usize| {
+            let start = (h - BASE_HIDDEN_SIZE) * EXTRA_HIDDEN_NEURON_GENES;
+            &extra_hidden_genes[start..start + EXTRA_HIDDEN_NEURON_GENES]
+        };
+
+        let l0 = PerceptronLayer::new(
+            INPUT_SIZE,
+            (0..hidden_size)
+                .map(|h| {
+                    let (mut weights, bias) = if h < BASE_HIDDEN_SIZE {
+                        (l0_weights(h), l0b_genes[h])
+                    } else {
+                        let genes = extra_hidden(h);
+                        let mut weights = [0.; INPUT_SIZE];
+                        weights[..EXTRA_HIDDEN_INPUT_SIZE]
+                            .copy_from_slice(&genes[..EXTRA_HIDDEN_INPUT_SIZE]);
+                        (weights, genes[EXTRA_HIDDEN_INPUT_SIZE])
+                    };
+                    weights[NEIGHBOR_INPUT_START..RETINA_INPUT_START].copy_from_slice(
+                        &l0w_neighbor_genes[h * NEIGHBOR_INPUT_SIZE..(h + 1) * NEIGHBOR_INPUT_SIZE],
+                    );
+                    weights[RETINA_INPUT_START..ODOR_INPUT_START].copy_from_slice(
+                        &l0w_retina_genes[h * RETINA_INPUT_SIZE..(h + 1) * RETINA_INPUT_SIZE],
+                    );
+                    weights[ODOR_INPUT_START..TOUCH_INPUT_START].copy_from_slice(
+                        &l0w_odor_genes[h * ODOR_INPUT_SIZE..(h + 1) * ODOR_INPUT_SIZE],
+                    );
+                    weights[TOUCH_INPUT_START..TERRITORY_INPUT_START].copy_from_slice(
+                        &l0w_touch_genes[h * TOUCH_INPUT_SIZE..(h + 1) * TOUCH_INPUT_SIZE],
+                    );
+                    weights[TERRITORY_INPUT_START..FLOCK_INPUT_START].copy_from_slice(
+                        &l0w_territory_genes
+                            [h * TERRITORY_INPUT_SIZE..(h + 1) * TERRITORY_INPUT_SIZE],
+                    );
+                    weights[FLOCK_INPUT_START..WALL_INPUT_START].copy_from_slice(
+                        &l0w_flock_genes[h * FLOCK_INPUT_SIZE..(h + 1) * FLOCK_INPUT_SIZE],
+                    );
+                    weights[WALL_INPUT_START..].copy_from_slice(
+                        &l0w_wall_genes[h * WALL_INPUT_SIZE..(h + 1) * WALL_INPUT_SIZE],
+                    );
+                    Perceptron::new(weights.to_vec(), bias)
+                })
+                .collect(),
+        );
+
+        let l1 = PerceptronLayer::new(
+            hidden_size,
+            (0..OUTPUT_SIZE)
+                .map(|j| {
+                    if j >= ACTUATOR_OUTPUT_START {
+                        let start = (j - ACTUATOR_OUTPUT_START) * ACTUATOR_GENES;
+                        let genes = &actuator_genes[start..start + ACTUATOR_GENES];
+                        return Perceptron::new(
+                            genes[..hidden_size].to_vec(),
+                            genes[MAX_HIDDEN_SIZE],
+                        );
+                    }
+                    // weights of base hidden neurons and bias
+                    let (base_weights, bias) = match j {
+                        0..=7 => (&l1w_genes[j * 8..(j + 1) * 8], l1b_genes[j]),
+                        8 => (&l1_sting_genes[0..8], l1_sting_genes[8]),
+                        9 => (&l1_signal_genes[0..8], l1_signal_genes[8]),
+                        _ => {
+                            let genes = &l1_memory_genes[(j - MEMORY_OUTPUT_START) * (8 + 1)..];
+                            (&genes[0..8], genes[8])
+                        }
+                    };
+                    let weights = (0..hidden_size)
+                        .map(|h| {
+                            if h < BASE_HIDDEN_SIZE {
+                                base_weights[h]
+                            } else {
+                                extra_hidden(h)[EXTRA_HIDDEN_INPUT_SIZE + 1 + j]
+                            }
+                        })
+                        .collect();
+                    Perceptron::new(weights, bias)
+                })
+                .collect(),
         );
 
-        Brain { net }
+        Brain { l0, l1 }
     }
 
+    /// Evaluated on CPU right after bug senses its surroundings, so brains of all bugs are never
+    /// batched (e.g. for a GPU pass)
     pub(crate) fn proceed(&self, input: Input) -> Output {
-        self.net.proceed(&input.into(), normalizers::sigmoid).into()
+        let i: [Float; INPUT_SIZE] = input.into();
+        let r0 = self.l0.proceed(&i, normalizers::sigmoid);
+        let r1 = self.l1.proceed(&r0, normalizers::sigmoid);
+        <[Float; OUTPUT_SIZE]>::try_from(r1).unwrap().into()
     }
 
     pub(crate) fn proceed_verbosely(&self, input: Input) -> VerboseOutput {
-        let i = input.into();
-        let (r0, r1) = self
-            .net
-            .proceed_verbosely(&i, |x| normalizers::sigmoid(x) * 2. - 1.);
+        let i: [Float; INPUT_SIZE] = input.into();
+        let normalizer = |x: Float| normalizers::sigmoid(x) * 2. - 1.;
+        let r0 = self.l0.proceed(&i, normalizer);
+        let r1 = <[Float; OUTPUT_SIZE]>::try_from(self.l1.proceed(&r0, normalizer)).unwrap();
         VerboseOutput {
-            output: r1.clone().into(),
-            activations: (i, *r0, *r1),
+            output: r1.into(),
+            activations: (i, r0, r1),
+        }
+    }
+
+    /// Record response to each of `inputs` (as bug would compute it during simulation)
+    pub fn test_vectors(&self, inputs: impl IntoIterator<Item = Input>) -> Vec<BrainTestVector> {
+        inputs
+            .into_iter()
+            .map(|input| {
+                let (input_activations, _, output_activations) =
+                    self.proceed_verbosely(input.clone()).activations;
+                BrainTestVector {
+                    input,
+                    input_activations,
+                    output_activations,
+                }
+            })
+            .collect()
+    }
+
+    /// Test vectors of `count` random inputs
+    pub fn random_test_vectors<R: RngCore>(
+        &self,
+        count: usize,
+        rng: &mut R,
+    ) -> Vec<BrainTestVector> {
+        self.test_vectors((0..count).map(|_| Input::random(rng)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chromosome::Chromosome;
+    use rand::SeedableRng as _;
+    use rand_pcg::Pcg64;
+
+    use crate::chromo_utils::{
+        BRAIN_HIDDEN_SIZE_GENES, BRAIN_MEMORY_INPUT_GENES, BRAIN_NEIGHBOR_INPUT_GENES,
+        BRAIN_SIGNAL_INPUT_GENES, BRAIN_THIRD_EXTRA_WEIGHT_GENES, GENOME_LEN,
+    };
+
+    use super::{
+        Brain, BrainTestVector, InputSlot, InputVector, MAX_HIDDEN_SIZE, MEMORY_OUTPUT_START,
+        MEMORY_SIZE, MIN_HIDDEN_SIZE, NEIGHBOR_INPUT_START, RETINA_INPUT_START,
+    };
+
+    /// Regenerate vectors after intended behavioral change of brain
+    const BLESS_ENV_VAR: &str = "BUGS_BLESS_BRAIN_VECTORS";
+
+    fn vectors_path() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_data/brain_test_vectors.json")
+    }
+
+    /// Genome which does not depend on implementation of random chromosome generation
+    fn genome() -> Chromosome<f64> {
+        Chromosome {
+            genes: (0..GENOME_LEN)
+                .map(|i| ((i * 37) % 101) as f64 / 50. - 1.)
+                .collect(),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "is not set")]
+    fn input_vector_requires_all_active_slots() {
+        let _ = InputVector::default().energy_ratio(0.5).age(0.1).build();
+    }
+
+    #[test]
+    fn genomes_without_nutrient_weights_ignore_nutrient_inputs() {
+        let mut short = genome();
+        short.genes.truncate(BRAIN_THIRD_EXTRA_WEIGHT_GENES.start);
+        let brain = Brain::from_genome(&short);
+        let (l0, _) = brain.layers();
+        for slot in [
+            InputSlot::FoodProtein,
+            InputSlot::FoodCarbs,
+            InputSlot::FoodFat,
+        ] {
+            assert!(l0
+                .perceptrons()
+                .iter()
+                .all(|perceptron| perceptron.weights()[slot.index()] == 0.));
+        }
+    }
+
+    #[test]
+    fn genomes_without_signal_genes_ignore_signal_and_never_glow() {
+        let mut short = genome();
+        short.genes.truncate(BRAIN_SIGNAL_INPUT_GENES.start);
+        let brain = Brain::from_genome(&short);
+        let (l0, l1) = brain.layers();
+        assert!(l0
+            .perceptrons()
+            .iter()
+            .all(|perceptron| perceptron.weights()[InputSlot::BugSignal.index()] == 0.));
+        let signal = &l1.perceptrons()[9];
+        assert!(signal.weights().iter().all(|weight| *weight == 0.));
+        assert_eq!(signal.bias(), 0.);
+    }
+
+    #[test]
+    fn genomes_without_memory_genes_remember_nothing() {
+        let mut short = genome();
+        short.genes.truncate(BRAIN_MEMORY_INPUT_GENES.start);
+        let brain = Brain::from_genome(&short);
+        let (l0, l1) = brain.layers();
+        for slot in [InputSlot::Memory0, InputSlot::Memory1] {
+            assert!(l0
+                .perceptrons()
+                .iter()
+                .all(|perceptron| perceptron.weights()[slot.index()] == 0.));
+        }
+        for memory in &l1.perceptrons()[MEMORY_OUTPUT_START..MEMORY_OUTPUT_START + MEMORY_SIZE] {
+            assert!(memory.weights().iter().all(|weight| *weight == 0.));
+            assert_eq!(memory.bias(), 0.);
+        }
+    }
+
+    #[test]
+    fn hidden_layer_size_is_set_by_gene() {
+        let mut short = genome();
+        short.genes.truncate(BRAIN_HIDDEN_SIZE_GENES.start);
+        assert_eq!(Brain::from_genome(&short).hidden_size(), 8);
+
+        for (gene, size) in [(-1., MIN_HIDDEN_SIZE), (0.25, 10), (1., MAX_HIDDEN_SIZE)] {
+            let mut genome = genome();
+            genome.genes[BRAIN_HIDDEN_SIZE_GENES.start] = gene;
+            let brain = Brain::from_genome(&genome);
+            let (l0, l1) = brain.layers();
+            assert_eq!(brain.hidden_size(), size);
+            assert_eq!(l0.output_size(), size);
+            assert_eq!(l1.input_size(), size);
+            let log = brain.proceed_verbosely(super::Input::random(&mut Pcg64::seed_from_u64(0)));
+            assert_eq!(log.activations.1.len(), size);
+        }
+    }
+
+    #[test]
+    fn genomes_without_neighbor_genes_ignore_farther_food_and_bugs() {
+        let mut short = genome();
+        short.genes.truncate(BRAIN_NEIGHBOR_INPUT_GENES.start);
+        short.genes[BRAIN_HIDDEN_SIZE_GENES.start] = 1.;
+        let brain = Brain::from_genome(&short);
+        let (l0, _) = brain.layers();
+        assert_eq!(l0.output_size(), MAX_HIDDEN_SIZE);
+        for perceptron in l0.perceptrons() {
+            assert!(
+                perceptron.weights()[NEIGHBOR_INPUT_START..RETINA_INPUT_START]
+                    .iter()
+                    .all(|weight| *weight == 0.)
+            );
+        }
+    }
+
+    /// Vectors are written on the first run (or when `BLESS_ENV_VAR` is set) and must be
+    /// committed. Afterwards every run replays their inputs and compares responses
+    #[test]
+    fn brain_responses_match_recorded_vectors() {
+        let brain = Brain::from_genome(&genome());
+        let path = vectors_path();
+        if std::env::var_os(BLESS_ENV_VAR).is_some() || !path.exists() {
+            let vectors = brain.random_test_vectors(64, &mut Pcg64::seed_from_u64(0));
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, serde_json::to_string_pretty(&vectors).unwrap()).unwrap();
+            return;
+        }
+
+        let recorded: Vec<BrainTestVector> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let actual = brain.test_vectors(recorded.iter().map(|vector| vector.input.clone()));
+        for (i, (recorded, actual)) in recorded.iter().zip(&actual).enumerate() {
+            let close = |a: &[f64], b: &[f64]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9);
+            assert!(
+                close(&recorded.input_activations, &actual.input_activations),
+                "input normalization of vector {} changed: {:?} != {:?}",
+                i,
+                recorded.input_activations,
+                actual.input_activations
+            );
+            assert!(
+                close(&recorded.output_activations, &actual.output_activations),
+                "response of vector {} changed: {:?} != {:?}",
+                i,
+                recorded.output_activations,
+                actual.output_activations
+            );
         }
     }
 }
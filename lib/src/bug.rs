@@ -1,6 +1,12 @@
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::{cell::Ref, error::Error, f64::consts::PI, fmt::Display, time::Duration};
+use std::{
+    cell::Ref,
+    error::Error,
+    f64::consts::PI,
+    fmt::Display,
+    time::{Duration, Instant},
+};
 
 use chromosome::Chromosome;
 use rand::Rng;
@@ -9,16 +15,94 @@ use serde::{Deserialize, Serialize};
 
 const EAT_FOOD_MAX_PROXIMITY: NoNeg<Float> = noneg_float(20.);
 
-use crate::chunk::Position;
+/// How much energy bug loses per unit of toxic energy eaten
+const TOXIN_POTENCY: NoNeg<Float> = noneg_float(2.);
+
+/// Bugs with digging ability lower than this can not burrow
+const MIN_DIGGING_ABILITY: NoNeg<Float> = noneg_float(0.1);
+
+/// Energy per second per size spent while buried (divided by digging ability)
+const BURIED_ENERGY_COST: NoNeg<Float> = noneg_float(0.01);
+
+/// Energy spent on a sound pulse of max loudness
+const SOUND_PULSE_ENERGY_COST: NoNeg<Float> = noneg_float(0.05);
+
+/// Min time between two sound pulses of one bug
+const SOUND_PULSE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Energy per second per size spent just to stay alive (multiplied by basal metabolism)
+const BASAL_ENERGY_COST: NoNeg<Float> = noneg_float(0.002);
+
+/// Bounds of basal metabolism. It scales both idle energy drain and speed of movement and
+/// rotation, 1 is speed of bugs from before metabolism was introduced
+const MIN_BASAL_METABOLISM: Float = 0.5;
+const MAX_BASAL_METABOLISM: Float = 2.;
+
+/// Energy per second per size moved from stomach to energy store (multiplied by digestion gene)
+const DIGESTION_RATE_PER_SIZE: NoNeg<Float> = noneg_float(0.2);
+
+/// Energy per unit of distance per size spent when bug is pushed away by another one
+const COLLISION_ENERGY_COST: NoNeg<Float> = noneg_float(0.001);
+
+/// Energy spent by attacker per unit of injected venom
+const STING_ENERGY_COST: NoNeg<Float> = noneg_float(0.5);
+
+/// Min time between two stings of one bug
+const STING_PERIOD: Duration = Duration::from_secs(2);
+
+/// Seconds of paralysis caused by a unit of venom in a bug of size 1 (bigger bugs recover faster)
+const PARALYSIS_PER_DOSE: Float = 5.;
+
+/// Longest paralysis a single sting can cause
+const MAX_PARALYSIS: Duration = Duration::from_secs(30);
+
+/// Energy per second spent on glowing with max brightness
+const SIGNAL_ENERGY_COST: NoNeg<Float> = noneg_float(0.01);
+
+/// Energy per second per size bitten off other bug (before armor)
+const BITE_RATE: NoNeg<Float> = noneg_float(0.05);
+
+/// Energy per second per size spent on biting
+const BITE_ENERGY_COST: NoNeg<Float> = noneg_float(0.01);
+
+/// Armor never blocks venom and energy theft completely
+const MAX_ARMOR: Float = 0.9;
+
+/// Extra movement energy cost of full armor (cost is multiplied by `1 + armor * this`)
+const ARMOR_MOVEMENT_COST: Float = 1.;
+
+/// Even perfectly camouflaged bug is seen from a tenth of vision range
+const MAX_CAMOUFLAGE: Float = 0.9;
+
+/// Energy per second per size gained by full photosynthesis under light of intensity 1
+const PHOTOSYNTHESIS_RATE: NoNeg<Float> = noneg_float(0.005);
+
+/// Share of speed lost by bug with full photosynthesis
+const PHOTOSYNTHESIS_SPEED_PENALTY: Float = 0.5;
+
+/// Bugs moving slower than this are considered standing still (and can photosynthesize)
+const STATIONARY_VELOCITY: Float = 0.1;
+
+use crate::chunk::{Identified, Position};
 use crate::{
-    brain::{self, Brain, VerboseOutput},
-    environment::{Environment, EnvironmentRequest, Food},
-    math::{noneg_float, sign, AbsAsNoNeg as _, Angle, Complex, DeltaAngle, NoNeg},
+    brain::{self, Brain, VerboseOutput, INPUT_SIZE, MEMORY_SIZE, OUTPUT_SIZE},
+    chromo_utils::{
+        self, MutationProfile, ARMOR_GENES, BODY_GENES, BRAIN_GENES, CAMOUFLAGE_GENES,
+        PHOTOSYNTHESIS_GENES, VENOM_GENES,
+    },
+    config::VisionMode,
+    egg::{Egg, EGG_NUTRIENTS},
+    environment::{Environment, EnvironmentRequest, Food, MAX_INTERACTION_RADIUS},
+    math::{noneg_float, sign, AbsAsNoNeg as _, Angle, Complex, DeltaAngle, NoNeg, Zero},
+    nutrient::Nutrients,
+    tag::Tag,
+    territory::TERRITORY_MARKING_RATE,
     time_point::TimePoint,
     utils::{self, Color, Float},
+    water::WATER_MOVEMENT_COST,
 };
 
-use crate::math::Point;
+use crate::math::{Point, Rect};
 
 mod capacity {
     use crate::{
@@ -43,14 +127,28 @@ mod capacity {
     pub fn heat_capacity(size: NoNeg<Float>) -> NoNeg<Float> {
         size * BUG_HEAT_CAPACITY_PER_SIZE
     }
+
+    static BUG_STOMACH_CAPACITY_PER_SIZE: NoNeg<Float> = noneg_float(10.);
+
+    pub fn stomach_capacity(
+        size: NoNeg<Float>,
+        stomach_capacity_per_size: NoNeg<Float>,
+    ) -> NoNeg<Float> {
+        size * stomach_capacity_per_size * BUG_STOMACH_CAPACITY_PER_SIZE
+    }
 }
 
 pub struct BrainLog {
     pub input: brain::Input,
     pub output: brain::Output,
-    pub activations: ([Float; 16], [Float; 8], [Float; 8]),
+    pub activations: ([Float; INPUT_SIZE], Vec<Float>, [Float; OUTPUT_SIZE]),
+    /// Iteration of environment at which log was written
+    pub generation: usize,
 }
 
+/// Whole state of a bug is kept together rather than split into per-chunk component arrays:
+/// sensing, brain and requests of a bug need most of it at once, and `Environment::bugs` hands
+/// out plain `Ref`s to it
 #[derive(Serialize)]
 pub struct Bug<T> {
     id: usize,
@@ -77,6 +175,67 @@ pub struct Bug<T> {
     vision_range: NoNeg<Float>,
     #[serde(skip)]
     vision_half_arc: DeltaAngle<NoNeg<Float>>,
+    #[serde(skip)]
+    digging_ability: NoNeg<Float>,
+    #[serde(skip)]
+    voice_pitch: Float,
+    #[serde(skip)]
+    swim_efficiency: NoNeg<Float>,
+    #[serde(skip)]
+    diet: Nutrients,
+    /// Eaten energy which is not digested yet
+    stomach_level: NoNeg<Float>,
+    #[serde(skip)]
+    stomach_capacity_per_size: NoNeg<Float>,
+    /// Energy per second per size moved from stomach to energy store
+    #[serde(skip)]
+    digestion_rate: NoNeg<Float>,
+    #[serde(skip)]
+    basal_metabolism: NoNeg<Float>,
+    /// Time left until the next sound pulse can be emitted
+    #[serde(skip)]
+    sound_cooldown: Duration,
+    /// Venom injected by one sting
+    #[serde(skip)]
+    venom_dose: NoNeg<Float>,
+    #[serde(skip)]
+    armor: NoNeg<Float>,
+    #[serde(skip)]
+    camouflage: NoNeg<Float>,
+    #[serde(skip)]
+    photosynthesis: NoNeg<Float>,
+    /// Time left until the next sting can be made
+    #[serde(skip)]
+    sting_cooldown: Duration,
+    /// Brightness of glow controlled by brain (in range 0..=1)
+    #[serde(skip)]
+    signal: NoNeg<Float>,
+    /// Speed of the last movement along rotation (negative if bug moved backwards)
+    #[serde(skip)]
+    velocity: Float,
+    /// Time left until bug can move again after being stung
+    #[serde(skip_serializing_if = "Duration::is_zero")]
+    paralyzed_for: Duration,
+    /// Time elapsed since the previous update of bug in a sleeping chunk (see `ChunkSleepConfig`)
+    #[serde(skip_serializing_if = "Duration::is_zero")]
+    skipped_time: Duration,
+    /// Written by brain every tick and fed back into it on the next one
+    #[serde(skip_serializing_if = "memory_is_blank")]
+    memory: [Float; MEMORY_SIZE],
+    buried: bool,
+    /// Total energy eaten during lifetime
+    consumed_energy: NoNeg<Float>,
+    offspring_count: usize,
+    /// Omitted when empty, so saves of untagged runs do not change
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<Tag>,
+    #[serde(skip)]
+    genome_errors: Vec<GeneticFeaturesError>,
+}
+
+/// Memory of bugs which never used it is not saved, so saves of older runs do not change
+fn memory_is_blank(memory: &[Float; MEMORY_SIZE]) -> bool {
+    memory.iter().all(|cell| *cell == 0.)
 }
 
 impl<T> Position for RefCell<Bug<T>> {
@@ -97,6 +256,14 @@ impl<T> Position for Rc<RefCell<Bug<T>>> {
     }
 }
 
+impl<T> Identified for Rc<RefCell<Bug<T>>> {
+    type Id = usize;
+
+    fn id(&self) -> usize {
+        self.borrow().id
+    }
+}
+
 impl<'de, T: Deserialize<'de>> Deserialize<'de> for Bug<T> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -112,6 +279,22 @@ impl<'de, T: Deserialize<'de>> Deserialize<'de> for Bug<T> {
             birth_instant: T,
             baby_charge_level: NoNeg<Float>,
             heat_level: NoNeg<Float>,
+            #[serde(default)]
+            buried: bool,
+            #[serde(default = "Zero::zero")]
+            consumed_energy: NoNeg<Float>,
+            #[serde(default)]
+            offspring_count: usize,
+            #[serde(default = "Zero::zero")]
+            stomach_level: NoNeg<Float>,
+            #[serde(default)]
+            tags: Vec<Tag>,
+            #[serde(default)]
+            paralyzed_for: Duration,
+            #[serde(default)]
+            skipped_time: Duration,
+            #[serde(default)]
+            memory: [Float; MEMORY_SIZE],
         }
 
         let val = TmpBug::deserialize(deserializer)?;
@@ -134,6 +317,30 @@ impl<'de, T: Deserialize<'de>> Deserialize<'de> for Bug<T> {
             heat_level: val.heat_level,
             vision_range: features.vision_range,
             vision_half_arc: features.vision_half_arc,
+            digging_ability: features.digging_ability,
+            voice_pitch: features.voice_pitch,
+            swim_efficiency: features.swim_efficiency,
+            diet: features.diet,
+            stomach_level: val.stomach_level,
+            stomach_capacity_per_size: features.stomach_capacity_per_size,
+            digestion_rate: features.digestion_rate,
+            basal_metabolism: features.basal_metabolism,
+            sound_cooldown: Duration::ZERO,
+            venom_dose: features.venom_dose,
+            armor: features.armor,
+            camouflage: features.camouflage,
+            photosynthesis: features.photosynthesis,
+            sting_cooldown: Duration::ZERO,
+            signal: noneg_float(0.),
+            velocity: 0.,
+            paralyzed_for: val.paralyzed_for,
+            skipped_time: val.skipped_time,
+            memory: val.memory,
+            buried: val.buried,
+            consumed_energy: val.consumed_energy,
+            offspring_count: val.offspring_count,
+            tags: val.tags,
+            genome_errors: features.errors,
         })
     }
 }
@@ -149,6 +356,33 @@ impl Display for BugEnergyCapacityExceeded {
 
 impl Error for BugEnergyCapacityExceeded {}
 
+/// Genome describes a feature which is out of bounds enforced by environment.
+/// Such feature is clamped to the bound
+#[derive(Debug, Clone, Copy)]
+pub enum GeneticFeaturesError {
+    VisionRangeExceeded { vision_range: NoNeg<Float> },
+    EatRangeExceeded { eat_range: NoNeg<Float> },
+}
+
+impl Display for GeneticFeaturesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneticFeaturesError::VisionRangeExceeded { vision_range } => write!(
+                f,
+                "vision range {} exceeds max interaction radius {}",
+                vision_range, MAX_INTERACTION_RADIUS
+            ),
+            GeneticFeaturesError::EatRangeExceeded { eat_range } => write!(
+                f,
+                "eat range {} exceeds max interaction radius {}",
+                eat_range, MAX_INTERACTION_RADIUS
+            ),
+        }
+    }
+}
+
+impl Error for GeneticFeaturesError {}
+
 struct GeneticFeatures {
     brain: Brain,
     max_age: Duration,
@@ -157,18 +391,39 @@ struct GeneticFeatures {
     vision_range: NoNeg<Float>,
     vision_half_arc: DeltaAngle<NoNeg<Float>>,
     baby_charge_capacity_per_size: NoNeg<Float>,
+    digging_ability: NoNeg<Float>,
+    voice_pitch: Float,
+    swim_efficiency: NoNeg<Float>,
+    diet: Nutrients,
+    stomach_capacity_per_size: NoNeg<Float>,
+    digestion_rate: NoNeg<Float>,
+    basal_metabolism: NoNeg<Float>,
+    venom_dose: NoNeg<Float>,
+    armor: NoNeg<Float>,
+    camouflage: NoNeg<Float>,
+    photosynthesis: NoNeg<Float>,
+    errors: Vec<GeneticFeaturesError>,
 }
 
 impl GeneticFeatures {
     fn from_chromosome(chromosome: &Chromosome<Float>) -> GeneticFeatures {
-        let brain = Brain::new(&chromosome, 0..208);
-        let body_genes = &chromosome.genes[208..256];
+        let brain = Brain::new(&chromosome, BRAIN_GENES);
+        let body_genes = &chromosome.genes[BODY_GENES];
         let max_age =
             Duration::from_secs_f64(body_genes[0].abs() * body_genes[1].abs() * 60. * 60. * 24.);
         let size = body_genes[1].abs_as_noneg();
         let baby_charge_capacity_per_size = body_genes[2].abs_as_noneg();
         static VISION_RANGE_MUL: NoNeg<Float> = noneg_float(100.);
-        let vision_range = body_genes[3].abs_as_noneg() * VISION_RANGE_MUL;
+        let mut errors: Vec<GeneticFeaturesError> = Default::default();
+        let mut vision_range = body_genes[3].abs_as_noneg() * VISION_RANGE_MUL;
+        if vision_range > MAX_INTERACTION_RADIUS {
+            errors.push(GeneticFeaturesError::VisionRangeExceeded { vision_range });
+            vision_range = MAX_INTERACTION_RADIUS;
+        }
+        let eat_range = size * EAT_FOOD_MAX_PROXIMITY;
+        if eat_range > MAX_INTERACTION_RADIUS {
+            errors.push(GeneticFeaturesError::EatRangeExceeded { eat_range });
+        }
         static VISION_ARC_MUL: NoNeg<Float> = noneg_float(0.1);
         let vision_half_arc = DeltaAngle::from_radians(
             noneg_float(0.5)
@@ -183,6 +438,58 @@ impl GeneticFeatures {
             b: body_genes[6].rem_euclid(1.),
         };
 
+        let digging_ability = body_genes[7].abs_as_noneg();
+
+        let voice_pitch = body_genes[8].rem_euclid(1.);
+
+        let swim_efficiency = NoNeg::wrap(body_genes[9].clamp(0., 1.)).unwrap();
+
+        let diet = Nutrients::new(body_genes[10], body_genes[11], body_genes[12]);
+
+        let stomach_capacity_per_size = body_genes[13].abs_as_noneg();
+        let digestion_rate = body_genes[14].abs_as_noneg() * DIGESTION_RATE_PER_SIZE;
+
+        let basal_metabolism = NoNeg::wrap(
+            body_genes[15]
+                .abs()
+                .clamp(MIN_BASAL_METABOLISM, MAX_BASAL_METABOLISM),
+        )
+        .unwrap();
+
+        // genomes saved before venom was added have no venom
+        let venom_dose = chromosome
+            .genes
+            .get(VENOM_GENES.start)
+            .map(|gene| gene.abs_as_noneg())
+            .unwrap_or(noneg_float(0.));
+
+        let armor = NoNeg::wrap(
+            chromosome
+                .genes
+                .get(ARMOR_GENES.start)
+                .map(|gene| gene.abs().min(MAX_ARMOR))
+                .unwrap_or(0.),
+        )
+        .unwrap();
+
+        let camouflage = NoNeg::wrap(
+            chromosome
+                .genes
+                .get(CAMOUFLAGE_GENES.start)
+                .map(|gene| gene.abs().min(MAX_CAMOUFLAGE))
+                .unwrap_or(0.),
+        )
+        .unwrap();
+
+        let photosynthesis = NoNeg::wrap(
+            chromosome
+                .genes
+                .get(PHOTOSYNTHESIS_GENES.start)
+                .map(|gene| gene.abs().min(1.))
+                .unwrap_or(0.),
+        )
+        .unwrap();
+
         GeneticFeatures {
             brain,
             max_age,
@@ -191,6 +498,18 @@ impl GeneticFeatures {
             vision_range,
             vision_half_arc,
             baby_charge_capacity_per_size,
+            digging_ability,
+            voice_pitch,
+            swim_efficiency,
+            diet,
+            stomach_capacity_per_size,
+            digestion_rate,
+            basal_metabolism,
+            venom_dose,
+            armor,
+            camouflage,
+            photosynthesis,
+            errors,
         }
     }
 }
@@ -200,6 +519,15 @@ impl<T> Bug<T> {
         self.id
     }
 
+    pub(crate) fn set_id(&mut self, id: usize) {
+        self.id = id
+    }
+
+    /// Replace time points with ones of another clock (see `Environment::merge`)
+    pub(crate) fn rebase_time<F: Fn(&T) -> T>(&mut self, rebase: F) {
+        self.birth_instant = rebase(&self.birth_instant);
+    }
+
     pub fn chromosome(&self) -> &Chromosome<Float> {
         &self.chromosome
     }
@@ -208,6 +536,10 @@ impl<T> Bug<T> {
         &mut self.chromosome
     }
 
+    pub(crate) fn skipped_time_mut(&mut self) -> &mut Duration {
+        &mut self.skipped_time
+    }
+
     pub fn brain(&self) -> &Brain {
         &self.brain
     }
@@ -251,6 +583,10 @@ impl<T> Bug<T> {
         .unwrap()
     }
 
+    pub fn birth_instant(&self) -> &T {
+        &self.birth_instant
+    }
+
     pub fn color(&self) -> &Color {
         &self.color
     }
@@ -275,8 +611,241 @@ impl<T> Bug<T> {
         self.vision_half_arc
     }
 
+    pub fn digging_ability(&self) -> NoNeg<Float> {
+        self.digging_ability
+    }
+
+    /// Frequency of emitted sound pulses (in range 0..=1)
+    pub fn voice_pitch(&self) -> Float {
+        self.voice_pitch
+    }
+
+    /// Part of extra energy cost of moving through water which bug avoids (in range 0..=1)
+    pub fn swim_efficiency(&self) -> NoNeg<Float> {
+        self.swim_efficiency
+    }
+
+    /// Nutrients bug digests best (see `Nutrients::digestibility`)
+    pub fn diet(&self) -> &Nutrients {
+        &self.diet
+    }
+
+    /// Eaten energy which is not digested yet
+    pub fn stomach_level(&self) -> NoNeg<Float> {
+        self.stomach_level
+    }
+
+    pub fn stomach_capacity(&self) -> NoNeg<Float> {
+        capacity::stomach_capacity(self.size, self.stomach_capacity_per_size)
+    }
+
+    /// High metabolism makes bug faster but hungrier
+    pub fn basal_metabolism(&self) -> NoNeg<Float> {
+        self.basal_metabolism
+    }
+
+    /// Buried bug is invisible for others, but can not move or eat
+    pub fn buried(&self) -> bool {
+        self.buried
+    }
+
+    /// Venom injected into other bug by one sting (0 - bug can not sting)
+    pub fn venom_dose(&self) -> NoNeg<Float> {
+        self.venom_dose
+    }
+
+    /// Share of venom and of energy taken by parasites which is blocked (in range 0..1).
+    /// Heavier body makes movement more expensive
+    pub fn armor(&self) -> NoNeg<Float> {
+        self.armor
+    }
+
+    /// Share of vision range of others lost when they look at this bug (in range 0..1)
+    pub fn camouflage(&self) -> NoNeg<Float> {
+        self.camouflage
+    }
+
+    /// Share of full photosynthesis rate (in range 0..=1). Standing still in light gives energy,
+    /// but max speed is lower
+    pub fn photosynthesis(&self) -> NoNeg<Float> {
+        self.photosynthesis
+    }
+
+    /// Multipliers of mutation scale and probability of offspring and of irradiation
+    /// (see `MUTATION_RATE_GENES`)
+    pub fn mutation_rate(&self) -> MutationProfile {
+        chromo_utils::mutation_rate(&self.chromosome)
+    }
+
+    /// Factor of vision range of others at which this bug can be seen
+    pub fn visibility(&self) -> NoNeg<Float> {
+        noneg_float(1.).limited_sub(self.camouflage)
+    }
+
+    /// Brightness of glow seen by others, set by brain every tick (in range 0..=1).
+    /// Unlike color it is not inherited
+    pub fn signal(&self) -> NoNeg<Float> {
+        self.signal
+    }
+
+    /// Speed of the last movement along rotation (negative if bug moved backwards, 0 if it
+    /// was buried or paralyzed)
+    pub fn velocity(&self) -> Float {
+        self.velocity
+    }
+
+    /// Short-term memory of brain (each cell in range -1..=1)
+    pub fn memory(&self) -> &[Float; MEMORY_SIZE] {
+        &self.memory
+    }
+
+    /// Time left until bug can move again (zero if it is not paralyzed)
+    pub fn paralyzed_for(&self) -> Duration {
+        self.paralyzed_for
+    }
+
+    /// Paralyzed bug can not move or rotate, but still senses, eats and spends energy
+    pub fn paralyzed(&self) -> bool {
+        !self.paralyzed_for.is_zero()
+    }
+
+    /// Part of incoming damage or theft which gets through armor
+    fn armor_penetration(&self) -> NoNeg<Float> {
+        noneg_float(1.).limited_sub(self.armor)
+    }
+
+    /// Paralyze for a time proportional to `dose` and inversely proportional to size.
+    /// Overlapping stings do not add up: the longer paralysis wins
+    pub(crate) fn inject_venom(&mut self, dose: NoNeg<Float>) {
+        let dose = dose * self.armor_penetration();
+        let seconds = dose.unwrap() * PARALYSIS_PER_DOSE / self.size.unwrap().max(1.);
+        let duration = Duration::from_secs_f64(seconds.min(MAX_PARALYSIS.as_secs_f64()));
+        self.paralyzed_for = self.paralyzed_for.max(duration);
+    }
+
+    pub fn consumed_energy(&self) -> NoNeg<Float> {
+        self.consumed_energy
+    }
+
+    pub fn offspring_count(&self) -> usize {
+        self.offspring_count
+    }
+
+    /// Offspring produced per unit of consumed energy (0 if nothing was eaten yet)
+    pub fn efficiency(&self) -> Float {
+        if self.consumed_energy == noneg_float(0.) {
+            0.
+        } else {
+            self.offspring_count as Float / self.consumed_energy.unwrap()
+        }
+    }
+
+    pub fn tags(&self) -> &[Tag] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, name: &str) -> bool {
+        self.tags.iter().any(|tag| tag.name == name)
+    }
+
+    /// Color of the first tag which overrides color
+    pub fn tag_color(&self) -> Option<&Color> {
+        self.tags.iter().find_map(|tag| tag.color.as_ref())
+    }
+
+    /// Replaces tag with the same name if there is one
+    pub(crate) fn add_tag(&mut self, tag: Tag) {
+        self.remove_tag(&tag.name);
+        self.tags.push(tag);
+    }
+
+    /// Returns false if there was no such tag
+    pub(crate) fn remove_tag(&mut self, name: &str) -> bool {
+        let len = self.tags.len();
+        self.tags.retain(|tag| tag.name != name);
+        self.tags.len() != len
+    }
+
+    pub(crate) fn set_tags(&mut self, tags: Vec<Tag>) {
+        self.tags = tags
+    }
+
+    /// Tags passed to offspring
+    pub(crate) fn inherited_tags(&self) -> Vec<Tag> {
+        self.tags
+            .iter()
+            .filter(|tag| tag.inherited)
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn register_offspring(&mut self) {
+        self.offspring_count += 1;
+    }
+
+    /// Features which were clamped because genome exceeds environment bounds
+    pub fn genome_errors(&self) -> &[GeneticFeaturesError] {
+        &self.genome_errors
+    }
+
+    /// Mirror bug back inside `bounds` if it crossed an edge and turn it away from that edge
+    pub(crate) fn reflect_from(&mut self, bounds: &Rect<Float>) {
+        let (mut x, mut y) = (*self.position.x(), *self.position.y());
+        let mut rotation = self.rotation.radians();
+        if (x < bounds.left() && rotation.cos() < 0.) || (x > bounds.right() && rotation.cos() > 0.)
+        {
+            rotation = PI - rotation;
+        }
+        if (y < bounds.top() && rotation.sin() < 0.) || (y > bounds.bottom() && rotation.sin() > 0.)
+        {
+            rotation = -rotation;
+        }
+        if x < bounds.left() {
+            x = 2. * bounds.left() - x;
+        } else if x > bounds.right() {
+            x = 2. * bounds.right() - x;
+        }
+        if y < bounds.top() {
+            y = 2. * bounds.top() - y;
+        } else if y > bounds.bottom() {
+            y = 2. * bounds.bottom() - y;
+        }
+        // a bug which jumped further than the whole arena is just put on its edge
+        self.position = (
+            x.clamp(bounds.left(), bounds.right()),
+            y.clamp(bounds.top(), bounds.bottom()),
+        )
+            .into();
+        self.rotation = Angle::from_radians(rotation);
+    }
+
+    /// Move bug which crossed an edge of `bounds` to the opposite edge keeping its rotation
+    pub(crate) fn wrap_around(&mut self, bounds: &Rect<Float>) {
+        let (x, y) = (*self.position.x(), *self.position.y());
+        self.position = (
+            bounds.left() + (x - bounds.left()).rem_euclid(*bounds.w()),
+            bounds.top() + (y - bounds.top()).rem_euclid(*bounds.h()),
+        )
+            .into();
+    }
+
+    /// Shift bug by `offset` without turning it (used to resolve collisions)
+    pub(crate) fn push_by(&mut self, offset: (Float, Float), metabolism: NoNeg<Float>) {
+        self.position = (self.position.x() + offset.0, self.position.y() + offset.1).into();
+        let distance = NoNeg::wrap(offset.0.hypot(offset.1)).unwrap();
+        utils::drain_energy(
+            &mut self.energy_level,
+            distance * COLLISION_ENERGY_COST * self.size() * metabolism,
+        );
+    }
+
     pub fn eat_range(&self) -> NoNeg<Float> {
-        self.size * EAT_FOOD_MAX_PROXIMITY
+        let eat_range = self.size * EAT_FOOD_MAX_PROXIMITY;
+        if eat_range > MAX_INTERACTION_RADIUS {
+            MAX_INTERACTION_RADIUS
+        } else {
+            eat_range
+        }
     }
 
     pub(crate) fn give_birth(
@@ -306,6 +875,30 @@ impl<T> Bug<T> {
             heat_level: noneg_float(0.),
             vision_range: features.vision_range,
             vision_half_arc: features.vision_half_arc,
+            digging_ability: features.digging_ability,
+            voice_pitch: features.voice_pitch,
+            swim_efficiency: features.swim_efficiency,
+            diet: features.diet,
+            stomach_level: noneg_float(0.),
+            stomach_capacity_per_size: features.stomach_capacity_per_size,
+            digestion_rate: features.digestion_rate,
+            basal_metabolism: features.basal_metabolism,
+            sound_cooldown: Duration::ZERO,
+            venom_dose: features.venom_dose,
+            armor: features.armor,
+            camouflage: features.camouflage,
+            photosynthesis: features.photosynthesis,
+            sting_cooldown: Duration::ZERO,
+            signal: noneg_float(0.),
+            velocity: 0.,
+            paralyzed_for: Duration::ZERO,
+            skipped_time: Duration::ZERO,
+            memory: [0.; MEMORY_SIZE],
+            buried: false,
+            consumed_energy: Zero::zero(),
+            offspring_count: 0,
+            tags: vec![],
+            genome_errors: features.errors.clone(),
         };
 
         *next_id += 1;
@@ -343,6 +936,30 @@ impl<T> Bug<T> {
             heat_level: noneg_float(0.),
             vision_range: features.vision_range,
             vision_half_arc: features.vision_half_arc,
+            digging_ability: features.digging_ability,
+            voice_pitch: features.voice_pitch,
+            swim_efficiency: features.swim_efficiency,
+            diet: features.diet,
+            stomach_level: noneg_float(0.),
+            stomach_capacity_per_size: features.stomach_capacity_per_size,
+            digestion_rate: features.digestion_rate,
+            basal_metabolism: features.basal_metabolism,
+            sound_cooldown: Duration::ZERO,
+            venom_dose: features.venom_dose,
+            armor: features.armor,
+            camouflage: features.camouflage,
+            photosynthesis: features.photosynthesis,
+            sting_cooldown: Duration::ZERO,
+            signal: noneg_float(0.),
+            velocity: 0.,
+            paralyzed_for: Duration::ZERO,
+            skipped_time: Duration::ZERO,
+            memory: [0.; MEMORY_SIZE],
+            buried: false,
+            consumed_energy: Zero::zero(),
+            offspring_count: 0,
+            tags: vec![],
+            genome_errors: features.errors.clone(),
         }
     }
 
@@ -382,6 +999,30 @@ impl<T> Bug<T> {
                 heat_level: noneg_float(0.),
                 vision_range: features.vision_range,
                 vision_half_arc: features.vision_half_arc,
+                digging_ability: features.digging_ability,
+                voice_pitch: features.voice_pitch,
+                swim_efficiency: features.swim_efficiency,
+                diet: features.diet,
+                stomach_level: noneg_float(0.),
+                stomach_capacity_per_size: features.stomach_capacity_per_size,
+                digestion_rate: features.digestion_rate,
+                basal_metabolism: features.basal_metabolism,
+                sound_cooldown: Duration::ZERO,
+                venom_dose: features.venom_dose,
+                armor: features.armor,
+                camouflage: features.camouflage,
+                photosynthesis: features.photosynthesis,
+                sting_cooldown: Duration::ZERO,
+                signal: noneg_float(0.),
+                velocity: 0.,
+                paralyzed_for: Duration::ZERO,
+                skipped_time: Duration::ZERO,
+                memory: [0.; MEMORY_SIZE],
+                buried: false,
+                consumed_energy: Zero::zero(),
+                offspring_count: 0,
+                tags: vec![],
+                genome_errors: features.errors.clone(),
             });
             *next_id += 1;
         }
@@ -405,6 +1046,30 @@ impl<T> Bug<T> {
             heat_level: noneg_float(0.),
             vision_range: features.vision_range,
             vision_half_arc: features.vision_half_arc,
+            digging_ability: features.digging_ability,
+            voice_pitch: features.voice_pitch,
+            swim_efficiency: features.swim_efficiency,
+            diet: features.diet,
+            stomach_level: noneg_float(0.),
+            stomach_capacity_per_size: features.stomach_capacity_per_size,
+            digestion_rate: features.digestion_rate,
+            basal_metabolism: features.basal_metabolism,
+            sound_cooldown: Duration::ZERO,
+            venom_dose: features.venom_dose,
+            armor: features.armor,
+            camouflage: features.camouflage,
+            photosynthesis: features.photosynthesis,
+            sting_cooldown: Duration::ZERO,
+            signal: noneg_float(0.),
+            velocity: 0.,
+            paralyzed_for: Duration::ZERO,
+            skipped_time: Duration::ZERO,
+            memory: [0.; MEMORY_SIZE],
+            buried: false,
+            consumed_energy: Zero::zero(),
+            offspring_count: 0,
+            tags: vec![],
+            genome_errors: features.errors.clone(),
         });
         *next_id += 1;
 
@@ -445,12 +1110,70 @@ impl<T> Bug<T> {
         )
     }
 
+    /// Up to `n` nearest bugs seen sorted from near to far
+    pub fn find_n_nearest_bugs_in_vision_arc<'a>(
+        &self,
+        env: &'a Environment<T>,
+        n: usize,
+    ) -> Vec<(Ref<'a, Self>, NoNeg<Float>)> {
+        env.find_n_nearest_bugs_in_vision_arc(
+            self.position,
+            self.vision_range,
+            self.rotation(),
+            self.vision_half_arc(),
+            n,
+        )
+    }
+
+    /// Up to `n` nearest food items seen sorted from near to far
+    pub fn find_n_nearest_food_in_vision_arc<'a>(
+        &self,
+        env: &'a Environment<T>,
+        n: usize,
+    ) -> Vec<(&'a Food, NoNeg<Float>)> {
+        env.find_n_nearest_food_in_vision_arc(
+            self.position,
+            self.vision_range,
+            self.rotation(),
+            self.vision_half_arc(),
+            n,
+        )
+    }
+
+    /// Nearest object in each retina sector of vision arc (see `VisionMode::Retina`)
+    pub fn look_with_retina(
+        &self,
+        env: &Environment<T>,
+    ) -> [Option<brain::RetinaHit>; brain::RETINA_SECTORS] {
+        env.look_with_retina(
+            self.position,
+            self.vision_range,
+            self.rotation(),
+            self.vision_half_arc(),
+        )
+    }
+
+    /// Number, mean heading and mean velocity of all bugs seen
+    pub fn sense_flock(&self, env: &Environment<T>) -> brain::FlockInfo {
+        env.sense_flock(
+            self.position,
+            self.vision_range,
+            self.rotation(),
+            self.vision_half_arc(),
+        )
+    }
+
     fn reproduce_asexually<R: RngCore>(&self, rng: &mut R) -> EnvironmentRequest
     where
         T: Clone,
     {
         EnvironmentRequest::GiveBirth {
-            chromosome: self.chromosome.clone().mutated(|_, _| 0.01..0.8, 0.01, rng),
+            parent_id: self.id,
+            chromosome: chromo_utils::mutated_blocks(
+                self.chromosome.clone(),
+                &chromo_utils::bug_mutation_profiles_of(&self.chromosome),
+                rng,
+            ),
             position: self.position,
             rotation: Angle::from_radians(rng.gen_range(0. ..(PI * 2.))),
             energy_level: self.baby_charge_capacity(),
@@ -461,15 +1184,226 @@ impl<T> Bug<T> {
         todo!()
     }
 
+    /// Eaten energy goes to stomach and reaches energy store later (see `digest`).
     /// return true if food is completely drained
     pub(crate) fn eat(&mut self, food: &mut Food, delta_energy: NoNeg<Float>) -> bool {
+        let nutrients = *food.nutrients();
+        let toxicity = food.toxicity();
+        self.ingest(food.energy_mut(), delta_energy, &nutrients, toxicity)
+    }
+
+    /// Eat as much of the egg as stomach can hold. return true if egg is completely drained
+    pub(crate) fn eat_egg(&mut self, egg: &mut Egg<T>) -> bool {
+        let energy = egg.energy();
+        self.ingest(egg.energy_mut(), energy, &EGG_NUTRIENTS, noneg_float(0.))
+    }
+
+    /// Give up to `delta_energy` to `offspring` (as much as it can store)
+    pub(crate) fn feed(&mut self, offspring: &mut Bug<T>, delta_energy: NoNeg<Float>) {
+        let energy_capacity = offspring.energy_capacity();
+        utils::transfer_energy(
+            &mut self.energy_level,
+            &mut offspring.energy_level,
+            delta_energy,
+            energy_capacity,
+        );
+    }
+
+    /// Bite off up to `delta_energy` of energy stored by `victim` (armor blocks part of it).
+    /// Bitten energy is eaten like food rich in protein and fat. Victim keeps energy which does
+    /// not fit into stomach
+    pub(crate) fn bite(&mut self, victim: &mut Bug<T>, delta_energy: NoNeg<Float>) {
+        let delta_energy = delta_energy * victim.armor_penetration();
+        self.ingest(
+            &mut victim.energy_level,
+            delta_energy,
+            &Nutrients::new(0.6, 0., 0.4),
+            noneg_float(0.),
+        );
+    }
+
+    /// Take up to `delta_energy` from stomach (undigested intake). Armor lets only part of it
+    /// through. Returns energy taken
+    pub(crate) fn siphon(&mut self, delta_energy: NoNeg<Float>) -> NoNeg<Float> {
+        let stomach_before = self.stomach_level;
+        utils::drain_energy(
+            &mut self.stomach_level,
+            delta_energy * self.armor_penetration(),
+        );
+        stomach_before.limited_sub(self.stomach_level)
+    }
+
+    /// Move up to `delta_energy` from `source` to stomach. return true if source is completely
+    /// drained
+    fn ingest(
+        &mut self,
+        source: &mut NoNeg<Float>,
+        delta_energy: NoNeg<Float>,
+        nutrients: &Nutrients,
+        toxicity: NoNeg<Float>,
+    ) -> bool {
+        let stomach_capacity = self.stomach_capacity();
+        let stomach_before = self.stomach_level;
+        let completely_drained = utils::transfer_energy(
+            source,
+            &mut self.stomach_level,
+            delta_energy,
+            stomach_capacity,
+        );
+        let eaten = self.stomach_level.limited_sub(stomach_before);
+        self.consumed_energy += eaten;
+        // digestion turns eaten energy into more or less of it depending on how well diet fits
+        let digested = eaten * nutrients.digestibility(&self.diet);
+        utils::drain_energy(&mut self.stomach_level, eaten.limited_sub(digested));
+        let surplus = digested.limited_sub(eaten);
+        utils::transfer_energy(
+            &mut surplus.clone(),
+            &mut self.stomach_level,
+            surplus,
+            stomach_capacity,
+        );
+        utils::drain_energy(&mut self.energy_level, eaten * toxicity * TOXIN_POTENCY);
+        completely_drained
+    }
+
+    /// Move energy from stomach to energy store with genetically determined rate
+    fn digest(&mut self, dt: Duration) {
         let energy_capacity = self.energy_capacity();
+        let delta_energy = self.digestion_rate * self.size * NoNeg::wrap(dt.as_secs_f64()).unwrap();
         utils::transfer_energy(
-            food.energy_mut(),
+            &mut self.stomach_level,
             &mut self.energy_level,
             delta_energy,
             energy_capacity,
-        )
+        );
+    }
+
+    /// Gain energy from `light` proportionally to photosynthesis ability (up to energy capacity)
+    fn photosynthesize(&mut self, light: NoNeg<Float>, dt: Duration) {
+        let energy_capacity = self.energy_capacity();
+        if self.energy_level >= energy_capacity {
+            return;
+        }
+        let delta_energy = PHOTOSYNTHESIS_RATE
+            * self.photosynthesis
+            * light
+            * self.size
+            * NoNeg::wrap(dt.as_secs_f64()).unwrap();
+        utils::transfer_energy(
+            &mut delta_energy.clone(),
+            &mut self.energy_level,
+            delta_energy,
+            energy_capacity,
+        );
+    }
+
+    /// Movement in water costs up to `WATER_MOVEMENT_COST` times more depending on swim efficiency.
+    /// Climbing uphill from `from` costs more and going downhill less. Armor makes every move
+    /// more expensive
+    fn movement_cost_multiplier(&self, env: &Environment<T>, from: Point<Float>) -> NoNeg<Float> {
+        let water = if env.in_water(self.position) {
+            NoNeg::wrap(1. + (WATER_MOVEMENT_COST - 1.) * (1. - self.swim_efficiency.unwrap()))
+                .unwrap()
+        } else {
+            noneg_float(1.)
+        };
+        let slope = env
+            .terrain()
+            .map(|terrain| terrain.movement_cost_multiplier(from, self.position))
+            .unwrap_or(noneg_float(1.));
+        let armor = NoNeg::wrap(1. + self.armor.unwrap() * ARMOR_MOVEMENT_COST).unwrap();
+        let friction = env.biome_at(self.position).friction;
+        water * slope * armor * friction
+    }
+
+    /// Brain input built from what bug currently senses. Nearest food seen is returned as well
+    fn sense<'a>(
+        &self,
+        env: &'a Environment<T>,
+        age: NoNeg<Float>,
+    ) -> (brain::Input, Option<(&'a Food, NoNeg<Float>)>) {
+        let food = self.find_n_nearest_food_in_vision_arc(env, 1 + brain::FARTHER_NEIGHBOR_COUNT);
+        let bugs = self.find_n_nearest_bugs_in_vision_arc(env, 1 + brain::FARTHER_NEIGHBOR_COUNT);
+        let nearest_food = food.first().copied();
+        let nearest_bug = bugs.first().map(|(bug, dst)| brain::BugInfo {
+            dst: *dst,
+            direction: self.direction_to_bug(bug),
+            color: bug.color.clone(),
+            relative_radius: bug.eat_range() / self.eat_range(),
+            signal: bug.signal.unwrap(),
+        });
+        let farther_food = food
+            .iter()
+            .skip(1)
+            .map(|(food, dst)| brain::NeighborInfo {
+                dst: *dst,
+                direction: self.direction_to_food(food),
+            })
+            .collect();
+        let farther_bugs = bugs
+            .iter()
+            .skip(1)
+            .map(|(bug, dst)| brain::NeighborInfo {
+                dst: *dst,
+                direction: self.direction_to_bug(bug),
+            })
+            .collect();
+
+        let brain_input = brain::Input {
+            energy_level: self.energy_level,
+            energy_capacity: self.energy_capacity(),
+            rotation: self.rotation,
+            age,
+            baby_charge_level: self.baby_charge_level,
+            baby_charge_capacity: self.baby_charge_capacity(),
+            vision_range: self.vision_range,
+            nearest_food: nearest_food.map(|(food, dst)| brain::FoodInfo {
+                dst,
+                direction: self.direction_to_food(food),
+                relative_radius: food.radius() / self.eat_range(),
+                nutrients: *food.nutrients(),
+            }),
+            nearest_bug,
+            pheromone: env.sense_pheromone(self.position),
+            hearing: env.hear(self.position, self.id),
+            water: env.sense_water(self.position),
+            memory: self.memory,
+            farther_food,
+            farther_bugs,
+            retina: match env.config().vision_mode {
+                VisionMode::Nearest => None,
+                VisionMode::Retina => Some(self.look_with_retina(env)),
+            },
+            odor: env.sense_odor(self.position),
+            touch: env.touch(self.position, self.eat_range(), self.id),
+            foreign_territory: env.sense_foreign_territory(self.position, self.id),
+            flock: self.sense_flock(env),
+            wall_distance: env.wall_distance(self.position, self.rotation, self.vision_range),
+        };
+        (brain_input, nearest_food)
+    }
+
+    /// Sense environment and run brain without acting on its output.
+    /// None if bug is already dead of old age
+    pub(crate) fn evaluate_brain(&self, env: &Environment<T>) -> Option<BrainLog>
+    where
+        T: TimePoint + Clone,
+    {
+        let age = self.age(env.now().clone());
+        if age > noneg_float(1.) {
+            return None;
+        }
+        let (input, _) = self.sense(env, age);
+        let VerboseOutput {
+            output,
+            activations,
+        } = self.brain.proceed_verbosely(input.clone());
+        Some(BrainLog {
+            input,
+            output,
+            activations,
+            generation: env.iteration(),
+        })
     }
 
     pub(crate) fn proceed<R: RngCore>(
@@ -484,91 +1418,111 @@ impl<T> Bug<T> {
         let mut requests: Vec<EnvironmentRequest> = Default::default();
         let age = self.age(env.now().clone());
         if age <= noneg_float(1.) {
-            struct NearestFoodInfo<'a> {
-                food: &'a Food,
-                brain_input: brain::FoodInfo,
-            }
-
-            struct NearestBugInfo {
-                brain_input: brain::BugInfo,
-            }
-
-            let nearest_food = self
-                .find_nearest_food_in_vision_arc(env)
-                .map(|(food, dst)| NearestFoodInfo {
-                    food,
-                    brain_input: brain::FoodInfo {
-                        dst,
-                        direction: self.direction_to_food(food),
-                        relative_radius: food.radius() / self.eat_range(),
-                    },
-                });
-
-            let nearest_bug =
-                self.find_nearest_bug_in_vision_arc(env)
-                    .map(|(bug, dst)| NearestBugInfo {
-                        brain_input: brain::BugInfo {
-                            dst,
-                            direction: self.direction_to_bug(&bug),
-                            color: bug.color.clone(),
-                            relative_radius: bug.eat_range() / self.eat_range(),
-                        },
-                    });
-
-            let brain_input = brain::Input {
-                energy_level: self.energy_level,
-                energy_capacity: self.energy_capacity(),
-                rotation: self.rotation,
-                age,
-                baby_charge_level: self.baby_charge_level,
-                baby_charge_capacity: self.baby_charge_capacity(),
-                vision_range: self.vision_range,
-                nearest_food: nearest_food.as_ref().map(|x| x.brain_input.clone()),
-                nearest_bug: nearest_bug.as_ref().map(|x| x.brain_input.clone()),
-            };
+            let sense_start = Instant::now();
+            let (brain_input, nearest_food) = self.sense(env, age);
 
+            let brain_start = Instant::now();
             let VerboseOutput {
                 output: brain_output,
                 activations,
             } = self.brain.proceed_verbosely(brain_input.clone());
-
-            self.last_brain_log = Some(BrainLog {
-                input: brain_input.clone(),
-                output: brain_output.clone(),
-                activations,
+            env.record_tick_time(|timings| {
+                timings.sense += brain_start - sense_start;
+                timings.brain += brain_start.elapsed();
             });
 
-            {
-                let raw_delta = (self.rotation + brain_output.relative_desired_rotation)
-                    .signed_distance(self.rotation)
-                    .radians();
-
-                if raw_delta.abs() > 0.001 {
-                    let delta_rotation = DeltaAngle::from_radians(
-                        sign(raw_delta)
-                            * raw_delta
-                                .abs()
-                                .min(brain_output.rotation_velocity.unwrap().radians())
-                            * 0.1
-                            * dt.as_secs_f64(),
-                    );
-
-                    self.rotation += delta_rotation;
-
-                    let delta_energy =
-                        delta_rotation.radians().abs_as_noneg() * noneg_float(0.001) * self.size();
+            let policy = env.brain_log_policy();
+            if policy.should_log(self.id, env.iteration()) {
+                self.last_brain_log = Some(BrainLog {
+                    input: brain_input,
+                    output: brain_output.clone(),
+                    activations,
+                    generation: env.iteration(),
+                });
+            } else if !policy.keeps_stale_logs() {
+                self.last_brain_log = None;
+            }
+
+            self.memory = brain_output.memory;
+            self.buried = brain_output.burrow && self.digging_ability >= MIN_DIGGING_ABILITY;
+            let metabolism = env.metabolism_multiplier();
+            let paralyzed = self.paralyzed();
+            self.paralyzed_for = self.paralyzed_for.saturating_sub(dt);
+            self.velocity = 0.;
+
+            if self.buried {
+                let delta_energy = BURIED_ENERGY_COST * self.size() / self.digging_ability
+                    * metabolism
+                    * NoNeg::wrap(dt.as_secs_f64()).unwrap();
+                utils::drain_energy(&mut self.energy_level, delta_energy);
+            } else if !paralyzed {
+                {
+                    let raw_delta = (self.rotation + brain_output.relative_desired_rotation)
+                        .signed_distance(self.rotation)
+                        .radians();
+
+                    if raw_delta.abs() > 0.001 {
+                        let delta_rotation = DeltaAngle::from_radians(
+                            sign(raw_delta)
+                                * raw_delta
+                                    .abs()
+                                    .min(brain_output.rotation_velocity.unwrap().radians())
+                                * 0.1
+                                * self.basal_metabolism.unwrap()
+                                * dt.as_secs_f64(),
+                        );
+
+                        self.rotation += delta_rotation;
+
+                        let delta_energy = delta_rotation.radians().abs_as_noneg()
+                            * noneg_float(0.001)
+                            * self.size()
+                            * metabolism;
+                        utils::drain_energy(&mut self.energy_level, delta_energy);
+                    }
+                }
+
+                {
+                    self.velocity = brain_output.velocity
+                        * self.basal_metabolism.unwrap()
+                        * (1. - self.photosynthesis.unwrap() * PHOTOSYNTHESIS_SPEED_PENALTY);
+                    let delta_distance = self.velocity * dt.as_secs_f64();
+                    let new_pos = Complex::from_cartesian(*self.position.x(), *self.position.y())
+                        + Complex::from_polar(delta_distance, self.rotation);
+
+                    let old_pos = self.position;
+                    self.position = (*new_pos.real(), *new_pos.imag()).into();
+
+                    let delta_energy = delta_distance.abs_as_noneg()
+                        * noneg_float(0.001)
+                        * self.size()
+                        * metabolism
+                        * self.movement_cost_multiplier(env, old_pos);
                     utils::drain_energy(&mut self.energy_level, delta_energy);
                 }
             }
 
+            self.digest(dt);
+
+            /* basal metabolism */
             {
-                let delta_distance = brain_output.velocity * dt.as_secs_f64();
-                let new_pos = Complex::from_cartesian(*self.position.x(), *self.position.y())
-                    + Complex::from_polar(delta_distance, self.rotation);
+                let delta_energy = BASAL_ENERGY_COST
+                    * self.basal_metabolism
+                    * self.size()
+                    * metabolism
+                    * NoNeg::wrap(dt.as_secs_f64()).unwrap();
+                utils::drain_energy(&mut self.energy_level, delta_energy);
+            }
 
-                self.position = (*new_pos.real(), *new_pos.imag()).into();
+            if self.velocity.abs() < STATIONARY_VELOCITY {
+                self.photosynthesize(env.light_at(self.position), dt);
+            }
 
-                let delta_energy = delta_distance.abs_as_noneg() * noneg_float(0.001) * self.size();
+            /* biome hazard */
+            {
+                let delta_energy = env.biome_at(self.position).hazard
+                    * self.size()
+                    * NoNeg::wrap(dt.as_secs_f64()).unwrap();
                 utils::drain_energy(&mut self.energy_level, delta_energy);
             }
 
@@ -589,8 +1543,10 @@ impl<T> Bug<T> {
             /* heat generation */
             {
                 let heat_capacity = self.heat_capacity();
-                let delta_energy =
-                    noneg_float(0.001) * self.size() * NoNeg::wrap(dt.as_secs_f64()).unwrap();
+                let delta_energy = noneg_float(0.001)
+                    * self.size()
+                    * metabolism
+                    * NoNeg::wrap(dt.as_secs_f64()).unwrap();
                 utils::transfer_energy(
                     &mut self.energy_level,
                     &mut self.heat_level,
@@ -599,13 +1555,115 @@ impl<T> Bug<T> {
                 );
             }
 
-            if let Some(nearest_food) = nearest_food {
-                if nearest_food.brain_input.dst
-                    < EAT_FOOD_MAX_PROXIMITY * self.size() + nearest_food.food.radius()
+            /* pheromone emission */
+            {
+                let amount =
+                    brain_output.pheromone_emission_rate * NoNeg::wrap(dt.as_secs_f64()).unwrap();
+                if amount > noneg_float(0.) && brain_output.emit_pheromone {
+                    utils::drain_energy(&mut self.energy_level, amount * noneg_float(0.01));
+                    requests.push(EnvironmentRequest::DepositPheromone {
+                        position: self.position,
+                        amount,
+                    });
+                }
+            }
+
+            /* territory marking */
+            requests.push(EnvironmentRequest::MarkTerritory {
+                position: self.position,
+                owner_id: self.id,
+                color: self.color.clone(),
+                amount: noneg_float(TERRITORY_MARKING_RATE)
+                    * NoNeg::wrap(dt.as_secs_f64()).unwrap(),
+            });
+
+            /* sound emission */
+            {
+                self.sound_cooldown = self.sound_cooldown.saturating_sub(dt);
+                let loudness = brain_output.sound_loudness;
+                if loudness > noneg_float(0.) && self.sound_cooldown.is_zero() {
+                    utils::drain_energy(&mut self.energy_level, loudness * SOUND_PULSE_ENERGY_COST);
+                    requests.push(EnvironmentRequest::EmitSound {
+                        position: self.position,
+                        frequency: self.voice_pitch,
+                        loudness,
+                    });
+                    self.sound_cooldown = SOUND_PULSE_PERIOD;
+                }
+            }
+
+            /* glowing (buried bugs are not seen anyway) */
+            {
+                self.signal = if self.buried {
+                    noneg_float(0.)
+                } else {
+                    brain_output.signal
+                };
+                utils::drain_energy(
+                    &mut self.energy_level,
+                    self.signal * SIGNAL_ENERGY_COST * NoNeg::wrap(dt.as_secs_f64()).unwrap(),
+                );
+            }
+
+            /* feeding offspring */
+            {
+                let delta_energy =
+                    brain_output.feeding_rate * NoNeg::wrap(dt.as_secs_f64()).unwrap();
+                if delta_energy > noneg_float(0.) && !self.buried && brain_output.feed_offspring {
+                    if let Some((offspring, _)) =
+                        env.find_nearest_offspring(self.id, self.position, self.eat_range())
+                    {
+                        requests.push(EnvironmentRequest::TransferEnergyBugToBug {
+                            recipient_id: offspring.id(),
+                            delta_energy,
+                        });
+                    }
+                }
+            }
+
+            /* biting */
+            if brain_output.attack && !self.buried {
+                if let Some((victim, _)) =
+                    env.find_nearest_bug_in_range(self.position, self.eat_range())
+                {
+                    let dt = NoNeg::wrap(dt.as_secs_f64()).unwrap();
+                    utils::drain_energy(&mut self.energy_level, BITE_ENERGY_COST * self.size * dt);
+                    requests.push(EnvironmentRequest::Bite {
+                        victim_id: victim.id(),
+                        delta_energy: BITE_RATE * self.size * dt,
+                    });
+                }
+            }
+
+            /* stinging */
+            {
+                self.sting_cooldown = self.sting_cooldown.saturating_sub(dt);
+                if brain_output.sting
+                    && !self.buried
+                    && self.venom_dose > noneg_float(0.)
+                    && self.sting_cooldown.is_zero()
                 {
+                    if let Some((victim, _)) =
+                        env.find_nearest_bug_in_range(self.position, self.eat_range())
+                    {
+                        utils::drain_energy(
+                            &mut self.energy_level,
+                            self.venom_dose * STING_ENERGY_COST,
+                        );
+                        requests.push(EnvironmentRequest::Sting {
+                            victim_id: victim.id(),
+                            dose: self.venom_dose,
+                        });
+                        self.sting_cooldown = STING_PERIOD;
+                    }
+                }
+            }
+
+            if let Some((food, dst)) = nearest_food.filter(|_| !self.buried && brain_output.eat) {
+                if dst < self.eat_range() + food.radius() {
                     let eat_rate = noneg_float(0.1) * self.size;
                     requests.push(EnvironmentRequest::TransferEnergyFromFoodToBug {
-                        food_id: nearest_food.food.id(),
+                        food_id: food.id(),
                         delta_energy: NoNeg::wrap(dt.as_secs_f64()).unwrap() * eat_rate,
                     });
                 }
@@ -626,3 +1684,324 @@ impl<T> Bug<T> {
         requests
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{f64::consts::PI, time::Duration};
+
+    use chromosome::Chromosome;
+    use rand::SeedableRng as _;
+    use rand_pcg::Pcg64;
+
+    use super::{Bug, BURIED_ENERGY_COST};
+    use crate::{
+        chromo_utils::{
+            ARMOR_GENES, BODY_GENES, BRAIN_ACTUATOR_GENES, BRAIN_GENES, BRAIN_STING_GENES,
+            GENOME_LEN, PHOTOSYNTHESIS_GENES, VENOM_GENES,
+        },
+        environment::{
+            benchmark_internals, BugCreateInfo, Environment, EnvironmentRequest, Food,
+            FoodCreateInfo, FoodIdPool,
+        },
+        math::{noneg_float, Angle, DeltaAngle},
+        nutrient::Nutrients,
+        time_point::StaticTimePoint,
+        utils::Float,
+    };
+
+    /// Genome with body genes set to one and idle brain: all its outputs are zero
+    fn genes() -> Vec<Float> {
+        let mut genes = vec![0.; GENOME_LEN];
+        genes[BODY_GENES].fill(1.);
+        genes
+    }
+
+    /// `genes()` whose brain gives activation `sigmoid(bias) * 2 - 1` at each of `outputs`
+    /// regardless of input
+    fn genes_with_outputs(outputs: &[(usize, Float)]) -> Vec<Float> {
+        let mut genes = genes();
+        for &(output, bias) in outputs {
+            let bias_gene = match output {
+                0..=7 => BRAIN_GENES.start + 200 + output,
+                8 => BRAIN_STING_GENES.start + 8,
+                12.. => BRAIN_ACTUATOR_GENES.start + (output - 12) * 17 + 16,
+                _ => unimplemented!("output {output} is not used in tests"),
+            };
+            genes[bias_gene] = bias;
+        }
+        genes
+    }
+
+    fn bug(genes: Vec<Float>) -> Bug<StaticTimePoint> {
+        Bug::give_birth(
+            &mut 0,
+            Chromosome { genes },
+            (0., 0.).into(),
+            Angle::from_radians(0.),
+            noneg_float(50.),
+            StaticTimePoint::default(),
+        )
+        .unwrap()
+    }
+
+    /// Environment with `food` and bugs of `genes()` at `bug_positions` (ids in the same order)
+    fn environment(
+        food: Vec<FoodCreateInfo>,
+        bug_positions: &[(Float, Float)],
+    ) -> Environment<StaticTimePoint> {
+        let bugs = bug_positions
+            .iter()
+            .map(|&position| BugCreateInfo {
+                chromosome: Chromosome { genes: genes() },
+                position: position.into(),
+                rotation: Angle::from_radians(0.),
+            })
+            .collect();
+        Environment::new(StaticTimePoint::default(), food, vec![], bugs)
+    }
+
+    fn bury(env: &Environment<StaticTimePoint>, id: usize) {
+        benchmark_internals::find_bug_by_id(env, id)
+            .unwrap()
+            .borrow_mut()
+            .buried = true;
+    }
+
+    fn proceed(
+        bug: &mut Bug<StaticTimePoint>,
+        env: &Environment<StaticTimePoint>,
+    ) -> Vec<EnvironmentRequest> {
+        bug.proceed(env, Duration::from_secs(1), &mut Pcg64::seed_from_u64(0))
+    }
+
+    #[test]
+    fn eaten_energy_is_digested_gradually() {
+        let mut genes = vec![1.; GENOME_LEN];
+        // stomach holds 10 energy per size, digestion moves 0.2 per second
+        genes[BODY_GENES.start + 13] = 1.;
+        genes[BODY_GENES.start + 14] = 1.;
+        let mut bug = Bug::give_birth(
+            &mut 0,
+            Chromosome { genes },
+            (0., 0.).into(),
+            Angle::from_radians(0.),
+            noneg_float(50.),
+            StaticTimePoint::default(),
+        )
+        .unwrap();
+        let mut food = Food::new(
+            &mut FoodIdPool::default(),
+            (0., 0.).into(),
+            noneg_float(100.),
+        );
+
+        assert!(!bug.eat(&mut food, noneg_float(100.)));
+        assert!((bug.stomach_level().unwrap() - 10.).abs() < 1e-9);
+        assert_eq!(bug.stomach_capacity(), noneg_float(10.));
+        assert_eq!(bug.energy_level(), noneg_float(50.));
+
+        bug.digest(Duration::from_secs(10));
+        assert!((bug.energy_level().unwrap() - 52.).abs() < 1e-9);
+        assert!((bug.stomach_level().unwrap() - 8.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn photosynthesis_converts_light_to_energy() {
+        let bug = |photosynthesis| {
+            let mut genes = vec![1.; GENOME_LEN];
+            genes[PHOTOSYNTHESIS_GENES.start] = photosynthesis;
+            Bug::give_birth(
+                &mut 0,
+                Chromosome { genes },
+                (0., 0.).into(),
+                Angle::from_radians(0.),
+                noneg_float(50.),
+                StaticTimePoint::default(),
+            )
+            .unwrap()
+        };
+        let mut plain = bug(0.);
+        let mut green = bug(-0.5);
+        assert_eq!(green.photosynthesis(), noneg_float(0.5));
+        assert_eq!(bug(3.).photosynthesis(), noneg_float(1.));
+
+        plain.photosynthesize(noneg_float(2.), Duration::from_secs(100));
+        assert_eq!(plain.energy_level(), noneg_float(50.));
+        green.photosynthesize(noneg_float(0.), Duration::from_secs(100));
+        assert_eq!(green.energy_level(), noneg_float(50.));
+        // 0.005 * 0.5 * 2 light * size 1 * 100 seconds
+        green.photosynthesize(noneg_float(2.), Duration::from_secs(100));
+        assert!((green.energy_level().unwrap() - 50.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn armor_blocks_part_of_venom_and_siphoning() {
+        let bug = |armor| {
+            let mut genes = vec![1.; GENOME_LEN];
+            genes[ARMOR_GENES.start] = armor;
+            let mut bug = Bug::give_birth(
+                &mut 0,
+                Chromosome { genes },
+                (0., 0.).into(),
+                Angle::from_radians(0.),
+                noneg_float(50.),
+                StaticTimePoint::default(),
+            )
+            .unwrap();
+            let mut food = Food::new(
+                &mut FoodIdPool::default(),
+                (0., 0.).into(),
+                noneg_float(100.),
+            );
+            bug.eat(&mut food, noneg_float(100.));
+            bug
+        };
+        let mut bare = bug(0.);
+        let mut armored = bug(-0.5);
+        assert_eq!(bare.armor(), noneg_float(0.));
+        assert_eq!(armored.armor(), noneg_float(0.5));
+        assert_eq!(bug(2.).armor(), noneg_float(0.9));
+
+        bare.inject_venom(noneg_float(1.));
+        armored.inject_venom(noneg_float(1.));
+        assert_eq!(bare.paralyzed_for(), Duration::from_secs(5));
+        assert_eq!(armored.paralyzed_for(), Duration::from_millis(2500));
+
+        assert!((bare.siphon(noneg_float(4.)).unwrap() - 4.).abs() < 1e-9);
+        assert!((armored.siphon(noneg_float(4.)).unwrap() - 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bitten_energy_which_does_not_fit_into_stomach_stays_with_victim() {
+        // stomach holds 10 energy
+        let mut attacker = bug(vec![1.; GENOME_LEN]);
+        let mut victim = bug(genes());
+        assert_eq!(attacker.stomach_capacity(), noneg_float(10.));
+        assert_eq!(victim.armor(), noneg_float(0.));
+
+        attacker.bite(&mut victim, noneg_float(30.));
+        assert_eq!(attacker.consumed_energy(), noneg_float(10.));
+        assert_eq!(victim.energy_level(), noneg_float(40.));
+    }
+
+    #[test]
+    fn memory_survives_save_and_is_omitted_when_blank() {
+        let mut bug: Bug<StaticTimePoint> = Bug::give_birth(
+            &mut 0,
+            Chromosome {
+                genes: vec![1.; GENOME_LEN],
+            },
+            (0., 0.).into(),
+            Angle::from_radians(0.),
+            noneg_float(50.),
+            StaticTimePoint::default(),
+        )
+        .unwrap();
+        let blank = serde_json::to_value(&bug).unwrap();
+        assert!(blank.get("memory").is_none());
+
+        bug.memory = [0.25, -0.5];
+        let loaded: Bug<StaticTimePoint> =
+            serde_json::from_str(&serde_json::to_string(&bug).unwrap()).unwrap();
+        assert_eq!(loaded.memory(), &[0.25, -0.5]);
+    }
+
+    #[test]
+    fn buried_bugs_are_not_seen() {
+        let env = environment(vec![], &[(0., 0.), (5., 0.)]);
+        bury(&env, 0);
+        let nearest = |range| {
+            env.find_nearest_bug_in_vision_arc(
+                (0., 0.).into(),
+                noneg_float(range),
+                Angle::from_radians(0.),
+                DeltaAngle::from_radians(noneg_float(PI)),
+            )
+            .map(|(bug, _)| bug.id())
+        };
+        assert_eq!(nearest(10.), Some(1));
+        assert_eq!(nearest(2.), None);
+    }
+
+    #[test]
+    fn buried_bugs_can_not_be_bitten_or_stung() {
+        let attacks = |victim_buried| {
+            let env = environment(vec![], &[(0., 0.)]);
+            if victim_buried {
+                bury(&env, 0);
+            }
+            // stands still next to the victim and both bites and stings it
+            let mut genes = genes_with_outputs(&[(8, 10.), (13, 10.)]);
+            genes[VENOM_GENES.start] = 1.;
+            let mut attacker = bug(genes);
+            proceed(&mut attacker, &env)
+                .into_iter()
+                .filter(|request| {
+                    matches!(
+                        request,
+                        EnvironmentRequest::Bite { .. } | EnvironmentRequest::Sting { .. }
+                    )
+                })
+                .count()
+        };
+        assert_eq!(attacks(false), 2);
+        assert_eq!(attacks(true), 0);
+    }
+
+    #[test]
+    fn buried_bugs_can_not_eat_or_move() {
+        let env = environment(
+            vec![FoodCreateInfo {
+                position: (0., 0.).into(),
+                energy: noneg_float(10.),
+                toxicity: noneg_float(0.),
+                nutrients: Nutrients::BALANCED,
+            }],
+            &[],
+        );
+        let eats = |requests: &[EnvironmentRequest]| {
+            requests.iter().any(|request| {
+                matches!(
+                    request,
+                    EnvironmentRequest::TransferEnergyFromFoodToBug { .. }
+                )
+            })
+        };
+
+        // runs at max velocity
+        let mut active = bug(genes_with_outputs(&[(0, 10.)]));
+        let requests = proceed(&mut active, &env);
+        assert!(!active.buried());
+        assert!(*active.position().x() > 0.);
+        assert!(eats(&requests));
+
+        let mut buried = bug(genes_with_outputs(&[(0, 10.), (4, 10.)]));
+        let requests = proceed(&mut buried, &env);
+        assert!(buried.buried());
+        assert_eq!(*buried.position().x(), 0.);
+        assert_eq!(*buried.position().y(), 0.);
+        assert!(!eats(&requests));
+    }
+
+    #[test]
+    fn staying_buried_costs_energy_inversely_to_digging_ability() {
+        let env = environment(vec![], &[]);
+        let bury_with = |digging_ability| {
+            let mut genes = genes_with_outputs(&[(4, 10.)]);
+            genes[BODY_GENES.start + 7] = digging_ability;
+            let mut bug = bug(genes);
+            let _ = proceed(&mut bug, &env);
+            (bug.buried(), bug.energy_level().unwrap())
+        };
+        let (deep_buried, deep_energy) = bury_with(1.);
+        let (shallow_buried, shallow_energy) = bury_with(0.5);
+        assert!(deep_buried && shallow_buried);
+        // the rest of spending is the same, so the difference is `size / 0.5 - size / 1`
+        // (one) of the cost with size 1
+        let expected = (BURIED_ENERGY_COST * env.metabolism_multiplier()).unwrap();
+        assert!((deep_energy - shallow_energy - expected).abs() < 1e-9);
+
+        // too weak digger does not burrow
+        assert!(!bury_with(0.05).0);
+    }
+}
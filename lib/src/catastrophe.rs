@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{noneg_float, NoNeg, Point, Rect},
+    utils::Float,
+};
+
+/// Multiplier of food sources production during famine
+pub const FAMINE_FOOD_ABUNDANCE: Float = 0.5;
+
+/// Disaster which strikes environment at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Catastrophe {
+    /// Kills every bug and destroys all food within `radius` around `center`
+    Meteor {
+        center: Point<Float>,
+        radius: NoNeg<Float>,
+    },
+    /// Food sources produce `FAMINE_FOOD_ABUNDANCE` of usual food for `duration`
+    Famine { duration: Duration },
+    /// Parasite is put on each bug with probability `infection_rate` (in range 0..=1)
+    Plague { infection_rate: Float },
+}
+
+impl Catastrophe {
+    /// Radius of meteor crater (0 for catastrophes which strike everywhere)
+    pub fn radius(&self) -> NoNeg<Float> {
+        match self {
+            Catastrophe::Meteor { radius, .. } => *radius,
+            _ => noneg_float(0.),
+        }
+    }
+}
+
+/// Catastrophe scripted to happen at `at` (simulated time since creation of environment)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledCatastrophe {
+    pub at: Duration,
+    pub catastrophe: Catastrophe,
+}
+
+/// Catastrophes happening at random: one per `mean_interval` on average. Meteors fall within
+/// `area`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RandomCatastrophes {
+    pub mean_interval: Duration,
+    pub area: Rect<Float>,
+}
+
+impl RandomCatastrophes {
+    fn generate<R: RngCore>(&self, rng: &mut R) -> Catastrophe {
+        match rng.gen_range(0..3) {
+            0 => Catastrophe::Meteor {
+                center: (
+                    rng.gen_range(self.area.left()..=self.area.right()),
+                    rng.gen_range(self.area.top()..=self.area.bottom()),
+                )
+                    .into(),
+                radius: NoNeg::wrap(rng.gen_range(100. ..400.)).unwrap(),
+            },
+            1 => Catastrophe::Famine {
+                duration: Duration::from_secs(rng.gen_range(60..300)),
+            },
+            _ => Catastrophe::Plague {
+                infection_rate: rng.gen_range(0.1..0.3),
+            },
+        }
+    }
+}
+
+/// Triggers scripted and random catastrophes and keeps track of the lasting ones
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CatastropheScheduler {
+    /// Scripted catastrophes which have not happened yet, sorted by time
+    scripted: Vec<ScheduledCatastrophe>,
+    random: Option<RandomCatastrophes>,
+    /// Simulated time when the current famine ends
+    famine_until: Option<Duration>,
+    /// Simulated time of the previous call of `proceed`
+    last_check: Duration,
+}
+
+impl CatastropheScheduler {
+    pub fn new(random: Option<RandomCatastrophes>) -> Self {
+        Self {
+            random,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_scripted(mut self, scripted: Vec<ScheduledCatastrophe>) -> Self {
+        scripted.into_iter().for_each(|s| self.schedule(s));
+        self
+    }
+
+    pub fn schedule(&mut self, scheduled: ScheduledCatastrophe) {
+        let index = self.scripted.partition_point(|s| s.at <= scheduled.at);
+        self.scripted.insert(index, scheduled);
+    }
+
+    /// Scripted catastrophes which have not happened yet
+    pub fn scripted(&self) -> &[ScheduledCatastrophe] {
+        &self.scripted
+    }
+
+    pub fn random(&self) -> Option<&RandomCatastrophes> {
+        self.random.as_ref()
+    }
+
+    pub fn famine(&self, elapsed: Duration) -> bool {
+        self.famine_until.is_some_and(|until| elapsed < until)
+    }
+
+    /// Multiplier of food sources production (lowered during famine)
+    pub fn food_abundance(&self, elapsed: Duration) -> Float {
+        if self.famine(elapsed) {
+            FAMINE_FOOD_ABUNDANCE
+        } else {
+            1.
+        }
+    }
+
+    /// Catastrophes which strike by `elapsed`. Famines are tracked by scheduler itself, the
+    /// rest has to be applied by environment
+    pub(crate) fn proceed<R: RngCore>(
+        &mut self,
+        elapsed: Duration,
+        rng: &mut R,
+    ) -> Vec<Catastrophe> {
+        let due = self.scripted.partition_point(|s| s.at <= elapsed);
+        let mut catastrophes: Vec<Catastrophe> =
+            self.scripted.drain(..due).map(|s| s.catastrophe).collect();
+
+        if let Some(random) = &self.random {
+            let dt = elapsed.saturating_sub(self.last_check).as_secs_f64();
+            let probability = 1. - (-dt / random.mean_interval.as_secs_f64()).exp();
+            if rng.gen_bool(probability.clamp(0., 1.)) {
+                catastrophes.push(random.generate(rng));
+            }
+        }
+        self.last_check = elapsed;
+
+        for catastrophe in &catastrophes {
+            if let Catastrophe::Famine { duration } = catastrophe {
+                let start = self.famine_until.unwrap_or(elapsed).max(elapsed);
+                self.famine_until = Some(start + *duration);
+            }
+        }
+        catastrophes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::math::noneg_float;
+
+    use super::{Catastrophe, CatastropheScheduler, ScheduledCatastrophe, FAMINE_FOOD_ABUNDANCE};
+
+    #[test]
+    fn scripted_catastrophes_strike_in_order_and_famines_stack() {
+        let at = |secs| Duration::from_secs(secs);
+        let famine = Catastrophe::Famine { duration: at(10) };
+        let meteor = Catastrophe::Meteor {
+            center: (0., 0.).into(),
+            radius: noneg_float(100.),
+        };
+        let mut scheduler = CatastropheScheduler::new(None).with_scripted(vec![
+            ScheduledCatastrophe {
+                at: at(20),
+                catastrophe: meteor,
+            },
+            ScheduledCatastrophe {
+                at: at(5),
+                catastrophe: famine.clone(),
+            },
+            ScheduledCatastrophe {
+                at: at(6),
+                catastrophe: famine.clone(),
+            },
+        ]);
+        let mut rng = Pcg64::seed_from_u64(0);
+
+        assert!(scheduler.proceed(at(4), &mut rng).is_empty());
+        assert_eq!(scheduler.food_abundance(at(4)), 1.);
+        let struck = scheduler.proceed(at(6), &mut rng);
+        assert_eq!(struck.len(), 2);
+        assert!(struck
+            .iter()
+            .all(|c| matches!(c, Catastrophe::Famine { .. })));
+        // the second famine starts when the first one ends
+        assert_eq!(scheduler.food_abundance(at(24)), FAMINE_FOOD_ABUNDANCE);
+        let struck = scheduler.proceed(at(25), &mut rng);
+        assert!(matches!(struck[..], [Catastrophe::Meteor { .. }]));
+        assert_eq!(scheduler.food_abundance(at(26)), 1.);
+        assert!(scheduler.scripted().is_empty());
+    }
+}
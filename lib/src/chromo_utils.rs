@@ -0,0 +1,668 @@
+use std::ops::Range;
+
+use chromosome::Chromosome;
+use rand::{Rng, RngCore};
+
+use crate::utils::Float;
+
+/// Total number of genes in bug genome. Genomes saved before `BRAIN_THIRD_EXTRA_WEIGHT_GENES`
+/// were added are 256 genes long, and those saved before `BRAIN_STING_GENES` are 280 genes long
+pub const GENOME_LEN: usize = 1128;
+
+/// Genes of brain (weights and biases of both perceptron layers)
+pub const BRAIN_GENES: Range<usize> = 0..208;
+pub const BRAIN_WEIGHT_GENES: Range<usize> = 0..192;
+pub const BRAIN_BIAS_GENES: Range<usize> = 192..208;
+
+/// Genes of body (max age, size, vision, color, etc.)
+pub const BODY_GENES: Range<usize> = 208..224;
+
+/// Weights of input neurons 16, 17 and 18 (3 per neuron of the first layer).
+/// They take the tail of former body genes which was never expressed, so old genomes stay valid
+pub const BRAIN_EXTRA_WEIGHT_GENES: Range<usize> = 232..256;
+
+/// Weights of input neuron 19 (1 per neuron of the first layer). Taken from the unexpressed
+/// tail of body genes as well
+pub const BRAIN_SECOND_EXTRA_WEIGHT_GENES: Range<usize> = 224..232;
+
+/// Weights of input neurons 20, 21 and 22 (3 per neuron of the first layer). Appended to the end
+/// of genome: missing weights of shorter genomes are zero
+pub const BRAIN_THIRD_EXTRA_WEIGHT_GENES: Range<usize> = 256..280;
+
+/// Weights (8) and bias of output neuron 8 (sting). Appended to the end of genome: shorter
+/// genomes never sting
+pub const BRAIN_STING_GENES: Range<usize> = 280..289;
+
+/// Venom dose injected by one sting. Missing gene means no venom
+pub const VENOM_GENES: Range<usize> = 289..290;
+
+/// Armor which protects from venom and parasites but makes movement more expensive.
+/// Missing gene means no armor
+pub const ARMOR_GENES: Range<usize> = 290..291;
+
+/// Camouflage which makes bug visible to others only from closer distance.
+/// Missing gene means no camouflage
+pub const CAMOUFLAGE_GENES: Range<usize> = 291..292;
+
+/// Weights of input neuron 23 (signal of nearest bug, 1 per neuron of the first layer).
+/// Missing weights of shorter genomes are zero
+pub const BRAIN_SIGNAL_INPUT_GENES: Range<usize> = 292..300;
+
+/// Weights (8) and bias of output neuron 9 (signal). Shorter genomes never glow
+pub const BRAIN_SIGNAL_OUTPUT_GENES: Range<usize> = 300..309;
+
+/// Weights of input neurons 24 and 25 (memory fed back from the previous tick, 2 per neuron of
+/// the first layer). Missing weights of shorter genomes are zero
+pub const BRAIN_MEMORY_INPUT_GENES: Range<usize> = 309..325;
+
+/// Weights (8) and bias of output neurons 10 and 11 (memory). Shorter genomes remember nothing
+pub const BRAIN_MEMORY_OUTPUT_GENES: Range<usize> = 325..343;
+
+/// Number of neurons in hidden layer of brain. Missing gene means 8 neurons (the fixed size
+/// brain had before)
+pub const BRAIN_HIDDEN_SIZE_GENES: Range<usize> = 343..344;
+
+/// Hidden neurons 8..16 of brain, each is 39 genes: weights of all 26 inputs, bias and weights
+/// of it in the first 12 outputs. Neurons beyond the hidden layer size are not expressed
+pub const BRAIN_EXTRA_HIDDEN_GENES: Range<usize> = 344..656;
+
+/// Output neurons 12..16 of brain (eat, attack, emit pheromone, feed offspring), each is
+/// 17 genes: weights of all 16 possible hidden neurons and bias. Shorter genomes keep
+/// eating, emitting pheromone and feeding offspring, but never attack
+pub const BRAIN_ACTUATOR_GENES: Range<usize> = 656..724;
+
+/// Weights of input neurons 26..34 (distance and direction of the 2nd and 3rd nearest food
+/// and bug) in all 16 possible hidden neurons, 8 per neuron. Missing weights are zero
+pub const BRAIN_NEIGHBOR_INPUT_GENES: Range<usize> = 724..852;
+
+/// Weights of input neurons 34..42 (retina sectors, see `VisionMode::Retina`) in all 16
+/// possible hidden neurons, 8 per neuron. Missing weights are zero
+pub const BRAIN_RETINA_INPUT_GENES: Range<usize> = 852..980;
+
+/// Weights of input neurons 42 and 43 (odor intensity and gradient direction) in all 16
+/// possible hidden neurons, 2 per neuron. Missing weights are zero
+pub const BRAIN_ODOR_INPUT_GENES: Range<usize> = 980..1012;
+
+/// Weights of input neurons 44 and 45 (kind of object touched and direction to it) in all 16
+/// possible hidden neurons, 2 per neuron. Missing weights are zero
+pub const BRAIN_TOUCH_INPUT_GENES: Range<usize> = 1012..1044;
+
+/// Mutation rate of offspring and of irradiation: multipliers of mutation scale and of mutation
+/// probability, each is 2 raised to gene value (clamped by `MAX_MUTATION_RATE_EXPONENT`).
+/// Missing genes keep default rates
+pub const MUTATION_RATE_GENES: Range<usize> = 1044..1046;
+
+/// Mutation rate multipliers are in range `1/2^MAX..2^MAX`
+pub const MAX_MUTATION_RATE_EXPONENT: Float = 3.;
+
+/// Crossover used when this chromosome is combined with another one (see `CrossoverKind::of`).
+/// Missing gene means one point crossover
+pub const CROSSOVER_GENES: Range<usize> = 1046..1047;
+
+/// Weights of input neuron 46 (strength of foreign territory) in all 16 possible hidden
+/// neurons, 1 per neuron. Missing weights are zero
+pub const BRAIN_TERRITORY_INPUT_GENES: Range<usize> = 1047..1063;
+
+/// Weights of input neurons 47..50 (number, mean heading and mean speed of bugs seen) in all 16
+/// possible hidden neurons, 3 per neuron. Missing weights are zero
+pub const BRAIN_FLOCK_INPUT_GENES: Range<usize> = 1063..1111;
+
+/// Weights of input neuron 50 (distance to wall ahead) in all 16 possible hidden neurons,
+/// 1 per neuron. Missing weights are zero
+pub const BRAIN_WALL_INPUT_GENES: Range<usize> = 1111..1127;
+
+/// Ability to gain energy from light while standing still at the cost of lower max speed.
+/// Missing gene means no photosynthesis
+pub const PHOTOSYNTHESIS_GENES: Range<usize> = 1127..1128;
+
+/// Names of body genes which are actually expressed (offsets from `BODY_GENES.start`)
+pub const BODY_GENE_NAMES: [&str; 16] = [
+    "max age",
+    "size",
+    "baby charge",
+    "vision",
+    "red",
+    "green",
+    "blue",
+    "digging",
+    "voice pitch",
+    "swim efficiency",
+    "protein digestion",
+    "carb digestion",
+    "fat digestion",
+    "stomach capacity",
+    "digestion rate",
+    "basal metabolism",
+];
+
+/// How a block of genes is mutated: each gene is shifted by a uniform value in `-scale..scale`
+/// with given probability
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MutationProfile {
+    pub scale: Float,
+    pub probability: Float,
+}
+
+impl MutationProfile {
+    /// Profile with scale and probability multiplied by those of `rate`
+    pub fn scaled(self, rate: MutationProfile) -> Self {
+        Self {
+            scale: self.scale * rate.scale,
+            probability: (self.probability * rate.probability).min(1.),
+        }
+    }
+}
+
+/// Multipliers of mutation scale and probability encoded in `MUTATION_RATE_GENES`
+pub fn mutation_rate(chromosome: &Chromosome<Float>) -> MutationProfile {
+    let multiplier = |i: usize| {
+        let gene = chromosome
+            .genes
+            .get(MUTATION_RATE_GENES.start + i)
+            .copied()
+            .unwrap_or(0.);
+        (2. as Float).powf(gene.clamp(-MAX_MUTATION_RATE_EXPONENT, MAX_MUTATION_RATE_EXPONENT))
+    };
+    MutationProfile {
+        scale: multiplier(0),
+        probability: multiplier(1),
+    }
+}
+
+/// `bug_mutation_profiles` scaled by mutation rate of `chromosome` (see `MUTATION_RATE_GENES`)
+pub fn bug_mutation_profiles_of(chromosome: &Chromosome<Float>) -> [GeneBlock; 27] {
+    let rate = mutation_rate(chromosome);
+    bug_mutation_profiles().map(|block| GeneBlock {
+        profile: block.profile.scaled(rate),
+        ..block
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneBlock {
+    pub genes: Range<usize>,
+    pub profile: MutationProfile,
+}
+
+/// Mutation profiles of bug genome used on reproduction.
+/// Body genes are scales and ratios close to 1, so they are mutated more gently than brain
+pub fn bug_mutation_profiles() -> [GeneBlock; 27] {
+    [
+        GeneBlock {
+            genes: BRAIN_WEIGHT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_EXTRA_WEIGHT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_SECOND_EXTRA_WEIGHT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_THIRD_EXTRA_WEIGHT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_STING_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_SIGNAL_INPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_SIGNAL_OUTPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_MEMORY_INPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_MEMORY_OUTPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_EXTRA_HIDDEN_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_ACTUATOR_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_NEIGHBOR_INPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_RETINA_INPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_ODOR_INPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_TOUCH_INPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_TERRITORY_INPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_FLOCK_INPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_WALL_INPUT_GENES,
+            profile: MutationProfile {
+                scale: 0.8,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: MUTATION_RATE_GENES,
+            profile: MutationProfile {
+                scale: 0.1,
+                probability: 0.02,
+            },
+        },
+        GeneBlock {
+            genes: CROSSOVER_GENES,
+            profile: MutationProfile {
+                scale: 0.1,
+                probability: 0.02,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_BIAS_GENES,
+            profile: MutationProfile {
+                scale: 0.4,
+                probability: 0.01,
+            },
+        },
+        GeneBlock {
+            genes: BODY_GENES,
+            profile: MutationProfile {
+                scale: 0.1,
+                probability: 0.02,
+            },
+        },
+        GeneBlock {
+            genes: VENOM_GENES,
+            profile: MutationProfile {
+                scale: 0.1,
+                probability: 0.02,
+            },
+        },
+        GeneBlock {
+            genes: ARMOR_GENES,
+            profile: MutationProfile {
+                scale: 0.1,
+                probability: 0.02,
+            },
+        },
+        GeneBlock {
+            genes: CAMOUFLAGE_GENES,
+            profile: MutationProfile {
+                scale: 0.1,
+                probability: 0.02,
+            },
+        },
+        GeneBlock {
+            genes: PHOTOSYNTHESIS_GENES,
+            profile: MutationProfile {
+                scale: 0.1,
+                probability: 0.02,
+            },
+        },
+        GeneBlock {
+            genes: BRAIN_HIDDEN_SIZE_GENES,
+            profile: MutationProfile {
+                scale: 0.1,
+                probability: 0.02,
+            },
+        },
+    ]
+}
+
+/// Mutate genes block by block. Genes not covered by any block are left untouched
+pub fn mutate_blocks<R: RngCore>(
+    chromosome: &mut Chromosome<Float>,
+    blocks: &[GeneBlock],
+    rng: &mut R,
+) {
+    for block in blocks {
+        let end = block.genes.end.min(chromosome.genes.len());
+        let start = block.genes.start.min(end);
+        let profile = block.profile;
+        if profile.scale <= 0. || profile.probability <= 0. {
+            continue;
+        }
+        for gene in &mut chromosome.genes[start..end] {
+            if rng.gen_bool(profile.probability.min(1.)) {
+                *gene += rng.gen_range(-profile.scale..profile.scale);
+            }
+        }
+    }
+}
+
+pub fn mutated_blocks<R: RngCore>(
+    mut chromosome: Chromosome<Float>,
+    blocks: &[GeneBlock],
+    rng: &mut R,
+) -> Chromosome<Float> {
+    mutate_blocks(&mut chromosome, blocks, rng);
+    chromosome
+}
+
+/// How genes of two parents are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossoverKind {
+    OnePoint,
+    TwoPoint,
+    Uniform,
+}
+
+impl CrossoverKind {
+    /// Kind encoded in `CROSSOVER_GENES`: below 0.5 is one point, below 1.5 is two point and
+    /// the rest is uniform
+    pub fn of(chromosome: &Chromosome<Float>) -> Self {
+        match chromosome.genes.get(CROSSOVER_GENES.start).copied() {
+            Some(gene) if gene >= 1.5 => Self::Uniform,
+            Some(gene) if gene >= 0.5 => Self::TwoPoint,
+            _ => Self::OnePoint,
+        }
+    }
+}
+
+/// Combine parents with crossover selected by genes of `a`. Child is as long as `a`: only the
+/// part common to both parents is crossed, the tail of longer `a` is copied as is
+pub fn crossover<R: RngCore>(
+    a: &Chromosome<Float>,
+    b: &Chromosome<Float>,
+    rng: &mut R,
+) -> Chromosome<Float> {
+    match CrossoverKind::of(a) {
+        CrossoverKind::OnePoint => one_point_crossover(a, b, rng),
+        CrossoverKind::TwoPoint => two_point_crossover(a, b, rng),
+        CrossoverKind::Uniform => uniform_crossover(a, b, rng),
+    }
+}
+
+/// Genes of `b` before random point and genes of `a` after it
+pub fn one_point_crossover<R: RngCore>(
+    a: &Chromosome<Float>,
+    b: &Chromosome<Float>,
+    rng: &mut R,
+) -> Chromosome<Float> {
+    let point = rng.gen_range(0..=a.genes.len().min(b.genes.len()));
+    let mut genes = a.genes.clone();
+    genes[..point].copy_from_slice(&b.genes[..point]);
+    Chromosome { genes }
+}
+
+/// Genes of `b` between two random points and genes of `a` outside of them
+pub fn two_point_crossover<R: RngCore>(
+    a: &Chromosome<Float>,
+    b: &Chromosome<Float>,
+    rng: &mut R,
+) -> Chromosome<Float> {
+    let len = a.genes.len().min(b.genes.len());
+    let (first, second) = (rng.gen_range(0..=len), rng.gen_range(0..=len));
+    let segment = first.min(second)..first.max(second);
+    let mut genes = a.genes.clone();
+    genes[segment.clone()].copy_from_slice(&b.genes[segment]);
+    Chromosome { genes }
+}
+
+/// Each gene is taken from either parent with equal probability
+pub fn uniform_crossover<R: RngCore>(
+    a: &Chromosome<Float>,
+    b: &Chromosome<Float>,
+    rng: &mut R,
+) -> Chromosome<Float> {
+    let mut genes = a.genes.clone();
+    for (gene, other) in genes.iter_mut().zip(&b.genes) {
+        if rng.gen_bool(0.5) {
+            *gene = *other;
+        }
+    }
+    Chromosome { genes }
+}
+
+/// Insert a copy of random segment (at most `max_len` genes) right after the original one.
+/// Meant for variable-length genomes: bug genome has fixed layout and must not be duplicated
+pub fn duplicate_segment<R: RngCore>(
+    chromosome: &mut Chromosome<Float>,
+    max_len: usize,
+    rng: &mut R,
+) -> Range<usize> {
+    let segment = random_segment(chromosome.genes.len(), max_len, rng);
+    let copy = chromosome.genes[segment.clone()].to_vec();
+    chromosome.genes.splice(segment.end..segment.end, copy);
+    segment
+}
+
+/// Remove random segment (at most `max_len` genes).
+/// Meant for variable-length genomes: bug genome has fixed layout and must not be shortened
+pub fn delete_segment<R: RngCore>(
+    chromosome: &mut Chromosome<Float>,
+    max_len: usize,
+    rng: &mut R,
+) -> Range<usize> {
+    let segment = random_segment(chromosome.genes.len(), max_len, rng);
+    chromosome.genes.drain(segment.clone());
+    segment
+}
+
+fn random_segment<R: RngCore>(genes_count: usize, max_len: usize, rng: &mut R) -> Range<usize> {
+    let max_len = max_len.min(genes_count);
+    if max_len == 0 {
+        return 0..0;
+    }
+    let len = rng.gen_range(1..=max_len);
+    let start = rng.gen_range(0..=genes_count - len);
+    start..start + len
+}
+
+#[cfg(test)]
+mod tests {
+    use chromosome::Chromosome;
+    use rand::SeedableRng as _;
+    use rand_pcg::Pcg64;
+
+    use super::{
+        bug_mutation_profiles, bug_mutation_profiles_of, crossover, delete_segment,
+        duplicate_segment, mutate_blocks, one_point_crossover, two_point_crossover,
+        uniform_crossover, CrossoverKind, GeneBlock, MutationProfile, CROSSOVER_GENES, GENOME_LEN,
+        MUTATION_RATE_GENES,
+    };
+
+    fn zeros(len: usize) -> Chromosome<f64> {
+        Chromosome {
+            genes: vec![0.; len],
+        }
+    }
+
+    #[test]
+    fn block_mutation_follows_profiles() {
+        let mut rng = Pcg64::seed_from_u64(0);
+        let blocks = [
+            GeneBlock {
+                genes: 0..100,
+                profile: MutationProfile {
+                    scale: 1.,
+                    probability: 0.5,
+                },
+            },
+            GeneBlock {
+                genes: 100..200,
+                profile: MutationProfile {
+                    scale: 0.01,
+                    probability: 1.,
+                },
+            },
+        ];
+
+        let rounds = 200;
+        let mut mutated_count = 0;
+        let mut sum = 0.;
+        for _ in 0..rounds {
+            let mut chromosome = zeros(GENOME_LEN);
+            mutate_blocks(&mut chromosome, &blocks, &mut rng);
+            let (first, rest) = chromosome.genes.split_at(100);
+            let (second, uncovered) = rest.split_at(100);
+
+            assert!(first.iter().all(|gene| gene.abs() < 1.));
+            assert!(second.iter().all(|gene| *gene != 0. && gene.abs() < 0.01));
+            assert!(uncovered.iter().all(|gene| *gene == 0.));
+
+            mutated_count += first.iter().filter(|gene| **gene != 0.).count();
+            sum += first.iter().sum::<f64>();
+        }
+
+        let frequency = mutated_count as f64 / (rounds * 100) as f64;
+        assert!((frequency - 0.5).abs() < 0.02, "frequency: {}", frequency);
+        let mean = sum / mutated_count as f64;
+        assert!(mean.abs() < 0.02, "mean: {}", mean);
+    }
+
+    #[test]
+    fn duplication_and_deletion_change_length() {
+        let mut rng = Pcg64::seed_from_u64(0);
+        for _ in 0..100 {
+            let original = Chromosome {
+                genes: (0..32).map(|i| i as f64).collect(),
+            };
+
+            let mut duplicated = original.clone();
+            let segment = duplicate_segment(&mut duplicated, 8, &mut rng);
+            assert!((1..=8).contains(&segment.len()));
+            assert_eq!(duplicated.genes.len(), 32 + segment.len());
+            assert_eq!(
+                duplicated.genes[segment.clone()],
+                duplicated.genes[segment.end..segment.end + segment.len()]
+            );
+
+            let mut deleted = original.clone();
+            let segment = delete_segment(&mut deleted, 8, &mut rng);
+            assert_eq!(deleted.genes.len(), 32 - segment.len());
+            assert_eq!(
+                deleted.genes[..segment.start],
+                original.genes[..segment.start]
+            );
+            assert_eq!(
+                deleted.genes[segment.start..],
+                original.genes[segment.end..]
+            );
+        }
+    }
+
+    #[test]
+    fn crossover_keeps_gene_count_and_is_deterministic() {
+        let a = Chromosome {
+            genes: (0..64).map(|i| i as f64).collect(),
+        };
+        let b = Chromosome {
+            genes: (0..48).map(|i| -(i as f64) - 1.).collect(),
+        };
+        let operators: [fn(&Chromosome<f64>, &Chromosome<f64>, &mut Pcg64) -> Chromosome<f64>; 3] =
+            [one_point_crossover, two_point_crossover, uniform_crossover];
+        for operator in operators {
+            for seed in 0..20 {
+                let child = operator(&a, &b, &mut Pcg64::seed_from_u64(seed));
+                assert_eq!(
+                    child.genes,
+                    operator(&a, &b, &mut Pcg64::seed_from_u64(seed)).genes
+                );
+                assert_eq!(child.genes.len(), a.genes.len());
+                for (i, gene) in child.genes.iter().enumerate() {
+                    assert!(*gene == a.genes[i] || b.genes.get(i) == Some(gene));
+                }
+                assert_eq!(child.genes[48..], a.genes[48..]);
+            }
+        }
+
+        let mut uniform = zeros(GENOME_LEN);
+        assert_eq!(CrossoverKind::of(&uniform), CrossoverKind::OnePoint);
+        uniform.genes[CROSSOVER_GENES.start] = 2.;
+        assert_eq!(CrossoverKind::of(&uniform), CrossoverKind::Uniform);
+        assert_eq!(
+            crossover(&uniform, &a, &mut Pcg64::seed_from_u64(0)).genes,
+            uniform_crossover(&uniform, &a, &mut Pcg64::seed_from_u64(0)).genes
+        );
+    }
+
+    #[test]
+    fn mutation_rate_genes_scale_profiles() {
+        let short = zeros(MUTATION_RATE_GENES.start);
+        assert_eq!(bug_mutation_profiles_of(&short), bug_mutation_profiles());
+
+        let mut genome = zeros(GENOME_LEN);
+        genome.genes[MUTATION_RATE_GENES.start] = 1.;
+        genome.genes[MUTATION_RATE_GENES.start + 1] = -100.;
+        for (scaled, block) in bug_mutation_profiles_of(&genome)
+            .iter()
+            .zip(bug_mutation_profiles())
+        {
+            assert_eq!(scaled.genes, block.genes);
+            assert_eq!(scaled.profile.scale, block.profile.scale * 2.);
+            assert_eq!(scaled.profile.probability, block.profile.probability / 8.);
+        }
+    }
+}
@@ -1,9 +1,16 @@
 use crate::{
-    math::{NoNeg, Point, Rect},
+    math::{noneg_float, NoNeg, Point, Rect},
     utils::Float,
 };
-use serde::{Deserialize, Serialize};
-use std::{marker::PhantomData, usize};
+use rand::{Rng as _, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    marker::PhantomData,
+    ops::Deref,
+    usize,
+};
 
 #[derive(Serialize, Deserialize)]
 pub(crate) struct Chunk<T> {
@@ -43,7 +50,14 @@ pub(crate) trait Position {
     fn position(&self) -> Point<Float>;
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Item which can be found by id in `IdIndexedVec`
+pub(crate) trait Identified {
+    type Id: Clone + Eq + Hash;
+
+    fn id(&self) -> Self::Id;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum ChunkType {
     FromTopLeft,
     FromTopRight,
@@ -51,6 +65,9 @@ enum ChunkType {
     FromBottomRight,
 }
 
+/// Number of item buffers of collected chunks kept for reuse (see `ChunkedVec::spare_items`)
+const MAX_SPARE_CHUNKS: usize = 256;
+
 fn get_or_insert_mut<T, F>(v: &mut Vec<T>, i: usize, initialize: F) -> &mut T
 where
     F: FnMut() -> T,
@@ -111,6 +128,10 @@ impl ChunkType {
     }
 }
 
+/// Items grouped into chunks of `W`x`H` by their positions. Chunks are visited in a fixed order by
+/// a single thread: splitting them into tiles for worker threads would need a halo exchange for
+/// interactions crossing tile borders, and bugs (`Rc<RefCell<Bug>>`) can not be sent to other
+/// threads anyway
 #[derive(Serialize, Deserialize)]
 pub(crate) struct ChunkedVec<T, const W: usize, const H: usize> {
     from_top_left: Vec<Vec<Chunk<T>>>,
@@ -118,6 +139,11 @@ pub(crate) struct ChunkedVec<T, const W: usize, const H: usize> {
     from_bottom_left: Vec<Vec<Chunk<T>>>,
     from_bottom_right: Vec<Vec<Chunk<T>>>,
     len: usize,
+    /// Emptied item buffers of collected chunks. New chunks take them instead of allocating, so
+    /// chunks which appear and disappear under churn (e.g. food spawned and eaten at the edge of
+    /// populated area) keep their storage
+    #[serde(skip)]
+    spare_items: Vec<Vec<T>>,
 }
 
 impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
@@ -148,6 +174,46 @@ impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
         self.len
     }
 
+    /// `n` items picked uniformly at random (with replacement). Chunk of each item is selected
+    /// by occupancy with binary search, so items themselves are never iterated
+    pub(crate) fn sample<R: RngCore>(&self, n: usize, rng: &mut R) -> Vec<&T> {
+        let chunks: Vec<&Chunk<T>> = self
+            .from_top_left
+            .iter()
+            .chain(self.from_top_right.iter())
+            .chain(self.from_bottom_left.iter())
+            .chain(self.from_bottom_right.iter())
+            .flatten()
+            .filter(|chunk| !chunk.items.is_empty())
+            .collect();
+        // number of items in all chunks up to and including each one
+        let ends: Vec<usize> = chunks
+            .iter()
+            .scan(0, |sum, chunk| {
+                *sum += chunk.items.len();
+                Some(*sum)
+            })
+            .collect();
+        let Some(&total) = ends.last() else {
+            return Default::default();
+        };
+        (0..n)
+            .map(|_| {
+                let i = rng.gen_range(0..total);
+                let chunk = ends.partition_point(|end| *end <= i);
+                let start = chunk.checked_sub(1).map(|prev| ends[prev]).unwrap_or(0);
+                &chunks[chunk].items[i - start]
+            })
+            .collect()
+    }
+
+    /// Number of items in chunk which contains `position`
+    pub(crate) fn chunk_len_at(&self, position: Point<Float>) -> usize {
+        self.get_chunk(RawChunkIndex::from_position::<W, H>(position).into())
+            .map(|chunk| chunk.items.len())
+            .unwrap_or(0)
+    }
+
     pub(crate) fn retain<F>(&mut self, mut f: F)
     where
         F: FnMut(&T) -> bool,
@@ -159,22 +225,46 @@ impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
     where
         F: FnMut(&mut T) -> bool,
     {
+        let mut removed = 0;
         for tp in ChunkType::values() {
             let rows = tp.clone().part_mut(self);
             for y in 0..rows.len() {
                 let cols = &mut rows[y];
                 for x in 0..cols.len() {
                     let items = &mut cols[x].items;
-                    for i in 0..items.len() {
-                        if !f(&mut items[i]) {
-                            items.remove(i);
-                            self.len -= 1;
-                            return;
-                        }
+                    let len = items.len();
+                    items.retain_mut(|item| f(item));
+                    removed += len - items.len();
+                }
+            }
+        }
+        self.len -= removed;
+    }
+
+    /// Remove items which satisfy `f` and return them. Takes a single pass over items
+    pub(crate) fn take_if<F>(&mut self, mut f: F) -> Vec<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut taken: Vec<T> = Default::default();
+        for tp in ChunkType::values() {
+            let rows = tp.clone().part_mut(self);
+            for y in 0..rows.len() {
+                let cols = &mut rows[y];
+                for x in 0..cols.len() {
+                    let items = &mut cols[x].items;
+                    if !items.iter().any(&mut f) {
+                        continue;
                     }
+                    let (chunk_taken, kept): (Vec<T>, Vec<T>) =
+                        std::mem::take(items).into_iter().partition(&mut f);
+                    *items = kept;
+                    taken.extend(chunk_taken);
                 }
             }
         }
+        self.len -= taken.len();
+        taken
     }
 
     /// return true if any removed
@@ -214,66 +304,48 @@ impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
         self.len += 1;
     }
 
-    pub(crate) fn index_of<P>(&self, mut predicate: P) -> Option<Index>
-    where
-        P: FnMut(&T) -> bool,
-    {
-        for tp in ChunkType::values() {
-            let rows = tp.clone().part(self);
-            for y in 0..rows.len() {
-                let cols = &rows[y];
-                for x in 0..cols.len() {
-                    let items = &rows[y][x].items;
-                    for i in 0..items.len() {
-                        if predicate(&items[i]) {
-                            return Some(Index {
-                                chunk_index: ChunkIndex {
-                                    tp: tp.clone(),
-                                    x,
-                                    y,
-                                },
-                                item_index: i,
-                            });
-                        }
-                    }
-                }
-            }
-        }
-        None
+    pub(crate) fn remove(&mut self, index: Index) -> T {
+        self.len -= 1;
+        self[index.chunk_index].items.remove(index.item_index)
     }
 
-    /// gives index of element which satisfy predicate. but search area is limited to certain range
-    pub(crate) fn index_of_in_range<P>(
+    pub(crate) fn circular_traverse_iter(
         &self,
-        mut predicate: P,
         position: Point<Float>,
         range: NoNeg<Float>,
-    ) -> Option<Index>
-    where
-        P: FnMut(&T) -> bool,
-    {
-        self.circular_traverse_iter(position, range)
-            .find_map(|chunk_index| {
-                self.get_chunk(chunk_index.clone()).and_then(|chunk| {
-                    chunk.index_of_impl(&mut predicate).map(|item_index| Index {
-                        chunk_index,
-                        item_index,
-                    })
-                })
-            })
+    ) -> CircularTraverseIterator<T, W, H> {
+        CircularTraverseIterator::new(position, range)
     }
 
-    pub(crate) fn remove(&mut self, index: Index) -> T {
-        self.len -= 1;
-        self[index.chunk_index].items.remove(index.item_index)
+    /// Call `f` for each item of chunks intersecting circle. Items themselves can be slightly
+    /// outside of it, so exact distance is up to caller
+    pub(crate) fn for_each_in_range_mut<F>(
+        &mut self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        mut f: F,
+    ) where
+        F: FnMut(&mut T),
+    {
+        // positions are rounded when items are assigned to chunks
+        let range = range + noneg_float(1.);
+        for chunk_index in CircularTraverseIterator::<T, W, H>::new(position, range) {
+            if let Some(chunk) = self.get_chunk_mut(chunk_index) {
+                chunk.items.iter_mut().for_each(&mut f);
+            }
+        }
     }
 
-    pub(crate) fn circular_traverse_iter(
+    /// Items of chunks intersecting circle. Items themselves can be slightly outside of it, so
+    /// exact distance is up to caller
+    pub(crate) fn iter_in_range(
         &self,
         position: Point<Float>,
         range: NoNeg<Float>,
-    ) -> CircularTraverseIterator<T, W, H> {
-        CircularTraverseIterator::new(position, range)
+    ) -> impl Iterator<Item = &T> {
+        CircularTraverseIterator::<T, W, H>::new(position, range)
+            .filter_map(|chunk_index| self.get_chunk(chunk_index))
+            .flat_map(|chunk| chunk.items.iter())
     }
 
     fn get_chunk(&self, i: ChunkIndex) -> Option<&Chunk<T>> {
@@ -304,6 +376,37 @@ impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
         }
     }
 
+    /// Visits chunks until the nearest of visited candidates is closer than any unvisited chunk
+    fn find_nearest_impl<'a, B, F>(
+        &'a self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        mut nearest_in_chunk: F,
+    ) -> Option<(B, NoNeg<Float>)>
+    where
+        F: FnMut(&'a Chunk<T>) -> Option<(B, NoNeg<Float>)>,
+    {
+        let mut chunks = self.circular_traverse_iter(position, range);
+        let mut nearest: Option<(B, NoNeg<Float>)> = None;
+        while let Some(chunk_index) = chunks.next() {
+            if let Some(candidate) = self.get_chunk(chunk_index).and_then(&mut nearest_in_chunk) {
+                if nearest
+                    .as_ref()
+                    .map(|nearest| candidate.1 < nearest.1)
+                    .unwrap_or(true)
+                {
+                    nearest = Some(candidate);
+                }
+            }
+            if let Some((_, dst)) = &nearest {
+                if chunks.unvisited_distance() >= dst.unwrap() {
+                    break;
+                }
+            }
+        }
+        nearest
+    }
+
     pub(crate) fn find_nearest(
         &self,
         position: Point<Float>,
@@ -312,55 +415,114 @@ impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
     where
         T: Position,
     {
-        self.circular_traverse_iter(position, range).find_map(
-            |chunk_index| -> Option<(&T, NoNeg<Float>)> {
-                self.get_chunk(chunk_index).and_then(|chunk| {
-                    chunk
-                        .items
-                        .iter()
-                        .filter_map(|other| {
-                            let dst = NoNeg::wrap((position - other.position()).len()).unwrap();
-                            if dst < range {
-                                Some((other, dst))
-                            } else {
-                                None
-                            }
-                        })
-                        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        self.find_nearest_impl(position, range, |chunk| {
+            chunk
+                .items
+                .iter()
+                .filter_map(|other| {
+                    let dst = NoNeg::wrap((position - other.position()).len()).unwrap();
+                    if dst < range {
+                        Some((other, dst))
+                    } else {
+                        None
+                    }
                 })
-            },
-        )
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        })
     }
 
     pub(crate) fn find_nearest_filter_map<'a, B, F>(
         &'a self,
         position: Point<Float>,
         range: NoNeg<Float>,
-        f: F,
+        mut f: F,
     ) -> Option<(B, NoNeg<Float>)>
     where
         B: Position,
         F: FnMut(&'a T) -> Option<B> + Clone,
     {
-        self.circular_traverse_iter(position, range).find_map(
-            |chunk_index| -> Option<(B, NoNeg<Float>)> {
-                self.get_chunk(chunk_index).and_then(|chunk| {
-                    chunk
-                        .items
-                        .iter()
-                        .filter_map(f.clone())
-                        .filter_map(|other| {
-                            let dst = NoNeg::wrap((position - other.position()).len()).unwrap();
-                            if dst < range {
-                                Some((other, dst))
-                            } else {
-                                None
-                            }
-                        })
-                        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        self.find_nearest_filter_map_scaled(position, range, move |item| {
+            f(item).map(|other| (other, noneg_float(1.)))
+        })
+    }
+
+    /// Like `find_nearest_filter_map`, but `f` also returns a factor (in range 0..=1) by which
+    /// range is scaled for that item: item with factor 0.5 is found only at half of the range.
+    /// Returned distance is the effective one (real distance divided by the factor)
+    pub(crate) fn find_nearest_filter_map_scaled<'a, B, F>(
+        &'a self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        f: F,
+    ) -> Option<(B, NoNeg<Float>)>
+    where
+        B: Position,
+        F: FnMut(&'a T) -> Option<(B, NoNeg<Float>)> + Clone,
+    {
+        // effective distance is never less than real one, so visiting chunks from near to far
+        // still finds the nearest item
+        self.find_nearest_impl(position, range, |chunk| {
+            chunk
+                .items
+                .iter()
+                .filter_map(f.clone())
+                .filter_map(|(other, factor)| {
+                    let factor = factor.unwrap().min(1.);
+                    if factor <= 0. {
+                        return None;
+                    }
+                    let dst = NoNeg::wrap((position - other.position()).len() / factor).unwrap();
+                    if dst < range {
+                        Some((other, dst))
+                    } else {
+                        None
+                    }
                 })
-            },
-        )
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        })
+    }
+
+    /// Like `find_nearest_filter_map_scaled`, but returns up to `n` nearest items sorted from
+    /// near to far
+    pub(crate) fn find_n_nearest_filter_map_scaled<'a, B, F>(
+        &'a self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        n: usize,
+        mut f: F,
+    ) -> Vec<(B, NoNeg<Float>)>
+    where
+        B: Position,
+        F: FnMut(&'a T) -> Option<(B, NoNeg<Float>)>,
+    {
+        let mut nearest: Vec<(B, NoNeg<Float>)> = Vec::with_capacity(n + 1);
+        if n == 0 {
+            return nearest;
+        }
+        let mut chunks = self.circular_traverse_iter(position, range);
+        while let Some(chunk_index) = chunks.next() {
+            if let Some(chunk) = self.get_chunk(chunk_index) {
+                for (other, factor) in chunk.items.iter().filter_map(&mut f) {
+                    let factor = factor.unwrap().min(1.);
+                    if factor <= 0. {
+                        continue;
+                    }
+                    let dst = NoNeg::wrap((position - other.position()).len() / factor).unwrap();
+                    if dst >= range {
+                        continue;
+                    }
+                    let i = nearest.partition_point(|(_, d)| *d <= dst);
+                    if i < n {
+                        nearest.insert(i, (other, dst));
+                        nearest.truncate(n);
+                    }
+                }
+            }
+            if nearest.len() == n && chunks.unvisited_distance() >= nearest[n - 1].1.unwrap() {
+                break;
+            }
+        }
+        nearest
     }
 
     pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
@@ -386,9 +548,24 @@ impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
     }
 
     fn get_or_insert_mut(&mut self, i: ChunkIndex) -> &mut Chunk<T> {
-        let part = i.tp.part_mut(self);
+        let Self {
+            from_top_left,
+            from_top_right,
+            from_bottom_left,
+            from_bottom_right,
+            spare_items,
+            ..
+        } = self;
+        let part = match i.tp {
+            ChunkType::FromTopLeft => from_top_left,
+            ChunkType::FromTopRight => from_top_right,
+            ChunkType::FromBottomLeft => from_bottom_left,
+            ChunkType::FromBottomRight => from_bottom_right,
+        };
         let inner_part = get_or_insert_mut(part, i.y, || Default::default());
-        get_or_insert_mut(inner_part, i.x, || Default::default())
+        get_or_insert_mut(inner_part, i.x, || Chunk {
+            items: spare_items.pop().unwrap_or_default(),
+        })
     }
 
     /// Move all items to chunks corresponding to their position
@@ -396,7 +573,17 @@ impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
     where
         T: Position,
     {
-        let mut recipes: Vec<(T, ChunkIndex)> = Default::default();
+        self.shuffle_with(|_, _, _| {})
+    }
+
+    /// Same as `shuffle` but `moved` is called with every moved item, its old chunk and its new
+    /// index. Items which stay in old chunks can change their indices as well
+    fn shuffle_with<F>(&mut self, mut moved: F)
+    where
+        T: Position,
+        F: FnMut(&T, &ChunkIndex, Index),
+    {
+        let mut recipes: Vec<(T, ChunkIndex, ChunkIndex)> = Default::default();
         for tp in ChunkType::values() {
             let rows = tp.clone().part_mut(self);
             for y in 0..rows.len() {
@@ -414,7 +601,7 @@ impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
                         let new_chunk_index: ChunkIndex =
                             RawChunkIndex::from_position::<W, H>(items[i].position()).into();
                         if chunk_index != new_chunk_index {
-                            recipes.push((items.remove(i), new_chunk_index));
+                            recipes.push((items.remove(i), chunk_index.clone(), new_chunk_index));
                         } else {
                             i += 1
                         }
@@ -423,24 +610,314 @@ impl<T, const W: usize, const H: usize> ChunkedVec<T, W, H> {
             }
         }
 
-        for (what, to_where) in recipes {
-            self.get_or_insert_mut(to_where).items.push(what);
+        for (what, from_where, to_where) in recipes {
+            let items = &mut self.get_or_insert_mut(to_where.clone()).items;
+            moved(
+                &what,
+                &from_where,
+                Index {
+                    chunk_index: to_where,
+                    item_index: items.len(),
+                },
+            );
+            items.push(what);
         }
     }
 
     pub(crate) fn collect_unused_chunks(&mut self) {
+        let mut spare_items = std::mem::take(&mut self.spare_items);
         for tp in ChunkType::values() {
             let rows = tp.clone().part_mut(self);
             for y in (0..rows.len()).rev() {
                 let cols = &mut rows[y];
-                remove_from_end_until(cols, |c| c.items.len() > 0);
+                while cols.last().map_or(false, |c| c.items.is_empty()) {
+                    let items = cols.pop().unwrap().items;
+                    if items.capacity() > 0 && spare_items.len() < MAX_SPARE_CHUNKS {
+                        spare_items.push(items);
+                    }
+                }
             }
             remove_from_end_until(rows, |x| x.len() > 0);
         }
+        self.spare_items = spare_items;
+    }
+}
+
+/// `ChunkedVec` which also maps ids of items to their indices, so that lookup by id takes
+/// constant time. Removal shifts items after the removed one, so indices of the rest of its chunk
+/// are updated as well. Read-only access goes through `Deref`, everything which moves items goes
+/// through this type to keep the map up to date
+pub(crate) struct IdIndexedVec<T: Identified, const W: usize, const H: usize> {
+    vec: ChunkedVec<T, W, H>,
+    indices: HashMap<T::Id, Index>,
+}
+
+impl<T: Identified, const W: usize, const H: usize> IdIndexedVec<T, W, H> {
+    /// Index of item with `id` if it is present
+    pub(crate) fn index_of_id(&self, id: &T::Id) -> Option<Index> {
+        self.indices.get(id).cloned()
+    }
+
+    pub(crate) fn push(&mut self, v: T)
+    where
+        T: Position,
+    {
+        let chunk_index: ChunkIndex = RawChunkIndex::from_position::<W, H>(v.position()).into();
+        let id = v.id();
+        self.vec.push(v);
+        let item_index = self.vec[chunk_index.clone()].items.len() - 1;
+        self.indices.insert(
+            id,
+            Index {
+                chunk_index,
+                item_index,
+            },
+        );
+    }
+
+    pub(crate) fn remove(&mut self, index: Index) -> T {
+        let item = self.vec.remove(index.clone());
+        self.indices.remove(&item.id());
+        self.reindex_chunk(index.chunk_index, index.item_index);
+        item
+    }
+
+    pub(crate) fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_mut(|elem| f(elem));
+    }
+
+    pub(crate) fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let Self { vec, indices } = self;
+        let mut removed = 0;
+        for tp in ChunkType::values() {
+            let rows = tp.clone().part_mut(vec);
+            for y in 0..rows.len() {
+                for x in 0..rows[y].len() {
+                    let items = &mut rows[y][x].items;
+                    let len = items.len();
+                    // index of the first removed item, the ones before it keep their indices
+                    let mut first_removed = None;
+                    let mut i = 0;
+                    items.retain_mut(|item| {
+                        let keep = f(item);
+                        if !keep {
+                            indices.remove(&item.id());
+                            first_removed.get_or_insert(i);
+                        }
+                        i += 1;
+                        keep
+                    });
+                    if let Some(first_removed) = first_removed {
+                        removed += len - items.len();
+                        let chunk_index = ChunkIndex {
+                            tp: tp.clone(),
+                            x,
+                            y,
+                        };
+                        Self::update_indices(indices, items, chunk_index, first_removed);
+                    }
+                }
+            }
+        }
+        vec.len -= removed;
+    }
+
+    /// return true if any removed
+    pub(crate) fn retain_by_position<F>(&mut self, position: Point<Float>, mut f: F) -> bool
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let chunk_index: ChunkIndex = RawChunkIndex::from_position::<W, H>(position).into();
+        let Some(item_index) = self
+            .vec
+            .get_chunk(chunk_index.clone())
+            .and_then(|chunk| chunk.index_of(|item| !f(item)))
+        else {
+            return false;
+        };
+        self.remove(Index {
+            chunk_index,
+            item_index,
+        });
+        true
+    }
+
+    /// Items must not change their ids (see `reindex`)
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.vec.iter_mut()
+    }
+
+    pub(crate) fn for_each_in_range_mut<F>(
+        &mut self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        f: F,
+    ) where
+        F: FnMut(&mut T),
+    {
+        self.vec.for_each_in_range_mut(position, range, f)
+    }
+
+    /// Move all items to chunks corresponding to their position
+    pub(crate) fn shuffle(&mut self)
+    where
+        T: Position,
+    {
+        let indices = &mut self.indices;
+        let mut left: HashSet<ChunkIndex> = Default::default();
+        self.vec.shuffle_with(|item, from_where, index| {
+            indices.insert(item.id(), index);
+            left.insert(from_where.clone());
+        });
+        for chunk_index in left {
+            self.reindex_chunk(chunk_index, 0);
+        }
+    }
+
+    pub(crate) fn collect_unused_chunks(&mut self) {
+        // only empty chunks are removed, so indices of the rest stay the same
+        self.vec.collect_unused_chunks()
+    }
+
+    /// Rebuild the map. Must be called after ids of items were changed in place
+    pub(crate) fn reindex(&mut self) {
+        let mut indices: HashMap<T::Id, Index> = Default::default();
+        for tp in ChunkType::values() {
+            let rows = tp.clone().part(&self.vec);
+            for y in 0..rows.len() {
+                for x in 0..rows[y].len() {
+                    let chunk_index = ChunkIndex {
+                        tp: tp.clone(),
+                        x,
+                        y,
+                    };
+                    Self::update_indices(&mut indices, &rows[y][x].items, chunk_index, 0);
+                }
+            }
+        }
+        self.indices = indices;
+    }
+
+    /// Update indices of items of chunk starting from `from`
+    fn reindex_chunk(&mut self, chunk_index: ChunkIndex, from: usize) {
+        let items = &self.vec[chunk_index.clone()].items;
+        Self::update_indices(&mut self.indices, items, chunk_index, from);
+    }
+
+    fn update_indices(
+        indices: &mut HashMap<T::Id, Index>,
+        items: &[T],
+        chunk_index: ChunkIndex,
+        from: usize,
+    ) {
+        for (item_index, item) in items.iter().enumerate().skip(from) {
+            indices.insert(
+                item.id(),
+                Index {
+                    chunk_index: chunk_index.clone(),
+                    item_index,
+                },
+            );
+        }
+    }
+}
+
+impl<T: Identified, const W: usize, const H: usize> Deref for IdIndexedVec<T, W, H> {
+    type Target = ChunkedVec<T, W, H>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.vec
+    }
+}
+
+impl<T: Identified, const W: usize, const H: usize> From<ChunkedVec<T, W, H>>
+    for IdIndexedVec<T, W, H>
+{
+    fn from(vec: ChunkedVec<T, W, H>) -> Self {
+        let mut result = Self {
+            vec,
+            indices: Default::default(),
+        };
+        result.reindex();
+        result
+    }
+}
+
+impl<T: Identified, const W: usize, const H: usize> std::ops::Index<Index>
+    for IdIndexedVec<T, W, H>
+{
+    type Output = T;
+    fn index<'a>(&'a self, i: Index) -> &'a T {
+        &self.vec[i]
+    }
+}
+
+impl<T: Identified, const W: usize, const H: usize> std::ops::IndexMut<Index>
+    for IdIndexedVec<T, W, H>
+{
+    /// Item must not change its id (see `IdIndexedVec::reindex`)
+    fn index_mut<'a>(&'a mut self, i: Index) -> &'a mut T {
+        &mut self.vec[i]
+    }
+}
+
+impl<T, const W: usize, const H: usize> FromIterator<T> for IdIndexedVec<T, W, H>
+where
+    T: Identified + Position,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = IdIndexedVec::default();
+        for v in iter {
+            vec.push(v);
+        }
+        vec
+    }
+}
+
+impl<T: Identified, const W: usize, const H: usize> IntoIterator for IdIndexedVec<T, W, H> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vec.into_iter()
+    }
+}
+
+impl<T: Identified, const W: usize, const H: usize> Default for IdIndexedVec<T, W, H> {
+    fn default() -> Self {
+        Self {
+            vec: Default::default(),
+            indices: Default::default(),
+        }
+    }
+}
+
+/// The map is not saved, it is rebuilt on load
+impl<T, const W: usize, const H: usize> Serialize for IdIndexedVec<T, W, H>
+where
+    T: Identified + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.vec.serialize(serializer)
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl<'de, T, const W: usize, const H: usize> Deserialize<'de> for IdIndexedVec<T, W, H>
+where
+    T: Identified + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(ChunkedVec::<T, W, H>::deserialize(deserializer)?.into())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct ChunkIndex {
     tp: ChunkType,
     x: usize,
@@ -454,6 +931,10 @@ pub struct RawChunkIndex {
 }
 
 impl RawChunkIndex {
+    pub(crate) fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
     pub fn x(&self) -> isize {
         self.x
     }
@@ -462,7 +943,7 @@ impl RawChunkIndex {
         self.y
     }
 
-    fn from_position<const W: usize, const H: usize>(position: Point<Float>) -> Self {
+    pub(crate) fn from_position<const W: usize, const H: usize>(position: Point<Float>) -> Self {
         Self {
             x: (position.x().round() / W as Float).floor() as isize,
             y: (position.y().round() / H as Float).floor() as isize,
@@ -572,6 +1053,26 @@ where
     }
 }
 
+impl<T, const W: usize, const H: usize> IntoIterator for ChunkedVec<T, W, H> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        [
+            self.from_top_left,
+            self.from_top_right,
+            self.from_bottom_left,
+            self.from_bottom_right,
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .flat_map(|c| c.items)
+        .collect::<Vec<_>>()
+        .into_iter()
+    }
+}
+
 impl<T, const W: usize, const H: usize> Default for ChunkedVec<T, W, H> {
     fn default() -> Self {
         Self {
@@ -580,98 +1081,478 @@ impl<T, const W: usize, const H: usize> Default for ChunkedVec<T, W, H> {
             from_bottom_left: Default::default(),
             from_bottom_right: Default::default(),
             len: 0,
+            spare_items: Default::default(),
         }
     }
 }
 
+/// Visits chunks which intersect circle ring by ring (rings are squares of chunks around the chunk
+/// containing center). Chunks of one ring are visited in near-to-far order.
+/// Iteration stops as soon as the next ring is entirely out of range
 pub(crate) struct CircularTraverseIterator<T, const W: usize, const H: usize> {
-    index: RawChunkIndex,
-    iteration: usize,
-    i: usize,
-    max_i: usize,
-    // rect: Rect<Float>,
+    center: RawChunkIndex,
     position: Point<Float>,
     range: NoNeg<Float>,
-    number_of_key_skips: usize,
+    ring: usize,
+    /// Not yet visited chunks of previous ring sorted from far to near
+    pending: Vec<(RawChunkIndex, Float)>,
     _dp: PhantomData<T>,
 }
 
 impl<T, const W: usize, const H: usize> CircularTraverseIterator<T, W, H> {
     fn new(position: Point<Float>, range: NoNeg<Float>) -> Self {
-        let index = RawChunkIndex::from_position::<W, H>(position);
         Self {
-            index: RawChunkIndex {
-                x: index.x - 1,
-                y: index.y,
-            },
-            iteration: 0,
-            i: 0,
-            max_i: 0,
+            center: RawChunkIndex::from_position::<W, H>(position),
             position,
             range,
-            number_of_key_skips: 0,
+            ring: 0,
+            pending: Default::default(),
             _dp: Default::default(),
         }
     }
+
+    /// Lower bound of distance from center to chunks which are not visited yet
+    pub(crate) fn unvisited_distance(&self) -> Float {
+        let ring_distance = self.ring_distance(self.ring);
+        self.pending
+            .last()
+            .map(|(_, dst_sqr)| dst_sqr.sqrt().min(ring_distance))
+            .unwrap_or(ring_distance)
+    }
+
+    /// Left top corner of chunk with index `(x, y)`. Positions are rounded when items are
+    /// assigned to chunks (see `RawChunkIndex::from_position`), so chunk `k` starts half a unit
+    /// before `k * W`
+    fn chunk_origin(x: isize, y: isize) -> (Float, Float) {
+        (x as Float * W as Float - 0.5, y as Float * H as Float - 0.5)
+    }
+
+    /// Distance from center to the nearest chunk of `ring`
+    fn ring_distance(&self, ring: usize) -> Float {
+        if ring == 0 {
+            return 0.;
+        }
+        let r = ring as isize;
+        let (x, y) = (*self.position.x(), *self.position.y());
+        let (left, top) = Self::chunk_origin(self.center.x - r + 1, self.center.y - r + 1);
+        let (right, bottom) = Self::chunk_origin(self.center.x + r, self.center.y + r);
+        (x - left).min(right - x).min(y - top).min(bottom - y)
+    }
+
+    fn chunk_distance_sqr(&self, index: &RawChunkIndex) -> Float {
+        let (w, h) = (W as Float, H as Float);
+        let (x, y) = (*self.position.x(), *self.position.y());
+        let (left, top) = Self::chunk_origin(index.x, index.y);
+        let dx = (left - x).max(x - (left + w)).max(0.);
+        let dy = (top - y).max(y - (top + h)).max(0.);
+        dx * dx + dy * dy
+    }
+
+    fn ring_chunks(&self, ring: usize) -> Vec<RawChunkIndex> {
+        let r = ring as isize;
+        let (cx, cy) = (self.center.x, self.center.y);
+        if r == 0 {
+            return vec![self.center.clone()];
+        }
+        (cx - r..=cx + r)
+            .flat_map(|x| {
+                [
+                    RawChunkIndex { x, y: cy - r },
+                    RawChunkIndex { x, y: cy + r },
+                ]
+            })
+            .chain((cy - r + 1..cy + r).flat_map(|y| {
+                [
+                    RawChunkIndex { x: cx - r, y },
+                    RawChunkIndex { x: cx + r, y },
+                ]
+            }))
+            .collect()
+    }
 }
 
 impl<T, const W: usize, const H: usize> Iterator for CircularTraverseIterator<T, W, H> {
     type Item = ChunkIndex;
 
     fn next(&mut self) -> Option<Self::Item> {
-        enum StepResult {
-            Stop,
-            Skip,
-            Accept(ChunkIndex),
-        }
-
-        let mut step = || -> StepResult {
-            match self.iteration % 4 {
-                0 => self.index.x += 1,
-                1 => self.index.y += 1,
-                2 => self.index.x -= 1,
-                3 => self.index.y -= 1,
-                _ => panic!("Undefined bahaviour"),
-            };
-
-            if self.i >= self.max_i {
-                self.i = 0;
-                self.max_i = self.iteration as usize / 2;
-                self.iteration += 1;
-            } else {
-                self.i += 1;
+        loop {
+            if let Some((index, _)) = self.pending.pop() {
+                break Some(index.into());
             }
 
-            let tile: Rect<_> = (
-                self.index.x as Float * W as Float,
-                self.index.y as Float * H as Float,
-                W as Float,
-                H as Float,
-            )
-                .into();
-            let instersects = tile.instersects_circle(self.position, self.range);
-
-            if self.i == 0 && !instersects {
-                self.number_of_key_skips += 1;
+            if self.ring_distance(self.ring) > self.range.unwrap() {
+                break None;
             }
 
-            if self.number_of_key_skips > 5 {
-                StepResult::Stop
-            } else {
-                if instersects {
-                    StepResult::Accept(self.index.clone().into())
-                } else {
-                    StepResult::Skip
+            let mut pending: Vec<_> = self
+                .ring_chunks(self.ring)
+                .into_iter()
+                .filter(|index| {
+                    let (left, top) = Self::chunk_origin(index.x, index.y);
+                    let tile: Rect<_> = (left, top, W as Float, H as Float).into();
+                    tile.instersects_circle(self.position, self.range)
+                })
+                .map(|index| {
+                    let dst_sqr = self.chunk_distance_sqr(&index);
+                    (index, dst_sqr)
+                })
+                .collect();
+            pending.sort_by(|a, b| b.1.total_cmp(&a.1));
+            self.pending = pending;
+            self.ring += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        math::{noneg_float, Point},
+        utils::Float,
+    };
+
+    use std::collections::BTreeMap;
+
+    use rand::SeedableRng as _;
+    use rand_pcg::Pcg64;
+
+    use super::{ChunkedVec, IdIndexedVec, Identified, Position, RawChunkIndex};
+
+    struct Item(Point<Float>);
+
+    struct IdItem(usize, Point<Float>);
+
+    impl Position for IdItem {
+        fn position(&self) -> Point<Float> {
+            self.1
+        }
+    }
+
+    impl Identified for IdItem {
+        type Id = usize;
+
+        fn id(&self) -> usize {
+            self.0
+        }
+    }
+
+    impl Position for Item {
+        fn position(&self) -> Point<Float> {
+            self.0
+        }
+    }
+
+    impl Position for &Item {
+        fn position(&self) -> Point<Float> {
+            self.0
+        }
+    }
+
+    fn nearest(items: &[(Float, Float)], position: (Float, Float)) -> Option<Point<Float>> {
+        let items: ChunkedVec<Item, 10, 10> = items.iter().map(|p| Item((*p).into())).collect();
+        let nearest = items
+            .find_nearest(position.into(), noneg_float(100.))
+            .map(|(item, _)| item.position());
+        let nearest_filter_map = items
+            .find_nearest_filter_map(position.into(), noneg_float(100.), Some)
+            .map(|(item, _)| item.position());
+        assert_eq!(
+            nearest.map(|p| (*p.x(), *p.y())),
+            nearest_filter_map.map(|p| (*p.x(), *p.y()))
+        );
+        nearest
+    }
+
+    fn traverse(position: Point<Float>, range: Float) -> Vec<(isize, isize)> {
+        ChunkedVec::<(), 10, 10>::default()
+            .circular_traverse_iter(position, noneg_float(range))
+            .map(|i| {
+                let i: RawChunkIndex = i.into();
+                (i.x(), i.y())
+            })
+            .collect()
+    }
+
+    /// Nearest distance from `position` to points of a dense grid (step `GRID_STEP`) around it
+    /// grouped by chunks the points are assigned to
+    fn brute_force_chunk_distances(
+        position: Point<Float>,
+        range: Float,
+    ) -> BTreeMap<(isize, isize), Float> {
+        const GRID_STEP: Float = 0.25;
+        let steps = ((range + 12.) / GRID_STEP) as isize;
+        let (cx, cy) = (
+            (*position.x() / GRID_STEP).round() as isize,
+            (*position.y() / GRID_STEP).round() as isize,
+        );
+        let mut result: BTreeMap<(isize, isize), Float> = Default::default();
+        for x in cx - steps..=cx + steps {
+            for y in cy - steps..=cy + steps {
+                let point: Point<Float> = (x as Float * GRID_STEP, y as Float * GRID_STEP).into();
+                let chunk = RawChunkIndex::from_position::<10, 10>(point);
+                let distance = ((point.x() - position.x()).powi(2)
+                    + (point.y() - position.y()).powi(2))
+                .sqrt();
+                let nearest = result.entry((chunk.x(), chunk.y())).or_insert(distance);
+                *nearest = nearest.min(distance);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn circular_traverse_visits_exactly_intersecting_chunks() {
+        for (position, range) in [
+            ((5., 5.).into(), 0.),
+            ((5., 5.).into(), 4.),
+            ((5., 5.).into(), 4.6),
+            ((1., 9.).into(), 12.),
+            ((-23., 47.).into(), 35.),
+            ((0., 0.).into(), 100.),
+        ] {
+            let mut visited = traverse(position, range);
+            let count = visited.len();
+            visited.sort();
+            visited.dedup();
+            assert_eq!(visited.len(), count, "chunk visited twice");
+
+            let distances = brute_force_chunk_distances(position, range);
+            for (chunk, distance) in &distances {
+                if *distance <= range {
+                    assert!(visited.contains(chunk), "chunk {:?} is missed", chunk);
                 }
             }
+            for chunk in &visited {
+                // the grid is dense enough to have a point this close to any intersecting chunk
+                assert!(
+                    distances[chunk] <= range + 0.5,
+                    "chunk {:?} is too far",
+                    chunk
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn circular_traverse_goes_from_near_to_far() {
+        let position: Point<Float> = (13., 17.).into();
+        let ring = |(x, y): (isize, isize)| (x - 1).abs().max((y - 1).abs());
+        let chunks = traverse(position, 45.);
+        for pair in chunks.windows(2) {
+            assert!(ring(pair[0]) <= ring(pair[1]));
+        }
+        assert_eq!(chunks[0], (1, 1));
+    }
+
+    #[test]
+    fn items_in_range_are_visited_without_full_iteration() {
+        let mut items: ChunkedVec<Item, 10, 10> = (-50..50)
+            .flat_map(|x| (-50..50).map(move |y| Item((x as Float + 0.5, y as Float + 0.5).into())))
+            .collect();
+        let (center, range): (Point<Float>, Float) = ((3., -7.).into(), 12.);
+        let in_range = |item: &Item| (item.position() - center).len() < range;
+
+        let mut visited = 0;
+        let mut found = 0;
+        items.for_each_in_range_mut(center, noneg_float(range), |item| {
+            visited += 1;
+            if in_range(item) {
+                found += 1;
+            }
+        });
+        assert_eq!(found, items.iter().filter(|item| in_range(item)).count());
+        assert!(visited < items.len() / 4, "visited: {}", visited);
+    }
+
+    #[test]
+    fn samples_are_uniform_over_items() {
+        // one item in the first chunk and three in the second one
+        let items: ChunkedVec<Item, 10, 10> = [(1., 1.), (11., 1.), (12., 1.), (13., 1.)]
+            .iter()
+            .map(|p| Item((*p).into()))
+            .collect();
+        let mut rng = Pcg64::seed_from_u64(0);
+        let samples = items.sample(4000, &mut rng);
+        assert_eq!(samples.len(), 4000);
+        for x in [1., 11., 12., 13.] {
+            let count = samples.iter().filter(|item| *item.0.x() == x).count();
+            assert!((800..1200).contains(&count), "{}: {}", x, count);
+        }
+        assert!(ChunkedVec::<Item, 10, 10>::default()
+            .sample(10, &mut rng)
+            .is_empty());
+    }
+
+    #[test]
+    fn find_nearest_looks_into_neighbor_chunk() {
+        // the item in own chunk is further than the one just across the boundary
+        let nearest = nearest(&[(0.5, 5.), (10.5, 5.)], (9.5, 5.)).unwrap();
+        assert_eq!((*nearest.x(), *nearest.y()), (10.5, 5.));
+    }
+
+    #[test]
+    fn find_nearest_sees_items_rounded_into_next_chunk() {
+        // 9.6 is rounded to 10, so the nearest item is stored in chunk 1 although it is closer
+        // than the item of own chunk and than the naive edge of chunk 1
+        let nearest = nearest(&[(0., 5.), (9.6, 5.)], (5., 5.)).unwrap();
+        assert_eq!((*nearest.x(), *nearest.y()), (9.6, 5.));
+    }
+
+    #[test]
+    fn find_nearest_looks_into_next_ring() {
+        // item in corner chunk of ring 1 is further than item in ring 2
+        let nearest = nearest(&[(-0.1, -0.1), (5., 20.5)], (5., 9.9)).unwrap();
+        assert_eq!((*nearest.x(), *nearest.y()), (5., 20.5));
+    }
+
+    #[test]
+    fn scaled_range_hides_items() {
+        // the nearest item is visible only at a quarter of the range
+        let items: ChunkedVec<Item, 10, 10> = [(1., 0.), (3., 0.), (30., 0.)]
+            .iter()
+            .map(|p| Item((*p).into()))
+            .collect();
+        let find = |range| {
+            items
+                .find_nearest_filter_map_scaled((0., 0.).into(), noneg_float(range), |item| {
+                    let factor = if *item.0.x() == 1. { 0.25 } else { 1. };
+                    Some((item, noneg_float(factor)))
+                })
+                .map(|(item, dst)| (*item.0.x(), dst.unwrap()))
         };
+        assert_eq!(find(100.), Some((3., 3.)));
+        assert_eq!(find(2.), None);
+    }
 
-        loop {
-            match step() {
-                StepResult::Stop => break None,
-                StepResult::Skip => {}
-                StepResult::Accept(chunk_index) => break Some(chunk_index),
+    #[test]
+    fn find_n_nearest_matches_sorted_distances() {
+        let points: Vec<(Float, Float)> = (0..200)
+            .map(|i| ((i * 37 % 101) as Float - 50., (i * 53 % 97) as Float - 48.))
+            .collect();
+        let items: ChunkedVec<Item, 10, 10> = points.iter().map(|p| Item((*p).into())).collect();
+        let position: Point<Float> = (3., -4.).into();
+        let range = 40.;
+
+        let mut expected: Vec<Float> = points
+            .iter()
+            .map(|p| (Point::from(*p) - position).len())
+            .filter(|dst| *dst < range)
+            .collect();
+        expected.sort_by(|a, b| a.total_cmp(b));
+
+        for n in [0, 1, 3, 10, 1000] {
+            let found: Vec<Float> = items
+                .find_n_nearest_filter_map_scaled(position, noneg_float(range), n, |item| {
+                    Some((item, noneg_float(1.)))
+                })
+                .into_iter()
+                .map(|(_, dst)| dst.unwrap())
+                .collect();
+            assert_eq!(found, expected[..n.min(expected.len())]);
+        }
+    }
+
+    #[test]
+    fn items_are_found_by_id_after_moves_and_removals() {
+        let mut items: IdIndexedVec<IdItem, 10, 10> = (0..100)
+            .map(|i| IdItem(i, (i as Float, -(i as Float)).into()))
+            .collect();
+        let x = |items: &IdIndexedVec<IdItem, 10, 10>, id: usize| {
+            items.index_of_id(&id).map(|index| *items[index].1.x())
+        };
+        assert_eq!(x(&items, 42), Some(42.));
+
+        for item in items.iter_mut() {
+            item.1 = (*item.1.x() + 25., *item.1.y()).into();
+        }
+        items.shuffle();
+        assert_eq!(x(&items, 42), Some(67.));
+
+        items.retain(|item| item.0 % 2 == 0);
+        assert_eq!(x(&items, 43), None);
+        assert_eq!(x(&items, 44), Some(69.));
+        assert!(items.retain_by_position((69., -44.).into(), |item| item.0 != 44));
+        assert_eq!(x(&items, 44), None);
+        assert!((0..100)
+            .step_by(2)
+            .filter(|id| *id != 44)
+            .all(|id| x(&items, id) == Some(id as Float + 25.)));
+
+        for item in items.iter_mut() {
+            item.0 += 1000;
+        }
+        items.reindex();
+        assert_eq!(x(&items, 42), None);
+        assert_eq!(x(&items, 1042), Some(67.));
+    }
+
+    #[test]
+    fn indices_inside_crowded_chunk_are_kept_up_to_date() {
+        let mut items: IdIndexedVec<IdItem, 10, 10> = (0..50)
+            .map(|i| IdItem(i, (i as Float / 10., 5.).into()))
+            .collect();
+        let check = |items: &IdIndexedVec<IdItem, 10, 10>, ids: &[usize]| {
+            assert_eq!(items.len(), ids.len());
+            for id in ids {
+                let index = items.index_of_id(id).unwrap();
+                assert_eq!(items[index].0, *id);
             }
+        };
+        let mut ids: Vec<usize> = (0..50).collect();
+        check(&items, &ids);
+
+        let index = items.index_of_id(&7).unwrap();
+        items.remove(index);
+        ids.retain(|id| *id != 7);
+        check(&items, &ids);
+
+        items.retain(|item| item.0 % 3 != 0);
+        ids.retain(|id| id % 3 != 0);
+        check(&items, &ids);
+
+        // every fourth item leaves the chunk, the rest are shifted
+        for item in items.iter_mut().filter(|item| item.0 % 4 == 0) {
+            item.1 = (25., 5.).into();
         }
+        items.shuffle();
+        check(&items, &ids);
+        assert_eq!(items.index_of_id(&3), None);
+    }
+
+    #[test]
+    fn taken_items_are_removed() {
+        let mut items: ChunkedVec<Item, 10, 10> = (-20..20)
+            .map(|x| Item((x as Float, x as Float).into()))
+            .collect();
+        let mut taken: Vec<Float> = items
+            .take_if(|item| *item.0.x() as isize % 3 == 0)
+            .iter()
+            .map(|item| *item.0.x())
+            .collect();
+        taken.sort_by(Float::total_cmp);
+        assert_eq!(
+            taken,
+            (-18..20).step_by(3).map(|x| x as Float).collect::<Vec<_>>()
+        );
+        assert_eq!(items.len(), 40 - taken.len());
+        assert!(items.iter().all(|item| *item.0.x() as isize % 3 != 0));
+    }
+
+    #[test]
+    fn storage_of_collected_chunks_is_reused() {
+        let mut items: ChunkedVec<Item, 10, 10> = Default::default();
+        for _ in 0..100 {
+            items.push(Item((5., 5.).into()));
+        }
+        items.retain(|_| false);
+        items.collect_unused_chunks();
+        assert_eq!(items.spare_items.len(), 1);
+
+        items.push(Item((5., 5.).into()));
+        assert!(items.spare_items.is_empty());
+        let chunk = items
+            .get_chunk(RawChunkIndex::from_position::<10, 10>((5., 5.).into()).into())
+            .unwrap();
+        assert!(chunk.items.capacity() >= 100);
     }
 }
@@ -0,0 +1,183 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{noneg_float, NoNeg},
+    utils::Float,
+};
+
+/// Simulation parameters which are safe to change while simulation runs (they scale rates and do
+/// not invalidate any state). Missing keys of config file take default values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimConfig {
+    /// Multiplier of energy which bugs spend on movement, rotation, burrowing and heat
+    pub metabolism_multiplier: NoNeg<Float>,
+    /// Share of energy which uneaten food loses per hour (0 means food never spoils)
+    pub food_rot_rate: NoNeg<Float>,
+    /// How bugs see their surroundings
+    pub vision_mode: VisionMode,
+    /// Overlapping bugs push each other apart (otherwise bodies pass through each other)
+    pub bug_collisions: bool,
+    /// Food is not placed into chunks which already hold that many pieces (None means no limit).
+    /// Caps memory used by runaway food sources and makes bugs compete for space
+    pub max_food_per_chunk: Option<usize>,
+}
+
+/// What brain is told about objects in vision arc
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisionMode {
+    /// Only nearest food and bugs (distance and direction of each)
+    #[default]
+    Nearest,
+    /// Besides nearest ones, vision arc is divided into `RETINA_SECTORS` sectors and each of them
+    /// reports kind and distance of the nearest object in it
+    Retina,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            metabolism_multiplier: noneg_float(1.),
+            food_rot_rate: noneg_float(0.),
+            vision_mode: VisionMode::Nearest,
+            bug_collisions: false,
+            max_food_per_chunk: None,
+        }
+    }
+}
+
+impl SimConfig {
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(text)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// Config applied to environment at given iteration. Log of changes is saved with environment,
+/// so a run can be reproduced with the same parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub iteration: usize,
+    pub config: SimConfig,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "io error: {}", err),
+            ConfigError::Toml(err) => write!(f, "toml error: {}", err),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(value: toml::de::Error) -> Self {
+        Self::Toml(value)
+    }
+}
+
+/// Reloads config file when its modification time changes. Polled instead of subscribing to
+/// file system events, which is enough for a file edited by hand
+#[derive(Debug, Clone)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    missing_reported: bool,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            modified: None,
+            missing_reported: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Config if file was modified since the previous poll (the first poll always loads it).
+    /// Missing file is reported once, then watcher waits for it to appear
+    pub fn poll(&mut self) -> Option<Result<SimConfig, ConfigError>> {
+        let modified = match std::fs::metadata(&self.path).and_then(|x| x.modified()) {
+            Ok(modified) => modified,
+            Err(_) if self.missing_reported => return None,
+            Err(err) => {
+                self.missing_reported = true;
+                self.modified = None;
+                return Some(Err(err.into()));
+            }
+        };
+        self.missing_reported = false;
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+        Some(SimConfig::load(&self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::noneg_float;
+
+    use super::{ConfigError, ConfigWatcher, SimConfig, VisionMode};
+
+    #[test]
+    fn config_is_reloaded_when_file_changes() {
+        assert_eq!(SimConfig::from_toml("").unwrap(), SimConfig::default());
+        assert!(matches!(
+            SimConfig::from_toml("metabolism_multiplier = -1"),
+            Err(ConfigError::Toml(_))
+        ));
+        assert_eq!(
+            SimConfig::from_toml("vision_mode = \"retina\"")
+                .unwrap()
+                .vision_mode,
+            VisionMode::Retina
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("bugs_config_test_{}.toml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut watcher = ConfigWatcher::new(path.clone());
+        assert!(matches!(watcher.poll(), Some(Err(ConfigError::Io(_)))));
+        assert!(watcher.poll().is_none());
+
+        std::fs::write(&path, "metabolism_multiplier = 2.5\n").unwrap();
+        let config = watcher.poll().unwrap().unwrap();
+        assert_eq!(config.metabolism_multiplier, noneg_float(2.5));
+        assert_eq!(config.vision_mode, VisionMode::Nearest);
+        assert!(watcher.poll().is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{noneg_float, NoNeg},
+    utils::Float,
+};
+
+/// Multipliers of environment parameters at the hardest level of curriculum (level 0 leaves
+/// environment as is)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Difficulty {
+    /// Multiplier of food sources production
+    pub food_abundance: NoNeg<Float>,
+    /// Multiplier of energy spent by bugs (colder climate makes keeping warm more expensive)
+    pub metabolism: NoNeg<Float>,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self {
+            food_abundance: noneg_float(0.3),
+            metabolism: noneg_float(2.),
+        }
+    }
+}
+
+/// When curriculum changes its level
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CurriculumRule {
+    /// Harden by one step each `period` of simulated time
+    Schedule { period: Duration },
+    /// Each `period` harden by one step if population reached `target_population` and ease by
+    /// one step if it fell below half of it
+    Adaptive {
+        period: Duration,
+        target_population: usize,
+    },
+}
+
+/// Tightens environment over time, so long runs keep applying selective pressure instead of
+/// plateauing. Level goes from 0 (unchanged environment) to 1 (`hardest`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Curriculum {
+    pub rule: CurriculumRule,
+    pub hardest: Difficulty,
+    /// Change of level made at once
+    pub step: Float,
+    level: Float,
+    /// Simulated time since creation of environment when level was evaluated last time
+    last_evaluation: Duration,
+}
+
+impl Curriculum {
+    pub fn new(rule: CurriculumRule, hardest: Difficulty, step: Float) -> Self {
+        Self {
+            rule,
+            hardest,
+            step,
+            level: 0.,
+            last_evaluation: Duration::ZERO,
+        }
+    }
+
+    pub fn level(&self) -> Float {
+        self.level
+    }
+
+    pub fn food_abundance(&self) -> Float {
+        1. + (self.hardest.food_abundance.unwrap() - 1.) * self.level
+    }
+
+    pub fn metabolism(&self) -> NoNeg<Float> {
+        NoNeg::wrap(1. + (self.hardest.metabolism.unwrap() - 1.) * self.level).unwrap()
+    }
+
+    /// Evaluate rule if its period has passed. Returns true if level changed
+    pub(crate) fn proceed(&mut self, elapsed: Duration, population: usize) -> bool {
+        let period = match self.rule {
+            CurriculumRule::Schedule { period } => period,
+            CurriculumRule::Adaptive { period, .. } => period,
+        };
+        if elapsed.saturating_sub(self.last_evaluation) < period {
+            return false;
+        }
+        self.last_evaluation = elapsed;
+        let direction = match self.rule {
+            CurriculumRule::Schedule { .. } => 1.,
+            CurriculumRule::Adaptive {
+                target_population, ..
+            } => {
+                if population >= target_population {
+                    1.
+                } else if population < target_population / 2 {
+                    -1.
+                } else {
+                    0.
+                }
+            }
+        };
+        let level = (self.level + direction * self.step).clamp(0., 1.);
+        let changed = level != self.level;
+        self.level = level;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::math::noneg_float;
+
+    use super::{Curriculum, CurriculumRule, Difficulty};
+
+    #[test]
+    fn adaptive_curriculum_follows_population() {
+        let mut curriculum = Curriculum::new(
+            CurriculumRule::Adaptive {
+                period: Duration::from_secs(10),
+                target_population: 100,
+            },
+            Difficulty::default(),
+            0.5,
+        );
+        let at = |secs| Duration::from_secs(secs);
+
+        assert!(!curriculum.proceed(at(5), 200));
+        assert!(curriculum.proceed(at(10), 200));
+        assert!(curriculum.proceed(at(20), 100));
+        assert_eq!(curriculum.level(), 1.);
+        assert!(!curriculum.proceed(at(30), 500));
+        assert!((curriculum.food_abundance() - 0.3).abs() < 1e-9);
+        assert_eq!(curriculum.metabolism(), noneg_float(2.));
+
+        assert!(!curriculum.proceed(at(40), 70));
+        assert!(curriculum.proceed(at(50), 40));
+        assert_eq!(curriculum.level(), 0.5);
+        assert!((curriculum.food_abundance() - 0.65).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use chromosome::Chromosome;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chunk::Position,
+    math::{noneg_float, Angle, NoNeg, Point},
+    nutrient::Nutrients,
+    tag::Tag,
+    time_point::TimePoint,
+    utils::Float,
+};
+
+/// Time from laying an egg until bugs hatch from it
+pub const INCUBATION_DURATION: Duration = Duration::from_secs(60 * 10);
+
+/// Bug eats an egg when egg is closer than eat range of the bug plus this radius
+pub const EGG_RADIUS: NoNeg<Float> = noneg_float(3.);
+
+/// Eggs are mostly protein and fat
+pub const EGG_NUTRIENTS: Nutrients = Nutrients {
+    protein: 0.6,
+    carbs: 0.1,
+    fat: 0.3,
+};
+
+/// Offspring waiting to hatch. Holds everything needed to give birth: chromosome and energy
+/// which parent has spent on it. Other bugs can eat it before it hatches
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Egg<T> {
+    id: usize,
+    parent_id: usize,
+    chromosome: Chromosome<Float>,
+    position: Point<Float>,
+    /// Rotation of hatched bugs
+    rotation: Angle<Float>,
+    energy: NoNeg<Float>,
+    laid_instant: T,
+    /// Inherited tags of parent given to hatched bugs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<Tag>,
+}
+
+impl<T> Position for Egg<T> {
+    fn position(&self) -> Point<Float> {
+        self.position
+    }
+}
+
+impl<T> Egg<T> {
+    pub(crate) fn new(
+        next_id: &mut usize,
+        parent_id: usize,
+        chromosome: Chromosome<Float>,
+        position: Point<Float>,
+        rotation: Angle<Float>,
+        energy: NoNeg<Float>,
+        now: T,
+    ) -> Self {
+        *next_id += 1;
+        Self {
+            id: *next_id - 1,
+            parent_id,
+            chromosome,
+            position,
+            rotation,
+            energy,
+            laid_instant: now,
+            tags: vec![],
+        }
+    }
+
+    pub(crate) fn with_tags(mut self, tags: Vec<Tag>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn parent_id(&self) -> usize {
+        self.parent_id
+    }
+
+    pub(crate) fn set_id(&mut self, id: usize) {
+        self.id = id
+    }
+
+    pub(crate) fn set_parent_id(&mut self, parent_id: usize) {
+        self.parent_id = parent_id
+    }
+
+    /// Replace time points with ones of another clock (see `Environment::merge`)
+    pub(crate) fn rebase_time<F: Fn(&T) -> T>(&mut self, rebase: F) {
+        self.laid_instant = rebase(&self.laid_instant);
+    }
+
+    pub fn chromosome(&self) -> &Chromosome<Float> {
+        &self.chromosome
+    }
+
+    pub fn position(&self) -> Point<Float> {
+        self.position
+    }
+
+    pub fn rotation(&self) -> Angle<Float> {
+        self.rotation
+    }
+
+    pub fn energy(&self) -> NoNeg<Float> {
+        self.energy
+    }
+
+    pub(crate) fn energy_mut(&mut self) -> &mut NoNeg<Float> {
+        &mut self.energy
+    }
+
+    pub fn laid_instant(&self) -> &T {
+        &self.laid_instant
+    }
+
+    /// Share of incubation which has passed (reaches 1 when egg is ready to hatch)
+    pub fn incubation_progress(&self, now: &T) -> Float
+    where
+        T: TimePoint,
+    {
+        (now.duration_since(&self.laid_instant).as_secs_f64() / INCUBATION_DURATION.as_secs_f64())
+            .min(1.)
+    }
+
+    pub(crate) fn into_parts(
+        self,
+    ) -> (
+        usize,
+        Chromosome<Float>,
+        Point<Float>,
+        Angle<Float>,
+        NoNeg<Float>,
+        Vec<Tag>,
+    ) {
+        (
+            self.parent_id,
+            self.chromosome,
+            self.position,
+            self.rotation,
+            self.energy,
+            self.tags,
+        )
+    }
+}
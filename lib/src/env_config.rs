@@ -0,0 +1,185 @@
+use std::{f64::consts::PI, path::Path, time::Duration};
+
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{ConfigError, SimConfig},
+    environment::{
+        BugCreateInfo, EdgePolicy, Environment, FoodCreateInfo, FoodSourceCreateInfo,
+        SeededEnvironment,
+    },
+    light::LightSource,
+    math::Rect,
+    range::Range,
+    season::SeasonClock,
+    terrain::Terrain,
+    utils::Float,
+    water::WaterRegion,
+};
+
+fn default_gene_range() -> Range<Float> {
+    (-1. ..1.).into()
+}
+
+/// Food scattered at random when environment is created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitialFood {
+    pub x_range: Range<Float>,
+    pub y_range: Range<Float>,
+    pub energy_range: Range<Float>,
+    pub count: usize,
+}
+
+/// Bugs with random genomes placed at random when environment is created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitialBugs {
+    pub x_range: Range<Float>,
+    pub y_range: Range<Float>,
+    /// Genes are sampled from this range
+    #[serde(default = "default_gene_range")]
+    pub gene_range: Range<Float>,
+    pub count: usize,
+}
+
+/// Environment described by a toml file, so that worlds can be designed without recompiling
+/// `env_presets`. Missing keys take default values (flat unbounded world without food sources).
+/// Durations are written as `{ secs = 1, nanos = 0 }`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnvConfig {
+    pub food_sources: Vec<FoodSourceCreateInfo>,
+    pub initial_food: Option<InitialFood>,
+    pub bugs: Vec<InitialBugs>,
+    pub water: Vec<WaterRegion>,
+    pub seasons: Option<SeasonClock>,
+    pub day_length: Option<Duration>,
+    pub terrain: Option<Terrain>,
+    pub bounds: Option<Rect<Float>>,
+    pub edge_policy: EdgePolicy,
+    pub light_sources: Vec<LightSource>,
+    pub config: SimConfig,
+}
+
+impl EnvConfig {
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(text)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Create environment. Initial food and bugs are generated from `seed`
+    pub fn build<T: Clone>(
+        self,
+        now: T,
+        seed: <Pcg64 as SeedableRng>::Seed,
+    ) -> SeededEnvironment<T> {
+        let mut rng = Pcg64::from_seed(seed);
+        let food = self
+            .initial_food
+            .map(|food| {
+                FoodCreateInfo::generate_vec(
+                    &mut rng,
+                    food.x_range.start..food.x_range.end,
+                    food.y_range.start..food.y_range.end,
+                    food.energy_range.start..food.energy_range.end,
+                    food.count,
+                )
+            })
+            .unwrap_or_default();
+        let bugs = self
+            .bugs
+            .into_iter()
+            .flat_map(|bugs| {
+                BugCreateInfo::generate_vec(
+                    &mut rng,
+                    bugs.gene_range.start..bugs.gene_range.end,
+                    bugs.x_range.start..bugs.x_range.end,
+                    bugs.y_range.start..bugs.y_range.end,
+                    0. ..(PI * 2.),
+                    bugs.count,
+                )
+            })
+            .collect();
+
+        let mut environment =
+            SeededEnvironment::new(Environment::new(now, food, self.food_sources, bugs), seed)
+                .with_water(self.water)
+                .with_light_sources(self.light_sources);
+        if let Some(seasons) = self.seasons {
+            environment = environment.with_seasons(seasons);
+        }
+        if let Some(day_length) = self.day_length {
+            environment = environment.with_day_length(day_length);
+        }
+        if let Some(terrain) = self.terrain {
+            environment = environment.with_terrain(terrain);
+        }
+        if let Some(bounds) = self.bounds {
+            environment = environment.with_bounds(bounds);
+        }
+        environment = environment.with_edge_policy(self.edge_policy);
+        let _ = environment.apply_config(self.config);
+        environment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::VisionMode, environment::SeededEnvironment, time_point::StaticTimePoint};
+
+    use super::EnvConfig;
+
+    const EXAMPLE: &str = r#"
+        [[food_sources]]
+        position = { x = 0.0, y = 0.0 }
+        shape = { Circle = { radius = 500.0 } }
+        energy_range = { start = 0.0, end = 2.0 }
+        spawn_interval = { secs = 1, nanos = 0 }
+        aquatic = false
+
+        [initial_food]
+        x_range = { start = -500.0, end = 500.0 }
+        y_range = { start = -500.0, end = 500.0 }
+        energy_range = { start = 0.0, end = 1.0 }
+        count = 100
+
+        [[bugs]]
+        x_range = { start = -100.0, end = 100.0 }
+        y_range = { start = -100.0, end = 100.0 }
+        count = 8
+
+        [[water]]
+        Circle = { center = { x = 300.0, y = 300.0 }, radius = 100.0 }
+
+        [config]
+        vision_mode = "retina"
+    "#;
+
+    #[test]
+    fn environment_is_built_from_toml() {
+        let config = EnvConfig::from_toml(EXAMPLE).unwrap();
+        let environment = config.clone().build(StaticTimePoint::default(), [0; 32]);
+        assert_eq!(environment.food_sources().count(), 1);
+        assert_eq!(environment.food_count(), 100);
+        assert_eq!(environment.bugs_count(), 8);
+        assert_eq!(environment.water().len(), 1);
+        assert_eq!(environment.config().vision_mode, VisionMode::Retina);
+
+        let positions = |environment: &SeededEnvironment<StaticTimePoint>| {
+            environment
+                .bugs()
+                .map(|bug| (*bug.position().x(), *bug.position().y()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            positions(&config.build(StaticTimePoint::default(), [0; 32])),
+            positions(&environment)
+        );
+
+        assert!(EnvConfig::from_toml("unknown_key = 1").is_err());
+    }
+}
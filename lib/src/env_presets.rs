@@ -1,9 +1,20 @@
 use crate::{
-    environment::{FoodSourceCreateInfo, SeededEnvironment},
+    biome::{Biome, BiomeMap, BIOME_CELL_SIZE},
+    environment::{
+        BugCreateInfo, Environment, FoodCreateInfo, FoodSourceCreateInfo, SeededEnvironment,
+    },
     food_source::FoodSourceShape,
-    math::noneg_float,
+    light::LightSource,
+    math::{noneg_float, Angle, NoNeg, Point, Rect},
+    nutrient::Nutrients,
+    plant::PlantCreateInfo,
+    season::SeasonClock,
+    terrain::{fractal_noise, Terrain},
+    utils::Float,
+    water::WaterRegion,
 };
-use rand::SeedableRng;
+use chromosome::Chromosome;
+use rand::{Rng as _, SeedableRng};
 use rand_pcg::Pcg64;
 use std::time::Duration;
 
@@ -23,6 +34,8 @@ pub fn less_food_further_from_center<T: Clone>(
                 },
                 energy_range: (0. ..1.).into(),
                 spawn_interval: Duration::from_millis((4_u64).pow(0) * 1000),
+                aquatic: false,
+                nutrients: Default::default(),
             },
             FoodSourceCreateInfo {
                 position: (0., 0.).into(),
@@ -31,6 +44,8 @@ pub fn less_food_further_from_center<T: Clone>(
                 },
                 energy_range: (0. ..2.).into(),
                 spawn_interval: Duration::from_millis((4_u64).pow(1) * 1000),
+                aquatic: false,
+                nutrients: Default::default(),
             },
             FoodSourceCreateInfo {
                 position: (0., 0.).into(),
@@ -39,6 +54,8 @@ pub fn less_food_further_from_center<T: Clone>(
                 },
                 energy_range: (0. ..4.).into(),
                 spawn_interval: Duration::from_millis((4_u64).pow(2) * 1000),
+                aquatic: false,
+                nutrients: Default::default(),
             },
             FoodSourceCreateInfo {
                 position: (0., 0.).into(),
@@ -47,6 +64,8 @@ pub fn less_food_further_from_center<T: Clone>(
                 },
                 energy_range: (0. ..8.).into(),
                 spawn_interval: Duration::from_millis((4_u64).pow(3) * 1000),
+                aquatic: false,
+                nutrients: Default::default(),
             },
             FoodSourceCreateInfo {
                 position: (0., 0.).into(),
@@ -55,6 +74,8 @@ pub fn less_food_further_from_center<T: Clone>(
                 },
                 energy_range: (0. ..16.).into(),
                 spawn_interval: Duration::from_millis((4_u64).pow(4) * 1000),
+                aquatic: false,
+                nutrients: Default::default(),
             },
             FoodSourceCreateInfo {
                 position: (0., 0.).into(),
@@ -63,6 +84,8 @@ pub fn less_food_further_from_center<T: Clone>(
                 },
                 energy_range: (0. ..32.).into(),
                 spawn_interval: Duration::from_millis((4_u64).pow(5) * 1000),
+                aquatic: false,
+                nutrients: Default::default(),
             },
         ],
         -1000. ..1000.,
@@ -87,6 +110,8 @@ pub fn one_big_circle<T: Clone>(
             },
             energy_range: (0. ..128.).into(),
             spawn_interval: Duration::from_millis(5000),
+            aquatic: false,
+            nutrients: Default::default(),
         }],
         -10000. ..10000.,
         -10000. ..10000.,
@@ -94,4 +119,569 @@ pub fn one_big_circle<T: Clone>(
         262144,
         (0., 0.).into(),
     )
+    .with_seasons(SeasonClock {
+        year_length: Duration::from_secs(60 * 60),
+        amplitude: 0.5,
+    })
+}
+
+/// No food sources: all food after the initial one is produced by evolving plants
+pub fn plant_meadow<T: Clone>(now: T, seed: <Pcg64 as SeedableRng>::Seed) -> SeededEnvironment<T> {
+    let mut plants_rng = Pcg64::from_seed(seed);
+    let plants_seed = plants_rng.gen();
+    SeededEnvironment::generate(
+        now,
+        seed,
+        vec![],
+        -1000. ..1000.,
+        -1000. ..1000.,
+        0. ..1.,
+        4096,
+        (0., 0.).into(),
+    )
+    .with_plants(PlantCreateInfo::generate_vec(
+        &mut Pcg64::from_seed(plants_seed),
+        0. ..1.,
+        -4000. ..4000.,
+        -4000. ..4000.,
+        2048,
+    ))
+}
+
+/// Land with a few lakes. Food in lakes is richer, so it pays off to evolve swimming.
+/// Winters are harsh, so bugs have to survive on lakes food. Land food is mostly carbohydrates
+/// and lakes food is mostly protein, so diets of swimmers and land dwellers can diverge
+pub fn lakes<T: Clone>(now: T, seed: <Pcg64 as SeedableRng>::Seed) -> SeededEnvironment<T> {
+    SeededEnvironment::generate(
+        now,
+        seed,
+        vec![
+            FoodSourceCreateInfo {
+                position: (0., 0.).into(),
+                shape: FoodSourceShape::Rect {
+                    size: (4000., 4000.).into(),
+                },
+                energy_range: (0. ..1.).into(),
+                spawn_interval: Duration::from_millis(1000),
+                aquatic: false,
+                nutrients: Nutrients::new(0.1, 0.7, 0.2),
+            },
+            FoodSourceCreateInfo {
+                position: (0., 0.).into(),
+                shape: FoodSourceShape::Rect {
+                    size: (4000., 4000.).into(),
+                },
+                energy_range: (0. ..4.).into(),
+                spawn_interval: Duration::from_millis(250),
+                aquatic: true,
+                nutrients: Nutrients::new(0.7, 0.1, 0.2),
+            },
+        ],
+        -1000. ..1000.,
+        -1000. ..1000.,
+        0. ..1.,
+        8192,
+        (0., 0.).into(),
+    )
+    .with_water(vec![
+        WaterRegion::Circle {
+            center: (-800., -600.).into(),
+            radius: noneg_float(400.),
+        },
+        WaterRegion::Circle {
+            center: (1000., -200.).into(),
+            radius: noneg_float(250.),
+        },
+        WaterRegion::Polygon {
+            vertices: vec![
+                (-200., 600.).into(),
+                (400., 400.).into(),
+                (700., 1200.).into(),
+                (0., 1600.).into(),
+                (-600., 1100.).into(),
+            ],
+        },
+    ])
+    .with_seasons(SeasonClock {
+        year_length: Duration::from_secs(20 * 60),
+        amplitude: 0.8,
+    })
+}
+
+/// Hilly land: climbing costs energy, so bugs which stay in valleys are more efficient
+pub fn hills<T: Clone>(now: T, seed: <Pcg64 as SeedableRng>::Seed) -> SeededEnvironment<T> {
+    let terrain_seed = Pcg64::from_seed(seed).gen();
+    SeededEnvironment::generate(
+        now,
+        seed,
+        vec![FoodSourceCreateInfo {
+            position: (0., 0.).into(),
+            shape: FoodSourceShape::Rect {
+                size: (4000., 4000.).into(),
+            },
+            energy_range: (0. ..2.).into(),
+            spawn_interval: Duration::from_millis(500),
+            aquatic: false,
+            nutrients: Default::default(),
+        }],
+        -1000. ..1000.,
+        -1000. ..1000.,
+        0. ..1.,
+        8192,
+        (0., 0.).into(),
+    )
+    .with_terrain(Terrain {
+        seed: terrain_seed,
+        feature_size: noneg_float(1500.),
+        max_elevation: noneg_float(300.),
+    })
+}
+
+/// Square world of `size` surrounded by walls which reflect bugs. Food is spawned all over it
+pub fn bounded_arena<T: Clone>(
+    now: T,
+    seed: <Pcg64 as SeedableRng>::Seed,
+    size: Float,
+) -> SeededEnvironment<T> {
+    let half_size = size / 2.;
+    SeededEnvironment::generate(
+        now,
+        seed,
+        vec![FoodSourceCreateInfo {
+            position: (0., 0.).into(),
+            shape: FoodSourceShape::Rect {
+                size: (size, size).into(),
+            },
+            energy_range: (0. ..1.).into(),
+            spawn_interval: Duration::from_millis(500),
+            aquatic: false,
+            nutrients: Default::default(),
+        }],
+        -half_size..half_size,
+        -half_size..half_size,
+        0. ..1.,
+        4096,
+        (0., 0.).into(),
+    )
+    .with_bounds(Rect::from_center((0., 0.).into(), (size, size).into()))
+}
+
+/// Patchwork of biomes: fertile chunks grow more food, sticky ones make movement expensive and
+/// hazardous ones slowly drain energy of bugs staying in them
+pub fn biomes<T: Clone>(now: T, seed: <Pcg64 as SeedableRng>::Seed) -> SeededEnvironment<T> {
+    let biomes = BiomeMap::generate(
+        &mut Pcg64::seed_from_u64(Pcg64::from_seed(seed).gen()),
+        (-2000., -2000.),
+        (2000., 2000.),
+    );
+    SeededEnvironment::generate(
+        now,
+        seed,
+        vec![FoodSourceCreateInfo {
+            position: (0., 0.).into(),
+            shape: FoodSourceShape::Rect {
+                size: (4000., 4000.).into(),
+            },
+            energy_range: (0. ..1.).into(),
+            spawn_interval: Duration::from_millis(500),
+            aquatic: false,
+            nutrients: Default::default(),
+        }],
+        -1000. ..1000.,
+        -1000. ..1000.,
+        0. ..1.,
+        8192,
+        (0., 0.).into(),
+    )
+    .with_biomes(biomes)
+}
+
+/// Scarce food and a few glades lit by light sources, where bugs which photosynthesize can
+/// live without eating
+pub fn sunlit_glades<T: Clone>(now: T, seed: <Pcg64 as SeedableRng>::Seed) -> SeededEnvironment<T> {
+    let light_sources = [(-1000., -1000.), (1000., -600.), (0., 1000.)]
+        .into_iter()
+        .map(|position| LightSource {
+            position: position.into(),
+            intensity: noneg_float(2.),
+            radius: noneg_float(800.),
+        })
+        .collect();
+    SeededEnvironment::generate(
+        now,
+        seed,
+        vec![FoodSourceCreateInfo {
+            position: (0., 0.).into(),
+            shape: FoodSourceShape::Rect {
+                size: (4000., 4000.).into(),
+            },
+            energy_range: (0. ..1.).into(),
+            spawn_interval: Duration::from_secs(2),
+            aquatic: false,
+            nutrients: Default::default(),
+        }],
+        -1000. ..1000.,
+        -1000. ..1000.,
+        0. ..1.,
+        2048,
+        (0., 0.).into(),
+    )
+    .with_light_sources(light_sources)
+}
+
+/// Parameters of `procedural` preset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProceduralParams {
+    /// Width and height of the map
+    pub size: Float,
+    /// Size of the largest features of noise (distance between neighbouring fertile areas)
+    pub scale: Float,
+    /// Part of the map covered by food sources (in range 0..=1)
+    pub food_density: Float,
+    /// Part of the map covered by lakes (in range 0..=1)
+    pub obstacle_density: Float,
+}
+
+impl Default for ProceduralParams {
+    fn default() -> Self {
+        Self {
+            size: 16000.,
+            scale: 2000.,
+            food_density: 0.3,
+            obstacle_density: 0.1,
+        }
+    }
+}
+
+/// Centers of grid cells (cells are `step` wide) covering square of `size` with the highest
+/// values of noise. `density` is the part of cells taken. Values are returned along with centers
+/// (the highest first)
+fn noise_peaks(
+    seed: u64,
+    params: &ProceduralParams,
+    step: Float,
+    density: Float,
+) -> Vec<(Point<Float>, Float)> {
+    let cells = (params.size / step).ceil() as usize;
+    let mut centers: Vec<(Point<Float>, Float)> = (0..cells * cells)
+        .map(|i| {
+            let center: Point<_> = (
+                (i % cells) as Float * step + step / 2. - params.size / 2.,
+                (i / cells) as Float * step + step / 2. - params.size / 2.,
+            )
+                .into();
+            (center, fractal_noise(seed, center, params.scale))
+        })
+        .collect();
+    centers.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    centers.truncate((centers.len() as Float * density.clamp(0., 1.)).round() as usize);
+    centers
+}
+
+/// Large map generated from noise: food sources are scattered over fertile areas, lakes (costly
+/// to cross for bugs which can not swim) over wet ones and biomes follow their own noise layers.
+/// Every seed gives a different world
+pub fn procedural<T: Clone>(
+    now: T,
+    seed: <Pcg64 as SeedableRng>::Seed,
+    params: ProceduralParams,
+) -> SeededEnvironment<T> {
+    let (food_seed, lakes_seed, fertility_seed, friction_seed, hazard_seed): (
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+    ) = Pcg64::from_seed(seed).gen();
+    // a few cells per feature, so that fertile areas consist of several food sources
+    let step = params.scale / 4.;
+
+    let food_sources: Vec<_> = noise_peaks(food_seed, &params, step, params.food_density)
+        .into_iter()
+        .map(|(position, value)| FoodSourceCreateInfo {
+            position,
+            shape: FoodSourceShape::Rect {
+                size: (step, step).into(),
+            },
+            energy_range: (0. ..(1. + 3. * value)).into(),
+            spawn_interval: Duration::from_secs(2),
+            aquatic: false,
+            nutrients: Default::default(),
+        })
+        .collect();
+    let lakes = noise_peaks(lakes_seed, &params, step, params.obstacle_density)
+        .into_iter()
+        .map(|(center, _)| WaterRegion::Circle {
+            center,
+            radius: NoNeg::wrap(step * 0.6).unwrap(),
+        })
+        .collect();
+
+    let mut biomes = BiomeMap::default();
+    let cells = (params.size / 2. / BIOME_CELL_SIZE).ceil() as isize;
+    for y in -cells..cells {
+        for x in -cells..cells {
+            let center: Point<_> = (
+                (x as Float + 0.5) * BIOME_CELL_SIZE,
+                (y as Float + 0.5) * BIOME_CELL_SIZE,
+            )
+                .into();
+            let noise = |seed| fractal_noise(seed, center, params.scale);
+            biomes.set_biome(
+                (x, y),
+                Biome {
+                    fertility: NoNeg::wrap(0.5 + 1.5 * noise(fertility_seed)).unwrap(),
+                    friction: NoNeg::wrap(0.5 + 1.5 * noise(friction_seed)).unwrap(),
+                    hazard: NoNeg::wrap((noise(hazard_seed) - 0.7).max(0.) * 0.3).unwrap(),
+                },
+            );
+        }
+    }
+
+    // the first bug is born in the most fertile area
+    let origin = food_sources
+        .first()
+        .map(|food_source| food_source.position)
+        .unwrap_or((0., 0.).into());
+    let half_size = params.size / 2.;
+    SeededEnvironment::generate(
+        now,
+        seed,
+        food_sources,
+        -half_size..half_size,
+        -half_size..half_size,
+        0. ..1.,
+        (params.size * params.size / 32768.) as usize,
+        origin,
+    )
+    .with_water(lakes)
+    .with_biomes(biomes)
+}
+
+/// Small isolated environment with a single bug. Used to observe behavior of particular genome
+pub fn test_arena<T: Clone>(
+    now: T,
+    seed: <Pcg64 as SeedableRng>::Seed,
+    chromosome: Chromosome<Float>,
+) -> SeededEnvironment<T> {
+    let mut rng = Pcg64::from_seed(seed);
+    SeededEnvironment::new(
+        Environment::new(
+            now,
+            FoodCreateInfo::generate_vec(&mut rng, -500. ..500., -500. ..500., 0. ..1., 256),
+            vec![FoodSourceCreateInfo {
+                position: (0., 0.).into(),
+                shape: FoodSourceShape::Rect {
+                    size: (1000., 1000.).into(),
+                },
+                energy_range: (0. ..1.).into(),
+                spawn_interval: Duration::from_millis(1000),
+                aquatic: false,
+                nutrients: Default::default(),
+            }],
+            vec![BugCreateInfo {
+                chromosome,
+                position: (0., 0.).into(),
+                rotation: Angle::from_radians(0.),
+            }],
+        ),
+        rng.gen(),
+    )
+}
+
+/// Micro arena: strip of `length` by `width` along x axis with food spawned all over it once per
+/// `spawn_interval`. Single bug starts at the left end looking along the corridor
+pub fn corridor<T: Clone>(
+    now: T,
+    seed: <Pcg64 as SeedableRng>::Seed,
+    chromosome: Chromosome<Float>,
+    length: Float,
+    width: Float,
+    spawn_interval: Duration,
+) -> SeededEnvironment<T> {
+    let mut rng = Pcg64::from_seed(seed);
+    let (half_length, half_width) = (length / 2., width / 2.);
+    SeededEnvironment::new(
+        Environment::new(
+            now,
+            vec![],
+            vec![FoodSourceCreateInfo {
+                position: (0., 0.).into(),
+                shape: FoodSourceShape::Rect {
+                    size: (length, width).into(),
+                },
+                energy_range: (0. ..1.).into(),
+                spawn_interval,
+                aquatic: false,
+                nutrients: Default::default(),
+            }],
+            vec![BugCreateInfo {
+                chromosome,
+                position: (-half_length + half_width, 0.).into(),
+                rotation: Angle::from_radians(0.),
+            }],
+        ),
+        rng.gen(),
+    )
+}
+
+/// Micro arena: two square patches of `patch_size` with centers `distance` apart. Right patch
+/// spawns food `richness` times more often than the left one (once per second). Single bug starts
+/// in the middle between them
+pub fn two_food_patches<T: Clone>(
+    now: T,
+    seed: <Pcg64 as SeedableRng>::Seed,
+    chromosome: Chromosome<Float>,
+    distance: Float,
+    patch_size: Float,
+    richness: Float,
+) -> SeededEnvironment<T> {
+    let mut rng = Pcg64::from_seed(seed);
+    let patch = |x: Float, spawn_interval: Duration| FoodSourceCreateInfo {
+        position: (x, 0.).into(),
+        shape: FoodSourceShape::Rect {
+            size: (patch_size, patch_size).into(),
+        },
+        energy_range: (0. ..1.).into(),
+        spawn_interval,
+        aquatic: false,
+        nutrients: Default::default(),
+    };
+    SeededEnvironment::new(
+        Environment::new(
+            now,
+            vec![],
+            vec![
+                patch(-distance / 2., Duration::from_secs(1)),
+                patch(distance / 2., Duration::from_secs(1).div_f64(richness)),
+            ],
+            vec![BugCreateInfo {
+                chromosome,
+                position: (0., 0.).into(),
+                rotation: Angle::from_radians(0.),
+            }],
+        ),
+        rng.gen(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chromosome::Chromosome;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::{
+        chromo_utils::GENOME_LEN, environment::SeededEnvironment, time_point::StaticTimePoint,
+        utils::Float,
+    };
+
+    /// Positions of bugs and food after a few seconds of simulation
+    fn simulate(mut environment: SeededEnvironment<StaticTimePoint>) -> Vec<(Float, Float)> {
+        for _ in 0..300 {
+            let _ = environment.proceed(Duration::from_millis(100));
+        }
+        environment
+            .bugs()
+            .map(|bug| bug.position())
+            .chain(environment.food().map(|food| food.position()))
+            .map(|position| (*position.x(), *position.y()))
+            .collect()
+    }
+
+    #[test]
+    fn bounded_arena_keeps_bugs_inside() {
+        let mut environment = super::bounded_arena(StaticTimePoint::default(), [0; 32], 600.);
+        for _ in 0..300 {
+            let _ = environment.proceed(Duration::from_millis(100));
+        }
+        assert!(environment.bugs().count() > 0);
+        assert!(environment
+            .bugs()
+            .all(|bug| bug.position().x().abs() <= 300. && bug.position().y().abs() <= 300.));
+    }
+
+    #[test]
+    fn biomes_are_saved_with_environment() {
+        let environment = super::biomes(StaticTimePoint::default(), [0; 32]);
+        assert!(!environment.biomes().is_empty());
+        let restored: SeededEnvironment<StaticTimePoint> =
+            serde_json::from_str(&serde_json::to_string(&environment).unwrap()).unwrap();
+        assert!(environment.biomes().cells().eq(restored.biomes().cells()));
+    }
+
+    #[test]
+    fn procedural_worlds_follow_seed_and_density() {
+        let params = super::ProceduralParams {
+            size: 4000.,
+            scale: 1000.,
+            ..Default::default()
+        };
+        let food_sources = |seed, params| {
+            super::procedural(StaticTimePoint::default(), seed, params)
+                .food_sources()
+                .map(|food_source| {
+                    let position = food_source.position();
+                    (*position.x(), *position.y())
+                })
+                .collect::<Vec<_>>()
+        };
+        let world = food_sources([0; 32], params);
+        assert_eq!(world, food_sources([0; 32], params));
+        assert_ne!(world, food_sources([1; 32], params));
+        // 16x16 cells of 250
+        assert_eq!(world.len(), 77);
+        let dense = food_sources(
+            [0; 32],
+            super::ProceduralParams {
+                food_density: 0.6,
+                ..params
+            },
+        );
+        assert_eq!(dense.len(), 154);
+
+        let environment = super::procedural(StaticTimePoint::default(), [0; 32], params);
+        assert!(!environment.water().is_empty());
+        assert!(!environment.biomes().is_empty());
+    }
+
+    #[test]
+    fn micro_arenas_are_deterministic_and_keep_food_in_place() {
+        let chromosome = || {
+            Chromosome::<Float>::new_random(GENOME_LEN, -1. ..1., &mut Pcg64::from_seed([1; 32]))
+        };
+        let corridor = || {
+            super::corridor(
+                StaticTimePoint::default(),
+                [0; 32],
+                chromosome(),
+                400.,
+                20.,
+                Duration::from_millis(200),
+            )
+        };
+        let two_food_patches = || {
+            super::two_food_patches(
+                StaticTimePoint::default(),
+                [0; 32],
+                chromosome(),
+                600.,
+                100.,
+                4.,
+            )
+        };
+        assert_eq!(simulate(corridor()), simulate(corridor()));
+        assert_eq!(simulate(two_food_patches()), simulate(two_food_patches()));
+
+        let mut environment = corridor();
+        for _ in 0..50 {
+            let _ = environment.proceed(Duration::from_millis(100));
+        }
+        assert!(environment.food_count() > 0);
+        assert!(environment
+            .food()
+            .all(|food| food.position().x().abs() <= 200. && food.position().y().abs() <= 10.));
+    }
 }
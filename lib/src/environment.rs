@@ -1,19 +1,53 @@
 use std::{
     cell::{Ref, RefCell, RefMut},
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
     f64::consts::PI,
     ops::Deref,
     rc::Rc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    bug::Bug,
-    chunk::{ChunkedVec, Position, RawChunkIndex},
-    food_source::{FoodSource, FoodSourceShape},
-    math::{noneg_float, Angle, DeltaAngle, NoNeg, Point},
+    anomaly::{Anomaly, AnomalyDetector, AnomalyDetectorConfig},
+    biome::{Biome, BiomeMap},
+    brain::{Contact, FlockInfo, RetinaHit, SeenObject, RETINA_SECTORS},
+    bug::{BrainLog, Bug},
+    catastrophe::{Catastrophe, CatastropheScheduler},
+    chromo_utils::GENOME_LEN,
+    chunk::{ChunkedVec, IdIndexedVec, Identified, Position, RawChunkIndex},
+    config::{ConfigChange, SimConfig},
+    curriculum::Curriculum,
+    egg::{Egg, EGG_RADIUS, INCUBATION_DURATION},
+    events::{
+        DeathCause, EnvironmentEvent, EventHandler, EventQueue, SubscriptionId,
+        LOW_POPULATION_WARNING,
+    },
+    food_source::{FoodSource, FoodSourceChange, FoodSourceEdit, FoodSourceShape},
+    island::{IslandStats, Migrant, Migration},
+    light::{LightField, LightSource},
+    math::{noneg_float, Angle, DeltaAngle, NoNeg, Point, Rect, Vector, Zero},
+    nutrient::Nutrients,
+    odor::{OdorField, OdorInfo},
+    parasite::{Parasite, PARASITE_ATTACH_RANGE, PARASITE_BROOD_SIZE, PARASITE_SATED_ENERGY},
+    pheromone::{PheromoneField, PheromoneInfo},
+    phylogeny::{MutationCause, Phylogeny},
+    plant::{Plant, PlantCreateInfo, PLANT_CHUNK_FERTILITY, PLANT_FRUIT_SCATTER_RADIUS},
+    query::Query,
     range::Range,
+    scenario::{Scenario, ScenarioBug, ScenarioResult},
+    season::{Season, SeasonClock},
+    sim_clock::{SimClock, DEFAULT_DAY_LENGTH},
+    snapshot::EnvironmentSnapshot,
+    sound::{HearingInfo, Sound, SoundField},
+    species::{self, Species, SPECIES_DISTANCE_THRESHOLD},
+    stats::{ChunkLoad, LeaderboardEntry, StatsCollector, TickTimings},
+    tag::Tag,
+    terrain::Terrain,
+    territory::TerritoryMap,
     time_point::TimePoint,
-    utils::Float,
+    utils::{Color, Float},
+    water::{WaterInfo, WaterRegion},
 };
 use chromosome::Chromosome;
 use rand::{distributions::uniform::SampleRange, RngCore};
@@ -21,15 +55,115 @@ use rand::{Rng, SeedableRng};
 use rand_pcg::Pcg64;
 use serde::{Deserialize, Serialize};
 
+/// Id of food which is unique among existing food. Slots of eaten food are reused,
+/// generation distinguishes new food from the eaten one which had the same slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "FoodIdRepr")]
+pub struct FoodId {
+    slot: usize,
+    generation: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FoodIdRepr {
+    /// Saves made before ids were recycled
+    Legacy(usize),
+    Current {
+        slot: usize,
+        generation: usize,
+    },
+}
+
+impl From<FoodIdRepr> for FoodId {
+    fn from(value: FoodIdRepr) -> Self {
+        match value {
+            FoodIdRepr::Legacy(slot) => Self {
+                slot,
+                generation: 0,
+            },
+            FoodIdRepr::Current { slot, generation } => Self { slot, generation },
+        }
+    }
+}
+
+/// Allocates food ids recycling slots of removed food to keep ids compact under high food churn
+#[derive(Default, Serialize, Deserialize)]
+#[serde(from = "FoodIdPoolRepr")]
+pub(crate) struct FoodIdPool {
+    next_slot: usize,
+    /// Released slots with generation which is going to be used next
+    free_slots: Vec<(usize, usize)>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FoodIdPoolRepr {
+    /// `next_food_id` of saves made before ids were recycled
+    Legacy(usize),
+    Current {
+        next_slot: usize,
+        free_slots: Vec<(usize, usize)>,
+    },
+}
+
+impl From<FoodIdPoolRepr> for FoodIdPool {
+    fn from(value: FoodIdPoolRepr) -> Self {
+        match value {
+            FoodIdPoolRepr::Legacy(next_slot) => Self {
+                next_slot,
+                free_slots: Default::default(),
+            },
+            FoodIdPoolRepr::Current {
+                next_slot,
+                free_slots,
+            } => Self {
+                next_slot,
+                free_slots,
+            },
+        }
+    }
+}
+
+impl FoodIdPool {
+    pub(crate) fn acquire(&mut self) -> FoodId {
+        match self.free_slots.pop() {
+            Some((slot, generation)) => FoodId { slot, generation },
+            None => {
+                self.next_slot += 1;
+                FoodId {
+                    slot: self.next_slot - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    pub(crate) fn release(&mut self, id: FoodId) {
+        self.free_slots.push((id.slot, id.generation + 1));
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Food {
-    id: usize,
+    id: FoodId,
     position: Point<Float>,
     energy: NoNeg<Float>,
+    /// Share of energy which harms the eater instead of feeding it (see `Plant::toxin`)
+    #[serde(default = "Zero::zero")]
+    toxicity: NoNeg<Float>,
+    /// Time since food was placed. Old food rots (see `SimConfig::food_rot_rate`)
+    #[serde(default)]
+    age: Duration,
+    #[serde(default)]
+    nutrients: Nutrients,
+    /// Plant which dropped this fruit (see `Plant::toxin_harmed_eater`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    plant_id: Option<usize>,
 }
 
 impl Food {
-    pub(crate) fn id(&self) -> usize {
+    pub(crate) fn id(&self) -> FoodId {
         self.id
     }
 
@@ -37,7 +171,7 @@ impl Food {
         self.position
     }
 
-    pub(crate) fn energy(&self) -> NoNeg<Float> {
+    pub fn energy(&self) -> NoNeg<Float> {
         self.energy
     }
 
@@ -49,31 +183,56 @@ impl Food {
         &mut self.energy
     }
 
-    pub(crate) fn new(next_id: &mut usize, position: Point<Float>, energy: NoNeg<Float>) -> Self {
-        *next_id += 1;
+    pub fn toxicity(&self) -> NoNeg<Float> {
+        self.toxicity
+    }
+
+    pub fn age(&self) -> Duration {
+        self.age
+    }
+
+    pub fn nutrients(&self) -> &Nutrients {
+        &self.nutrients
+    }
+
+    pub(crate) fn new(ids: &mut FoodIdPool, position: Point<Float>, energy: NoNeg<Float>) -> Self {
+        Self::new_toxic(ids, position, energy, noneg_float(0.), Nutrients::BALANCED)
+    }
+
+    pub(crate) fn new_toxic(
+        ids: &mut FoodIdPool,
+        position: Point<Float>,
+        energy: NoNeg<Float>,
+        toxicity: NoNeg<Float>,
+        nutrients: Nutrients,
+    ) -> Self {
         Self {
-            id: *next_id - 1,
+            id: ids.acquire(),
             position,
             energy,
+            toxicity,
+            age: Duration::ZERO,
+            nutrients,
+            plant_id: None,
         }
     }
 
     pub(crate) fn generate<R: RngCore, RR: SampleRange<Float>>(
-        next_id: &mut usize,
+        ids: &mut FoodIdPool,
         rng: &mut R,
         x_range: RR,
         y_range: RR,
         e_range: RR,
     ) -> Self {
         Self::new(
-            next_id,
+            ids,
             (rng.gen_range(x_range), rng.gen_range(y_range)).into(),
             NoNeg::wrap(rng.gen_range(e_range)).unwrap(),
         )
     }
 
-    pub fn generate_vec<R: RngCore, RR: SampleRange<Float> + Clone>(
-        next_id: &mut usize,
+    pub(crate) fn generate_vec<R: RngCore, RR: SampleRange<Float> + Clone>(
+        ids: &mut FoodIdPool,
         rng: &mut R,
         x_range: RR,
         y_range: RR,
@@ -81,19 +240,27 @@ impl Food {
         count: usize,
     ) -> Vec<Self> {
         (0..count)
-            .map(|_| {
-                Self::generate(
-                    next_id,
-                    rng,
-                    x_range.clone(),
-                    y_range.clone(),
-                    e_range.clone(),
-                )
-            })
+            .map(|_| Self::generate(ids, rng, x_range.clone(), y_range.clone(), e_range.clone()))
             .collect()
     }
 }
 
+/// Reference to a bug which can be kept across iterations (e.g. selection or camera follow).
+/// Bug ids are never reused, but they are renumbered by `Environment::compact_ids`, so handle
+/// remembers generation of its id slot. Generation of a slot is bumped only when slot is given
+/// to another bug
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BugHandle {
+    id: usize,
+    generation: usize,
+}
+
+impl BugHandle {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
 impl Position for Food {
     fn position(&self) -> Point<Float> {
         self.position
@@ -106,9 +273,19 @@ impl Position for &Food {
     }
 }
 
+impl Identified for Food {
+    type Id = FoodId;
+
+    fn id(&self) -> FoodId {
+        self.id
+    }
+}
+
 pub struct FoodCreateInfo {
     pub position: Point<Float>,
     pub energy: NoNeg<Float>,
+    pub toxicity: NoNeg<Float>,
+    pub nutrients: Nutrients,
 }
 
 impl FoodCreateInfo {
@@ -121,6 +298,8 @@ impl FoodCreateInfo {
         Self {
             position: (rng.gen_range(x_range), rng.gen_range(y_range)).into(),
             energy: NoNeg::wrap(rng.gen_range(e_range)).unwrap(),
+            toxicity: noneg_float(0.),
+            nutrients: Nutrients::BALANCED,
         }
     }
 
@@ -136,8 +315,14 @@ impl FoodCreateInfo {
             .collect()
     }
 
-    pub(crate) fn create(self, next_id: &mut usize) -> Food {
-        Food::new(next_id, self.position, self.energy)
+    pub(crate) fn create(self, ids: &mut FoodIdPool) -> Food {
+        Food::new_toxic(
+            ids,
+            self.position,
+            self.energy,
+            self.toxicity,
+            self.nutrients,
+        )
     }
 }
 
@@ -156,7 +341,7 @@ impl BugCreateInfo {
         r_range: RR,
     ) -> Self {
         Self {
-            chromosome: Chromosome::new_random(256, g_range, rng),
+            chromosome: Chromosome::new_random(GENOME_LEN, g_range, rng),
             position: (rng.gen_range(x_range), rng.gen_range(y_range)).into(),
             rotation: Angle::from_radians(rng.gen_range(r_range)),
         }
@@ -184,11 +369,17 @@ impl BugCreateInfo {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FoodSourceCreateInfo {
     pub position: Point<Float>,
     pub shape: FoodSourceShape,
     pub energy_range: Range<Float>,
     pub spawn_interval: Duration,
+    /// Food is spawned only in water
+    pub aquatic: bool,
+    /// What spawned food consists of
+    #[serde(default)]
+    pub nutrients: Nutrients,
 }
 
 impl FoodSourceCreateInfo {
@@ -198,6 +389,8 @@ impl FoodSourceCreateInfo {
             self.shape,
             self.energy_range,
             self.spawn_interval,
+            self.aquatic,
+            self.nutrients,
             last_food_creation_instant,
         )
     }
@@ -206,28 +399,317 @@ impl FoodSourceCreateInfo {
 pub(crate) enum EnvironmentRequest {
     Suicide,
     GiveBirth {
+        parent_id: usize,
         chromosome: Chromosome<Float>,
         position: Point<Float>,
         rotation: Angle<Float>,
         energy_level: NoNeg<Float>,
     },
     TransferEnergyFromFoodToBug {
-        food_id: usize,
+        food_id: FoodId,
+        delta_energy: NoNeg<Float>,
+    },
+    /// Requester feeds its direct descendant
+    TransferEnergyBugToBug {
+        recipient_id: usize,
+        delta_energy: NoNeg<Float>,
+    },
+    /// Requester bites off energy of a bug within its eat range (energy cost is already paid)
+    Bite {
+        victim_id: usize,
         delta_energy: NoNeg<Float>,
     },
+    /// Requester injects venom into a bug within its eat range (energy cost is already paid)
+    Sting {
+        victim_id: usize,
+        dose: NoNeg<Float>,
+    },
     PlaceFood(FoodCreateInfo),
+    PlantSeed {
+        chromosome: Chromosome<Float>,
+        position: Point<Float>,
+        energy_level: NoNeg<Float>,
+    },
+    DepositPheromone {
+        position: Point<Float>,
+        amount: NoNeg<Float>,
+    },
+    /// Requester claims territory cell at `position`
+    MarkTerritory {
+        position: Point<Float>,
+        owner_id: usize,
+        color: Color,
+        amount: NoNeg<Float>,
+    },
+    EmitSound {
+        position: Point<Float>,
+        frequency: Float,
+        loudness: NoNeg<Float>,
+    },
+}
+
+/// Share of overlap of two bugs resolved per second when `SimConfig::bug_collisions` is on
+/// (bodies are soft, so a collision takes a few ticks to resolve)
+const COLLISION_STIFFNESS: Float = 10.;
+
+/// Width and height of chunks which store food, bugs and plants
+pub const CHUNK_SIZE: usize = 256;
+
+/// Max radius of sensing or interaction. Neighbor searches traverse only nearby chunks,
+/// so entities further than this can be silently missed
+pub const MAX_INTERACTION_RADIUS: NoNeg<Float> = noneg_float(CHUNK_SIZE as Float * 2.);
+
+/// Chunks far from observed region and tracked bug are simulated with reduced rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkSleepConfig {
+    /// Chunks further than this number of chunks from observed region and tracked bug are
+    /// sleeping
+    pub distance: usize,
+    /// Sleeping chunk is updated once per `period` iterations. Its entities are stepped by time
+    /// elapsed since their previous update, so moving between chunks does not add or lose time.
+    /// If 0, sleeping chunks are frozen (bugs and plants still age, because age depends on global time)
+    pub period: usize,
+}
+
+impl Default for ChunkSleepConfig {
+    fn default() -> Self {
+        Self {
+            distance: 2,
+            period: 8,
+        }
+    }
+}
+
+/// How entities of a chunk are simulated in current iteration (see `ChunkSleepConfig`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkTick {
+    Update,
+    /// Sleeping chunk waits for its turn
+    Skip,
+    /// Time of frozen chunk is not simulated at all
+    Freeze,
+}
+
+impl ChunkTick {
+    /// Time to step entity by (None if it is not updated). `skipped_time` of entity is time
+    /// elapsed since its previous update
+    fn dt(self, dt: Duration, skipped_time: &mut Duration) -> Option<Duration> {
+        match self {
+            ChunkTick::Update => Some(dt + std::mem::take(skipped_time)),
+            ChunkTick::Skip => {
+                *skipped_time += dt;
+                None
+            }
+            ChunkTick::Freeze => None,
+        }
+    }
+}
+
+/// Which bugs keep log of their last brain activity (inputs, outputs and activations).
+/// Logs are only needed for display, so large unattended simulations can mostly skip them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum BrainLogPolicy {
+    /// All bugs log every iteration
+    #[default]
+    All,
+    /// Only bugs with listed ids log. Logs of other bugs are dropped
+    Pinned(Vec<usize>),
+    /// All bugs log once per `n` iterations (logs are kept in between)
+    EveryNth(usize),
+}
+
+impl BrainLogPolicy {
+    pub(crate) fn should_log(&self, bug_id: usize, iteration: usize) -> bool {
+        match self {
+            BrainLogPolicy::All => true,
+            BrainLogPolicy::Pinned(ids) => ids.contains(&bug_id),
+            BrainLogPolicy::EveryNth(n) => iteration.rem_euclid((*n).max(1)) == 0,
+        }
+    }
+
+    /// If false, log of a bug is dropped on iterations it is not written
+    pub(crate) fn keeps_stale_logs(&self) -> bool {
+        matches!(self, BrainLogPolicy::EveryNth(_))
+    }
+}
+
+/// Conditions which stop simulation. All are disabled by default (completed scenario stops
+/// simulation regardless of them)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StopConditions {
+    /// Stop when all bugs are dead
+    pub extinction: bool,
+    /// Stop when population exceeds this number
+    pub max_population: Option<usize>,
+    /// Stop when simulated time since creation of environment exceeds this duration
+    pub max_sim_time: Option<Duration>,
+    /// Stop when number of species drops below this number.
+    /// Checked once per `SPECIES_CHECK_PERIOD` iterations, because clustering is expensive
+    pub min_species_count: Option<usize>,
+    /// Stop when anomaly detector flags something (see `SeededEnvironment::set_anomaly_detection`)
+    #[serde(default)]
+    pub anomaly: bool,
+}
+
+pub const SPECIES_CHECK_PERIOD: usize = 64;
+
+/// What happens to bugs at edges of the world. Bounding the world bounds memory used by chunks
+/// which runaway bugs would otherwise allocate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum EdgePolicy {
+    /// Bugs are reflected from edges of bounds (see `Environment::set_bounds`)
+    #[default]
+    Wall,
+    /// Bugs crossing an edge of bounds reappear at the opposite edge
+    Wrap,
+    /// Bugs further than `radius` from the origin die (bounds are ignored)
+    Death { radius: NoNeg<Float> },
+}
+
+/// Rotting food disappears when its energy drops below this value
+pub const ROTTEN_FOOD_ENERGY: NoNeg<Float> = noneg_float(0.01);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    Extinction,
+    PopulationExceeded {
+        population: usize,
+    },
+    SimTimeExceeded {
+        sim_time: Duration,
+    },
+    SpeciesCountBelow {
+        species_count: usize,
+    },
+    AnomalyDetected(Anomaly),
+    /// Victory or defeat goal of scenario set on the viewed island was met
+    ScenarioCompleted(ScenarioResult),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[must_use]
+pub enum TickOutcome {
+    Continue,
+    Stopped(StopReason),
+}
+
+fn default_day_length() -> Duration {
+    DEFAULT_DAY_LENGTH
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Environment<T> {
-    food: ChunkedVec<Food, 256, 256>,
+    food: IdIndexedVec<Food, CHUNK_SIZE, CHUNK_SIZE>,
     food_sources: Vec<Rc<RefCell<FoodSource<T>>>>,
-    bugs: ChunkedVec<Rc<RefCell<Bug<T>>>, 256, 256>,
+    /// Shared, because requests of a bug borrow other bugs (victims, recipients, hosts) by id while
+    /// it is borrowed itself. Bugs proceed one after another on a single thread
+    bugs: IdIndexedVec<Rc<RefCell<Bug<T>>>, CHUNK_SIZE, CHUNK_SIZE>,
+    #[serde(default = "ChunkedVec::default")]
+    plants: ChunkedVec<Plant<T>, CHUNK_SIZE, CHUNK_SIZE>,
+    #[serde(default = "ChunkedVec::default")]
+    eggs: ChunkedVec<Egg<T>, CHUNK_SIZE, CHUNK_SIZE>,
+    #[serde(default = "ChunkedVec::default")]
+    parasites: ChunkedVec<Parasite, CHUNK_SIZE, CHUNK_SIZE>,
     creation_time: T,
     now: T,
-    next_food_id: usize,
+    #[serde(alias = "next_food_id")]
+    food_ids: FoodIdPool,
     next_bug_id: usize,
+    /// Generation of id slots which are not in `id_generations`. Incremented whenever bug ids
+    /// are renumbered (see `BugHandle`)
+    #[serde(default)]
+    id_generation: usize,
+    /// Generations of id slots assigned by the last `compact_ids`
+    #[serde(default)]
+    id_generations: BTreeMap<usize, usize>,
+    #[serde(default)]
+    next_plant_id: usize,
+    #[serde(default)]
+    next_egg_id: usize,
+    #[serde(default)]
+    next_parasite_id: usize,
     iteration: usize,
+    #[serde(default)]
+    phylogeny: Phylogeny,
+    #[serde(skip)]
+    stats: StatsCollector,
+    /// Time when the last bug died (None while population is alive)
+    #[serde(default = "Option::default")]
+    extinct_since: Option<T>,
+    #[serde(skip)]
+    chunk_sleep: Option<ChunkSleepConfig>,
+    #[serde(skip)]
+    observed_region: Option<Rect<Float>>,
+    #[serde(default)]
+    tracked_bug: Option<BugHandle>,
+    /// Position of tracked bug at the beginning of current iteration
+    #[serde(skip)]
+    tracked_position: Option<Point<Float>>,
+    #[serde(skip)]
+    brain_log_policy: BrainLogPolicy,
+    #[serde(default)]
+    config: SimConfig,
+    /// Every config applied after creation
+    #[serde(default)]
+    config_log: Vec<ConfigChange>,
+    /// Every food source added, edited or removed after creation
+    #[serde(default)]
+    food_source_log: Vec<FoodSourceChange>,
+    /// Pheromones evaporate in seconds, so they are not saved
+    #[serde(skip)]
+    pheromones: PheromoneField,
+    /// Odor is rebuilt from food in a few seconds, so it is not saved either
+    #[serde(skip)]
+    odor: OdorField,
+    /// Territory is reclaimed by living bugs within a minute, so it is not saved either
+    #[serde(skip)]
+    territory: TerritoryMap,
+    /// Sounds fade out in a fraction of second, so they are not saved either
+    #[serde(skip)]
+    sounds: SoundField,
+    #[serde(default)]
+    water: Vec<WaterRegion>,
+    /// None if food production does not depend on time of year
+    #[serde(default)]
+    seasons: Option<SeasonClock>,
+    /// None if environment is flat
+    #[serde(default)]
+    terrain: Option<Terrain>,
+    /// None if world is unbounded, otherwise bugs are reflected from edges of this rect
+    #[serde(default)]
+    bounds: Option<Rect<Float>>,
+    #[serde(default)]
+    edge_policy: EdgePolicy,
+    /// Chunks without biome are neutral ground
+    #[serde(default)]
+    biomes: BiomeMap,
+    #[serde(default)]
+    light: LightField,
+    /// None if environment difficulty does not change over time
+    #[serde(default)]
+    curriculum: Option<Curriculum>,
+    /// None if environment is never struck by catastrophes
+    #[serde(default)]
+    catastrophes: Option<CatastropheScheduler>,
+    /// None if run has no victory or defeat conditions
+    #[serde(default)]
+    scenario: Option<Scenario>,
+    /// Bugs arrived from other islands (see `SeededEnvironment::add_island`)
+    #[serde(default)]
+    immigrants: usize,
+    /// Bugs left to other islands
+    #[serde(default)]
+    emigrants: usize,
+    #[serde(default = "default_day_length")]
+    day_length: Duration,
+    #[serde(skip)]
+    events: EventQueue,
+    /// Work done in each chunk during the last iteration
+    #[serde(skip)]
+    chunk_load: RefCell<HashMap<(isize, isize), ChunkLoad>>,
+    /// Time spent in phases of the last iteration
+    #[serde(skip)]
+    tick_timings: RefCell<TickTimings>,
 }
 
 impl<T> Environment<T> {
@@ -240,29 +722,30 @@ impl<T> Environment<T> {
     where
         T: Clone,
     {
-        let mut next_food_id = 0;
+        let mut food_ids = FoodIdPool::default();
         let mut next_bug_id = 0;
 
         let food = food
             .into_iter()
-            .map(|create_info| {
-                Food::new(&mut next_food_id, create_info.position, create_info.energy)
-            })
+            .map(|create_info| create_info.create(&mut food_ids))
             .collect();
         let food_sources = food_sources
             .into_iter()
             .map(|create_info| Rc::new(RefCell::new(create_info.create(now.clone()))))
             .collect();
+        let mut phylogeny = Phylogeny::default();
         let bugs = bugs
             .into_iter()
             .map(|create_info| {
-                Rc::new(RefCell::new(Bug::give_birth_with_max_energy(
+                let bug = Bug::give_birth_with_max_energy(
                     &mut next_bug_id,
                     create_info.chromosome,
                     create_info.position,
                     create_info.rotation,
                     now.clone(),
-                )))
+                );
+                phylogeny.record_birth(bug.id(), None, bug.chromosome(), 0);
+                Rc::new(RefCell::new(bug))
             })
             .collect();
 
@@ -270,11 +753,50 @@ impl<T> Environment<T> {
             food,
             food_sources,
             bugs,
+            plants: Default::default(),
+            eggs: Default::default(),
+            parasites: Default::default(),
             creation_time: now.clone(),
             now,
-            next_food_id: 0,
-            next_bug_id: 0,
+            food_ids,
+            next_bug_id,
+            id_generation: 0,
+            id_generations: Default::default(),
+            next_plant_id: 0,
+            next_egg_id: 0,
+            next_parasite_id: 0,
             iteration: 0,
+            phylogeny,
+            stats: Default::default(),
+            chunk_sleep: None,
+            observed_region: None,
+            tracked_bug: None,
+            tracked_position: None,
+            brain_log_policy: Default::default(),
+            config: Default::default(),
+            config_log: Default::default(),
+            food_source_log: Default::default(),
+            pheromones: Default::default(),
+            odor: Default::default(),
+            territory: Default::default(),
+            sounds: Default::default(),
+            water: Default::default(),
+            seasons: None,
+            terrain: None,
+            bounds: None,
+            edge_policy: EdgePolicy::Wall,
+            biomes: Default::default(),
+            light: Default::default(),
+            curriculum: None,
+            catastrophes: None,
+            scenario: None,
+            immigrants: 0,
+            emigrants: 0,
+            day_length: DEFAULT_DAY_LENGTH,
+            events: Default::default(),
+            chunk_load: Default::default(),
+            tick_timings: Default::default(),
+            extinct_since: None,
         }
     }
 
@@ -292,11 +814,11 @@ impl<T> Environment<T> {
         Range: Clone,
         T: Clone,
     {
-        let mut next_food_id = 0;
+        let mut food_ids = FoodIdPool::default();
         let mut next_bug_id = 0;
 
         let food = Food::generate_vec(
-            &mut next_food_id,
+            &mut food_ids,
             rng,
             x_range,
             y_range,
@@ -337,15 +859,60 @@ impl<T> Environment<T> {
             .unwrap(),
         ))];
 
+        let mut phylogeny = Phylogeny::default();
+        for bug in &bugs {
+            let bug = bug.borrow();
+            phylogeny.record_birth(bug.id(), None, bug.chromosome(), 0);
+        }
+
         Self {
             food: food.into_iter().collect(),
             food_sources,
             bugs: bugs.into_iter().collect(),
+            plants: Default::default(),
+            eggs: Default::default(),
+            parasites: Default::default(),
             creation_time: now.clone(),
             now,
             next_bug_id,
-            next_food_id,
+            id_generation: 0,
+            id_generations: Default::default(),
+            food_ids,
+            next_plant_id: 0,
+            next_egg_id: 0,
+            next_parasite_id: 0,
             iteration: 0,
+            phylogeny,
+            stats: Default::default(),
+            chunk_sleep: None,
+            observed_region: None,
+            tracked_bug: None,
+            tracked_position: None,
+            brain_log_policy: Default::default(),
+            config: Default::default(),
+            config_log: Default::default(),
+            food_source_log: Default::default(),
+            pheromones: Default::default(),
+            odor: Default::default(),
+            territory: Default::default(),
+            sounds: Default::default(),
+            water: Default::default(),
+            seasons: None,
+            terrain: None,
+            bounds: None,
+            edge_policy: EdgePolicy::Wall,
+            biomes: Default::default(),
+            light: Default::default(),
+            curriculum: None,
+            catastrophes: None,
+            scenario: None,
+            immigrants: 0,
+            emigrants: 0,
+            day_length: DEFAULT_DAY_LENGTH,
+            events: Default::default(),
+            chunk_load: Default::default(),
+            tick_timings: Default::default(),
+            extinct_since: None,
         }
     }
 
@@ -357,19 +924,49 @@ impl<T> Environment<T> {
         &self.creation_time
     }
 
+    /// Time passed since creation of environment in days
+    pub fn clock(&self) -> SimClock
+    where
+        T: TimePoint,
+    {
+        self.clock_at(&self.now)
+    }
+
+    /// Clock showing `time` (e.g. of some past event)
+    pub fn clock_at(&self, time: &T) -> SimClock
+    where
+        T: TimePoint,
+    {
+        SimClock::new(time.duration_since(&self.creation_time), self.day_length)
+    }
+
+    pub fn set_day_length(&mut self, day_length: Duration) {
+        self.day_length = day_length
+    }
+
     pub fn iteration(&self) -> usize {
         self.iteration
     }
 
+    /// Advance world by `dt`. Plants, food sources and bugs make requests, which are applied
+    /// afterwards one by one in the order requesters were visited. Bug state is not double
+    /// buffered: a bug senses neighbours already moved during this tick
     pub fn proceed<R: RngCore>(&mut self, dt: Duration, rng: &mut R)
     where
         T: TimePoint + Clone,
     {
         self.now += dt;
+        self.tracked_position = self
+            .tracked_bug
+            .and_then(|handle| self.resolve(handle).map(|bug| bug.position()));
+        self.chunk_load.get_mut().clear();
+        *self.tick_timings.get_mut() = Default::default();
+        let requests_start = Instant::now();
 
         enum Requester<T> {
             FoodSource(Rc<RefCell<FoodSource<T>>>),
             Bug(Rc<RefCell<Bug<T>>>),
+            Plant { id: usize, position: Point<Float> },
         }
 
         impl<T> Requester<T> {
@@ -377,6 +974,7 @@ impl<T> Environment<T> {
                 match self {
                     Requester::FoodSource(_) => None,
                     Requester::Bug(rc) => Some(rc.borrow_mut()),
+                    Requester::Plant { .. } => None,
                 }
             }
         }
@@ -384,48 +982,127 @@ impl<T> Environment<T> {
         let mut requests: Vec<(Requester<T>, Vec<EnvironmentRequest>)> = Default::default();
         {
             let now = self.now().clone();
+            let abundance = self.food_abundance();
             for food_source in &mut self.food_sources {
-                let r = food_source.as_ref().borrow_mut().proceed(&now, rng);
+                let r =
+                    food_source
+                        .as_ref()
+                        .borrow_mut()
+                        .proceed(&now, &self.water, abundance, rng);
+                Self::record_chunk_load(
+                    &self.chunk_load,
+                    food_source.borrow().position(),
+                    |load| load.requests += r.len(),
+                );
                 requests.push((Requester::FoodSource(food_source.clone()), r));
             }
         }
 
+        {
+            let now = self.now().clone();
+            let fertility: Vec<NoNeg<Float>> = self
+                .plants
+                .iter()
+                .map(|plant| {
+                    PLANT_CHUNK_FERTILITY * self.biomes.biome_at(plant.position()).fertility
+                        / NoNeg::wrap(self.plants.chunk_len_at(plant.position()) as Float).unwrap()
+                })
+                .collect();
+            let ticks: Vec<ChunkTick> = self
+                .plants
+                .iter()
+                .map(|plant| self.chunk_tick(plant.position()))
+                .collect();
+            for ((plant, fertility), tick) in self.plants.iter_mut().zip(fertility).zip(ticks) {
+                let Some(dt) = tick.dt(dt, plant.skipped_time_mut()) else {
+                    continue;
+                };
+                let r = plant.proceed(&now, fertility, dt, rng);
+                Self::record_chunk_load(&self.chunk_load, plant.position(), |load| {
+                    load.requests += r.len()
+                });
+                requests.push((
+                    Requester::Plant {
+                        id: plant.id(),
+                        position: plant.position(),
+                    },
+                    r,
+                ));
+            }
+        }
+
+        let bugs_start = Instant::now();
+        let mut requests_time = bugs_start - requests_start;
         for b in self.bugs.iter() {
+            let tick = self.chunk_tick(b.position());
+            let Some(dt) = tick.dt(dt, b.borrow_mut().skipped_time_mut()) else {
+                continue;
+            };
             let r = b.as_ref().borrow_mut().proceed(&self, dt, rng);
+            Self::record_chunk_load(&self.chunk_load, b.position(), |load| {
+                load.requests += r.len()
+            });
             requests.push((Requester::Bug(b.clone()), r));
         }
 
+        if self.config.bug_collisions {
+            self.resolve_collisions(dt);
+        }
+
+        let shuffle_start = Instant::now();
         self.bugs.shuffle();
+        let requests_start = Instant::now();
+        {
+            let timings = self.tick_timings.get_mut();
+            timings.shuffle = requests_start - shuffle_start;
+            // sense and brain are measured by bugs themselves
+            timings.movement =
+                (shuffle_start - bugs_start).saturating_sub(timings.sense + timings.brain);
+        }
 
         for (requester, requests) in requests {
             for request in requests {
                 match request {
                     EnvironmentRequest::Suicide => {
-                        let (position, id) = {
-                            let b = requester.bug_ref().unwrap();
-                            (b.position(), b.id())
-                        };
-                        let chunk_found = self
-                            .bugs
-                            .retain_by_position(position, |x| x.borrow().id() != id);
-                        assert!(chunk_found);
+                        if let Requester::Plant { id, position } = requester {
+                            let chunk_found =
+                                self.plants.retain_by_position(position, |x| x.id() != id);
+                            assert!(chunk_found);
+                        } else if let Requester::Bug(bug) = &requester {
+                            let cause = if bug.borrow().energy_level() == noneg_float(0.) {
+                                DeathCause::Starvation
+                            } else {
+                                DeathCause::OldAge
+                            };
+                            self.remove_dead_bug(bug, cause);
+                        }
                     }
                     EnvironmentRequest::GiveBirth {
+                        parent_id,
                         chromosome,
                         position,
                         rotation,
                         energy_level,
                     } => {
-                        for bug in Bug::give_birth_to_twins(
-                            &mut self.next_bug_id,
-                            chromosome,
-                            position,
-                            rotation,
-                            energy_level,
-                            self.now.clone(),
-                        ) {
-                            self.bugs.push(Rc::new(RefCell::new(bug)));
-                        }
+                        let tags = match requester.bug_ref() {
+                            Some(mut parent) => {
+                                parent.register_offspring();
+                                parent.inherited_tags()
+                            }
+                            None => vec![],
+                        };
+                        self.eggs.push(
+                            Egg::new(
+                                &mut self.next_egg_id,
+                                parent_id,
+                                chromosome,
+                                position,
+                                rotation,
+                                energy_level,
+                                self.now.clone(),
+                            )
+                            .with_tags(tags),
+                        );
                     }
                     EnvironmentRequest::TransferEnergyFromFoodToBug {
                         food_id,
@@ -435,299 +1112,3683 @@ impl<T> Environment<T> {
                         &mut requester.bug_ref().unwrap(),
                         delta_energy,
                     ),
-                    EnvironmentRequest::PlaceFood(food_create_info) => self
-                        .food
-                        .push(food_create_info.create(&mut self.next_food_id)),
+                    EnvironmentRequest::TransferEnergyBugToBug {
+                        recipient_id,
+                        delta_energy,
+                    } => self.transfer_energy_bug_to_bug(
+                        &mut requester.bug_ref().unwrap(),
+                        recipient_id,
+                        delta_energy,
+                    ),
+                    EnvironmentRequest::Bite {
+                        victim_id,
+                        delta_energy,
+                    } => self.bite(&mut requester.bug_ref().unwrap(), victim_id, delta_energy),
+                    EnvironmentRequest::Sting { victim_id, dose } => {
+                        self.sting(&requester.bug_ref().unwrap(), victim_id, dose)
+                    }
+                    EnvironmentRequest::PlaceFood(mut food_create_info) => {
+                        // saturated chunks reject new food
+                        if self.config.max_food_per_chunk.is_some_and(|max| {
+                            self.food.chunk_len_at(food_create_info.position) >= max
+                        }) {
+                            continue;
+                        }
+                        food_create_info.energy = food_create_info.energy
+                            * self.biomes.biome_at(food_create_info.position).fertility;
+                        self.events.push(EnvironmentEvent::FoodSpawned {
+                            position: food_create_info.position,
+                            energy: food_create_info.energy,
+                        });
+                        let mut food = food_create_info.create(&mut self.food_ids);
+                        if let Requester::Plant { id, .. } = &requester {
+                            food.plant_id = Some(*id);
+                        }
+                        self.food.push(food)
+                    }
+                    EnvironmentRequest::PlantSeed {
+                        chromosome,
+                        position,
+                        energy_level,
+                    } => self.plants.push(Plant::new(
+                        &mut self.next_plant_id,
+                        chromosome,
+                        position,
+                        energy_level,
+                        self.now.clone(),
+                    )),
+                    EnvironmentRequest::DepositPheromone { position, amount } => {
+                        self.pheromones.deposit(position, amount)
+                    }
+                    EnvironmentRequest::MarkTerritory {
+                        position,
+                        owner_id,
+                        color,
+                        amount,
+                    } => self.territory.mark(position, owner_id, &color, amount),
+                    EnvironmentRequest::EmitSound {
+                        position,
+                        frequency,
+                        loudness,
+                    } => self.sounds.emit(Sound {
+                        emitter_id: requester.bug_ref().unwrap().id(),
+                        position,
+                        frequency,
+                        loudness,
+                        age: Duration::ZERO,
+                    }),
                 }
             }
         }
 
+        self.stats.record_food_sources(
+            self.food_sources
+                .iter()
+                .map(|food_source| food_source.borrow().stats().clone())
+                .collect(),
+        );
+        // after requests, so bugs killed by the edge do not act (or die once more) in this tick
+        self.enforce_edge_policy();
+        self.proceed_eggs();
+        self.proceed_parasites(dt);
+        self.proceed_curriculum();
+        self.proceed_catastrophes(rng);
+        self.proceed_scenario();
+        self.rot_food(dt);
+        self.pheromones.proceed(dt);
+        self.odor.proceed(
+            dt,
+            self.food
+                .iter()
+                .map(|food| (food.position(), food.energy())),
+        );
+        self.territory.proceed(dt);
+        self.sounds.proceed(dt);
+        requests_time += requests_start.elapsed();
+        self.tick_timings.get_mut().requests = requests_time;
         self.iteration += 1;
     }
 
-    pub fn find_bug_by_id<'a>(&'a self, id: usize) -> Option<Ref<'a, Bug<T>>> {
-        self.bugs
-            .iter()
-            .find_map(|bug| bug.try_borrow().ok().filter(|bug| bug.id() == id))
+    /// Push overlapping bugs (body radius is eat range, buried ones are ignored) apart. Each bug
+    /// of a pair is moved by a share of overlap proportional to the size of the other one, so
+    /// larger bugs shove smaller ones. Offsets of all pairs are summed up before anything moves,
+    /// so the result does not depend on order of bugs
+    fn resolve_collisions(&mut self, dt: Duration) {
+        let share = (COLLISION_STIFFNESS * dt.as_secs_f64()).min(1.);
+        let mut offsets: BTreeMap<usize, (Rc<RefCell<Bug<T>>>, (Float, Float))> =
+            Default::default();
+        for a in self.bugs.iter() {
+            let a_ref = a.borrow();
+            if a_ref.buried() {
+                continue;
+            }
+            let a_radius = a_ref.eat_range();
+            // every overlapping pair is within twice the radius of its larger bug,
+            // so it is resolved only from the side of that bug
+            for b in self
+                .bugs
+                .iter_in_range(a_ref.position(), a_radius * noneg_float(2.))
+            {
+                let b_ref = b.borrow();
+                let b_radius = b_ref.eat_range();
+                if b_ref.buried()
+                    || b_ref.id() == a_ref.id()
+                    || (b_radius, b_ref.id()) > (a_radius, a_ref.id())
+                {
+                    continue;
+                }
+                let delta = b_ref.position() - a_ref.position();
+                let distance = delta.len();
+                let overlap = (a_radius + b_radius).unwrap() - distance;
+                let total_size = (a_ref.size() + b_ref.size()).unwrap();
+                if overlap <= 0. || total_size <= 0. {
+                    continue;
+                }
+                // bugs in the same point are pushed apart along x axis
+                let direction = if distance > 0. {
+                    (*delta.x() / distance, *delta.y() / distance)
+                } else {
+                    (1., 0.)
+                };
+                let shift = overlap * share / total_size;
+                for (bug, id, sign, other_size) in [
+                    (a, a_ref.id(), -1., b_ref.size()),
+                    (b, b_ref.id(), 1., a_ref.size()),
+                ] {
+                    let shift = sign * shift * other_size.unwrap();
+                    let offset = &mut offsets
+                        .entry(id)
+                        .or_insert_with(|| (bug.clone(), (0., 0.)))
+                        .1;
+                    offset.0 += direction.0 * shift;
+                    offset.1 += direction.1 * shift;
+                }
+            }
+        }
+        let metabolism = self.metabolism_multiplier();
+        for (bug, offset) in offsets.into_values() {
+            bug.borrow_mut().push_by(offset, metabolism);
+        }
     }
 
-    fn transfer_energy_from_food_to_bug(
-        &mut self,
-        food_id: usize,
-        bug: &mut Bug<T>,
-        delta_energy: NoNeg<Float>,
-    ) {
-        if let Some(food_index) =
-            self.food
-                .index_of_in_range(|b| b.id() == food_id, bug.position(), bug.eat_range())
-        {
-            if bug.eat(&mut self.food[food_index.clone()], delta_energy) {
-                self.food.remove(food_index);
+    /// Return bugs which crossed edges of bounds back inside or kill them depending on
+    /// `EdgePolicy`
+    fn enforce_edge_policy(&mut self)
+    where
+        T: TimePoint + Clone,
+    {
+        match (self.edge_policy, &self.bounds) {
+            (EdgePolicy::Wall, Some(bounds)) => {
+                for bug in self.bugs.iter() {
+                    bug.borrow_mut().reflect_from(bounds);
+                }
+            }
+            (EdgePolicy::Wrap, Some(bounds)) => {
+                for bug in self.bugs.iter() {
+                    bug.borrow_mut().wrap_around(bounds);
+                }
             }
+            (EdgePolicy::Death { radius }, _) => {
+                let victims: Vec<_> = self
+                    .bugs
+                    .iter()
+                    .filter(|bug| {
+                        (bug.borrow().position() - Point::from((0., 0.))).len() > radius.unwrap()
+                    })
+                    .cloned()
+                    .collect();
+                for bug in &victims {
+                    self.remove_dead_bug(bug, DeathCause::WorldEdge);
+                }
+            }
+            (_, None) => {}
         }
     }
 
-    pub fn food(&self) -> impl Iterator<Item = &Food> {
-        self.food.iter()
-    }
+    /// Let bugs eat eggs within their eat range (parents do not eat their own eggs) and hatch
+    /// eggs which were incubated for `INCUBATION_DURATION`
+    fn proceed_eggs(&mut self)
+    where
+        T: TimePoint + Clone,
+    {
+        let bugs = &self.bugs;
+        self.eggs.retain_mut(|egg| {
+            let parent_id = egg.parent_id();
+            let eater = bugs.find_nearest_filter_map(
+                egg.position(),
+                MAX_INTERACTION_RADIUS + EGG_RADIUS,
+                |bug| {
+                    let b = bug.borrow();
+                    (b.id() != parent_id && !b.buried()).then(|| bug.clone())
+                },
+            );
+            match eater {
+                Some((bug, dst)) if dst < bug.borrow().eat_range() + EGG_RADIUS => {
+                    !bug.borrow_mut().eat_egg(egg)
+                }
+                _ => true,
+            }
+        });
 
-    pub fn food_count(&self) -> usize {
-        self.food.len()
+        let now = &self.now;
+        let hatched = self
+            .eggs
+            .take_if(|egg| now.duration_since(egg.laid_instant()) >= INCUBATION_DURATION);
+        for egg in hatched {
+            let (parent_id, chromosome, position, rotation, energy_level, tags) = egg.into_parts();
+            for mut bug in Bug::give_birth_to_twins(
+                &mut self.next_bug_id,
+                chromosome,
+                position,
+                rotation,
+                energy_level,
+                self.now.clone(),
+            ) {
+                self.phylogeny.record_birth(
+                    bug.id(),
+                    Some(parent_id),
+                    bug.chromosome(),
+                    self.iteration,
+                );
+                self.events.push(EnvironmentEvent::BugBorn {
+                    id: bug.id(),
+                    parent_id: Some(parent_id),
+                    position: bug.position(),
+                });
+                bug.set_tags(tags.clone());
+                self.bugs.push(Rc::new(RefCell::new(bug)));
+            }
+        }
     }
 
-    pub(crate) fn find_nearest_food_in_vision_arc(
-        &self,
-        position: Point<Float>,
-        range: NoNeg<Float>,
-        vision_rotation: Angle<Float>,
-        vision_half_arc: DeltaAngle<NoNeg<Float>>,
-    ) -> Option<(&Food, NoNeg<Float>)> {
-        self.food.find_nearest_filter_map(position, range, |food| {
-            let arc = Range {
-                start: vision_rotation - vision_half_arc.unwrap(),
-                end: vision_rotation + vision_half_arc.unwrap(),
+    /// Attached parasites follow their hosts and feed on them. Sated ones split into brood, free
+    /// ones starve until they attach to a bug passing by
+    fn proceed_parasites(&mut self, dt: Duration) {
+        let dt = NoNeg::wrap(dt.as_secs_f64()).unwrap();
+        let bugs = &self.bugs;
+        let next_parasite_id = &mut self.next_parasite_id;
+        let mut brood = vec![];
+        self.parasites.retain_mut(|parasite| {
+            let Some(host_id) = parasite.host_id() else {
+                if parasite.starve(dt) {
+                    return false;
+                }
+                if let Some((host, _)) = bugs.find_nearest_filter_map(
+                    parasite.position(),
+                    PARASITE_ATTACH_RANGE,
+                    |bug| (!bug.borrow().buried()).then(|| bug.clone()),
+                ) {
+                    parasite.attach(&host.borrow());
+                }
+                return true;
             };
-
-            if vision_half_arc == DeltaAngle::from_radians(noneg_float(PI))
-                || (food.position().clone() - position)
-                    .angle()
-                    .is_contained_in(arc)
-            {
-                Some(food)
-            } else {
-                None
+            match bugs.index_of_id(&host_id) {
+                Some(index) => {
+                    parasite.feed_on(&mut bugs[index].borrow_mut());
+                    if parasite.sated() {
+                        brood.push(parasite.clone());
+                        return false;
+                    }
+                }
+                None => parasite.set_host_id(None),
             }
-        })
+            true
+        });
+        for parasite in brood {
+            for parasite in parasite.split(next_parasite_id) {
+                self.parasites.push(parasite);
+            }
+        }
+        self.parasites.shuffle();
     }
 
-    pub(crate) fn find_nearest_bug_in_vision_arc<'a>(
-        &'a self,
-        position: Point<Float>,
-        range: NoNeg<Float>,
-        vision_rotation: Angle<Float>,
-        vision_half_arc: DeltaAngle<NoNeg<Float>>,
-    ) -> Option<(Ref<'a, Bug<T>>, NoNeg<Float>)> {
-        self.bugs.find_nearest_filter_map(position, range, |x| {
-            x.try_borrow().ok().and_then(|other| {
-                if vision_half_arc == DeltaAngle::from_radians(noneg_float(PI))
-                    || (other.position().clone() - position)
-                        .angle()
-                        .is_contained_in(Range {
-                            start: vision_rotation - vision_half_arc.unwrap(),
-                            end: vision_rotation + vision_half_arc.unwrap(),
-                        })
-                {
-                    Some(other)
-                } else {
-                    None
-                }
-            })
-        })
+    /// Remove `bug` recording its death in stats, phylogeny and events. Bugs which are already
+    /// removed are ignored
+    fn remove_dead_bug(&mut self, bug: &Rc<RefCell<Bug<T>>>, cause: DeathCause)
+    where
+        T: TimePoint + Clone,
+    {
+        // bug could have moved to another chunk since the last shuffle, so it is looked up by id
+        let Some(index) = self.bugs.index_of_id(&bug.borrow().id()) else {
+            return;
+        };
+        let id = {
+            let b = bug.borrow();
+            self.stats.record_death(
+                &b.chromosome().genes,
+                self.now.duration_since(b.birth_instant()),
+                self.phylogeny.offspring_count(b.id()).unwrap_or(0),
+            );
+            self.events.push(EnvironmentEvent::BugDied {
+                id: b.id(),
+                position: b.position(),
+                rotation: b.rotation(),
+                size: b.size(),
+                color: b.color().clone(),
+                cause,
+            });
+            b.id()
+        };
+        self.bugs.remove(index);
+        self.phylogeny.record_death(id, self.iteration);
+        if self.bugs.len() == LOW_POPULATION_WARNING {
+            self.events.push(EnvironmentEvent::PopulationLow {
+                population: LOW_POPULATION_WARNING,
+            });
+        }
+        if self.bugs.len() == 0 {
+            self.extinct_since = Some(self.now.clone());
+        }
     }
 
-    pub fn food_sources<'a>(&'a self) -> impl Iterator<Item = Ref<'a, FoodSource<T>>> {
-        self.food_sources.iter().map(|x| x.as_ref().borrow())
+    fn proceed_catastrophes<R: RngCore>(&mut self, rng: &mut R)
+    where
+        T: TimePoint + Clone,
+    {
+        let elapsed = self.clock().elapsed();
+        let Some(scheduler) = &mut self.catastrophes else {
+            return;
+        };
+        for catastrophe in scheduler.proceed(elapsed, rng) {
+            self.strike(catastrophe, rng);
+        }
     }
 
-    pub fn bugs_count(&self) -> usize {
-        self.bugs.len()
+    /// Apply effects of `catastrophe` (famines are tracked by `CatastropheScheduler`) and
+    /// announce it
+    fn strike<R: RngCore>(&mut self, catastrophe: Catastrophe, rng: &mut R)
+    where
+        T: TimePoint + Clone,
+    {
+        match &catastrophe {
+            Catastrophe::Meteor { center, radius } => {
+                let (center, radius) = (*center, *radius);
+                let mut victims = Vec::new();
+                self.bugs.for_each_in_range_mut(center, radius, |bug| {
+                    if (center - bug.borrow().position()).len() < radius.unwrap() {
+                        victims.push(bug.clone());
+                    }
+                });
+                for bug in &victims {
+                    self.remove_dead_bug(bug, DeathCause::Catastrophe);
+                }
+                let food_ids = &mut self.food_ids;
+                self.food.retain(|food| {
+                    let hit = (center - food.position()).len() < radius.unwrap();
+                    if hit {
+                        food_ids.release(food.id);
+                    }
+                    !hit
+                });
+            }
+            Catastrophe::Famine { .. } => {}
+            Catastrophe::Plague { infection_rate } => {
+                let infected: Vec<Point<Float>> = self
+                    .bugs
+                    .iter()
+                    .map(|bug| bug.borrow().position())
+                    .filter(|_| rng.gen_bool(infection_rate.clamp(0., 1.)))
+                    .collect();
+                for position in infected {
+                    self.parasites.push(Parasite::new(
+                        &mut self.next_parasite_id,
+                        position,
+                        PARASITE_SATED_ENERGY / NoNeg::wrap(PARASITE_BROOD_SIZE as Float).unwrap(),
+                    ));
+                }
+            }
+        }
+        self.events
+            .push(EnvironmentEvent::CatastropheStruck(catastrophe));
     }
 
-    pub fn bugs<'a>(&'a self) -> impl Iterator<Item = Ref<'a, Bug<T>>> {
-        self.bugs.iter().filter_map(|x| x.try_borrow().ok())
+    fn proceed_scenario(&mut self)
+    where
+        T: TimePoint,
+    {
+        let clock = self.clock();
+        let Some(scenario) = &mut self.scenario else {
+            return;
+        };
+        let bugs: Vec<_> = self.bugs.iter().map(|bug| bug.borrow()).collect();
+        let bugs: Vec<_> = bugs
+            .iter()
+            .map(|bug| ScenarioBug {
+                energy: bug.energy_level().unwrap(),
+                genes: &bug.chromosome().genes,
+            })
+            .collect();
+        if let Some(result) = scenario.proceed(clock, &bugs) {
+            self.events
+                .push(EnvironmentEvent::ScenarioCompleted(result));
+        }
     }
 
-    pub fn irradiate_area<R: RngCore>(
-        &mut self,
-        center: Point<Float>,
-        radius: NoNeg<Float>,
-        rng: &mut R,
+    fn proceed_curriculum(&mut self)
+    where
+        T: TimePoint,
+    {
+        let elapsed = self.clock().elapsed();
+        let population = self.bugs.len();
+        if let Some(curriculum) = &mut self.curriculum {
+            curriculum.proceed(elapsed, population);
+        }
+    }
+
+    /// Age food and drain energy of it according to `SimConfig::food_rot_rate`. Food which is
+    /// almost drained disappears
+    fn rot_food(&mut self, dt: Duration) {
+        let rot_rate = self.config.food_rot_rate.unwrap();
+        if rot_rate == 0. {
+            self.food.iter_mut().for_each(|food| food.age += dt);
+            return;
+        }
+        let remaining = noneg_float((-rot_rate * dt.as_secs_f64() / 3600.).exp());
+        let food_ids = &mut self.food_ids;
+        self.food.retain_mut(|food| {
+            food.age += dt;
+            food.energy = food.energy * remaining;
+            if food.energy < ROTTEN_FOOD_ENERGY {
+                food_ids.release(food.id);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// How chunk which contains `position` is simulated in current iteration
+    fn chunk_tick(&self, position: Point<Float>) -> ChunkTick {
+        let Some(config) = &self.chunk_sleep else {
+            return ChunkTick::Update;
+        };
+        let chunk = RawChunkIndex::from_position::<CHUNK_SIZE, CHUNK_SIZE>(position);
+        let distance_to = |top_left: Point<Float>, bottom_right: Point<Float>| {
+            let top_left = RawChunkIndex::from_position::<CHUNK_SIZE, CHUNK_SIZE>(top_left);
+            let bottom_right = RawChunkIndex::from_position::<CHUNK_SIZE, CHUNK_SIZE>(bottom_right);
+            (top_left.x() - chunk.x())
+                .max(chunk.x() - bottom_right.x())
+                .max(top_left.y() - chunk.y())
+                .max(chunk.y() - bottom_right.y())
+                .max(0) as usize
+        };
+        let Some(distance) = self
+            .observed_region
+            .as_ref()
+            .map(|region| distance_to(region.left_top(), region.right_bottom()))
+            .into_iter()
+            .chain(
+                self.tracked_position
+                    .map(|position| distance_to(position, position)),
+            )
+            .min()
+        else {
+            return ChunkTick::Update;
+        };
+
+        if distance <= config.distance {
+            ChunkTick::Update
+        } else if config.period == 0 {
+            ChunkTick::Freeze
+        } else if (self.iteration as isize + chunk.x() + chunk.y())
+            .rem_euclid(config.period as isize)
+            == 0
+        {
+            ChunkTick::Update
+        } else {
+            ChunkTick::Skip
+        }
+    }
+
+    fn record_chunk_load(
+        chunk_load: &RefCell<HashMap<(isize, isize), ChunkLoad>>,
+        position: Point<Float>,
+        f: impl FnOnce(&mut ChunkLoad),
     ) {
-        self.bugs
-            .iter_mut()
-            .filter_map(|x| x.try_borrow_mut().ok())
-            .filter(|bug| (center - bug.position()).len() < radius.unwrap())
-            .for_each(|mut bug| {
-                bug.chromosome_mut().mutate(|_, _| 0.001..1., 1., rng);
-            });
+        let index = RawChunkIndex::from_position::<CHUNK_SIZE, CHUNK_SIZE>(position);
+        f(chunk_load
+            .borrow_mut()
+            .entry((index.x(), index.y()))
+            .or_default());
     }
 
-    pub fn add_food<R: RngCore>(&mut self, center: Point<Float>, rng: &mut R) {
-        self.food.push(Food::new(
-            &mut self.next_bug_id,
-            center,
-            NoNeg::wrap(rng.gen_range((0.)..8.)).unwrap(),
-        ));
+    pub(crate) fn record_tick_time(&self, f: impl FnOnce(&mut TickTimings)) {
+        f(&mut self.tick_timings.borrow_mut())
     }
 
-    pub fn add_bug<R: RngCore>(&mut self, center: Point<Float>, rng: &mut R)
-    where
-        T: Clone,
-    {
-        self.bugs
-            .push(Rc::new(RefCell::new(Bug::give_birth_with_max_energy(
-                &mut self.next_bug_id,
-                Chromosome {
-                    genes: (0..256)
-                        .map(|i| {
-                            if i == 0 {
-                                2.
-                            } else if i == 128 {
-                                0.
-                            } else if i == 18 {
-                                2.
-                            } else if i == 137 {
-                                2.
-                            } else if i == 33 {
-                                2.
-                            } else if i == 146 {
-                                -2.
-                            } else if i == 202 {
-                                1.
-                            } else if i == 130 {
-                                2.
-                            } else if i == 128 + 8 + 8 + 8 {
-                                2. // baby charge
-                            } else if (0..208).contains(&i) {
-                                0.
-                            } else {
-                                1.
-                            }
-                        })
-                        .collect(),
-                },
-                center,
-                Angle::from_radians(rng.gen_range(0. ..(PI * 2.))),
-                self.now.clone(),
-            ))));
+    /// Wall-clock time spent in phases of the last iteration
+    pub fn tick_timings(&self) -> TickTimings {
+        self.tick_timings.borrow().clone()
     }
 
-    pub fn food_chunks(&self) -> Vec<(RawChunkIndex, usize)> {
-        self.food.chunks()
+    /// Sensing queries and requests issued from each chunk during the last iteration
+    pub fn chunk_load(&self) -> Vec<(RawChunkIndex, ChunkLoad)> {
+        self.chunk_load
+            .borrow()
+            .iter()
+            .map(|((x, y), load)| (RawChunkIndex::new(*x, *y), load.clone()))
+            .collect()
     }
 
-    pub fn food_chunks_circular_traverse_iter(
-        &self,
-        position: Point<Float>,
-        range: NoNeg<Float>,
-    ) -> impl Iterator<Item = (isize, isize)> {
-        self.food.circular_traverse_iter(position, range).map(|a| {
-            let i: RawChunkIndex = a.into();
-            (i.x(), i.y())
-        })
+    /// None disables chunk sleeping (default)
+    pub fn set_chunk_sleep(&mut self, config: Option<ChunkSleepConfig>) {
+        self.chunk_sleep = config;
     }
 
-    pub fn bug_chunks(&self) -> Vec<(RawChunkIndex, usize)> {
-        self.bugs.chunks()
+    pub fn chunk_sleep(&self) -> &Option<ChunkSleepConfig> {
+        &self.chunk_sleep
     }
 
-    pub fn bug_chunks_circular_traverse_iter(
-        &self,
-        position: Point<Float>,
-        range: NoNeg<Float>,
-    ) -> impl Iterator<Item = (isize, isize)> {
-        self.bugs.circular_traverse_iter(position, range).map(|a| {
-            let i: RawChunkIndex = a.into();
-            (i.x(), i.y())
-        })
+    /// Region which is simulated with full fidelity when chunk sleeping is enabled
+    pub fn set_observed_region(&mut self, region: Option<Rect<Float>>) {
+        self.observed_region = region;
     }
 
-    pub(crate) fn collect_unused_chunks(&mut self) {
-        self.bugs.collect_unused_chunks();
-        self.food.collect_unused_chunks();
+    /// Bug whose surroundings are simulated with full fidelity when chunk sleeping is enabled,
+    /// even if it is outside of observed region
+    pub fn set_tracked_bug(&mut self, handle: Option<BugHandle>) {
+        self.tracked_bug = handle;
     }
-}
 
-#[derive(Serialize, Deserialize)]
-pub struct SeededEnvironment<T> {
-    env: Environment<T>,
-    rng: Pcg64,
-}
+    pub fn tracked_bug(&self) -> Option<BugHandle> {
+        self.tracked_bug
+    }
 
-impl<T> SeededEnvironment<T> {
-    pub fn generate<Range: SampleRange<Float>>(
-        now: T,
-        seed: <Pcg64 as SeedableRng>::Seed,
-        food_sources: Vec<FoodSourceCreateInfo>,
-        x_range: Range,
-        y_range: Range,
-        food_e_range: Range,
-        food_count: usize,
-        bug_position: Point<Float>,
-    ) -> Self
-    where
-        Range: Clone,
-        T: Clone,
-    {
-        let mut rng = Pcg64::from_seed(seed);
-        Self {
-            env: Environment::generate(
-                now,
-                &mut rng,
-                food_sources,
-                x_range,
-                y_range,
-                food_e_range,
-                food_count,
-                bug_position,
-            ),
-            rng,
+    pub fn set_brain_log_policy(&mut self, policy: BrainLogPolicy) {
+        self.brain_log_policy = policy;
+    }
+
+    pub fn brain_log_policy(&self) -> &BrainLogPolicy {
+        &self.brain_log_policy
+    }
+
+    /// Renumber bugs (including ancestors kept in phylogeny) and food contiguously from 0.
+    /// Relative order of bug ids is kept, so ancestors still have lower ids than descendants.
+    /// Returns map from old bug ids to new ones
+    pub fn compact_ids(&mut self) -> BTreeMap<usize, usize> {
+        let old_ids: BTreeSet<usize> = self
+            .bugs
+            .iter()
+            .map(|bug| bug.borrow().id())
+            .chain(self.phylogeny.ids())
+            .chain(self.eggs.iter().map(|egg| egg.parent_id()))
+            .collect();
+        let id_map: BTreeMap<usize, usize> = old_ids
+            .into_iter()
+            .enumerate()
+            .map(|(new_id, old_id)| (old_id, new_id))
+            .collect();
+        let id_generations: BTreeMap<usize, usize> = (0..id_map.len())
+            .map(|slot| {
+                let generation = self.id_generation_of(slot);
+                if id_map.get(&slot) == Some(&slot) {
+                    (slot, generation)
+                } else {
+                    // handles made for the previous owner of the slot expire
+                    (slot, generation + 1)
+                }
+            })
+            .collect();
+        let tracked_bug_id = self
+            .tracked_bug
+            .and_then(|handle| self.resolve(handle).map(|bug| bug.id()));
+
+        for bug in self.bugs.iter() {
+            let mut bug = bug.borrow_mut();
+            let id = id_map[&bug.id()];
+            bug.set_id(id);
+        }
+        self.phylogeny.renumber(|id| id_map[&id]);
+        for egg in self.eggs.iter_mut() {
+            egg.set_parent_id(id_map[&egg.parent_id()]);
+        }
+        for parasite in self.parasites.iter_mut() {
+            let host_id = parasite
+                .host_id()
+                .and_then(|host_id| id_map.get(&host_id).copied());
+            parasite.set_host_id(host_id);
+        }
+        if let BrainLogPolicy::Pinned(ids) = &mut self.brain_log_policy {
+            ids.retain(|id| id_map.contains_key(id));
+            ids.iter_mut().for_each(|id| *id = id_map[id]);
         }
+        self.next_bug_id = id_map.len();
+
+        self.food_ids = FoodIdPool::default();
+        for food in self.food.iter_mut() {
+            food.id = self.food_ids.acquire();
+        }
+        self.bugs.reindex();
+        self.food.reindex();
+        self.id_generations = id_generations;
+        self.id_generation += 1;
+        self.tracked_bug = tracked_bug_id.and_then(|id| self.bug_handle(id_map[&id]));
+        // emitters of sounds still heard are referenced by old ids
+        self.sounds = Default::default();
+        id_map
     }
 
-    pub fn proceed(&mut self, dt: Duration)
+    /// Move bugs, plants, eggs, parasites, food and food sources of `other` into this
+    /// environment. Ids of `other` are shifted past the ones issued here, so they never collide.
+    /// Time points of `other` are moved to this clock keeping ages (ages exceeding the age of
+    /// this environment are clamped). World features and config of `other` (water, terrain,
+    /// biomes, seasons etc.) are dropped. Return map from old bug ids of `other` to new ones
+    pub fn merge(&mut self, other: Environment<T>) -> BTreeMap<usize, usize>
     where
         T: TimePoint + Clone,
     {
-        self.env.proceed(dt, &mut self.rng);
+        let bug_offset = self.next_bug_id;
+        let id_map: BTreeMap<usize, usize> = other
+            .bugs
+            .iter()
+            .map(|bug| bug.borrow().id())
+            .chain(other.phylogeny.ids())
+            .chain(other.eggs.iter().map(|egg| egg.parent_id()))
+            .map(|id| (id, id + bug_offset))
+            .collect();
+
+        let creation_time = self.creation_time.clone();
+        let elapsed = self.now.duration_since(&self.creation_time);
+        let other_now = other.now.clone();
+        let rebase = |instant: &T| {
+            let mut result = creation_time.clone();
+            result += elapsed.saturating_sub(other_now.duration_since(instant));
+            result
+        };
+
+        for bug in other.bugs {
+            {
+                let mut bug = bug.borrow_mut();
+                let id = id_map[&bug.id()];
+                bug.set_id(id);
+                bug.rebase_time(&rebase);
+            }
+            self.bugs.push(bug);
+        }
+        for mut plant in other.plants {
+            plant.set_id(plant.id() + self.next_plant_id);
+            plant.rebase_time(&rebase);
+            self.plants.push(plant);
+        }
+        for mut egg in other.eggs {
+            egg.set_id(egg.id() + self.next_egg_id);
+            egg.set_parent_id(id_map[&egg.parent_id()]);
+            egg.rebase_time(&rebase);
+            self.eggs.push(egg);
+        }
+        for mut parasite in other.parasites {
+            parasite.set_id(parasite.id() + self.next_parasite_id);
+            let host_id = parasite
+                .host_id()
+                .and_then(|host_id| id_map.get(&host_id).copied());
+            parasite.set_host_id(host_id);
+            self.parasites.push(parasite);
+        }
+        for mut food in other.food {
+            food.id = self.food_ids.acquire();
+            food.plant_id = food.plant_id.map(|id| id + self.next_plant_id);
+            self.food.push(food);
+        }
+        for food_source in other.food_sources {
+            food_source.borrow_mut().rebase_time(&rebase);
+            self.food_sources.push(food_source);
+        }
+
+        let mut phylogeny = other.phylogeny;
+        phylogeny.renumber(|id| id + bug_offset);
+        let (iteration, other_iteration) = (self.iteration, other.iteration);
+        self.phylogeny.merge(phylogeny, |i| {
+            (i + iteration).saturating_sub(other_iteration)
+        });
+
+        self.next_bug_id += other.next_bug_id;
+        self.next_plant_id += other.next_plant_id;
+        self.next_egg_id += other.next_egg_id;
+        self.next_parasite_id += other.next_parasite_id;
+        if self.bugs.len() > 0 {
+            self.extinct_since = None;
+        }
+        id_map
     }
 
-    pub fn irradiate_area(&mut self, center: Point<Float>, radius: NoNeg<Float>) {
-        self.env.irradiate_area(center, radius, &mut self.rng);
+    pub fn config(&self) -> &SimConfig {
+        &self.config
     }
 
-    pub fn add_food(&mut self, center: Point<Float>) {
-        self.env.add_food(center, &mut self.rng);
+    pub fn config_log(&self) -> &[ConfigChange] {
+        &self.config_log
     }
 
-    pub fn add_bug(&mut self, center: Point<Float>)
+    /// Apply config from the next iteration on. Return false if it equals the current one
+    pub fn apply_config(&mut self, config: SimConfig) -> bool {
+        if config == self.config {
+            return false;
+        }
+        self.config_log.push(ConfigChange {
+            iteration: self.iteration,
+            config: config.clone(),
+        });
+        self.config = config;
+        true
+    }
+
+    /// Index of food source whose area contains `point` (the last one if areas overlap)
+    pub fn food_source_at(&self, point: Point<Float>) -> Option<usize> {
+        self.food_sources
+            .iter()
+            .rposition(|food_source| food_source.borrow().contains(point))
+    }
+
+    /// Add food source which starts spawning food from now. Return its index
+    pub fn add_food_source(&mut self, info: FoodSourceCreateInfo) -> usize
     where
         T: Clone,
     {
-        self.env.add_bug(center, &mut self.rng);
+        self.log_food_source_edit(FoodSourceEdit::Add(info.clone()));
+        self.food_sources
+            .push(Rc::new(RefCell::new(info.create(self.now.clone()))));
+        self.food_sources.len() - 1
     }
 
-    pub fn collect_unused_chunks(&mut self) {
-        self.env.collect_unused_chunks();
+    /// Replace parameters of food source keeping its stats. False if there is no such source
+    pub fn edit_food_source(&mut self, index: usize, info: FoodSourceCreateInfo) -> bool {
+        let Some(food_source) = self.food_sources.get(index).cloned() else {
+            return false;
+        };
+        self.log_food_source_edit(FoodSourceEdit::Edit {
+            index,
+            info: info.clone(),
+        });
+        food_source.borrow_mut().edit(info);
+        true
     }
-}
 
-// Note this impl does not brake SeededEnvironment invariant only if there is no immutable member function in Environment which accepts rng as argument
-impl<T> Deref for SeededEnvironment<T> {
-    type Target = Environment<T>;
+    /// Remove food source (food it has already spawned stays). False if there is no such source
+    pub fn remove_food_source(&mut self, index: usize) -> bool {
+        if index >= self.food_sources.len() {
+            return false;
+        }
+        self.log_food_source_edit(FoodSourceEdit::Remove { index });
+        self.food_sources.remove(index);
+        self.stats.forget_food_sources();
+        true
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.env
+    fn log_food_source_edit(&mut self, edit: FoodSourceEdit) {
+        self.food_source_log.push(FoodSourceChange {
+            iteration: self.iteration,
+            edit,
+        });
     }
-}
 
-pub mod benchmark_internals {
-    use std::{cell::RefCell, rc::Rc};
+    pub fn food_source_log(&self) -> &[FoodSourceChange] {
+        &self.food_source_log
+    }
 
-    use crate::{bug::Bug, math::NoNeg, utils::Float};
+    pub fn find_bug_by_id<'a>(&'a self, id: usize) -> Option<Ref<'a, Bug<T>>> {
+        let index = self.bugs.index_of_id(&id)?;
+        self.bugs[index].try_borrow().ok()
+    }
 
-    use super::Environment;
+    fn id_generation_of(&self, id: usize) -> usize {
+        self.id_generations
+            .get(&id)
+            .copied()
+            .unwrap_or(self.id_generation)
+    }
 
-    pub fn transfer_energy_from_food_to_bug<T>(
-        env: &mut Environment<T>,
-        food_id: usize,
+    /// Handle of living bug with `id`
+    pub fn bug_handle(&self, id: usize) -> Option<BugHandle> {
+        self.bugs.index_of_id(&id).map(|_| BugHandle {
+            id,
+            generation: self.id_generation_of(id),
+        })
+    }
+
+    /// Bug referenced by `handle`. None if it has died or its id was given to another bug after
+    /// handle was made
+    pub fn resolve<'a>(&'a self, handle: BugHandle) -> Option<Ref<'a, Bug<T>>> {
+        if handle.generation != self.id_generation_of(handle.id) {
+            return None;
+        }
+        self.find_bug_by_id(handle.id)
+    }
+
+    /// Sense environment and run brain of a single bug without changing it.
+    /// Lets brain be inspected when brain log policy skips the bug
+    pub fn evaluate_bug_brain(&self, handle: BugHandle) -> Option<BrainLog>
+    where
+        T: TimePoint + Clone,
+    {
+        if handle.generation != self.id_generation_of(handle.id) {
+            return None;
+        }
+        let id = handle.id;
+        // bug is borrowed mutably (as during `proceed`) so it does not see itself
+        let bug = self.bugs[self.bugs.index_of_id(&id)?]
+            .try_borrow_mut()
+            .ok()?;
+        bug.evaluate_brain(self)
+    }
+
+    fn transfer_energy_from_food_to_bug(
+        &mut self,
+        food_id: FoodId,
         bug: &mut Bug<T>,
         delta_energy: NoNeg<Float>,
     ) {
-        env.transfer_energy_from_food_to_bug(food_id, bug, delta_energy)
+        if let Some(food_index) = self.food.index_of_id(&food_id) {
+            let food = &mut self.food[food_index.clone()];
+            let (position, energy_before) = (food.position(), food.energy());
+            let bug_energy_before = bug.energy_level();
+            let drained = bug.eat(food, delta_energy);
+            let eaten = energy_before.limited_sub(food.energy());
+            let harm = bug_energy_before.limited_sub(bug.energy_level());
+            if let Some(plant_id) = food.plant_id.filter(|_| harm > noneg_float(0.)) {
+                // fruits fall near the plant, so it is found without scanning all plants
+                self.plants
+                    .for_each_in_range_mut(position, PLANT_FRUIT_SCATTER_RADIUS, |plant| {
+                        if plant.id() == plant_id {
+                            plant.toxin_harmed_eater(harm);
+                        }
+                    });
+            }
+            for food_source in &self.food_sources {
+                let mut food_source = food_source.borrow_mut();
+                if food_source.contains(position) {
+                    food_source.record_eaten(eaten, drained);
+                }
+            }
+            if drained {
+                let food = self.food.remove(food_index);
+                self.food_ids.release(food.id());
+                self.events.push(EnvironmentEvent::FoodEaten {
+                    position,
+                    bug_id: bug.id(),
+                });
+            }
+        }
     }
 
-    pub fn find_bug_by_id<T>(env: &Environment<T>, id: usize) -> Option<Rc<RefCell<Bug<T>>>> {
-        env.bugs.iter().find(|b| b.borrow().id() == id).cloned()
+    /// Give energy of `donor` to bug with `recipient_id` if it is a child of donor and is within
+    /// its eat range
+    fn transfer_energy_bug_to_bug(
+        &self,
+        donor: &mut Bug<T>,
+        recipient_id: usize,
+        delta_energy: NoNeg<Float>,
+    ) {
+        if self.phylogeny.parent_id(recipient_id) != Some(donor.id()) {
+            return;
+        }
+        if let Some(recipient) =
+            self.bug_in_range(recipient_id, donor.position(), donor.eat_range())
+        {
+            donor.feed(&mut recipient.borrow_mut(), delta_energy);
+        }
+    }
+
+    /// Feed `attacker` with energy of bug with `victim_id` if it is still within its eat range
+    fn bite(&self, attacker: &mut Bug<T>, victim_id: usize, delta_energy: NoNeg<Float>) {
+        if let Some(victim) =
+            self.bug_in_range(victim_id, attacker.position(), attacker.eat_range())
+        {
+            attacker.bite(&mut victim.borrow_mut(), delta_energy);
+        }
+    }
+
+    /// Paralyze bug with `victim_id` if it is still within eat range of `attacker`
+    fn sting(&self, attacker: &Bug<T>, victim_id: usize, dose: NoNeg<Float>) {
+        if let Some(victim) =
+            self.bug_in_range(victim_id, attacker.position(), attacker.eat_range())
+        {
+            victim.borrow_mut().inject_venom(dose);
+        }
+    }
+
+    /// Bug with `id` if it is within `range` of `position`. Bug which is borrowed mutably (e.g. the
+    /// one making request) is not found
+    fn bug_in_range(
+        &self,
+        id: usize,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+    ) -> Option<&Rc<RefCell<Bug<T>>>> {
+        let bug = &self.bugs[self.bugs.index_of_id(&id)?];
+        let in_range = (bug.try_borrow().ok()?.position() - position).len() <= range.unwrap();
+        in_range.then_some(bug)
+    }
+
+    /// Composable query over bugs, food or plants (see `query::EntityQuery`)
+    pub fn query(&self) -> Query<'_, T> {
+        Query::new(self)
+    }
+
+    pub(crate) fn food_chunked(&self) -> &ChunkedVec<Food, CHUNK_SIZE, CHUNK_SIZE> {
+        &self.food
+    }
+
+    pub(crate) fn bugs_chunked(&self) -> &ChunkedVec<Rc<RefCell<Bug<T>>>, CHUNK_SIZE, CHUNK_SIZE> {
+        &self.bugs
+    }
+
+    pub(crate) fn plants_chunked(&self) -> &ChunkedVec<Plant<T>, CHUNK_SIZE, CHUNK_SIZE> {
+        &self.plants
+    }
+
+    pub fn food(&self) -> impl Iterator<Item = &Food> {
+        self.food.iter()
+    }
+
+    /// `n` pieces of food picked uniformly at random (with replacement) without iterating over
+    /// all food
+    pub fn sample_random_food<R: RngCore>(&self, n: usize, rng: &mut R) -> Vec<&Food> {
+        self.food.sample(n, rng)
+    }
+
+    pub fn food_count(&self) -> usize {
+        self.food.len()
+    }
+
+    /// Energy of all food lying in environment
+    pub fn food_total_energy(&self) -> NoNeg<Float> {
+        self.food
+            .iter()
+            .fold(noneg_float(0.), |sum, food| sum + food.energy())
+    }
+
+    pub(crate) fn find_nearest_food_in_vision_arc(
+        &self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        vision_rotation: Angle<Float>,
+        vision_half_arc: DeltaAngle<NoNeg<Float>>,
+    ) -> Option<(&Food, NoNeg<Float>)> {
+        self.find_n_nearest_food_in_vision_arc(position, range, vision_rotation, vision_half_arc, 1)
+            .into_iter()
+            .next()
+    }
+
+    /// Up to `n` nearest food items seen, sorted from near to far
+    pub(crate) fn find_n_nearest_food_in_vision_arc(
+        &self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        vision_rotation: Angle<Float>,
+        vision_half_arc: DeltaAngle<NoNeg<Float>>,
+        n: usize,
+    ) -> Vec<(&Food, NoNeg<Float>)> {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        let arc = Range {
+            start: vision_rotation - vision_half_arc.unwrap(),
+            end: vision_rotation + vision_half_arc.unwrap(),
+        };
+        self.food
+            .find_n_nearest_filter_map_scaled(position, range, n, |food| {
+                if vision_half_arc == DeltaAngle::from_radians(noneg_float(PI))
+                    || (food.position().clone() - position)
+                        .angle()
+                        .is_contained_in(arc)
+                {
+                    Some((food, noneg_float(1.)))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Camouflaged bugs are seen only at a part of `range` (see `Bug::camouflage`), and their
+    /// distance is the effective one
+    pub(crate) fn find_nearest_bug_in_vision_arc<'a>(
+        &'a self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        vision_rotation: Angle<Float>,
+        vision_half_arc: DeltaAngle<NoNeg<Float>>,
+    ) -> Option<(Ref<'a, Bug<T>>, NoNeg<Float>)> {
+        self.find_n_nearest_bugs_in_vision_arc(position, range, vision_rotation, vision_half_arc, 1)
+            .into_iter()
+            .next()
+    }
+
+    /// Up to `n` nearest bugs seen, sorted from near to far (by effective distance, see
+    /// `find_nearest_bug_in_vision_arc`)
+    pub(crate) fn find_n_nearest_bugs_in_vision_arc<'a>(
+        &'a self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        vision_rotation: Angle<Float>,
+        vision_half_arc: DeltaAngle<NoNeg<Float>>,
+        n: usize,
+    ) -> Vec<(Ref<'a, Bug<T>>, NoNeg<Float>)> {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        let arc = Range {
+            start: vision_rotation - vision_half_arc.unwrap(),
+            end: vision_rotation + vision_half_arc.unwrap(),
+        };
+        self.bugs
+            .find_n_nearest_filter_map_scaled(position, range, n, |x| {
+                x.try_borrow().ok().and_then(|other| {
+                    if other.buried() {
+                        None
+                    } else if vision_half_arc == DeltaAngle::from_radians(noneg_float(PI))
+                        || (other.position().clone() - position)
+                            .angle()
+                            .is_contained_in(arc)
+                    {
+                        let visibility = other.visibility();
+                        Some((other, visibility))
+                    } else {
+                        None
+                    }
+                })
+            })
+    }
+
+    /// Number, mean heading and mean velocity of all bugs seen (excluding buried ones and the
+    /// observer itself). Only chunks within `range` are traversed
+    pub(crate) fn sense_flock(
+        &self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        vision_rotation: Angle<Float>,
+        vision_half_arc: DeltaAngle<NoNeg<Float>>,
+    ) -> FlockInfo {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        let arc = Range {
+            start: vision_rotation - vision_half_arc.unwrap(),
+            end: vision_rotation + vision_half_arc.unwrap(),
+        };
+        let mut count = 0;
+        let mut heading = (0., 0.);
+        let mut velocity = (0., 0.);
+        for bug in self.bugs.iter_in_range(position, range) {
+            // the observer itself is borrowed mutably
+            let Ok(other) = bug.try_borrow() else {
+                continue;
+            };
+            let offset = other.position() - position;
+            if other.buried()
+                || offset.len() >= range.unwrap()
+                || (vision_half_arc != DeltaAngle::from_radians(noneg_float(PI))
+                    && !offset.angle().is_contained_in(arc))
+            {
+                continue;
+            }
+            let rotation = other.rotation();
+            count += 1;
+            heading.0 += rotation.cos();
+            heading.1 += rotation.sin();
+            velocity.0 += rotation.cos() * other.velocity();
+            velocity.1 += rotation.sin() * other.velocity();
+        }
+        if count == 0 {
+            return Default::default();
+        }
+        let heading: Vector<Float> = heading.into();
+        let velocity: Vector<Float> = velocity.into();
+        FlockInfo {
+            count,
+            heading: (*heading.x() != 0. || *heading.y() != 0.).then(|| heading.angle()),
+            speed: velocity.len() / count as Float,
+        }
+    }
+
+    /// Nearest object in each of `RETINA_SECTORS` equal sectors of vision arc, ordered from
+    /// `vision_rotation - vision_half_arc` on. Bugs are seen at effective distance (see
+    /// `find_nearest_bug_in_vision_arc`)
+    pub(crate) fn look_with_retina(
+        &self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+        vision_rotation: Angle<Float>,
+        vision_half_arc: DeltaAngle<NoNeg<Float>>,
+    ) -> [Option<RetinaHit>; RETINA_SECTORS] {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        let mut retina = [None; RETINA_SECTORS];
+        let sector_width = vision_half_arc.unwrap().radians() * 2. / RETINA_SECTORS as Float;
+        if sector_width <= 0. {
+            return retina;
+        }
+        let start = vision_rotation - vision_half_arc.unwrap();
+        let mut see = |point: Point<Float>, object: SeenObject, dst: NoNeg<Float>| {
+            if dst >= range {
+                return;
+            }
+            let offset = (point - position).angle().signed_distance(start).radians();
+            let offset = if offset < 0. {
+                offset + PI * 2.
+            } else {
+                offset
+            };
+            let i = (offset / sector_width) as usize;
+            if i < RETINA_SECTORS
+                && retina[i]
+                    .as_ref()
+                    .map(|hit: &RetinaHit| dst < hit.dst)
+                    .unwrap_or(true)
+            {
+                retina[i] = Some(RetinaHit { object, dst });
+            }
+        };
+        for food in self.food.iter_in_range(position, range) {
+            let dst = NoNeg::wrap((food.position() - position).len()).unwrap();
+            see(food.position(), SeenObject::Food, dst);
+        }
+        for bug in self.bugs.iter_in_range(position, range) {
+            // the observer itself is borrowed mutably
+            let Ok(other) = bug.try_borrow() else {
+                continue;
+            };
+            let visibility = other.visibility().unwrap().min(1.);
+            if other.buried() || visibility <= 0. {
+                continue;
+            }
+            let dst = NoNeg::wrap((other.position() - position).len() / visibility).unwrap();
+            see(other.position(), SeenObject::Bug, dst);
+        }
+        retina
+    }
+
+    /// Food or bug (other than `toucher_id`, excluding buried ones) whose body overlaps circle of
+    /// `radius` the most. Only occupants of chunks within `radius * 2` are checked, so a bug
+    /// much larger than toucher can be missed if its center is in a chunk further away
+    pub(crate) fn touch(
+        &self,
+        position: Point<Float>,
+        radius: NoNeg<Float>,
+        toucher_id: usize,
+    ) -> Option<Contact> {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        let search_range = radius * noneg_float(2.);
+        let mut deepest: Option<(Contact, Float)> = None;
+        let mut touch = |point: Point<Float>, other_radius: NoNeg<Float>, object: SeenObject| {
+            let depth = (radius + other_radius).unwrap() - (point - position).len();
+            if depth > 0. && deepest.as_ref().map(|(_, d)| depth > *d).unwrap_or(true) {
+                let contact = Contact {
+                    object,
+                    direction: (point - position).angle(),
+                };
+                deepest = Some((contact, depth));
+            }
+        };
+        for food in self.food.iter_in_range(position, search_range) {
+            touch(food.position(), food.radius(), SeenObject::Food);
+        }
+        for bug in self.bugs.iter_in_range(position, search_range) {
+            let Ok(other) = bug.try_borrow() else {
+                continue;
+            };
+            if other.id() != toucher_id && !other.buried() {
+                touch(other.position(), other.eat_range(), SeenObject::Bug);
+            }
+        }
+        deepest.map(|(contact, _)| contact)
+    }
+
+    /// Nearest bug within `range` (excluding buried ones and the one which is borrowed mutably)
+    pub(crate) fn find_nearest_bug_in_range<'a>(
+        &'a self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+    ) -> Option<(Ref<'a, Bug<T>>, NoNeg<Float>)> {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        self.bugs.find_nearest_filter_map(position, range, |x| {
+            x.try_borrow().ok().filter(|other| !other.buried())
+        })
+    }
+
+    /// Nearest child of bug with `parent_id` within `range` (excluding buried ones)
+    pub(crate) fn find_nearest_offspring<'a>(
+        &'a self,
+        parent_id: usize,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+    ) -> Option<(Ref<'a, Bug<T>>, NoNeg<Float>)> {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        self.bugs.find_nearest_filter_map(position, range, |x| {
+            x.try_borrow().ok().filter(|other| {
+                !other.buried() && self.phylogeny.parent_id(other.id()) == Some(parent_id)
+            })
+        })
+    }
+
+    pub fn food_sources<'a>(&'a self) -> impl Iterator<Item = Ref<'a, FoodSource<T>>> {
+        self.food_sources.iter().map(|x| x.as_ref().borrow())
+    }
+
+    /// Immutable copy of bugs, food, plants and phylogeny which can be analyzed in another
+    /// thread while environment proceeds
+    pub fn freeze(&self) -> EnvironmentSnapshot
+    where
+        T: TimePoint + Clone,
+    {
+        EnvironmentSnapshot::of(self)
+    }
+
+    /// Living bugs clustered by genetic similarity
+    pub fn species(&self) -> Vec<Species> {
+        let bugs: Vec<_> = self.bugs().collect();
+        species::cluster(
+            bugs.iter().map(|bug| (bug.id(), bug.chromosome())),
+            SPECIES_DISTANCE_THRESHOLD,
+        )
+    }
+
+    pub fn pheromones(&self) -> &PheromoneField {
+        &self.pheromones
+    }
+
+    pub(crate) fn sense_pheromone(&self, position: Point<Float>) -> PheromoneInfo {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        self.pheromones.sense(position)
+    }
+
+    pub fn odor(&self) -> &OdorField {
+        &self.odor
+    }
+
+    pub(crate) fn sense_odor(&self, position: Point<Float>) -> OdorInfo {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        self.odor.sense(position)
+    }
+
+    pub fn territory(&self) -> &TerritoryMap {
+        &self.territory
+    }
+
+    pub(crate) fn sense_foreign_territory(
+        &self,
+        position: Point<Float>,
+        id: usize,
+    ) -> NoNeg<Float> {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        self.territory.foreign_strength(position, id)
+    }
+
+    /// Sound pulses which can still be heard
+    pub fn sounds(&self) -> impl Iterator<Item = &Sound> {
+        self.sounds.iter()
+    }
+
+    pub(crate) fn hear(&self, position: Point<Float>, listener_id: usize) -> HearingInfo {
+        Self::record_chunk_load(&self.chunk_load, position, |load| load.sensing_queries += 1);
+        self.sounds.hear(position, listener_id)
+    }
+
+    pub fn water(&self) -> &[WaterRegion] {
+        &self.water
+    }
+
+    pub fn add_water(&mut self, regions: Vec<WaterRegion>) {
+        self.water.extend(regions)
+    }
+
+    pub fn in_water(&self, position: Point<Float>) -> bool {
+        self.water.iter().any(|region| region.contains(position))
+    }
+
+    pub(crate) fn sense_water(&self, position: Point<Float>) -> WaterInfo {
+        WaterInfo::sense(&self.water, position)
+    }
+
+    pub fn terrain(&self) -> Option<&Terrain> {
+        self.terrain.as_ref()
+    }
+
+    pub fn set_terrain(&mut self, terrain: Option<Terrain>) {
+        self.terrain = terrain
+    }
+
+    pub fn bounds(&self) -> Option<&Rect<Float>> {
+        self.bounds.as_ref()
+    }
+
+    pub fn set_bounds(&mut self, bounds: Option<Rect<Float>>) {
+        self.bounds = bounds
+    }
+
+    pub fn edge_policy(&self) -> EdgePolicy {
+        self.edge_policy
+    }
+
+    pub fn set_edge_policy(&mut self, edge_policy: EdgePolicy) {
+        self.edge_policy = edge_policy
+    }
+
+    pub fn biomes(&self) -> &BiomeMap {
+        &self.biomes
+    }
+
+    pub fn set_biomes(&mut self, biomes: BiomeMap) {
+        self.biomes = biomes
+    }
+
+    pub fn biome_at(&self, position: Point<Float>) -> Biome {
+        self.biomes.biome_at(position)
+    }
+
+    pub fn light(&self) -> &LightField {
+        &self.light
+    }
+
+    pub fn add_light_sources(&mut self, sources: Vec<LightSource>) {
+        self.light.add_sources(sources)
+    }
+
+    pub fn light_at(&self, position: Point<Float>) -> NoNeg<Float> {
+        self.light.intensity_at(position)
+    }
+
+    /// Distance from `position` along `rotation` to the edge of bounds. None if world is
+    /// unbounded, edges are not walls or the edge is not closer than `range`
+    pub(crate) fn wall_distance(
+        &self,
+        position: Point<Float>,
+        rotation: Angle<Float>,
+        range: NoNeg<Float>,
+    ) -> Option<NoNeg<Float>> {
+        if self.edge_policy != EdgePolicy::Wall {
+            return None;
+        }
+        let bounds = self.bounds.as_ref()?;
+        let (cos, sin) = (rotation.cos(), rotation.sin());
+        let (x, y) = (*position.x(), *position.y());
+        let along_x = if cos > 0. {
+            (bounds.right() - x) / cos
+        } else if cos < 0. {
+            (bounds.left() - x) / cos
+        } else {
+            Float::INFINITY
+        };
+        let along_y = if sin > 0. {
+            (bounds.bottom() - y) / sin
+        } else if sin < 0. {
+            (bounds.top() - y) / sin
+        } else {
+            Float::INFINITY
+        };
+        let distance = NoNeg::wrap(along_x.min(along_y).max(0.)).unwrap();
+        (distance < range).then_some(distance)
+    }
+
+    /// Elevation of ground at `position` (0 if environment is flat)
+    pub fn elevation(&self, position: Point<Float>) -> Float {
+        self.terrain
+            .as_ref()
+            .map(|terrain| terrain.elevation(position))
+            .unwrap_or(0.)
+    }
+
+    pub fn season_clock(&self) -> Option<&SeasonClock> {
+        self.seasons.as_ref()
+    }
+
+    pub fn set_season_clock(&mut self, seasons: Option<SeasonClock>) {
+        self.seasons = seasons
+    }
+
+    pub fn catastrophes(&self) -> Option<&CatastropheScheduler> {
+        self.catastrophes.as_ref()
+    }
+
+    pub fn set_catastrophes(&mut self, catastrophes: Option<CatastropheScheduler>) {
+        self.catastrophes = catastrophes
+    }
+
+    pub fn scenario(&self) -> Option<&Scenario> {
+        self.scenario.as_ref()
+    }
+
+    /// Replace scenario. New one starts from the next iteration
+    pub fn set_scenario(&mut self, scenario: Option<Scenario>) {
+        self.scenario = scenario
+    }
+
+    /// None while scenario is in progress or if there is no scenario
+    pub fn scenario_result(&self) -> Option<&ScenarioResult> {
+        self.scenario.as_ref()?.result()
+    }
+
+    pub fn curriculum(&self) -> Option<&Curriculum> {
+        self.curriculum.as_ref()
+    }
+
+    pub fn set_curriculum(&mut self, curriculum: Option<Curriculum>) {
+        self.curriculum = curriculum
+    }
+
+    /// Multiplier of energy spent by bugs: configured one tightened by curriculum
+    pub fn metabolism_multiplier(&self) -> NoNeg<Float> {
+        self.config.metabolism_multiplier
+            * self
+                .curriculum
+                .as_ref()
+                .map(|curriculum| curriculum.metabolism())
+                .unwrap_or(noneg_float(1.))
+    }
+
+    pub fn season(&self) -> Option<Season>
+    where
+        T: TimePoint,
+    {
+        self.seasons
+            .as_ref()
+            .map(|clock| clock.season(self.clock().elapsed()))
+    }
+
+    /// Multiplier of food sources production at the current time of year (1 without seasons)
+    /// tightened by curriculum
+    pub fn food_abundance(&self) -> Float
+    where
+        T: TimePoint,
+    {
+        self.seasons
+            .as_ref()
+            .map(|clock| clock.abundance(self.clock().elapsed()))
+            .unwrap_or(1.)
+            * self
+                .curriculum
+                .as_ref()
+                .map(|curriculum| curriculum.food_abundance())
+                .unwrap_or(1.)
+            * self
+                .catastrophes
+                .as_ref()
+                .map(|catastrophes| catastrophes.food_abundance(self.clock().elapsed()))
+                .unwrap_or(1.)
+    }
+
+    /// Events which happened since the previous call (at most `MAX_PENDING_EVENTS` latest ones)
+    pub fn drain_events(&mut self) -> Vec<EnvironmentEvent> {
+        self.events.drain()
+    }
+
+    /// Call `handler` with every event as soon as it happens (events are still queued for
+    /// `drain_events`)
+    pub fn subscribe(
+        &mut self,
+        handler: impl FnMut(&EnvironmentEvent) + 'static,
+    ) -> SubscriptionId {
+        self.events.subscribe(Box::new(handler) as EventHandler)
+    }
+
+    /// Returns false if there is no such subscription
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.events.unsubscribe(id)
+    }
+
+    pub fn phylogeny(&self) -> &Phylogeny {
+        &self.phylogeny
+    }
+
+    pub fn stats(&self) -> &StatsCollector {
+        &self.stats
+    }
+
+    /// Living bugs with the best energy-to-offspring efficiency (best first)
+    pub fn efficiency_leaderboard(&self, count: usize) -> Vec<LeaderboardEntry> {
+        let mut result: Vec<LeaderboardEntry> = self
+            .bugs()
+            .filter(|bug| bug.offspring_count() > 0)
+            .map(|bug| LeaderboardEntry {
+                bug_id: bug.id(),
+                consumed_energy: bug.consumed_energy().unwrap(),
+                offspring_count: bug.offspring_count(),
+                efficiency: bug.efficiency(),
+            })
+            .collect();
+        result.sort_by(|a, b| b.efficiency.total_cmp(&a.efficiency));
+        result.truncate(count);
+        result
+    }
+
+    pub fn plants(&self) -> impl Iterator<Item = &Plant<T>> {
+        self.plants.iter()
+    }
+
+    pub fn plants_count(&self) -> usize {
+        self.plants.len()
+    }
+
+    pub fn eggs(&self) -> impl Iterator<Item = &Egg<T>> {
+        self.eggs.iter()
+    }
+
+    pub fn eggs_count(&self) -> usize {
+        self.eggs.len()
+    }
+
+    /// Attach `tag` to bug. False if there is no such bug
+    pub fn tag_bug(&mut self, id: usize, tag: Tag) -> bool {
+        match self.bugs.iter().find(|bug| bug.borrow().id() == id) {
+            Some(bug) => {
+                bug.borrow_mut().add_tag(tag);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove tag named `name` from bug. False if bug does not have it
+    pub fn untag_bug(&mut self, id: usize, name: &str) -> bool {
+        self.bugs
+            .iter()
+            .find(|bug| bug.borrow().id() == id)
+            .is_some_and(|bug| bug.borrow_mut().remove_tag(name))
+    }
+
+    /// Attach `tag` to every bug closer than `radius` to `center`. Returns number of tagged bugs
+    pub fn tag_bugs_in_circle(
+        &mut self,
+        center: Point<Float>,
+        radius: NoNeg<Float>,
+        tag: Tag,
+    ) -> usize {
+        let mut count = 0;
+        for bug in self.bugs.iter_in_range(center, radius) {
+            let mut bug = bug.borrow_mut();
+            if (bug.position() - center).len() < radius.unwrap() {
+                bug.add_tag(tag.clone());
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Number of living bugs with each tag
+    pub fn tag_census(&self) -> BTreeMap<String, usize> {
+        let mut census = BTreeMap::new();
+        for bug in self.bugs() {
+            for tag in bug.tags() {
+                *census.entry(tag.name.clone()).or_default() += 1;
+            }
+        }
+        census
+    }
+
+    pub fn parasites(&self) -> impl Iterator<Item = &Parasite> {
+        self.parasites.iter()
+    }
+
+    pub fn parasites_count(&self) -> usize {
+        self.parasites.len()
+    }
+
+    pub(crate) fn add_plants(&mut self, plants: Vec<PlantCreateInfo>)
+    where
+        T: Clone,
+    {
+        for create_info in plants {
+            self.plants
+                .push(create_info.create(&mut self.next_plant_id, self.now.clone()));
+        }
+    }
+
+    /// `n` bugs picked uniformly at random (with replacement) without iterating over all bugs
+    pub fn sample_random_bugs<'a, R: RngCore>(
+        &'a self,
+        n: usize,
+        rng: &mut R,
+    ) -> Vec<Ref<'a, Bug<T>>> {
+        self.bugs
+            .sample(n, rng)
+            .into_iter()
+            .map(|bug| bug.borrow())
+            .collect()
+    }
+
+    pub fn bugs_count(&self) -> usize {
+        self.bugs.len()
+    }
+
+    pub fn bugs<'a>(&'a self) -> impl Iterator<Item = Ref<'a, Bug<T>>> {
+        self.bugs.iter().filter_map(|x| x.try_borrow().ok())
+    }
+
+    pub fn irradiate_area<R: RngCore>(
+        &mut self,
+        center: Point<Float>,
+        radius: NoNeg<Float>,
+        rng: &mut R,
+    ) {
+        self.events
+            .push(EnvironmentEvent::AreaIrradiated { center, radius });
+        let phylogeny = &mut self.phylogeny;
+        let iteration = self.iteration;
+        self.bugs.for_each_in_range_mut(center, radius, |bug| {
+            let Ok(mut bug) = bug.try_borrow_mut() else {
+                return;
+            };
+            if (center - bug.position()).len() >= radius.unwrap() {
+                return;
+            }
+            let rate = bug.mutation_rate();
+            bug.chromosome_mut().mutate(
+                |_, _| 0.001 * rate.scale..rate.scale,
+                rate.probability.min(1.),
+                rng,
+            );
+            phylogeny.record_mutations(
+                bug.id(),
+                bug.chromosome(),
+                MutationCause::Irradiation,
+                iteration,
+            );
+        });
+    }
+
+    pub fn add_food<R: RngCore>(&mut self, center: Point<Float>, rng: &mut R) {
+        self.food.push(Food::new(
+            &mut self.food_ids,
+            center,
+            NoNeg::wrap(rng.gen_range((0.)..8.)).unwrap(),
+        ));
+    }
+
+    /// Scatter `count` free parasites around `center`
+    pub fn add_parasites<R: RngCore>(&mut self, center: Point<Float>, count: usize, rng: &mut R) {
+        for _ in 0..count {
+            let (distance, direction) = (rng.gen_range(0. ..50.), rng.gen_range(0. ..(PI * 2.)));
+            let position = (
+                center.x() + distance * direction.cos(),
+                center.y() + distance * direction.sin(),
+            )
+                .into();
+            self.parasites.push(Parasite::new(
+                &mut self.next_parasite_id,
+                position,
+                PARASITE_SATED_ENERGY / NoNeg::wrap(PARASITE_BROOD_SIZE as Float).unwrap(),
+            ));
+        }
+    }
+
+    pub fn add_bug<R: RngCore>(&mut self, center: Point<Float>, rng: &mut R)
+    where
+        T: Clone,
+    {
+        self.spawn_bug(
+            Chromosome {
+                genes: (0..256)
+                    .map(|i| {
+                        if i == 0 {
+                            2.
+                        } else if i == 128 {
+                            0.
+                        } else if i == 18 {
+                            2.
+                        } else if i == 137 {
+                            2.
+                        } else if i == 33 {
+                            2.
+                        } else if i == 146 {
+                            -2.
+                        } else if i == 202 {
+                            1.
+                        } else if i == 130 {
+                            2.
+                        } else if i == 128 + 8 + 8 + 8 {
+                            2. // baby charge
+                        } else if (0..208).contains(&i) {
+                            0.
+                        } else {
+                            1.
+                        }
+                    })
+                    .collect(),
+            },
+            center,
+            rng,
+        );
+    }
+
+    /// Spawn one bug per hall of fame member with its genome around `center`.
+    /// Returns number of spawned bugs
+    pub fn spawn_hall_of_fame<R: RngCore>(&mut self, center: Point<Float>, rng: &mut R) -> usize
+    where
+        T: Clone,
+    {
+        let chromosomes: Vec<_> = self
+            .phylogeny
+            .hall_of_fame()
+            .iter()
+            .filter_map(|id| self.phylogeny.line_of_descent(*id).pop())
+            .map(|entry| entry.chromosome)
+            .collect();
+        let count = chromosomes.len();
+        for chromosome in chromosomes {
+            let (distance, direction) = (rng.gen_range(0. ..50.), rng.gen_range(0. ..(PI * 2.)));
+            let position = (
+                center.x() + distance * direction.cos(),
+                center.y() + distance * direction.sin(),
+            )
+                .into();
+            self.spawn_bug(chromosome, position, rng);
+        }
+        count
+    }
+
+    fn spawn_bug<R: RngCore>(
+        &mut self,
+        chromosome: Chromosome<Float>,
+        position: Point<Float>,
+        rng: &mut R,
+    ) where
+        T: Clone,
+    {
+        let bug = Bug::give_birth_with_max_energy(
+            &mut self.next_bug_id,
+            chromosome,
+            position,
+            Angle::from_radians(rng.gen_range(0. ..(PI * 2.))),
+            self.now.clone(),
+        );
+        self.phylogeny
+            .record_birth(bug.id(), None, bug.chromosome(), self.iteration);
+        self.events.push(EnvironmentEvent::BugBorn {
+            id: bug.id(),
+            parent_id: None,
+            position: bug.position(),
+        });
+        self.bugs.push(Rc::new(RefCell::new(bug)));
+        self.extinct_since = None;
+    }
+
+    /// Remove up to `n` random bugs which leave for another island. Their lineage ends here
+    pub(crate) fn emigrate<R: RngCore>(&mut self, n: usize, rng: &mut R) -> Vec<Migrant>
+    where
+        T: Clone,
+    {
+        let leaving: Vec<Rc<RefCell<Bug<T>>>> =
+            self.bugs.sample(n, rng).into_iter().cloned().collect();
+        let migrants: Vec<Migrant> = leaving
+            .into_iter()
+            .map(|bug| {
+                let migrant = {
+                    let bug = bug.borrow();
+                    Migrant {
+                        chromosome: bug.chromosome().clone(),
+                        position: bug.position(),
+                        rotation: bug.rotation(),
+                        energy_level: bug.energy_level(),
+                    }
+                };
+                let id = bug.borrow().id();
+                let chunk_found = self
+                    .bugs
+                    .retain_by_position(migrant.position, |x| x.borrow().id() != id);
+                assert!(chunk_found);
+                self.phylogeny.record_death(id, self.iteration);
+                self.emigrants += 1;
+                migrant
+            })
+            .collect();
+        if !migrants.is_empty() && self.bugs.len() == 0 {
+            self.extinct_since = Some(self.now.clone());
+        }
+        migrants
+    }
+
+    /// Settle bugs arrived from another island. They get new ids and start new lineages
+    pub(crate) fn immigrate(&mut self, migrants: Vec<Migrant>)
+    where
+        T: Clone,
+    {
+        for migrant in migrants {
+            let Ok(bug) = Bug::give_birth(
+                &mut self.next_bug_id,
+                migrant.chromosome,
+                migrant.position,
+                migrant.rotation,
+                migrant.energy_level,
+                self.now.clone(),
+            ) else {
+                continue;
+            };
+            self.phylogeny
+                .record_birth(bug.id(), None, bug.chromosome(), self.iteration);
+            self.events.push(EnvironmentEvent::BugBorn {
+                id: bug.id(),
+                parent_id: None,
+                position: bug.position(),
+            });
+            self.bugs.push(Rc::new(RefCell::new(bug)));
+            self.immigrants += 1;
+            self.extinct_since = None;
+        }
+    }
+
+    pub fn island_stats(&self) -> IslandStats {
+        IslandStats {
+            population: self.bugs.len(),
+            food_count: self.food.len(),
+            iteration: self.iteration,
+            immigrants: self.immigrants,
+            emigrants: self.emigrants,
+        }
+    }
+
+    /// Time when population died out or None if there are living bugs
+    pub fn extinct_since(&self) -> Option<&T> {
+        self.extinct_since.as_ref()
+    }
+
+    pub fn food_chunks(&self) -> Vec<(RawChunkIndex, usize)> {
+        self.food.chunks()
+    }
+
+    pub fn food_chunks_circular_traverse_iter(
+        &self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+    ) -> impl Iterator<Item = (isize, isize)> {
+        self.food.circular_traverse_iter(position, range).map(|a| {
+            let i: RawChunkIndex = a.into();
+            (i.x(), i.y())
+        })
+    }
+
+    pub fn bug_chunks(&self) -> Vec<(RawChunkIndex, usize)> {
+        self.bugs.chunks()
+    }
+
+    pub fn bug_chunks_circular_traverse_iter(
+        &self,
+        position: Point<Float>,
+        range: NoNeg<Float>,
+    ) -> impl Iterator<Item = (isize, isize)> {
+        self.bugs.circular_traverse_iter(position, range).map(|a| {
+            let i: RawChunkIndex = a.into();
+            (i.x(), i.y())
+        })
+    }
+
+    pub(crate) fn collect_unused_chunks(&mut self) {
+        self.bugs.collect_unused_chunks();
+        self.food.collect_unused_chunks();
+        self.plants.collect_unused_chunks();
+        self.eggs.collect_unused_chunks();
+        self.parasites.collect_unused_chunks();
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SeededEnvironment<T> {
+    /// Viewed island (the one environment derefs to)
+    env: Environment<T>,
+    /// Other islands. Island with index `i` is `env` if `i == viewed_island`, otherwise it is
+    /// here at `i` (below viewed one) or `i - 1` (above it)
+    #[serde(default)]
+    islands: Vec<Environment<T>>,
+    #[serde(default)]
+    viewed_island: usize,
+    /// None if islands are isolated
+    #[serde(default)]
+    migration: Option<Migration>,
+    rng: Pcg64,
+    #[serde(skip)]
+    stop_conditions: StopConditions,
+    #[serde(skip)]
+    anomaly_detector: Option<AnomalyDetector>,
+    /// Found by the last check of anomaly detector, if it was made during the last iteration
+    #[serde(skip)]
+    last_anomalies: Vec<Anomaly>,
+}
+
+impl<T> SeededEnvironment<T> {
+    pub fn new(env: Environment<T>, seed: <Pcg64 as SeedableRng>::Seed) -> Self {
+        Self {
+            env,
+            islands: vec![],
+            viewed_island: 0,
+            migration: None,
+            rng: Pcg64::from_seed(seed),
+            stop_conditions: Default::default(),
+            anomaly_detector: None,
+            last_anomalies: vec![],
+        }
+    }
+
+    pub fn generate<Range: SampleRange<Float>>(
+        now: T,
+        seed: <Pcg64 as SeedableRng>::Seed,
+        food_sources: Vec<FoodSourceCreateInfo>,
+        x_range: Range,
+        y_range: Range,
+        food_e_range: Range,
+        food_count: usize,
+        bug_position: Point<Float>,
+    ) -> Self
+    where
+        Range: Clone,
+        T: Clone,
+    {
+        let mut rng = Pcg64::from_seed(seed);
+        Self {
+            env: Environment::generate(
+                now,
+                &mut rng,
+                food_sources,
+                x_range,
+                y_range,
+                food_e_range,
+                food_count,
+                bug_position,
+            ),
+            islands: vec![],
+            viewed_island: 0,
+            migration: None,
+            rng,
+            stop_conditions: Default::default(),
+            anomaly_detector: None,
+            last_anomalies: vec![],
+        }
+    }
+
+    pub fn set_stop_conditions(&mut self, stop_conditions: StopConditions) {
+        self.stop_conditions = stop_conditions;
+    }
+
+    pub fn stop_conditions(&self) -> &StopConditions {
+        &self.stop_conditions
+    }
+
+    /// Periodically check for suspicious dynamics and report them as
+    /// `EnvironmentEvent::AnomalyDetected` (None disables detection)
+    pub fn set_anomaly_detection(&mut self, config: Option<AnomalyDetectorConfig>) {
+        self.anomaly_detector = config.map(AnomalyDetector::new);
+        self.last_anomalies.clear();
+    }
+
+    pub fn anomaly_detection(&self) -> Option<&AnomalyDetectorConfig> {
+        self.anomaly_detector
+            .as_ref()
+            .map(|detector| detector.config())
+    }
+
+    /// Anomalies found during the last iteration
+    pub fn last_anomalies(&self) -> &[Anomaly] {
+        &self.last_anomalies
+    }
+
+    pub fn proceed(&mut self, dt: Duration) -> TickOutcome
+    where
+        T: TimePoint + Clone,
+    {
+        self.env.proceed(dt, &mut self.rng);
+        for island in &mut self.islands {
+            island.proceed(dt, &mut self.rng);
+        }
+        if let Some(migration) = &mut self.migration {
+            if migration.proceed(dt) {
+                let migrants = migration.migrants;
+                self.migrate(migrants);
+            }
+        }
+        self.detect_anomalies(dt);
+        self.check_stop_conditions()
+    }
+
+    /// Viewed island (other islands are dropped)
+    pub fn into_environment(self) -> Environment<T> {
+        self.env
+    }
+
+    /// Index in `islands` of island with `index` (None for the viewed one)
+    fn island_slot(&self, index: usize) -> Option<usize> {
+        match index.cmp(&self.viewed_island) {
+            Ordering::Less => Some(index),
+            Ordering::Equal => None,
+            Ordering::Greater => Some(index - 1),
+        }
+    }
+
+    pub fn island_count(&self) -> usize {
+        self.islands.len() + 1
+    }
+
+    pub fn viewed_island(&self) -> usize {
+        self.viewed_island
+    }
+
+    pub fn island(&self, index: usize) -> Option<&Environment<T>> {
+        match self.island_slot(index) {
+            Some(slot) => self.islands.get(slot),
+            None => Some(&self.env),
+        }
+    }
+
+    fn island_mut(&mut self, index: usize) -> Option<&mut Environment<T>> {
+        match self.island_slot(index) {
+            Some(slot) => self.islands.get_mut(slot),
+            None => Some(&mut self.env),
+        }
+    }
+
+    /// Stats of all islands in order of their indices
+    pub fn island_stats(&self) -> Vec<IslandStats> {
+        (0..self.island_count())
+            .filter_map(|index| self.island(index))
+            .map(|island| island.island_stats())
+            .collect()
+    }
+
+    /// Add island which evolves alongside existing ones. Returns its index
+    pub fn add_island(&mut self, island: Environment<T>) -> usize {
+        self.islands.push(island);
+        self.island_count() - 1
+    }
+
+    /// Remove island with `index`. The last remaining island can not be removed. When the viewed
+    /// island is removed, the previous one (or the next one for the first) becomes viewed
+    pub fn remove_island(&mut self, index: usize) -> Option<Environment<T>> {
+        if index >= self.island_count() || self.island_count() == 1 {
+            return None;
+        }
+        if index == self.viewed_island {
+            self.view_island(if index == 0 { 1 } else { index - 1 });
+        }
+        let slot = self.island_slot(index).unwrap();
+        if index < self.viewed_island {
+            self.viewed_island -= 1;
+        }
+        Some(self.islands.remove(slot))
+    }
+
+    /// Make island with `index` the one environment derefs to. Returns false if there is no
+    /// such island
+    pub fn view_island(&mut self, index: usize) -> bool {
+        let Some(slot) = self.island_slot(index) else {
+            return index == self.viewed_island;
+        };
+        if slot >= self.islands.len() {
+            return false;
+        }
+        let island = self.islands.remove(slot);
+        let previous = std::mem::replace(&mut self.env, island);
+        let previous_slot = if self.viewed_island < index {
+            self.viewed_island
+        } else {
+            self.viewed_island - 1
+        };
+        self.islands.insert(previous_slot, previous);
+        self.viewed_island = index;
+        true
+    }
+
+    pub fn migration(&self) -> Option<&Migration> {
+        self.migration.as_ref()
+    }
+
+    pub fn set_migration(&mut self, migration: Option<Migration>) {
+        self.migration = migration
+    }
+
+    /// Move `n` random bugs from each island to a random other one
+    fn migrate(&mut self, n: usize)
+    where
+        T: Clone,
+    {
+        let count = self.island_count();
+        if count < 2 {
+            return;
+        }
+        let mut departures = Vec::with_capacity(count);
+        for index in 0..count {
+            let island = match self.island_slot(index) {
+                Some(slot) => &mut self.islands[slot],
+                None => &mut self.env,
+            };
+            let migrants = island.emigrate(n, &mut self.rng);
+            let destination = (index + self.rng.gen_range(1..count)) % count;
+            departures.push((destination, migrants));
+        }
+        for (destination, migrants) in departures {
+            self.island_mut(destination).unwrap().immigrate(migrants);
+        }
+    }
+
+    fn detect_anomalies(&mut self, dt: Duration) {
+        self.last_anomalies = match &mut self.anomaly_detector {
+            Some(detector) => detector.proceed(&self.env, dt),
+            None => vec![],
+        };
+        for anomaly in &self.last_anomalies {
+            self.env
+                .events
+                .push(EnvironmentEvent::AnomalyDetected(anomaly.clone()));
+        }
+    }
+
+    fn check_stop_conditions(&self) -> TickOutcome
+    where
+        T: TimePoint,
+    {
+        let conditions = &self.stop_conditions;
+        let population = self.env.bugs_count()
+            + self
+                .islands
+                .iter()
+                .map(|island| island.bugs_count())
+                .sum::<usize>();
+        let sim_time = self.env.now().duration_since(self.env.creation_time());
+        let reason = if let Some(result) = self.env.scenario_result() {
+            Some(StopReason::ScenarioCompleted(result.clone()))
+        } else if conditions.extinction && population == 0 {
+            Some(StopReason::Extinction)
+        } else if conditions
+            .max_population
+            .is_some_and(|max_population| population > max_population)
+        {
+            Some(StopReason::PopulationExceeded { population })
+        } else if conditions
+            .max_sim_time
+            .is_some_and(|max_sim_time| sim_time > max_sim_time)
+        {
+            Some(StopReason::SimTimeExceeded { sim_time })
+        } else if let Some(anomaly) = self.last_anomalies.first().filter(|_| conditions.anomaly) {
+            Some(StopReason::AnomalyDetected(anomaly.clone()))
+        } else if let Some(min_species_count) = conditions
+            .min_species_count
+            .filter(|_| self.env.iteration().rem_euclid(SPECIES_CHECK_PERIOD) == 0)
+        {
+            let species_count = self.env.species().len();
+            (species_count < min_species_count)
+                .then_some(StopReason::SpeciesCountBelow { species_count })
+        } else {
+            None
+        };
+        reason.map_or(TickOutcome::Continue, TickOutcome::Stopped)
+    }
+
+    pub fn irradiate_area(&mut self, center: Point<Float>, radius: NoNeg<Float>) {
+        self.env.irradiate_area(center, radius, &mut self.rng);
+    }
+
+    pub fn add_food(&mut self, center: Point<Float>) {
+        self.env.add_food(center, &mut self.rng);
+    }
+
+    pub fn tag_bug(&mut self, id: usize, tag: Tag) -> bool {
+        self.env.tag_bug(id, tag)
+    }
+
+    pub fn untag_bug(&mut self, id: usize, name: &str) -> bool {
+        self.env.untag_bug(id, name)
+    }
+
+    pub fn tag_bugs_in_circle(
+        &mut self,
+        center: Point<Float>,
+        radius: NoNeg<Float>,
+        tag: Tag,
+    ) -> usize {
+        self.env.tag_bugs_in_circle(center, radius, tag)
+    }
+
+    pub fn add_parasites(&mut self, center: Point<Float>, count: usize) {
+        self.env.add_parasites(center, count, &mut self.rng);
+    }
+
+    pub fn add_bug(&mut self, center: Point<Float>)
+    where
+        T: Clone,
+    {
+        self.env.add_bug(center, &mut self.rng);
+    }
+
+    pub fn spawn_hall_of_fame(&mut self, center: Point<Float>) -> usize
+    where
+        T: Clone,
+    {
+        self.env.spawn_hall_of_fame(center, &mut self.rng)
+    }
+
+    pub fn collect_unused_chunks(&mut self) {
+        self.env.collect_unused_chunks();
+    }
+
+    pub fn set_chunk_sleep(&mut self, config: Option<ChunkSleepConfig>) {
+        self.env.set_chunk_sleep(config);
+    }
+
+    pub fn set_observed_region(&mut self, region: Option<Rect<Float>>) {
+        self.env.set_observed_region(region);
+    }
+
+    pub fn set_tracked_bug(&mut self, handle: Option<BugHandle>) {
+        self.env.set_tracked_bug(handle);
+    }
+
+    pub fn set_brain_log_policy(&mut self, policy: BrainLogPolicy) {
+        self.env.set_brain_log_policy(policy);
+    }
+
+    pub fn set_curriculum(&mut self, curriculum: Option<Curriculum>) {
+        self.env.set_curriculum(curriculum);
+    }
+
+    pub fn set_scenario(&mut self, scenario: Option<Scenario>) {
+        self.env.set_scenario(scenario);
+    }
+
+    pub fn compact_ids(&mut self) -> BTreeMap<usize, usize> {
+        self.env.compact_ids()
+    }
+
+    /// Merge `other` into the viewed island (see `Environment::merge`)
+    pub fn merge(&mut self, other: Environment<T>) -> BTreeMap<usize, usize>
+    where
+        T: TimePoint + Clone,
+    {
+        self.env.merge(other)
+    }
+
+    pub fn apply_config(&mut self, config: SimConfig) -> bool {
+        self.env.apply_config(config)
+    }
+
+    pub fn add_food_source(&mut self, info: FoodSourceCreateInfo) -> usize
+    where
+        T: Clone,
+    {
+        self.env.add_food_source(info)
+    }
+
+    pub fn edit_food_source(&mut self, index: usize, info: FoodSourceCreateInfo) -> bool {
+        self.env.edit_food_source(index, info)
+    }
+
+    pub fn remove_food_source(&mut self, index: usize) -> bool {
+        self.env.remove_food_source(index)
+    }
+
+    pub fn drain_events(&mut self) -> Vec<EnvironmentEvent> {
+        self.env.drain_events()
+    }
+
+    pub fn subscribe(
+        &mut self,
+        handler: impl FnMut(&EnvironmentEvent) + 'static,
+    ) -> SubscriptionId {
+        self.env.subscribe(handler)
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.env.unsubscribe(id)
+    }
+
+    pub fn with_plants(mut self, plants: Vec<PlantCreateInfo>) -> Self
+    where
+        T: Clone,
+    {
+        self.env.add_plants(plants);
+        self
+    }
+
+    pub fn with_water(mut self, regions: Vec<WaterRegion>) -> Self {
+        self.env.add_water(regions);
+        self
+    }
+
+    pub fn with_seasons(mut self, seasons: SeasonClock) -> Self {
+        self.env.set_season_clock(Some(seasons));
+        self
+    }
+
+    pub fn with_day_length(mut self, day_length: Duration) -> Self {
+        self.env.set_day_length(day_length);
+        self
+    }
+
+    pub fn with_terrain(mut self, terrain: Terrain) -> Self {
+        self.env.set_terrain(Some(terrain));
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: Rect<Float>) -> Self {
+        self.env.set_bounds(Some(bounds));
+        self
+    }
+
+    pub fn with_edge_policy(mut self, edge_policy: EdgePolicy) -> Self {
+        self.env.set_edge_policy(edge_policy);
+        self
+    }
+
+    pub fn with_biomes(mut self, biomes: BiomeMap) -> Self {
+        self.env.set_biomes(biomes);
+        self
+    }
+
+    pub fn with_light_sources(mut self, sources: Vec<LightSource>) -> Self {
+        self.env.add_light_sources(sources);
+        self
+    }
+
+    pub fn with_catastrophes(mut self, catastrophes: CatastropheScheduler) -> Self {
+        self.env.set_catastrophes(Some(catastrophes));
+        self
+    }
+
+    pub fn with_scenario(mut self, scenario: Scenario) -> Self {
+        self.env.set_scenario(Some(scenario));
+        self
+    }
+
+    pub fn with_curriculum(mut self, curriculum: Curriculum) -> Self {
+        self.env.set_curriculum(Some(curriculum));
+        self
+    }
+}
+
+// Note this impl does not brake SeededEnvironment invariant only if there is no immutable member function in Environment which accepts rng as argument
+impl<T> Deref for SeededEnvironment<T> {
+    type Target = Environment<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.env
+    }
+}
+
+pub mod benchmark_internals {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{bug::Bug, math::NoNeg, utils::Float};
+
+    use super::{Environment, FoodId};
+
+    /// Id of food which was created `slot`-th and no food was removed before
+    pub fn food_id(slot: usize) -> FoodId {
+        FoodId {
+            slot,
+            generation: 0,
+        }
+    }
+
+    pub fn transfer_energy_from_food_to_bug<T>(
+        env: &mut Environment<T>,
+        food_id: FoodId,
+        bug: &mut Bug<T>,
+        delta_energy: NoNeg<Float>,
+    ) {
+        env.transfer_energy_from_food_to_bug(food_id, bug, delta_energy)
+    }
+
+    pub fn bite<T>(
+        env: &Environment<T>,
+        attacker: &mut Bug<T>,
+        victim_id: usize,
+        delta_energy: NoNeg<Float>,
+    ) {
+        env.bite(attacker, victim_id, delta_energy)
+    }
+
+    pub fn find_bug_by_id<T>(env: &Environment<T>, id: usize) -> Option<Rc<RefCell<Bug<T>>>> {
+        env.bugs
+            .index_of_id(&id)
+            .map(|index| env.bugs[index].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        collections::BTreeSet,
+        f64::consts::{FRAC_PI_2, PI},
+        rc::Rc,
+        time::Duration,
+    };
+
+    use chromosome::Chromosome;
+
+    use super::{
+        BrainLogPolicy, Bug, BugCreateInfo, BugHandle, ChunkSleepConfig, ChunkTick, EdgePolicy,
+        Environment, FoodCreateInfo, FoodIdPool, FoodSourceCreateInfo, SeededEnvironment,
+        StopConditions, StopReason, TickOutcome, CHUNK_SIZE,
+    };
+    use crate::{
+        anomaly::{Anomaly, AnomalyDetectorConfig},
+        brain::SeenObject,
+        catastrophe::{Catastrophe, CatastropheScheduler, ScheduledCatastrophe},
+        chromo_utils::{ARMOR_GENES, BODY_GENES, CAMOUFLAGE_GENES, GENOME_LEN},
+        config::SimConfig,
+        curriculum::{Curriculum, CurriculumRule, Difficulty},
+        egg::{Egg, INCUBATION_DURATION},
+        env_presets,
+        events::{DeathCause, EnvironmentEvent},
+        food_source::{FoodSourceEdit, FoodSourceShape},
+        island::Migration,
+        math::{noneg_float, Angle, DeltaAngle, Point, Rect},
+        nutrient::Nutrients,
+        plant::PlantCreateInfo,
+        tag::Tag,
+        time_point::{StaticTimePoint, TimePoint},
+        utils::Float,
+    };
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    /// Genome with every gene set to one
+    fn genes() -> Vec<Float> {
+        vec![1.; GENOME_LEN]
+    }
+
+    /// Bug facing along x axis to be passed to `Environment::new`
+    fn bug_at(x: Float, y: Float) -> BugCreateInfo {
+        BugCreateInfo {
+            chromosome: Chromosome { genes: genes() },
+            position: (x, y).into(),
+            rotation: Angle::from_radians(0.),
+        }
+    }
+
+    /// Bug with max energy placed into `environment` facing along x axis
+    fn push_bug(
+        environment: &mut Environment<StaticTimePoint>,
+        genes: Vec<Float>,
+        position: (Float, Float),
+    ) -> Rc<RefCell<Bug<StaticTimePoint>>> {
+        let bug = Rc::new(RefCell::new(Bug::give_birth_with_max_energy(
+            &mut environment.next_bug_id,
+            Chromosome { genes },
+            position.into(),
+            Angle::from_radians(0.),
+            StaticTimePoint::default(),
+        )));
+        environment.bugs.push(bug.clone());
+        bug
+    }
+
+    #[test]
+    fn food_id_slots_are_recycled_with_new_generation() {
+        let mut ids = FoodIdPool::default();
+        let a = ids.acquire();
+        let b = ids.acquire();
+        assert_ne!(a, b);
+
+        ids.release(a);
+        let c = ids.acquire();
+        assert_eq!(c.slot, a.slot);
+        assert_ne!(c, a);
+        assert_eq!(ids.acquire().slot, 2);
+    }
+
+    #[test]
+    fn tick_timings_cover_bug_phases() {
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        let _ = environment.proceed(Duration::from_millis(100));
+        let timings = environment.tick_timings();
+        assert!(timings.sense > Duration::ZERO);
+        assert!(timings.brain > Duration::ZERO);
+        assert!(timings.total() >= timings.sense + timings.brain);
+    }
+
+    #[test]
+    fn chunk_load_is_counted_per_iteration() {
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        let total = |environment: &super::Environment<StaticTimePoint>| {
+            environment
+                .chunk_load()
+                .iter()
+                .map(|(_, load)| load.sensing_queries)
+                .sum::<usize>()
+        };
+
+        let _ = environment.proceed(Duration::from_millis(100));
+        let first = total(&environment);
+        assert!(first >= environment.bugs_count());
+
+        // counters are reset each iteration instead of accumulating
+        let _ = environment.proceed(Duration::from_millis(100));
+        assert!(total(&environment) < first * 2);
+    }
+
+    #[test]
+    fn food_source_stats_are_accumulated() {
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        for _ in 0..300 {
+            let _ = environment.proceed(Duration::from_millis(100));
+        }
+
+        let stats = environment.food_sources().next().unwrap().stats().clone();
+        assert!(stats.food_spawned > 0);
+        assert!(stats.energy_injected > 0.);
+        assert!(stats.energy_eaten > 0.);
+
+        let report = environment.stats().food_source_report();
+        assert_eq!(report.len(), environment.food_sources().count());
+        assert_eq!(report[0].totals, stats);
+        let share: f64 = report.iter().map(|x| x.recent_share).sum();
+        assert!((share - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn food_sources_are_edited_and_logged() {
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        let count = environment.food_sources().count();
+        assert_eq!(environment.food_source_at((0., 0.).into()), Some(count - 1));
+
+        let mut info = environment.food_sources().next().unwrap().create_info();
+        info.position = (100000., 0.).into();
+        let index = environment.add_food_source(info.clone());
+        assert_eq!(index, count);
+        assert_eq!(
+            environment.food_source_at((100000., 0.).into()),
+            Some(index)
+        );
+
+        info.spawn_interval = Duration::from_millis(10);
+        assert!(environment.edit_food_source(index, info));
+        assert_eq!(
+            environment.food_sources().last().unwrap().spawn_interval(),
+            Duration::from_millis(10)
+        );
+        assert!(environment.remove_food_source(0));
+        assert!(!environment.remove_food_source(count));
+        assert_eq!(environment.food_sources().count(), count);
+        assert_eq!(environment.food_source_log().len(), 3);
+        assert!(matches!(
+            environment.food_source_log()[2].edit,
+            FoodSourceEdit::Remove { index: 0 }
+        ));
+    }
+
+    #[test]
+    fn ids_are_compacted_with_lineage() {
+        let mut environment = env_presets::plant_meadow(StaticTimePoint::default(), [0; 32]);
+        for _ in 0..40 {
+            let _ = environment.proceed(Duration::from_secs(60));
+        }
+        let bug_id = |environment: &SeededEnvironment<StaticTimePoint>| {
+            environment.bugs().map(|bug| bug.id()).max().unwrap()
+        };
+        let old_id = bug_id(&environment);
+        let lineage = |environment: &SeededEnvironment<StaticTimePoint>, id| {
+            environment
+                .phylogeny()
+                .line_of_descent(id)
+                .into_iter()
+                .map(|entry| (entry.bug_id, entry.chromosome.genes))
+                .collect::<Vec<_>>()
+        };
+        let old_lineage = lineage(&environment, old_id);
+
+        let id_map = environment.compact_ids();
+        // ids of dead bugs without living descendants are reused
+        assert!(id_map.len() <= old_id);
+        assert_eq!(environment.next_bug_id, id_map.len());
+        assert!(bug_id(&environment) < id_map.len());
+        let new_lineage = lineage(&environment, id_map[&old_id]);
+        assert_eq!(
+            new_lineage,
+            old_lineage
+                .into_iter()
+                .map(|(id, genes)| (id_map[&id], genes))
+                .collect::<Vec<_>>()
+        );
+        let mut food_slots: Vec<_> = environment.food().map(|food| food.id().slot).collect();
+        food_slots.sort();
+        assert!(food_slots.iter().enumerate().all(|(i, slot)| i == *slot));
+
+        // compacted environment keeps simulating
+        let _ = environment.proceed(Duration::from_secs(60));
+    }
+
+    #[test]
+    fn merged_worlds_get_unique_ids_and_keep_lineages() {
+        let mut environment = env_presets::plant_meadow(StaticTimePoint::default(), [0; 32]);
+        let mut other = env_presets::plant_meadow(StaticTimePoint::default(), [1; 32]);
+        for _ in 0..20 {
+            let _ = environment.proceed(Duration::from_secs(60));
+        }
+        for _ in 0..10 {
+            let _ = other.proceed(Duration::from_secs(60));
+        }
+        let ids = |environment: &SeededEnvironment<StaticTimePoint>| {
+            environment
+                .bugs()
+                .map(|bug| bug.id())
+                .collect::<BTreeSet<_>>()
+        };
+        let (own_ids, other_ids) = (ids(&environment), ids(&other));
+        // both worlds issue ids from zero
+        assert!(own_ids.intersection(&other_ids).next().is_some());
+        let other_id = *other_ids.iter().max().unwrap();
+        let other_lineage: Vec<_> = other
+            .phylogeny()
+            .line_of_descent(other_id)
+            .into_iter()
+            .map(|entry| (entry.bug_id, entry.chromosome.genes))
+            .collect();
+        let counts = |environment: &SeededEnvironment<StaticTimePoint>| {
+            (
+                environment.bugs().count(),
+                environment.plants().count(),
+                environment.food().count(),
+            )
+        };
+        let (own_counts, other_counts) = (counts(&environment), counts(&other));
+
+        let id_map = environment.merge(other.into_environment());
+        assert_eq!(
+            counts(&environment),
+            (
+                own_counts.0 + other_counts.0,
+                own_counts.1 + other_counts.1,
+                own_counts.2 + other_counts.2
+            )
+        );
+        let merged_ids = ids(&environment);
+        assert_eq!(merged_ids.len(), own_ids.len() + other_ids.len());
+        assert!(own_ids.is_subset(&merged_ids));
+        assert!(other_ids.iter().all(|id| merged_ids.contains(&id_map[id])));
+        let plant_ids: BTreeSet<_> = environment.plants().map(|plant| plant.id()).collect();
+        assert_eq!(plant_ids.len(), environment.plants().count());
+        let food_slots: BTreeSet<_> = environment.food().map(|food| food.id().slot).collect();
+        assert_eq!(food_slots.len(), environment.food().count());
+        assert_eq!(
+            environment
+                .phylogeny()
+                .line_of_descent(id_map[&other_id])
+                .into_iter()
+                .map(|entry| (entry.bug_id, entry.chromosome.genes))
+                .collect::<Vec<_>>(),
+            other_lineage
+                .into_iter()
+                .map(|(id, genes)| (id_map[&id], genes))
+                .collect::<Vec<_>>()
+        );
+        // bugs of both worlds have a common clock and keep simulating
+        let age = environment
+            .now()
+            .duration_since(environment.creation_time());
+        assert!(environment
+            .bugs()
+            .all(|bug| environment.now().duration_since(bug.birth_instant()) <= age));
+        let _ = environment.proceed(Duration::from_secs(60));
+    }
+
+    #[test]
+    fn uneaten_food_rots_away() {
+        let food = FoodCreateInfo::generate_vec(
+            &mut Pcg64::from_seed([0; 32]),
+            -500. ..500.,
+            -500. ..500.,
+            0.5..1.,
+            64,
+        );
+        let mut environment = SeededEnvironment::new(
+            Environment::new(StaticTimePoint::default(), food, vec![], vec![]),
+            [0; 32],
+        );
+        let energy = environment.food_total_energy();
+        let _ = environment.proceed(Duration::from_secs(60));
+        assert_eq!(environment.food_total_energy(), energy);
+        assert_eq!(environment.food_count(), 64);
+
+        environment.apply_config(SimConfig {
+            food_rot_rate: noneg_float(1.),
+            ..Default::default()
+        });
+        let _ = environment.proceed(Duration::from_secs(60 * 60));
+        assert!(environment.food_total_energy() < energy * noneg_float(0.4));
+        assert!(environment
+            .food()
+            .all(|food| food.age() > Duration::from_secs(60 * 60)));
+
+        let _ = environment.proceed(Duration::from_secs(60 * 60 * 10));
+        assert_eq!(environment.food_count(), 0);
+    }
+
+    #[test]
+    fn saturated_chunks_reject_food() {
+        let food_source = FoodSourceCreateInfo {
+            position: (CHUNK_SIZE as Float / 2., CHUNK_SIZE as Float / 2.).into(),
+            shape: FoodSourceShape::Rect {
+                size: (CHUNK_SIZE as Float / 2., CHUNK_SIZE as Float / 2.).into(),
+            },
+            energy_range: (0.5..1.).into(),
+            spawn_interval: Duration::from_millis(100),
+            aquatic: false,
+            nutrients: Default::default(),
+        };
+        let mut environment = SeededEnvironment::new(
+            Environment::new(
+                StaticTimePoint::default(),
+                vec![],
+                vec![food_source],
+                vec![],
+            ),
+            [0; 32],
+        );
+        environment.apply_config(SimConfig {
+            max_food_per_chunk: Some(16),
+            ..Default::default()
+        });
+        for _ in 0..100 {
+            let _ = environment.proceed(Duration::from_millis(100));
+        }
+        assert_eq!(environment.food_count(), 16);
+
+        environment.apply_config(SimConfig::default());
+        let _ = environment.proceed(Duration::from_secs(1));
+        assert!(environment.food_count() > 16);
+    }
+
+    #[test]
+    fn handles_of_dead_or_renumbered_bugs_are_not_resolved() {
+        let mut environment = Environment::new(
+            StaticTimePoint::default(),
+            vec![],
+            vec![],
+            vec![bug_at(0., 0.), bug_at(1000., 0.), bug_at(2000., 0.)],
+        );
+        let first = environment.bug_handle(0).unwrap();
+        let second = environment.bug_handle(1).unwrap();
+        let third = environment.bug_handle(2).unwrap();
+        assert_eq!(environment.resolve(second).unwrap().id(), 1);
+
+        let dead = environment.bugs[environment.bugs.index_of_id(&1).unwrap()].clone();
+        environment.remove_dead_bug(&dead, DeathCause::OldAge);
+        assert!(environment.resolve(second).is_none());
+        assert!(environment.bug_handle(1).is_none());
+        assert!(environment.resolve(third).is_some());
+
+        environment.set_tracked_bug(Some(third));
+        let id_map = environment.compact_ids();
+        assert_eq!(id_map[&2], 1);
+        // first bug kept its id, so its handle is still valid
+        assert_eq!(*environment.resolve(first).unwrap().position().x(), 0.);
+        // slot of dead second bug is given to the third one
+        assert!(environment.resolve(second).is_none());
+        assert!(environment.resolve(third).is_none());
+        let renumbered = environment.bug_handle(1).unwrap();
+        assert_ne!(renumbered, second);
+        assert_eq!(
+            *environment.resolve(renumbered).unwrap().position().x(),
+            2000.
+        );
+        assert_eq!(environment.tracked_bug(), Some(renumbered));
+
+        // ids of bugs born after renumbering are not confused with old ones
+        let restored: Environment<StaticTimePoint> =
+            serde_json::from_str(&serde_json::to_string(&environment).unwrap()).unwrap();
+        assert!(restored.resolve(third).is_none());
+        assert_eq!(*restored.resolve(first).unwrap().position().x(), 0.);
+        assert_eq!(restored.tracked_bug(), Some(renumbered));
+    }
+
+    #[test]
+    fn tracked_bug_keeps_its_chunk_awake() {
+        let mut environment = SeededEnvironment::new(
+            Environment::new(
+                StaticTimePoint::default(),
+                vec![],
+                vec![],
+                vec![bug_at(0., 0.), bug_at(10000., 0.)],
+            ),
+            [0; 32],
+        );
+        environment.set_chunk_sleep(Some(ChunkSleepConfig {
+            distance: 1,
+            period: 0,
+        }));
+        environment.set_observed_region(Some(Rect::from_center(
+            (0., 0.).into(),
+            (100., 100.).into(),
+        )));
+        let dt = Duration::from_millis(100);
+        let far: Point<Float> = (10000., 0.).into();
+        assert_eq!(environment.chunk_tick((0., 0.).into()), ChunkTick::Update);
+        assert_eq!(environment.chunk_tick(far), ChunkTick::Freeze);
+
+        let far_bug_id = environment
+            .bugs()
+            .find(|bug| *bug.position().x() > 5000.)
+            .unwrap()
+            .id();
+        let far_bug = environment.bug_handle(far_bug_id);
+        environment.set_tracked_bug(far_bug);
+        let _ = environment.proceed(dt);
+        assert_eq!(environment.chunk_tick(far), ChunkTick::Update);
+        assert_eq!(
+            environment.chunk_tick((5000., 0.).into()),
+            ChunkTick::Freeze
+        );
+    }
+
+    #[test]
+    fn islands_can_be_viewed_removed_and_exchange_migrants() {
+        let island = |seed| {
+            env_presets::plant_meadow(StaticTimePoint::default(), [seed; 32]).into_environment()
+        };
+        let mut environment = env_presets::plant_meadow(StaticTimePoint::default(), [0; 32]);
+        assert_eq!(environment.add_island(island(1)), 1);
+        assert_eq!(environment.add_island(island(2)), 2);
+        let populations: Vec<usize> = environment
+            .island_stats()
+            .iter()
+            .map(|stats| stats.population)
+            .collect();
+
+        assert!(environment.view_island(2));
+        assert!(!environment.view_island(3));
+        assert_eq!(environment.viewed_island(), 2);
+        assert_eq!(environment.bugs_count(), populations[2]);
+        assert_eq!(environment.island(0).unwrap().bugs_count(), populations[0]);
+        assert_eq!(environment.island(1).unwrap().bugs_count(), populations[1]);
+
+        assert_eq!(
+            environment.remove_island(2).unwrap().bugs_count(),
+            populations[2]
+        );
+        assert_eq!(environment.viewed_island(), 1);
+        assert_eq!(environment.bugs_count(), populations[1]);
+        assert_eq!(environment.island_count(), 2);
+
+        environment.set_migration(Some(Migration::new(Duration::from_secs(1), 2)));
+        for _ in 0..10 {
+            let _ = environment.proceed(Duration::from_millis(100));
+        }
+        let stats = environment.island_stats();
+        assert!(stats.iter().all(|stats| stats.emigrants == 2));
+        assert!(stats.iter().all(|stats| stats.immigrants == 2));
+    }
+
+    #[test]
+    fn meteor_clears_crater_and_famine_is_saved() {
+        let center: Point<_> = (0., 0.).into();
+        let radius = noneg_float(300.);
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32])
+                .with_catastrophes(CatastropheScheduler::new(None).with_scripted(vec![
+                    ScheduledCatastrophe {
+                        at: Duration::from_secs(1),
+                        catastrophe: Catastrophe::Meteor { center, radius },
+                    },
+                    ScheduledCatastrophe {
+                        at: Duration::from_secs(2),
+                        catastrophe: Catastrophe::Famine {
+                            duration: Duration::from_secs(60),
+                        },
+                    },
+                ]));
+        assert!(environment
+            .bugs()
+            .any(|bug| (bug.position() - center).len() < radius.unwrap()));
+
+        let struck = |environment: &mut SeededEnvironment<StaticTimePoint>| {
+            let _ = environment.proceed(Duration::from_millis(100));
+            environment
+                .drain_events()
+                .into_iter()
+                .any(|event| matches!(event, EnvironmentEvent::CatastropheStruck(_)))
+        };
+        while !struck(&mut environment) {}
+        assert!(environment
+            .bugs()
+            .all(|bug| (bug.position() - center).len() >= radius.unwrap()));
+        assert!(environment
+            .food()
+            .all(|food| (food.position() - center).len() >= radius.unwrap()));
+
+        let abundance = environment.food_abundance();
+        while !struck(&mut environment) {}
+        assert_eq!(environment.food_abundance(), abundance * 0.5);
+        let restored: SeededEnvironment<StaticTimePoint> =
+            serde_json::from_str(&serde_json::to_string(&environment).unwrap()).unwrap();
+        assert_eq!(restored.food_abundance(), environment.food_abundance());
+    }
+
+    #[test]
+    fn curriculum_tightens_environment_and_is_saved() {
+        let mut environment = env_presets::plant_meadow(StaticTimePoint::default(), [0; 32])
+            .with_curriculum(Curriculum::new(
+                CurriculumRule::Schedule {
+                    period: Duration::from_secs(60),
+                },
+                Difficulty::default(),
+                0.25,
+            ));
+        assert_eq!(environment.food_abundance(), 1.);
+        for _ in 0..3 {
+            let _ = environment.proceed(Duration::from_secs(30));
+        }
+        assert_eq!(environment.curriculum().unwrap().level(), 0.25);
+        assert!(environment.food_abundance() < 1.);
+        assert!(environment.metabolism_multiplier() > noneg_float(1.));
+
+        let restored: SeededEnvironment<StaticTimePoint> =
+            serde_json::from_str(&serde_json::to_string(&environment).unwrap()).unwrap();
+        assert_eq!(restored.curriculum().unwrap().level(), 0.25);
+        assert_eq!(restored.food_abundance(), environment.food_abundance());
+    }
+
+    #[test]
+    fn deaths_are_reported_as_events() {
+        let mut environment = env_presets::plant_meadow(StaticTimePoint::default(), [0; 32]);
+        let _ = environment.proceed(Duration::from_millis(100));
+        assert!(!environment
+            .drain_events()
+            .iter()
+            .any(|event| matches!(event, EnvironmentEvent::BugDied { .. })));
+
+        // nobody lives longer than a day
+        let deaths_before = environment.stats().deaths_count();
+        let _ = environment.proceed(Duration::from_secs(60 * 60 * 24 * 2));
+        let died = environment
+            .drain_events()
+            .into_iter()
+            .filter(|event| matches!(event, EnvironmentEvent::BugDied { .. }))
+            .count();
+        assert!(died > 0);
+        assert_eq!(died, environment.stats().deaths_count() - deaths_before);
+        assert!(environment.drain_events().is_empty());
+    }
+
+    #[test]
+    fn subscribers_are_notified_without_consuming_events() {
+        let mut environment = env_presets::plant_meadow(StaticTimePoint::default(), [0; 32]);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let subscription = environment.subscribe({
+            let seen = seen.clone();
+            move |event| seen.borrow_mut().push(event.clone())
+        });
+
+        // nobody lives longer than a day
+        let _ = environment.proceed(Duration::from_secs(60 * 60 * 24 * 2));
+        let causes = |events: &[EnvironmentEvent]| {
+            events
+                .iter()
+                .filter_map(|event| match event {
+                    EnvironmentEvent::BugDied { cause, .. } => Some(*cause),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let events = environment.drain_events();
+        assert!(!causes(&events).is_empty());
+        assert_eq!(causes(&seen.borrow()), causes(&events));
+        assert!(causes(&events)
+            .iter()
+            .all(|cause| matches!(cause, DeathCause::Starvation | DeathCause::OldAge)));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, EnvironmentEvent::FoodSpawned { .. })));
+
+        assert!(environment.unsubscribe(subscription));
+        assert!(!environment.unsubscribe(subscription));
+        let seen_count = seen.borrow().len();
+        environment.irradiate_area((0., 0.).into(), noneg_float(100.));
+        assert_eq!(environment.drain_events().len(), 1);
+        assert_eq!(seen.borrow().len(), seen_count);
+    }
+
+    #[test]
+    fn births_and_nukes_are_reported_as_events() {
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        environment.add_bug((0., 0.).into());
+        environment.irradiate_area((0., 0.).into(), noneg_float(100.));
+        let events = environment.drain_events();
+        assert!(matches!(
+            events[0],
+            EnvironmentEvent::BugBorn {
+                parent_id: None,
+                ..
+            }
+        ));
+        assert!(matches!(events[1], EnvironmentEvent::AreaIrradiated { .. }));
+    }
+
+    #[test]
+    fn eggs_hatch_after_incubation_unless_eaten() {
+        let egg = |next_id: &mut usize, position: Point<Float>| {
+            Egg::new(
+                next_id,
+                1000,
+                Chromosome {
+                    genes: vec![1.; GENOME_LEN],
+                },
+                position,
+                Angle::from_radians(0.),
+                noneg_float(10.),
+                StaticTimePoint::default(),
+            )
+        };
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        environment
+            .eggs
+            .push(egg(&mut environment.next_egg_id, (0., 0.).into()));
+        let mut environment = SeededEnvironment::new(environment, [0; 32]);
+
+        let _ = environment.proceed(INCUBATION_DURATION / 2);
+        assert_eq!(environment.eggs_count(), 1);
+        assert_eq!(environment.bugs_count(), 0);
+
+        let _ = environment.proceed(INCUBATION_DURATION / 2);
+        assert_eq!(environment.eggs_count(), 0);
+        assert!(environment.bugs_count() > 0);
+        assert!(environment.drain_events().iter().any(|event| matches!(
+            event,
+            EnvironmentEvent::BugBorn {
+                parent_id: Some(1000),
+                ..
+            }
+        )));
+
+        // egg laid right under a bug is eaten
+        let position = environment.bugs().next().unwrap().position();
+        let egg = egg(&mut environment.env.next_egg_id, position);
+        environment.env.eggs.push(egg);
+        let _ = environment.proceed(Duration::from_millis(100));
+        assert!(environment
+            .eggs()
+            .all(|egg| egg.energy() < noneg_float(10.)));
+    }
+
+    #[test]
+    fn tags_are_queried_saved_and_inherited() {
+        let mut environment = env_presets::plant_meadow(StaticTimePoint::default(), [0; 32]);
+        let ids: Vec<usize> = environment.bugs().take(2).map(|bug| bug.id()).collect();
+        assert!(environment.tag_bug(ids[0], Tag::new("cohort-A").inherited()));
+        assert!(environment.tag_bug(ids[1], Tag::new("vaccinated")));
+        assert!(environment.tag_bug(ids[1], Tag::new("vaccinated")));
+        assert!(!environment.tag_bug(usize::MAX, Tag::new("vaccinated")));
+        assert_eq!(
+            environment.tag_census(),
+            [("cohort-A".to_string(), 1), ("vaccinated".to_string(), 1)].into()
+        );
+        assert_eq!(
+            environment
+                .query()
+                .bugs()
+                .tagged("cohort-A")
+                .iter()
+                .map(|bug| bug.id())
+                .collect::<Vec<_>>(),
+            [ids[0]]
+        );
+
+        let restored: SeededEnvironment<StaticTimePoint> =
+            serde_json::from_str(&serde_json::to_string(&environment).unwrap()).unwrap();
+        assert_eq!(restored.tag_census(), environment.tag_census());
+        assert!(environment.untag_bug(ids[1], "vaccinated"));
+        assert!(!environment.untag_bug(ids[1], "vaccinated"));
+
+        let parent = environment.find_bug_by_id(ids[0]).unwrap().inherited_tags();
+        let egg = Egg::new(
+            &mut environment.env.next_egg_id,
+            ids[0],
+            Chromosome {
+                genes: vec![1.; GENOME_LEN],
+            },
+            (5000., 5000.).into(),
+            Angle::from_radians(0.),
+            noneg_float(10.),
+            environment.now().clone(),
+        )
+        .with_tags(parent);
+        environment.env.eggs.push(egg);
+        let _ = environment.proceed(INCUBATION_DURATION);
+        let hatched: Vec<_> = environment
+            .bugs()
+            .filter(|bug| environment.phylogeny().parent_id(bug.id()) == Some(ids[0]))
+            .map(|bug| bug.has_tag("cohort-A"))
+            .collect();
+        assert!(!hatched.is_empty());
+        assert!(hatched.into_iter().all(|tagged| tagged));
+    }
+
+    #[test]
+    fn energy_is_given_only_to_own_offspring() {
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        environment.add_bug((0., 0.).into(), &mut Pcg64::from_seed([0; 32]));
+        let parent = environment.bugs.iter().next().unwrap().clone();
+        let (parent_id, chromosome) = {
+            let parent = parent.borrow();
+            (parent.id(), parent.chromosome().clone())
+        };
+        let hungry_bug = |environment: &mut Environment<StaticTimePoint>, parent_id| {
+            let bug = Bug::give_birth(
+                &mut environment.next_bug_id,
+                chromosome.clone(),
+                (1., 0.).into(),
+                Angle::from_radians(0.),
+                noneg_float(1.),
+                StaticTimePoint::default(),
+            )
+            .unwrap();
+            environment
+                .phylogeny
+                .record_birth(bug.id(), parent_id, bug.chromosome(), 0);
+            let bug = Rc::new(RefCell::new(bug));
+            environment.bugs.push(bug.clone());
+            bug
+        };
+        let stranger = hungry_bug(&mut environment, None);
+        let child = hungry_bug(&mut environment, Some(parent_id));
+
+        for recipient in [&stranger, &child] {
+            let recipient_id = recipient.borrow().id();
+            environment.transfer_energy_bug_to_bug(
+                &mut parent.borrow_mut(),
+                recipient_id,
+                noneg_float(5.),
+            );
+        }
+        assert_eq!(stranger.borrow().energy_level(), noneg_float(1.));
+        assert_eq!(child.borrow().energy_level(), noneg_float(6.));
+    }
+
+    #[test]
+    fn sting_paralyzes_bug_within_eat_range() {
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        let bug = |environment: &mut Environment<StaticTimePoint>, x| {
+            let mut genes = genes();
+            genes[ARMOR_GENES.start] = 0.;
+            push_bug(environment, genes, (x, 0.))
+        };
+        let attacker = bug(&mut environment, 0.);
+        let victim = bug(&mut environment, 1.);
+        let distant = bug(&mut environment, 1000.);
+        let dose = attacker.borrow().venom_dose();
+        assert_eq!(dose, noneg_float(1.));
+
+        for bug in [&victim, &distant] {
+            let id = bug.borrow().id();
+            environment.sting(&attacker.borrow(), id, dose);
+        }
+        assert_eq!(victim.borrow().paralyzed_for(), Duration::from_secs(5));
+        assert!(!distant.borrow().paralyzed());
+
+        let position = victim.borrow().position();
+        let _ = victim.borrow_mut().proceed(
+            &environment,
+            Duration::from_secs(1),
+            &mut Pcg64::from_seed([0; 32]),
+        );
+        assert_eq!(victim.borrow().position(), position);
+        assert_eq!(victim.borrow().paralyzed_for(), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn plant_is_paid_back_when_its_toxic_fruit_harms_eater() {
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        environment.add_plants(vec![PlantCreateInfo {
+            chromosome: Chromosome {
+                genes: vec![0., 0., 1.],
+            },
+            position: (10., 0.).into(),
+        }]);
+        let plant_energy = environment.plants().next().unwrap().energy_level();
+        let mut food = FoodCreateInfo {
+            position: (0., 0.).into(),
+            energy: noneg_float(1.),
+            toxicity: noneg_float(0.5),
+            nutrients: Nutrients::BALANCED,
+        }
+        .create(&mut environment.food_ids);
+        food.plant_id = Some(environment.plants().next().unwrap().id());
+        let food_id = food.id();
+        environment.food.push(food);
+        let bug = push_bug(&mut environment, genes(), (0., 0.));
+        let bug_energy = bug.borrow().energy_level();
+
+        environment.transfer_energy_from_food_to_bug(
+            food_id,
+            &mut bug.borrow_mut(),
+            noneg_float(1.),
+        );
+        let harm = bug_energy.limited_sub(bug.borrow().energy_level());
+        assert!(harm > noneg_float(0.));
+        let paid_back = environment
+            .plants()
+            .next()
+            .unwrap()
+            .energy_level()
+            .limited_sub(plant_energy);
+        assert!((paid_back.unwrap() - harm.unwrap() / 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bite_moves_energy_of_bug_within_eat_range_into_stomach() {
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        let bug = |environment: &mut Environment<StaticTimePoint>, x| {
+            let mut genes = genes();
+            genes[ARMOR_GENES.start] = 0.;
+            push_bug(environment, genes, (x, 0.))
+        };
+        let attacker = bug(&mut environment, 0.);
+        let victim = bug(&mut environment, 1.);
+        let distant = bug(&mut environment, 1000.);
+        let energy = victim.borrow().energy_level();
+
+        for bug in [&victim, &distant] {
+            let id = bug.borrow().id();
+            environment.bite(&mut attacker.borrow_mut(), id, noneg_float(1.));
+        }
+        assert_eq!(
+            victim.borrow().energy_level(),
+            energy.limited_sub(noneg_float(1.))
+        );
+        assert_eq!(distant.borrow().energy_level(), energy);
+        assert_eq!(attacker.borrow().consumed_energy(), noneg_float(1.));
+    }
+
+    #[test]
+    fn retina_reports_nearest_object_of_each_sector() {
+        let food = |x, y| FoodCreateInfo {
+            position: (x, y).into(),
+            energy: noneg_float(1.),
+            toxicity: noneg_float(0.),
+            nutrients: Nutrients::BALANCED,
+        };
+        let mut environment = Environment::new(
+            StaticTimePoint::default(),
+            vec![food(10., 5.), food(20., 10.), food(-10., 0.)],
+            vec![],
+            vec![],
+        );
+        let mut genes = genes();
+        genes[CAMOUFLAGE_GENES.start] = 0.;
+        push_bug(&mut environment, genes, (20., -5.));
+
+        // sectors of a quarter of right angle each, from -90 to 90 degrees
+        let retina = environment.look_with_retina(
+            (0., 0.).into(),
+            noneg_float(100.),
+            Angle::from_radians(0.),
+            DeltaAngle::from_radians(noneg_float(std::f64::consts::FRAC_PI_2)),
+        );
+        assert!(retina[0].is_none());
+        let bug = retina[1].unwrap();
+        assert_eq!(bug.object, SeenObject::Bug);
+        assert_eq!(bug.dst, noneg_float((425. as Float).sqrt()));
+        let food = retina[2].unwrap();
+        assert_eq!(food.object, SeenObject::Food);
+        assert_eq!(food.dst, noneg_float((125. as Float).sqrt()));
+        assert!(retina[3].is_none());
+    }
+
+    #[test]
+    fn flock_summarizes_bugs_in_vision_arc() {
+        let bugs = [(10., 0., 0.), (20., 10., FRAC_PI_2), (-10., 0., PI)]
+            .into_iter()
+            .map(|(x, y, rotation)| BugCreateInfo {
+                rotation: Angle::from_radians(rotation),
+                ..bug_at(x, y)
+            })
+            .collect();
+        let environment = Environment::new(StaticTimePoint::default(), vec![], vec![], bugs);
+
+        let flock = environment.sense_flock(
+            (0., 0.).into(),
+            noneg_float(100.),
+            Angle::from_radians(0.),
+            DeltaAngle::from_radians(noneg_float(FRAC_PI_2)),
+        );
+        // the bug behind observer is not seen
+        assert_eq!(flock.count, 2);
+        let heading = flock.heading.unwrap();
+        assert!((heading.radians() - FRAC_PI_2 / 2.).abs() < 1e-9);
+        assert_eq!(flock.speed, 0.);
+
+        let nobody = environment.sense_flock(
+            (1000., 0.).into(),
+            noneg_float(100.),
+            Angle::from_radians(0.),
+            DeltaAngle::from_radians(noneg_float(PI)),
+        );
+        assert_eq!(nobody.count, 0);
+        assert!(nobody.heading.is_none());
+    }
+
+    #[test]
+    fn larger_bug_shoves_smaller_one_on_collision() {
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        let mut bug = |x, size| {
+            let mut genes = genes();
+            genes[BODY_GENES.start + 1] = size;
+            push_bug(&mut environment, genes, (x, 0.))
+        };
+        let large = bug(0., 2.);
+        let small = bug(10., 1.);
+        let small_energy = small.borrow().energy_level();
+
+        environment.resolve_collisions(Duration::from_secs(1));
+        let large_x = *large.borrow().position().x();
+        let small_x = *small.borrow().position().x();
+        // overlap of 50 is split 1:2
+        assert!((large_x + 50. / 3.).abs() < 1e-9, "large: {}", large_x);
+        assert!(
+            (small_x - 10. - 100. / 3.).abs() < 1e-9,
+            "small: {}",
+            small_x
+        );
+        assert!(small.borrow().energy_level() < small_energy);
+    }
+
+    #[test]
+    fn bugs_are_reflected_from_bounds() {
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        environment.set_bounds(Some(Rect::from_center(
+            (0., 0.).into(),
+            (600., 600.).into(),
+        )));
+        let bug = push_bug(&mut environment, genes(), (310., 0.));
+
+        assert_eq!(
+            environment.wall_distance(
+                (200., 0.).into(),
+                Angle::from_radians(0.),
+                noneg_float(150.)
+            ),
+            Some(noneg_float(100.))
+        );
+        assert_eq!(
+            environment.wall_distance(
+                (200., 0.).into(),
+                Angle::from_radians(PI),
+                noneg_float(150.)
+            ),
+            None
+        );
+
+        environment.enforce_edge_policy();
+        assert_eq!(*bug.borrow().position().x(), 290.);
+        assert!((bug.borrow().rotation().radians() - PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn edge_policy_wraps_or_kills_runaway_bugs() {
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        environment.set_bounds(Some(Rect::from_center(
+            (0., 0.).into(),
+            (600., 600.).into(),
+        )));
+        environment.set_edge_policy(EdgePolicy::Wrap);
+        let runaway = push_bug(&mut environment, genes(), (310., -320.));
+        assert_eq!(
+            environment.wall_distance(
+                (200., 0.).into(),
+                Angle::from_radians(0.),
+                noneg_float(150.)
+            ),
+            None
+        );
+        environment.enforce_edge_policy();
+        assert_eq!(*runaway.borrow().position().x(), -290.);
+        assert_eq!(*runaway.borrow().position().y(), 280.);
+        assert_eq!(runaway.borrow().rotation().radians(), 0.);
+
+        environment.set_edge_policy(EdgePolicy::Death {
+            radius: noneg_float(1000.),
+        });
+        push_bug(&mut environment, genes(), (5000., 0.));
+        environment.enforce_edge_policy();
+        assert_eq!(environment.bugs_count(), 1);
+        assert_eq!(*environment.bugs().next().unwrap().position().x(), -290.);
+    }
+
+    #[test]
+    fn bug_starving_beyond_death_radius_dies_once() {
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        environment.set_edge_policy(EdgePolicy::Death {
+            radius: noneg_float(1000.),
+        });
+        // nothing to gain energy from
+        let mut genes = vec![0.; GENOME_LEN];
+        genes[BODY_GENES].fill(1.);
+        let bug = Bug::give_birth(
+            &mut environment.next_bug_id,
+            Chromosome { genes },
+            (5000., 0.).into(),
+            Angle::from_radians(0.),
+            noneg_float(0.),
+            StaticTimePoint::default(),
+        )
+        .unwrap();
+        environment.bugs.push(Rc::new(RefCell::new(bug)));
+
+        environment.proceed(Duration::from_millis(100), &mut Pcg64::from_seed([0; 32]));
+        assert_eq!(environment.bugs_count(), 0);
+        let causes: Vec<_> = environment
+            .drain_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                EnvironmentEvent::BugDied { cause, .. } => Some(cause),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(causes[..], [DeathCause::Starvation]));
+    }
+
+    #[test]
+    fn touch_reports_the_deepest_overlapping_object() {
+        let food = |x| FoodCreateInfo {
+            position: (x, 0.).into(),
+            energy: noneg_float(1.),
+            toxicity: noneg_float(0.),
+            nutrients: Nutrients::BALANCED,
+        };
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        let bug = |environment: &mut Environment<StaticTimePoint>, x| {
+            let bug = push_bug(environment, genes(), (x, 0.));
+            let bug = bug.borrow();
+            (bug.id(), bug.eat_range())
+        };
+        let (toucher, radius) = bug(&mut environment, 0.);
+        assert!(environment
+            .touch((0., 0.).into(), radius, toucher)
+            .is_none());
+
+        // food just inside body circle
+        let r = radius.unwrap();
+        let food = food(r).create(&mut environment.food_ids);
+        environment.food.push(food);
+        let contact = environment.touch((0., 0.).into(), radius, toucher).unwrap();
+        assert_eq!(contact.object, SeenObject::Food);
+        assert!(contact.direction.cos() > 0.999);
+
+        // bug of the same size overlaps much deeper
+        bug(&mut environment, -r);
+        let contact = environment.touch((0., 0.).into(), radius, toucher).unwrap();
+        assert_eq!(contact.object, SeenObject::Bug);
+        assert!(contact.direction.cos() < -0.999);
+    }
+
+    #[test]
+    fn anomalies_are_reported_once_and_stop_simulation() {
+        let mut env = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        let id = push_bug(&mut env, genes(), (2_000_000., 0.)).borrow().id();
+        let mut environment = SeededEnvironment::new(env, [0; 32]);
+        assert_eq!(
+            environment.proceed(Duration::from_millis(100)),
+            TickOutcome::Continue
+        );
+
+        environment.set_anomaly_detection(Some(AnomalyDetectorConfig {
+            period: 1,
+            ..Default::default()
+        }));
+        environment.set_stop_conditions(StopConditions {
+            anomaly: true,
+            ..Default::default()
+        });
+        let outcome = environment.proceed(Duration::from_millis(100));
+        let TickOutcome::Stopped(StopReason::AnomalyDetected(Anomaly::OutOfBounds {
+            bug_id, ..
+        })) = outcome
+        else {
+            panic!("unexpected outcome: {:?}", outcome);
+        };
+        assert_eq!(bug_id, id);
+        assert!(environment
+            .drain_events()
+            .iter()
+            .any(|event| matches!(event, EnvironmentEvent::AnomalyDetected(_))));
+
+        assert_eq!(
+            environment.proceed(Duration::from_millis(100)),
+            TickOutcome::Continue
+        );
+        assert!(environment.last_anomalies().is_empty());
+    }
+
+    #[test]
+    fn stop_conditions_are_reported_by_proceed() {
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        assert_eq!(
+            environment.proceed(Duration::from_millis(100)),
+            TickOutcome::Continue
+        );
+
+        environment.set_stop_conditions(StopConditions {
+            max_sim_time: Some(Duration::from_secs(1)),
+            ..Default::default()
+        });
+        let iterations = (0..20)
+            .position(|_| environment.proceed(Duration::from_millis(100)) != TickOutcome::Continue)
+            .unwrap();
+        assert_eq!(iterations, 9);
+
+        environment.set_stop_conditions(StopConditions {
+            max_population: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(
+            environment.proceed(Duration::from_millis(100)),
+            TickOutcome::Stopped(StopReason::PopulationExceeded {
+                population: environment.bugs_count()
+            })
+        );
+    }
+
+    #[test]
+    fn brain_logs_follow_policy() {
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        let logged = |environment: &super::Environment<StaticTimePoint>| {
+            environment
+                .bugs()
+                .filter(|bug| bug.last_brain_log().is_some())
+                .map(|bug| bug.id())
+                .collect::<Vec<_>>()
+        };
+
+        let _ = environment.proceed(Duration::from_millis(100));
+        assert_eq!(logged(&environment).len(), environment.bugs_count());
+
+        let pinned = environment.bugs().next().unwrap().id();
+        environment.set_brain_log_policy(BrainLogPolicy::Pinned(vec![pinned]));
+        let _ = environment.proceed(Duration::from_millis(100));
+        assert_eq!(logged(&environment), vec![pinned]);
+    }
+
+    #[test]
+    fn brain_can_be_evaluated_on_demand() {
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        environment.set_brain_log_policy(BrainLogPolicy::Pinned(Default::default()));
+        let _ = environment.proceed(Duration::from_millis(100));
+
+        let id = environment.bugs().next().unwrap().id();
+        assert!(environment
+            .find_bug_by_id(id)
+            .unwrap()
+            .last_brain_log()
+            .is_none());
+        let handle = environment.bug_handle(id).unwrap();
+        let log = environment.evaluate_bug_brain(handle).unwrap();
+        assert_eq!(log.generation, environment.iteration());
+        assert!(environment
+            .evaluate_bug_brain(BugHandle {
+                id: usize::MAX,
+                generation: 0,
+            })
+            .is_none());
+    }
+
+    #[test]
+    fn sleeping_chunks_are_updated_once_per_period_with_skipped_time() {
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        let dt = Duration::from_millis(100);
+        let far: Point<Float> = (10000., 0.).into();
+        // steps of entity staying at `position` during 8 iterations and time it has not been
+        // stepped by yet
+        let steps = |environment: &mut Environment<StaticTimePoint>, position: Point<Float>| {
+            let mut skipped_time = Duration::ZERO;
+            let dts = (0..8)
+                .map(|iteration| {
+                    environment.iteration = iteration;
+                    environment.chunk_tick(position).dt(dt, &mut skipped_time)
+                })
+                .collect::<Vec<_>>();
+            (dts, skipped_time)
+        };
+        assert_eq!(environment.chunk_tick(far), ChunkTick::Update);
+        environment.set_chunk_sleep(Some(ChunkSleepConfig {
+            distance: 1,
+            period: 4,
+        }));
+        // nothing is observed, so nothing sleeps
+        assert_eq!(environment.chunk_tick(far), ChunkTick::Update);
+
+        environment.set_observed_region(Some(Rect::from_center(
+            (0., 0.).into(),
+            (100., 100.).into(),
+        )));
+        for awake in [(0., 0.), (CHUNK_SIZE as Float, CHUNK_SIZE as Float)] {
+            assert_eq!(
+                steps(&mut environment, awake.into()),
+                (vec![Some(dt); 8], Duration::ZERO)
+            );
+        }
+        let (far_dts, far_skipped_time) = steps(&mut environment, far);
+        assert_eq!(far_dts.iter().flatten().count(), 2);
+        assert_eq!(far_dts.iter().flatten().last(), Some(&(dt * 4)));
+        assert_eq!(
+            far_dts.iter().flatten().sum::<Duration>() + far_skipped_time,
+            dt * 8
+        );
+        // neighbor sleeping chunks are woken on different iterations
+        let (next_dts, _) = steps(&mut environment, (10000. + CHUNK_SIZE as Float, 0.).into());
+        assert_eq!(next_dts.iter().flatten().count(), 2);
+        assert_ne!(
+            far_dts.iter().position(Option::is_some),
+            next_dts.iter().position(Option::is_some)
+        );
+
+        environment.set_chunk_sleep(Some(ChunkSleepConfig {
+            distance: 1,
+            period: 0,
+        }));
+        assert_eq!(
+            steps(&mut environment, far),
+            (vec![None; 8], Duration::ZERO)
+        );
+        assert_eq!(
+            steps(&mut environment, (0., 0.).into()),
+            (vec![Some(dt); 8], Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn sleeping_bug_spends_as_much_energy_as_awake_one() {
+        // every output of brain is zero and nothing is gained, so spending is proportional to
+        // time bug is simulated for
+        let mut genes = vec![0.; GENOME_LEN];
+        genes[BODY_GENES].fill(1.);
+        // `crossing` bug is moved back and forth between neighbor chunks of different phases
+        let spent_energy = |chunk_sleep, crossing: bool| {
+            let mut environment =
+                Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+            environment.set_chunk_sleep(chunk_sleep);
+            environment.set_observed_region(Some(Rect::from_center(
+                (0., 0.).into(),
+                (100., 100.).into(),
+            )));
+            let bug = push_bug(&mut environment, genes.clone(), (10000., 0.));
+            let initial = bug.borrow().energy_level().unwrap();
+            let mut rng = Pcg64::from_seed([0; 32]);
+            for iteration in 0..8 {
+                if crossing {
+                    let offset = if iteration % 2 == 0 { 1. } else { -1. } * CHUNK_SIZE as Float;
+                    bug.borrow_mut().push_by((offset, 0.), noneg_float(0.));
+                }
+                environment.proceed(Duration::from_millis(100), &mut rng);
+            }
+            // time skipped by sleeping bug is simulated when it wakes up
+            environment.set_chunk_sleep(None);
+            environment.proceed(Duration::from_millis(100), &mut rng);
+            let energy = bug.borrow().energy_level().unwrap();
+            initial - energy
+        };
+
+        let awake = spent_energy(None, false);
+        assert!(awake > 0.);
+        for period in [2, 4, 8] {
+            for crossing in [false, true] {
+                let sleeping = spent_energy(
+                    Some(ChunkSleepConfig {
+                        distance: 1,
+                        period,
+                    }),
+                    crossing,
+                );
+                assert!(
+                    (sleeping - awake).abs() < 1e-9 * awake,
+                    "period: {period}, crossing: {crossing}"
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+
+use crate::{
+    anomaly::Anomaly,
+    catastrophe::Catastrophe,
+    math::{Angle, NoNeg, Point},
+    scenario::ScenarioResult,
+    utils::{Color, Float},
+};
+
+/// Max number of events kept until they are drained. The oldest ones are dropped when nobody
+/// drains the queue (e.g. in headless simulator)
+pub const MAX_PENDING_EVENTS: usize = 16384;
+
+/// `PopulationLow` is emitted when population falls to that many bugs
+pub const LOW_POPULATION_WARNING: usize = 10;
+
+/// Why bug has died
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    /// Energy has run out
+    Starvation,
+    /// Bug has outlived its lifespan
+    OldAge,
+    /// Bug has gone too far (see `environment::EdgePolicy::Death`)
+    WorldEdge,
+    /// Bug was hit by catastrophe (see `catastrophe::Catastrophe`)
+    Catastrophe,
+}
+
+/// Something which happened during an iteration and may be interesting to observers
+#[derive(Debug, Clone)]
+pub enum EnvironmentEvent {
+    BugDied {
+        id: usize,
+        position: Point<Float>,
+        rotation: Angle<Float>,
+        size: NoNeg<Float>,
+        color: Color,
+        cause: DeathCause,
+    },
+    BugBorn {
+        id: usize,
+        parent_id: Option<usize>,
+        position: Point<Float>,
+    },
+    /// Food was eaten completely and removed
+    FoodEaten {
+        position: Point<Float>,
+        bug_id: usize,
+    },
+    /// Food source or plant has placed new food
+    FoodSpawned {
+        position: Point<Float>,
+        energy: NoNeg<Float>,
+    },
+    AreaIrradiated {
+        center: Point<Float>,
+        radius: NoNeg<Float>,
+    },
+    /// Population has just fallen to `LOW_POPULATION_WARNING` bugs and extinction is close
+    PopulationLow { population: usize },
+    /// Anomaly detector flagged suspicious dynamics (see `anomaly::Anomaly`)
+    AnomalyDetected(Anomaly),
+    /// Scheduled or random catastrophe has just struck (see `catastrophe::Catastrophe`)
+    CatastropheStruck(Catastrophe),
+    /// Scenario has just been completed (see `scenario::Scenario`). Environment stops after it
+    ScenarioCompleted(ScenarioResult),
+}
+
+/// Callback invoked with every event as soon as it happens
+pub type EventHandler = Box<dyn FnMut(&EnvironmentEvent)>;
+
+/// Returned by `Environment::subscribe` to unsubscribe later
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionId(usize);
+
+/// Pending events plus subscribers notified immediately. Subscribers do not consume events, so
+/// they can still be drained afterwards
+#[derive(Default)]
+pub(crate) struct EventQueue {
+    events: VecDeque<EnvironmentEvent>,
+    subscribers: Vec<(SubscriptionId, EventHandler)>,
+    next_subscription_id: usize,
+}
+
+impl EventQueue {
+    pub(crate) fn subscribe(&mut self, handler: EventHandler) -> SubscriptionId {
+        let id = SubscriptionId(self.next_subscription_id);
+        self.next_subscription_id += 1;
+        self.subscribers.push((id, handler));
+        id
+    }
+
+    /// Returns false if there is no such subscription
+    pub(crate) fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let len = self.subscribers.len();
+        self.subscribers.retain(|(x, _)| *x != id);
+        self.subscribers.len() != len
+    }
+
+    pub(crate) fn push(&mut self, event: EnvironmentEvent) {
+        for (_, handler) in &mut self.subscribers {
+            handler(&event);
+        }
+        if self.events.len() >= MAX_PENDING_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    pub(crate) fn drain(&mut self) -> Vec<EnvironmentEvent> {
+        self.events.drain(..).collect()
+    }
+}
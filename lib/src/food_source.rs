@@ -1,22 +1,55 @@
 use std::{f64::consts::PI, time::Duration};
 
 use crate::{
-    environment::{EnvironmentRequest, FoodCreateInfo},
-    math::{Angle, Complex, NoNeg, Point, Rect, Size},
+    environment::{EnvironmentRequest, FoodCreateInfo, FoodSourceCreateInfo},
+    math::{noneg_float, Angle, Complex, NoNeg, Point, Rect, Size},
+    nutrient::Nutrients,
     range::Range,
     time_point::TimePoint,
     utils::{sample_range_from_range, Float},
+    water::WaterRegion,
 };
 use rand::Rng;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FoodSourceShape {
     Rect { size: Size<Float> },
     Circle { radius: NoNeg<Float> },
 }
 
+/// Change of food sources made while simulation runs (see `Environment::food_source_log`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FoodSourceEdit {
+    Add(FoodSourceCreateInfo),
+    Edit {
+        index: usize,
+        info: FoodSourceCreateInfo,
+    },
+    Remove {
+        index: usize,
+    },
+}
+
+/// Edit of food sources applied at given iteration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoodSourceChange {
+    pub iteration: usize,
+    pub edit: FoodSourceEdit,
+}
+
+/// Totals of a food source since its creation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FoodSourceStats {
+    pub food_spawned: usize,
+    pub energy_injected: Float,
+    /// Pieces of food eaten completely inside the area of the source
+    pub food_eaten: usize,
+    /// Energy bugs got from food inside the area of the source
+    pub energy_eaten: Float,
+}
+
 /// Generates food around itself over time
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FoodSource<T> {
@@ -25,6 +58,14 @@ pub struct FoodSource<T> {
     energy_range: Range<Float>,
     spawn_interval: Duration,
     last_food_creation_instant: T,
+    /// Food is spawned only in water
+    #[serde(default)]
+    aquatic: bool,
+    /// What spawned food consists of
+    #[serde(default)]
+    nutrients: Nutrients,
+    #[serde(default)]
+    stats: FoodSourceStats,
 }
 
 impl<T> FoodSource<T> {
@@ -36,11 +77,81 @@ impl<T> FoodSource<T> {
         &self.shape
     }
 
+    pub fn aquatic(&self) -> bool {
+        self.aquatic
+    }
+
+    pub fn nutrients(&self) -> &Nutrients {
+        &self.nutrients
+    }
+
+    pub fn energy_range(&self) -> Range<Float> {
+        self.energy_range
+    }
+
+    pub fn spawn_interval(&self) -> Duration {
+        self.spawn_interval
+    }
+
+    /// Parameters of the source (can be edited and applied with `Environment::edit_food_source`)
+    pub fn create_info(&self) -> FoodSourceCreateInfo {
+        FoodSourceCreateInfo {
+            position: self.position,
+            shape: self.shape.clone(),
+            energy_range: self.energy_range,
+            spawn_interval: self.spawn_interval,
+            aquatic: self.aquatic,
+            nutrients: self.nutrients,
+        }
+    }
+
+    /// Replace parameters keeping spawn timer and stats
+    pub(crate) fn edit(&mut self, info: FoodSourceCreateInfo) {
+        self.position = info.position;
+        self.shape = info.shape;
+        self.energy_range = info.energy_range;
+        self.spawn_interval = info.spawn_interval;
+        self.aquatic = info.aquatic;
+        self.nutrients = info.nutrients;
+    }
+
+    /// Replace spawn timer with one of another clock (see `Environment::merge`)
+    pub(crate) fn rebase_time<F: Fn(&T) -> T>(&mut self, rebase: F) {
+        self.last_food_creation_instant = rebase(&self.last_food_creation_instant);
+    }
+
+    pub fn stats(&self) -> &FoodSourceStats {
+        &self.stats
+    }
+
+    /// Whether `point` is inside the area where source spawns food
+    pub fn contains(&self, point: Point<Float>) -> bool {
+        match self.shape {
+            FoodSourceShape::Rect { size } => {
+                let rect = Rect::from_center(self.position, size);
+                *point.x() >= rect.left()
+                    && *point.x() <= rect.right()
+                    && *point.y() >= rect.top()
+                    && *point.y() <= rect.bottom()
+            }
+            FoodSourceShape::Circle { radius } => (point - self.position).len() < radius.unwrap(),
+        }
+    }
+
+    pub(crate) fn record_eaten(&mut self, energy: NoNeg<Float>, food_drained: bool) {
+        self.stats.energy_eaten += energy.unwrap();
+        if food_drained {
+            self.stats.food_eaten += 1;
+        }
+    }
+
     pub(crate) fn new(
         position: Point<Float>,
         shape: FoodSourceShape,
         energy_range: Range<Float>,
         spawn_interval: Duration,
+        aquatic: bool,
+        nutrients: Nutrients,
         last_food_creation_instant: T,
     ) -> Self {
         Self {
@@ -49,47 +160,73 @@ impl<T> FoodSource<T> {
             energy_range,
             spawn_interval,
             last_food_creation_instant,
+            aquatic,
+            nutrients,
+            stats: Default::default(),
         }
     }
 
-    pub(crate) fn proceed<R: RngCore>(&mut self, now: &T, rng: &mut R) -> Vec<EnvironmentRequest>
+    /// Aquatic source skips food which would be placed outside of `water`.
+    /// Spawn rate and energy of food are multiplied by seasonal `abundance`
+    pub(crate) fn proceed<R: RngCore>(
+        &mut self,
+        now: &T,
+        water: &[WaterRegion],
+        abundance: Float,
+        rng: &mut R,
+    ) -> Vec<EnvironmentRequest>
     where
         T: TimePoint + Clone,
     {
         let mut requests: Vec<EnvironmentRequest> = Default::default();
 
+        let spawn_interval = self.spawn_interval.div_f64(abundance);
+        let energy_range = Range {
+            start: self.energy_range.start * abundance,
+            end: self.energy_range.end * abundance,
+        };
         let n = now
             .duration_since(&self.last_food_creation_instant)
-            .div_duration_f64(self.spawn_interval)
+            .div_duration_f64(spawn_interval)
             .floor();
 
         for _ in 0..(n as usize) {
-            match self.shape {
+            let food = match self.shape {
                 FoodSourceShape::Rect { size } => {
                     let rect = Rect::from_center(self.position, size);
-                    requests.push(EnvironmentRequest::PlaceFood(FoodCreateInfo::generate(
-                        rng,
-                        sample_range_from_range(rect.x_range()),
-                        sample_range_from_range(rect.y_range()),
-                        sample_range_from_range(self.energy_range),
-                    )));
-                }
-                FoodSourceShape::Circle { radius } => {
-                    requests.push(EnvironmentRequest::PlaceFood(FoodCreateInfo {
-                        position: Complex::from_polar(
-                            rng.gen_range(0. ..radius.unwrap()),
-                            Angle::from_radians(rng.gen_range(0. ..(PI * 2.))),
-                        )
-                        .into_cartesian(),
-                        energy: NoNeg::wrap(
-                            rng.gen_range(sample_range_from_range(self.energy_range)),
+                    FoodCreateInfo {
+                        nutrients: self.nutrients,
+                        ..FoodCreateInfo::generate(
+                            rng,
+                            sample_range_from_range(rect.x_range()),
+                            sample_range_from_range(rect.y_range()),
+                            sample_range_from_range(energy_range),
                         )
-                        .unwrap(),
-                    }));
+                    }
                 }
+                FoodSourceShape::Circle { radius } => FoodCreateInfo {
+                    position: Complex::from_polar(
+                        rng.gen_range(0. ..radius.unwrap()),
+                        Angle::from_radians(rng.gen_range(0. ..(PI * 2.))),
+                    )
+                    .into_cartesian(),
+                    energy: NoNeg::wrap(rng.gen_range(sample_range_from_range(energy_range)))
+                        .unwrap(),
+                    toxicity: noneg_float(0.),
+                    nutrients: self.nutrients,
+                },
+            };
+            if !self.aquatic || water.iter().any(|region| region.contains(food.position)) {
+                requests.push(EnvironmentRequest::PlaceFood(food));
+            }
+        }
+        for request in &requests {
+            if let EnvironmentRequest::PlaceFood(info) = request {
+                self.stats.food_spawned += 1;
+                self.stats.energy_injected += info.energy.unwrap();
             }
         }
-        self.last_food_creation_instant += self.spawn_interval.mul_f64(n);
+        self.last_food_creation_instant += spawn_interval.mul_f64(n);
         requests
     }
 }
@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use chromosome::Chromosome;
+use rand::{Rng, RngCore};
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chromo_utils::{bug_mutation_profiles_of, crossover, mutate_blocks, GENOME_LEN},
+    env_presets,
+    seeds::{self, Seed, SeedTree},
+    time_point::StaticTimePoint,
+    utils::Float,
+};
+
+/// Stream of seeds of episode arenas (one per generation)
+const ARENA_SEEDS: &str = "arena";
+
+/// Stream of the first generation genomes
+const FIRST_GENERATION_SEEDS: &str = "first generation";
+
+/// Id of the only bug placed into an episode arena
+const FOUNDER_ID: usize = 0;
+
+/// Fitness gained by each offspring born during an episode (in units of consumed energy)
+const OFFSPRING_FITNESS: Float = 10.;
+
+/// How parents of the next generation are picked
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Selection {
+    /// Best of `size` random individuals
+    Tournament { size: usize },
+    /// Random individual with probability proportional to its fitness
+    Roulette,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationalConfig {
+    pub population: usize,
+    /// Simulated time each individual lives before it is scored
+    pub episode: Duration,
+    pub dt: Duration,
+    /// Best individuals copied into the next generation unchanged
+    pub elite: usize,
+    pub selection: Selection,
+}
+
+impl Default for GenerationalConfig {
+    fn default() -> Self {
+        Self {
+            population: 64,
+            episode: Duration::from_secs(60 * 5),
+            dt: Duration::from_millis(1000 / 30),
+            elite: 4,
+            selection: Selection::Tournament { size: 4 },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Individual {
+    pub chromosome: Chromosome<Float>,
+    pub fitness: Float,
+}
+
+/// Fitness of a generation after all its episodes were run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationSummary {
+    pub generation: usize,
+    pub best_fitness: Float,
+    pub mean_fitness: Float,
+}
+
+/// Alternative to the continuous ecosystem: every individual of a generation lives through the
+/// same fixed episode alone in `env_presets::test_arena`, and the next generation is bred from the
+/// best scored ones in one step. Converges faster, but nothing competes or co-evolves
+pub struct GenerationalDriver {
+    config: GenerationalConfig,
+    arena_seeds: SeedTree,
+    /// Selection, crossover and mutation
+    rng: Pcg64,
+    generation: usize,
+    population: Vec<Chromosome<Float>>,
+}
+
+impl GenerationalDriver {
+    pub fn new(config: GenerationalConfig, seed: Seed) -> Self {
+        let seeds = SeedTree::new(seed);
+        let mut rng = seeds.child(FIRST_GENERATION_SEEDS).rng();
+        let population = (0..config.population)
+            .map(|_| Chromosome::new_random(GENOME_LEN, -1. ..1., &mut rng))
+            .collect();
+        Self {
+            config,
+            arena_seeds: seeds.child(ARENA_SEEDS),
+            rng: seeds.child(seeds::MUTATION).rng(),
+            generation: 0,
+            population,
+        }
+    }
+
+    pub fn config(&self) -> &GenerationalConfig {
+        &self.config
+    }
+
+    /// Number of generations bred so far
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn population(&self) -> &[Chromosome<Float>] {
+        &self.population
+    }
+
+    /// Run episodes of the current generation, replace it with the bred one and return scored
+    /// individuals of the current generation (best first)
+    pub fn proceed(&mut self) -> (GenerationSummary, Vec<Individual>) {
+        // All individuals of a generation face the same arena
+        let arena_seed = self.arena_seeds.indexed(self.generation as u64).seed();
+        let mut scored: Vec<Individual> = self
+            .population
+            .iter()
+            .map(|chromosome| Individual {
+                fitness: run_episode(chromosome.clone(), arena_seed, &self.config),
+                chromosome: chromosome.clone(),
+            })
+            .collect();
+        scored.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+
+        let summary = GenerationSummary {
+            generation: self.generation,
+            best_fitness: scored.first().map(|x| x.fitness).unwrap_or_default(),
+            mean_fitness: scored.iter().map(|x| x.fitness).sum::<Float>()
+                / scored.len().max(1) as Float,
+        };
+        self.population = breed(&scored, &self.config, &mut self.rng);
+        self.generation += 1;
+        (summary, scored)
+    }
+}
+
+/// Energy consumed by the founder during the episode plus a bonus for each of its offspring.
+/// Episode ends early if the founder dies
+pub fn run_episode(
+    chromosome: Chromosome<Float>,
+    seed: Seed,
+    config: &GenerationalConfig,
+) -> Float {
+    let mut arena = env_presets::test_arena(StaticTimePoint::default(), seed, chromosome);
+    let mut fitness = 0.;
+    let mut elapsed = Duration::ZERO;
+    while elapsed < config.episode {
+        let _ = arena.proceed(config.dt);
+        elapsed += config.dt;
+        match arena.find_bug_by_id(FOUNDER_ID) {
+            Some(founder) => {
+                fitness = founder.consumed_energy().unwrap()
+                    + founder.offspring_count() as Float * OFFSPRING_FITNESS
+            }
+            None => break,
+        }
+    }
+    fitness
+}
+
+/// Next generation: elite copied as is, the rest are mutated crossovers of selected parents.
+/// `scored` must be sorted best first
+fn breed<R: RngCore>(
+    scored: &[Individual],
+    config: &GenerationalConfig,
+    rng: &mut R,
+) -> Vec<Chromosome<Float>> {
+    if scored.is_empty() {
+        return vec![];
+    }
+    let elite = config.elite.min(config.population).min(scored.len());
+    let mut next: Vec<_> = scored[..elite]
+        .iter()
+        .map(|x| x.chromosome.clone())
+        .collect();
+    while next.len() < config.population {
+        let a = select(scored, config.selection, rng);
+        let b = select(scored, config.selection, rng);
+        let mut child = crossover(&a.chromosome, &b.chromosome, rng);
+        // child inherits mutation rate genes from parents
+        let profiles = bug_mutation_profiles_of(&child);
+        mutate_blocks(&mut child, &profiles, rng);
+        next.push(child);
+    }
+    next
+}
+
+fn select<'a, R: RngCore>(
+    scored: &'a [Individual],
+    selection: Selection,
+    rng: &mut R,
+) -> &'a Individual {
+    match selection {
+        Selection::Tournament { size } => (0..size.max(1))
+            .map(|_| &scored[rng.gen_range(0..scored.len())])
+            .max_by(|a, b| a.fitness.total_cmp(&b.fitness))
+            .unwrap(),
+        Selection::Roulette => {
+            let total: Float = scored.iter().map(|x| x.fitness.max(0.)).sum();
+            if total <= 0. {
+                return &scored[rng.gen_range(0..scored.len())];
+            }
+            let mut point = rng.gen_range(0. ..total);
+            for individual in scored {
+                point -= individual.fitness.max(0.);
+                if point < 0. {
+                    return individual;
+                }
+            }
+            scored.last().unwrap()
+        }
+    }
+}
+
+/// Single point crossover. Genes past the end of the shorter parent are taken from `a`
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{GenerationalConfig, GenerationalDriver, Selection};
+
+    #[test]
+    fn generation_is_replaced_by_bred_one() {
+        for selection in [Selection::Tournament { size: 3 }, Selection::Roulette] {
+            let config = GenerationalConfig {
+                population: 6,
+                episode: Duration::from_secs(10),
+                dt: Duration::from_secs(1),
+                elite: 2,
+                selection,
+            };
+            let mut driver = GenerationalDriver::new(config, [0; 32]);
+            let (summary, scored) = driver.proceed();
+
+            assert_eq!(summary.generation, 0);
+            assert_eq!(driver.generation(), 1);
+            assert_eq!(scored.len(), 6);
+            assert!(scored.windows(2).all(|x| x[0].fitness >= x[1].fitness));
+            assert_eq!(summary.best_fitness, scored[0].fitness);
+            assert!(summary.mean_fitness <= summary.best_fitness);
+            assert_eq!(driver.population().len(), 6);
+            assert_eq!(driver.population()[0].genes, scored[0].chromosome.genes);
+            assert_eq!(driver.population()[1].genes, scored[1].chromosome.genes);
+        }
+    }
+}
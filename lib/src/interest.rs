@@ -0,0 +1,184 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    chunk::RawChunkIndex,
+    environment::{Environment, CHUNK_SIZE},
+    events::EnvironmentEvent,
+    math::Point,
+    species::{genetic_distance, SPECIES_DISTANCE_THRESHOLD},
+    utils::Float,
+};
+
+/// Deaths older than that many iterations are not considered recent
+pub const MASS_DEATH_WINDOW: usize = 512;
+
+/// Why a region deserves attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterestKind {
+    /// Chunk with the most bugs
+    LargestCluster,
+    /// Chunk where the most bugs died recently
+    MassDeath,
+    /// Species which gained the most members since the previous scoring
+    GrowingSpecies,
+}
+
+/// Interesting region of environment
+#[derive(Debug, Clone)]
+pub struct RegionInterest {
+    pub kind: InterestKind,
+    pub center: Point<Float>,
+    /// Number of bugs involved (bugs in cluster, dead bugs or new members of species)
+    pub score: Float,
+}
+
+/// Scores regions of environment by what happens there. Deaths are learned from
+/// `Environment::drain_events` and species growth from differences between two scorings
+#[derive(Default)]
+pub struct InterestScorer {
+    /// Iteration and position of recent deaths (oldest first)
+    deaths: VecDeque<(usize, Point<Float>)>,
+    /// Representative genes and member count of species at the previous scoring
+    species_sizes: Vec<(Vec<Float>, usize)>,
+}
+
+fn chunk_center(index: RawChunkIndex) -> Point<Float> {
+    (
+        (index.x() as Float + 0.5) * CHUNK_SIZE as Float,
+        (index.y() as Float + 0.5) * CHUNK_SIZE as Float,
+    )
+        .into()
+}
+
+impl InterestScorer {
+    /// Remember deaths among `events` which happened by `iteration`
+    pub fn handle_events(&mut self, events: &[EnvironmentEvent], iteration: usize) {
+        for event in events {
+            if let EnvironmentEvent::BugDied { position, .. } = event {
+                self.deaths.push_back((iteration, *position))
+            }
+        }
+    }
+
+    /// The most interesting region of each kind (kinds with nothing going on are skipped),
+    /// best first
+    pub fn score<T>(&mut self, environment: &Environment<T>) -> Vec<RegionInterest> {
+        let iteration = environment.iteration();
+        while self
+            .deaths
+            .front()
+            .is_some_and(|(died_at, _)| died_at + MASS_DEATH_WINDOW < iteration)
+        {
+            self.deaths.pop_front();
+        }
+
+        let mut result: Vec<RegionInterest> = Default::default();
+
+        if let Some((index, count)) = environment
+            .bug_chunks()
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+        {
+            result.push(RegionInterest {
+                kind: InterestKind::LargestCluster,
+                center: chunk_center(index),
+                score: count as Float,
+            });
+        }
+
+        let mut deaths_per_chunk: HashMap<(isize, isize), usize> = Default::default();
+        for (_, position) in &self.deaths {
+            let index = RawChunkIndex::from_position::<CHUNK_SIZE, CHUNK_SIZE>(*position);
+            *deaths_per_chunk.entry((index.x(), index.y())).or_default() += 1;
+        }
+        if let Some(((x, y), count)) = deaths_per_chunk
+            .into_iter()
+            .max_by_key(|(index, count)| (*count, *index))
+        {
+            result.push(RegionInterest {
+                kind: InterestKind::MassDeath,
+                center: chunk_center(RawChunkIndex::new(x, y)),
+                score: count as Float,
+            });
+        }
+
+        let species = environment.species();
+        let positions: HashMap<usize, Point<Float>> = environment
+            .bugs()
+            .map(|bug| (bug.id(), bug.position()))
+            .collect();
+        let growth = species
+            .iter()
+            .map(|species| {
+                let previous_size = self
+                    .species_sizes
+                    .iter()
+                    .find(|(representative, _)| {
+                        genetic_distance(representative, &species.representative)
+                            < SPECIES_DISTANCE_THRESHOLD
+                    })
+                    .map(|(_, size)| *size)
+                    .unwrap_or(0);
+                (species, species.members.len().saturating_sub(previous_size))
+            })
+            .filter(|(_, growth)| *growth > 0)
+            .max_by_key(|(_, growth)| *growth);
+        if let Some((species, growth)) = growth {
+            let members: Vec<Point<Float>> = species
+                .members
+                .iter()
+                .filter_map(|id| positions.get(id).copied())
+                .collect();
+            let n = members.len().max(1) as Float;
+            result.push(RegionInterest {
+                kind: InterestKind::GrowingSpecies,
+                center: (
+                    members.iter().map(|p| *p.x()).sum::<Float>() / n,
+                    members.iter().map(|p| *p.y()).sum::<Float>() / n,
+                )
+                    .into(),
+                score: growth as Float,
+            });
+        }
+        self.species_sizes = species
+            .into_iter()
+            .map(|species| (species.representative, species.members.len()))
+            .collect();
+
+        result.sort_by(|a, b| b.score.total_cmp(&a.score));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{env_presets, time_point::StaticTimePoint};
+
+    use super::{InterestKind, InterestScorer};
+
+    #[test]
+    fn regions_are_scored_by_kind() {
+        let mut environment = env_presets::plant_meadow(StaticTimePoint::default(), [0; 32]);
+        let mut scorer = InterestScorer::default();
+
+        let regions = scorer.score(&environment);
+        let kinds: Vec<_> = regions.iter().map(|region| region.kind).collect();
+        assert!(kinds.contains(&InterestKind::LargestCluster));
+        // every species is new at the first scoring
+        assert!(kinds.contains(&InterestKind::GrowingSpecies));
+        assert!(!kinds.contains(&InterestKind::MassDeath));
+        assert!(regions
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        // nobody lives longer than a day
+        let _ = environment.proceed(Duration::from_secs(60 * 60 * 24 * 2));
+        scorer.handle_events(&environment.drain_events(), environment.iteration());
+        let regions = scorer.score(&environment);
+        assert!(regions
+            .iter()
+            .any(|region| region.kind == InterestKind::MassDeath && region.score > 0.));
+    }
+}
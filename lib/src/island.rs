@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use chromosome::Chromosome;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{Angle, NoNeg, Point},
+    utils::Float,
+};
+
+/// How often and how many bugs move between islands of `SeededEnvironment`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Migration {
+    /// Simulated time between migrations
+    pub period: Duration,
+    /// Number of random bugs leaving each island per migration
+    pub migrants: usize,
+    /// Simulated time since the previous migration
+    #[serde(default)]
+    pub(crate) since_last: Duration,
+}
+
+impl Migration {
+    pub fn new(period: Duration, migrants: usize) -> Self {
+        Self {
+            period,
+            migrants,
+            since_last: Duration::ZERO,
+        }
+    }
+
+    /// Returns true if it is time to migrate
+    pub(crate) fn proceed(&mut self, dt: Duration) -> bool {
+        self.since_last += dt;
+        if self.since_last < self.period {
+            return false;
+        }
+        self.since_last = Duration::ZERO;
+        true
+    }
+}
+
+/// Bug on its way between islands. On arrival it gets a new id of destination island
+#[derive(Debug, Clone)]
+pub(crate) struct Migrant {
+    pub chromosome: Chromosome<Float>,
+    pub position: Point<Float>,
+    pub rotation: Angle<Float>,
+    pub energy_level: NoNeg<Float>,
+}
+
+/// Summary of a single island
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IslandStats {
+    pub population: usize,
+    pub food_count: usize,
+    pub iteration: usize,
+    /// Bugs arrived from other islands during the whole run
+    pub immigrants: usize,
+    /// Bugs left to other islands during the whole run
+    pub emigrants: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Migration;
+
+    #[test]
+    fn migration_happens_once_per_period() {
+        let mut migration = Migration::new(Duration::from_secs(10), 2);
+        let migrations = (0..100)
+            .filter(|_| migration.proceed(Duration::from_millis(500)))
+            .count();
+        assert_eq!(migrations, 5);
+    }
+}
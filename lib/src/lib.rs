@@ -1,12 +1,47 @@
 #![deny(unused_imports)]
 
+pub mod anomaly;
+pub mod biome;
 pub mod brain;
 pub mod bug;
+pub mod catastrophe;
+pub mod chromo_utils;
 pub mod chunk;
+pub mod config;
+pub mod curriculum;
+pub mod egg;
+pub mod env_config;
 pub mod env_presets;
 pub mod environment;
+pub mod events;
 pub mod food_source;
+pub mod generational;
+pub mod interest;
+pub mod island;
+pub mod light;
 pub mod math;
+pub mod nutrient;
+pub mod odor;
+pub mod parasite;
+pub mod perceptron;
+pub mod pheromone;
+pub mod phylogeny;
+pub mod plant;
+pub mod query;
 pub mod range;
+pub mod report;
+pub mod saves;
+pub mod scenario;
+pub mod season;
+pub mod seeds;
+pub mod sim_clock;
+pub mod snapshot;
+pub mod sound;
+pub mod species;
+pub mod stats;
+pub mod tag;
+pub mod terrain;
+pub mod territory;
 pub mod time_point;
 pub mod utils;
+pub mod water;
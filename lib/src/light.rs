@@ -0,0 +1,127 @@
+use std::{cell::RefCell, collections::BTreeMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    environment::CHUNK_SIZE,
+    math::{NoNeg, Point},
+    utils::Float,
+};
+
+/// Width and height of light cell (one cell per chunk). Light is constant within a cell
+pub const LIGHT_CELL_SIZE: Float = CHUNK_SIZE as Float;
+
+/// Emitter of light. Intensity falls linearly from `intensity` at center to zero at `radius`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightSource {
+    pub position: Point<Float>,
+    pub intensity: NoNeg<Float>,
+    pub radius: NoNeg<Float>,
+}
+
+impl LightSource {
+    fn intensity_at(&self, point: Point<Float>) -> Float {
+        let radius = self.radius.unwrap();
+        if radius == 0. {
+            return 0.;
+        }
+        self.intensity.unwrap() * (1. - (point - self.position).len() / radius).max(0.)
+    }
+}
+
+/// Light sources with intensity of light cached per cell. Cache is rebuilt on first query after
+/// sources change (or after load, as it is not saved)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LightField {
+    sources: Vec<LightSource>,
+    #[serde(skip)]
+    cache: RefCell<Option<BTreeMap<(isize, isize), Float>>>,
+}
+
+impl LightField {
+    fn cell_of(position: Point<Float>) -> (isize, isize) {
+        (
+            (position.x() / LIGHT_CELL_SIZE).floor() as isize,
+            (position.y() / LIGHT_CELL_SIZE).floor() as isize,
+        )
+    }
+
+    fn build_cache(&self) -> BTreeMap<(isize, isize), Float> {
+        let mut cells = BTreeMap::new();
+        for source in &self.sources {
+            let radius = source.radius.unwrap();
+            let (x0, y0) =
+                Self::cell_of((source.position.x() - radius, source.position.y() - radius).into());
+            let (x1, y1) =
+                Self::cell_of((source.position.x() + radius, source.position.y() + radius).into());
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    let center: Point<_> = (
+                        (x as Float + 0.5) * LIGHT_CELL_SIZE,
+                        (y as Float + 0.5) * LIGHT_CELL_SIZE,
+                    )
+                        .into();
+                    let intensity = source.intensity_at(center);
+                    if intensity > 0. {
+                        *cells.entry((x, y)).or_default() += intensity;
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    fn with_cache<R>(&self, f: impl FnOnce(&BTreeMap<(isize, isize), Float>) -> R) -> R {
+        let mut cache = self.cache.borrow_mut();
+        f(cache.get_or_insert_with(|| self.build_cache()))
+    }
+
+    pub fn sources(&self) -> &[LightSource] {
+        &self.sources
+    }
+
+    pub fn add_sources(&mut self, sources: Vec<LightSource>) {
+        self.sources.extend(sources);
+        *self.cache.get_mut() = None;
+    }
+
+    /// Light of the cell containing `position` (sum of all sources)
+    pub fn intensity_at(&self, position: Point<Float>) -> NoNeg<Float> {
+        let cell = Self::cell_of(position);
+        NoNeg::wrap(self.with_cache(|cells| cells.get(&cell).copied().unwrap_or(0.))).unwrap()
+    }
+
+    /// Indices (in units of `LIGHT_CELL_SIZE`) and intensities of all lit cells
+    pub fn cells(&self) -> Vec<((isize, isize), Float)> {
+        self.with_cache(|cells| cells.iter().map(|(cell, c)| (*cell, *c)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::noneg_float;
+
+    use super::{LightField, LightSource, LIGHT_CELL_SIZE};
+
+    #[test]
+    fn light_fades_with_distance_and_cache_follows_sources() {
+        let mut field = LightField::default();
+        assert_eq!(field.intensity_at((0., 0.).into()), noneg_float(0.));
+
+        field.add_sources(vec![LightSource {
+            position: (LIGHT_CELL_SIZE / 2., LIGHT_CELL_SIZE / 2.).into(),
+            intensity: noneg_float(1.),
+            radius: noneg_float(LIGHT_CELL_SIZE * 4.),
+        }]);
+        let center = field.intensity_at((1., 1.).into());
+        let near = field.intensity_at((LIGHT_CELL_SIZE * 2., 1.).into());
+        let far = field.intensity_at((LIGHT_CELL_SIZE * 10., 1.).into());
+        assert_eq!(center, noneg_float(1.));
+        assert!(near < center && near > noneg_float(0.));
+        assert_eq!(far, noneg_float(0.));
+
+        let restored: LightField =
+            serde_json::from_str(&serde_json::to_string(&field).unwrap()).unwrap();
+        assert_eq!(restored.cells(), field.cells());
+    }
+}
@@ -1,9 +1,10 @@
 use crate::range::Range;
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, Div, Mul, Sub};
 
 use super::{NoNeg, Point, Size, Sqr, Two, Vector};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Rect<T> {
     x: T,
     y: T,
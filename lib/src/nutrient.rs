@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{noneg_float, NoNeg},
+    utils::Float,
+};
+
+/// Number of nutrient kinds (protein, carbohydrates and fat)
+pub const NUTRIENT_COUNT: usize = 3;
+
+/// Shares of protein, carbohydrates and fat (each in range 0..=1, summing to 1).
+/// Describes both what food consists of and what diet a bug is adapted to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Nutrients {
+    pub protein: Float,
+    pub carbs: Float,
+    pub fat: Float,
+}
+
+impl Default for Nutrients {
+    /// Equal shares (food and bugs from before nutrients were introduced)
+    fn default() -> Self {
+        Self::BALANCED
+    }
+}
+
+impl Nutrients {
+    pub const BALANCED: Self = Self {
+        protein: 1. / NUTRIENT_COUNT as Float,
+        carbs: 1. / NUTRIENT_COUNT as Float,
+        fat: 1. / NUTRIENT_COUNT as Float,
+    };
+
+    /// Shares proportional to absolute values of arguments (balanced if all of them are zero)
+    pub fn new(protein: Float, carbs: Float, fat: Float) -> Self {
+        let (protein, carbs, fat) = (protein.abs(), carbs.abs(), fat.abs());
+        let total = protein + carbs + fat;
+        if total > 0. && total.is_finite() {
+            Self {
+                protein: protein / total,
+                carbs: carbs / total,
+                fat: fat / total,
+            }
+        } else {
+            Self::BALANCED
+        }
+    }
+
+    pub fn as_array(&self) -> [Float; NUTRIENT_COUNT] {
+        [self.protein, self.carbs, self.fat]
+    }
+
+    /// Energy which eater adapted to `diet` gets from a unit of energy of food made of these
+    /// nutrients. It is 1 if either food or diet is balanced, up to `NUTRIENT_COUNT` if they
+    /// match exactly and 0 if they do not overlap at all
+    pub fn digestibility(&self, diet: &Nutrients) -> NoNeg<Float> {
+        let overlap: Float = self
+            .as_array()
+            .iter()
+            .zip(diet.as_array())
+            .map(|(food, diet)| food * diet)
+            .sum();
+        NoNeg::wrap(overlap * NUTRIENT_COUNT as Float).unwrap_or(noneg_float(0.))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Nutrients, NUTRIENT_COUNT};
+
+    #[test]
+    fn balanced_food_or_diet_is_digested_as_before() {
+        let meat = Nutrients::new(1., 0., 0.);
+        let fruit = Nutrients::new(0.1, 0.8, 0.1);
+        for food in [Nutrients::BALANCED, meat, fruit] {
+            assert!((food.digestibility(&Nutrients::BALANCED).unwrap() - 1.).abs() < 1e-9);
+            assert!((Nutrients::BALANCED.digestibility(&food).unwrap() - 1.).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn specialists_digest_matching_food_better() {
+        let carnivore = Nutrients::new(1., 0., 0.);
+        let meat = Nutrients::new(2., 0., 0.);
+        let fruit = Nutrients::new(0., 1., 0.);
+        assert_eq!(meat, carnivore);
+        assert!((meat.digestibility(&carnivore).unwrap() - NUTRIENT_COUNT as f64).abs() < 1e-9);
+        assert_eq!(fruit.digestibility(&carnivore).unwrap(), 0.);
+        assert_eq!(Nutrients::new(0., 0., 0.), Nutrients::BALANCED);
+    }
+}
@@ -0,0 +1,150 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    environment::CHUNK_SIZE,
+    math::{noneg_float, Angle, NoNeg, Point, Vector},
+    utils::Float,
+};
+
+/// Width and height of odor cell (one cell per chunk)
+pub const ODOR_CELL_SIZE: Float = CHUNK_SIZE as Float;
+
+/// Odor emitted per second by each unit of food energy
+pub const ODOR_EMISSION_RATE: Float = 0.001;
+
+/// Fraction of odor a cell shares with its 4 neighbors per second
+pub const ODOR_DIFFUSION_RATE: Float = 0.5;
+
+/// Fraction of odor which fades per second
+pub const ODOR_DECAY_RATE: Float = 0.1;
+
+/// Cells with lower intensity are dropped
+const MIN_INTENSITY: Float = 0.0001;
+
+/// What bug smells at its position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OdorInfo {
+    pub intensity: NoNeg<Float>,
+    /// Direction in which intensity grows (None if field is flat)
+    pub gradient_direction: Option<Angle<Float>>,
+}
+
+impl Default for OdorInfo {
+    fn default() -> Self {
+        Self {
+            intensity: noneg_float(0.),
+            gradient_direction: None,
+        }
+    }
+}
+
+/// Sparse low resolution grid of food odor. Unlike pheromone it is not deposited by bugs:
+/// every food item emits odor proportionally to its energy, so the field follows food density
+/// and lets bugs find food clusters far beyond vision range
+#[derive(Debug, Clone, Default)]
+pub struct OdorField {
+    cells: BTreeMap<(isize, isize), Float>,
+}
+
+impl OdorField {
+    fn cell_of(position: Point<Float>) -> (isize, isize) {
+        (
+            (position.x() / ODOR_CELL_SIZE).floor() as isize,
+            (position.y() / ODOR_CELL_SIZE).floor() as isize,
+        )
+    }
+
+    fn intensity_of(&self, cell: (isize, isize)) -> Float {
+        self.cells.get(&cell).copied().unwrap_or(0.)
+    }
+
+    pub fn intensity_at(&self, position: Point<Float>) -> NoNeg<Float> {
+        NoNeg::wrap(self.intensity_of(Self::cell_of(position))).unwrap()
+    }
+
+    pub fn sense(&self, position: Point<Float>) -> OdorInfo {
+        let (x, y) = Self::cell_of(position);
+        let gradient: Vector<Float> = (
+            self.intensity_of((x + 1, y)) - self.intensity_of((x - 1, y)),
+            self.intensity_of((x, y + 1)) - self.intensity_of((x, y - 1)),
+        )
+            .into();
+        OdorInfo {
+            intensity: self.intensity_at(position),
+            gradient_direction: (*gradient.x() != 0. || *gradient.y() != 0.)
+                .then(|| gradient.angle()),
+        }
+    }
+
+    /// Indices (in units of `ODOR_CELL_SIZE`) and intensities of all non empty cells
+    pub fn cells(&self) -> impl Iterator<Item = ((isize, isize), Float)> + '_ {
+        self.cells.iter().map(|(cell, c)| (*cell, *c))
+    }
+
+    pub fn total(&self) -> Float {
+        self.cells.values().sum()
+    }
+
+    /// Emit odor of `food` (positions and energies), then diffuse and fade the field
+    pub(crate) fn proceed<I>(&mut self, dt: Duration, food: I)
+    where
+        I: IntoIterator<Item = (Point<Float>, NoNeg<Float>)>,
+    {
+        let dt = dt.as_secs_f64();
+        for (position, energy) in food {
+            *self.cells.entry(Self::cell_of(position)).or_default() +=
+                energy.unwrap() * ODOR_EMISSION_RATE * dt;
+        }
+        if self.cells.is_empty() {
+            return;
+        }
+        let shared = (ODOR_DIFFUSION_RATE * dt).min(1.);
+        let remaining = (-ODOR_DECAY_RATE * dt).exp();
+
+        let mut next: BTreeMap<(isize, isize), Float> = BTreeMap::new();
+        for (&(x, y), &c) in &self.cells {
+            *next.entry((x, y)).or_default() += c * (1. - shared);
+            for neighbor in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                *next.entry(neighbor).or_default() += c * shared / 4.;
+            }
+        }
+        next.retain(|_, c| {
+            *c *= remaining;
+            *c >= MIN_INTENSITY
+        });
+        self.cells = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::math::{noneg_float, Point};
+
+    use super::{OdorField, ODOR_CELL_SIZE};
+
+    #[test]
+    fn odor_spreads_from_food_beyond_its_cell_and_fades_without_it() {
+        let mut field = OdorField::default();
+        let food: Point<_> = (1., 1.).into();
+        for _ in 0..600 {
+            field.proceed(Duration::from_millis(100), [(food, noneg_float(100.))]);
+        }
+
+        // two cells away intensity is lower but still grows towards the food
+        let far: Point<_> = (1. - ODOR_CELL_SIZE * 2., 1.).into();
+        let info = field.sense(far);
+        assert!(info.intensity.unwrap() > 0.);
+        assert!(info.intensity < field.intensity_at(food));
+        let direction = info.gradient_direction.unwrap();
+        assert!(direction.cos() > 0.999, "direction: {}", direction);
+
+        for _ in 0..2000 {
+            field.proceed(Duration::from_millis(100), []);
+        }
+        assert_eq!(field.cells().count(), 0);
+    }
+}
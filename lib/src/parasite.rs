@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bug::Bug,
+    chunk::Position,
+    math::{noneg_float, NoNeg, Point},
+    utils::{self, Float},
+};
+
+/// Free parasite attaches to a bug which is closer than that
+pub const PARASITE_ATTACH_RANGE: NoNeg<Float> = noneg_float(8.);
+
+/// Radius of parasite body (used for rendering)
+pub const PARASITE_RADIUS: NoNeg<Float> = noneg_float(1.5);
+
+/// Share of host energy intake which attached parasite takes
+pub const PARASITE_SIPHON_SHARE: NoNeg<Float> = noneg_float(0.2);
+
+/// Parasite detaches and splits into brood after gathering that much energy
+pub const PARASITE_SATED_ENERGY: NoNeg<Float> = noneg_float(20.);
+
+/// Number of parasites which a sated one splits into
+pub const PARASITE_BROOD_SIZE: usize = 3;
+
+/// Energy which free parasite loses per second while looking for a host
+pub const PARASITE_STARVATION_RATE: NoNeg<Float> = noneg_float(0.05);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Host {
+    id: usize,
+    /// Energy consumed by host at the moment of the last siphoning
+    consumed_energy: NoNeg<Float>,
+}
+
+/// Small organism which rides a host bug and takes a share of everything the host eats. Free
+/// parasites slowly starve until a bug comes close enough to attach to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parasite {
+    id: usize,
+    position: Point<Float>,
+    energy: NoNeg<Float>,
+    host: Option<Host>,
+}
+
+impl Position for Parasite {
+    fn position(&self) -> Point<Float> {
+        self.position
+    }
+}
+
+impl Parasite {
+    pub(crate) fn new(next_id: &mut usize, position: Point<Float>, energy: NoNeg<Float>) -> Self {
+        *next_id += 1;
+        Self {
+            id: *next_id - 1,
+            position,
+            energy,
+            host: None,
+        }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: usize) {
+        self.id = id
+    }
+
+    pub fn position(&self) -> Point<Float> {
+        self.position
+    }
+
+    pub fn energy(&self) -> NoNeg<Float> {
+        self.energy
+    }
+
+    pub fn host_id(&self) -> Option<usize> {
+        self.host.map(|host| host.id)
+    }
+
+    pub(crate) fn set_host_id(&mut self, id: Option<usize>) {
+        match id {
+            Some(id) => {
+                if let Some(host) = &mut self.host {
+                    host.id = id
+                }
+            }
+            None => self.host = None,
+        }
+    }
+
+    pub fn sated(&self) -> bool {
+        self.energy >= PARASITE_SATED_ENERGY
+    }
+
+    pub(crate) fn attach<T>(&mut self, host: &Bug<T>) {
+        self.position = host.position();
+        self.host = Some(Host {
+            id: host.id(),
+            consumed_energy: host.consumed_energy(),
+        });
+    }
+
+    /// Follow host and take a share of what it has eaten since the previous call
+    pub(crate) fn feed_on<T>(&mut self, host: &mut Bug<T>) {
+        let Some(state) = &mut self.host else {
+            return;
+        };
+        self.position = host.position();
+        let intake = host.consumed_energy().limited_sub(state.consumed_energy);
+        state.consumed_energy = host.consumed_energy();
+        self.energy += host.siphon(intake * PARASITE_SIPHON_SHARE);
+    }
+
+    /// Lose energy while looking for host. Returns true if parasite starved to death
+    pub(crate) fn starve(&mut self, dt: NoNeg<Float>) -> bool {
+        utils::drain_energy(&mut self.energy, PARASITE_STARVATION_RATE * dt)
+    }
+
+    /// Detach from host and split into `PARASITE_BROOD_SIZE` free parasites sharing its energy
+    pub(crate) fn split(self, next_id: &mut usize) -> Vec<Parasite> {
+        let energy = self.energy / NoNeg::wrap(PARASITE_BROOD_SIZE as Float).unwrap();
+        (0..PARASITE_BROOD_SIZE)
+            .map(|_| Parasite::new(next_id, self.position, energy))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chromosome::Chromosome;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use super::{Parasite, PARASITE_BROOD_SIZE, PARASITE_SIPHON_SHARE};
+    use crate::{
+        bug::Bug,
+        chromo_utils::GENOME_LEN,
+        egg::Egg,
+        math::{noneg_float, Angle},
+        time_point::StaticTimePoint,
+    };
+
+    #[test]
+    fn parasite_takes_share_of_host_intake_and_splits() {
+        let chromosome =
+            Chromosome::new_random(GENOME_LEN, -1. ..1., &mut Pcg64::from_seed([0; 32]));
+        let mut host = Bug::give_birth_with_max_energy(
+            &mut 0,
+            chromosome.clone(),
+            (0., 0.).into(),
+            Angle::from_radians(0.),
+            StaticTimePoint::default(),
+        );
+        let mut parasite = Parasite::new(&mut 0, (3., 4.).into(), noneg_float(1.));
+        parasite.attach(&host);
+        assert_eq!(parasite.host_id(), Some(host.id()));
+
+        // nothing eaten since attaching
+        parasite.feed_on(&mut host);
+        assert_eq!(parasite.energy(), noneg_float(1.));
+
+        let mut egg = Egg::new(
+            &mut 0,
+            host.id() + 1,
+            chromosome,
+            (0., 0.).into(),
+            Angle::from_radians(0.),
+            noneg_float(10.),
+            StaticTimePoint::default(),
+        );
+        host.eat_egg(&mut egg);
+        let stomach = host.stomach_level();
+        parasite.feed_on(&mut host);
+        let siphoned = parasite.energy().limited_sub(noneg_float(1.));
+        assert!(siphoned > noneg_float(0.));
+        assert!(siphoned <= host.consumed_energy() * PARASITE_SIPHON_SHARE);
+        assert!(((host.stomach_level() + siphoned).unwrap() - stomach.unwrap()).abs() < 1e-9);
+
+        let energy = parasite.energy();
+        let brood = parasite.split(&mut 1);
+        assert_eq!(brood.len(), PARASITE_BROOD_SIZE);
+        assert!(brood.iter().all(|parasite| parasite.host_id().is_none()));
+        assert_eq!(
+            brood
+                .iter()
+                .map(|parasite| parasite.id())
+                .collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert!((brood[0].energy().unwrap() * 3. - energy.unwrap()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::utils::Float;
+
+#[derive(Debug, Clone)]
+pub struct Perceptron {
+    weights: Vec<Float>,
+    bias: Float,
+}
+
+impl Perceptron {
+    pub fn new(weights: Vec<Float>, bias: Float) -> Self {
+        Self { weights, bias }
+    }
+
+    pub fn weights(&self) -> &[Float] {
+        &self.weights
+    }
+
+    pub fn bias(&self) -> Float {
+        self.bias
+    }
+
+    fn proceed<N: Fn(Float) -> Float>(&self, input: &[Float], normalizer: N) -> Float {
+        normalizer(
+            self.weights
+                .iter()
+                .zip(input)
+                .map(|(w, x)| w * x)
+                .sum::<Float>()
+                + self.bias,
+        )
+    }
+}
+
+/// Fully connected layer whose size is known only at runtime (unlike layers composed by
+/// `simple_neural_net::compose_layers!`), so it can be shaped by genes
+#[derive(Debug, Clone)]
+pub struct PerceptronLayer {
+    input_size: usize,
+    perceptrons: Vec<Perceptron>,
+}
+
+impl PerceptronLayer {
+    pub fn new(input_size: usize, perceptrons: Vec<Perceptron>) -> Self {
+        assert!(
+            perceptrons.iter().all(|p| p.weights.len() == input_size),
+            "every perceptron must have {} weights",
+            input_size
+        );
+        Self {
+            input_size,
+            perceptrons,
+        }
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.input_size
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.perceptrons.len()
+    }
+
+    pub fn perceptrons(&self) -> &[Perceptron] {
+        &self.perceptrons
+    }
+
+    pub fn proceed<N: Fn(Float) -> Float>(&self, input: &[Float], normalizer: N) -> Vec<Float> {
+        assert_eq!(input.len(), self.input_size);
+        self.perceptrons
+            .iter()
+            .map(|p| p.proceed(input, &normalizer))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Perceptron, PerceptronLayer};
+
+    #[test]
+    fn layer_computes_weighted_sum_plus_bias() {
+        let layer = PerceptronLayer::new(
+            2,
+            vec![
+                Perceptron::new(vec![1., 2.], 0.5),
+                Perceptron::new(vec![-1., 0.], 0.),
+            ],
+        );
+        assert_eq!(layer.proceed(&[3., 4.], |x| x), vec![11.5, -3.]);
+        assert_eq!(layer.proceed(&[3., 4.], |x| x * 2.), vec![23., -6.]);
+    }
+}
@@ -0,0 +1,144 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    environment::CHUNK_SIZE,
+    math::{noneg_float, Angle, NoNeg, Point, Vector},
+    utils::Float,
+};
+
+/// Width and height of pheromone cell (each chunk is split into 8x8 cells)
+pub const PHEROMONE_CELL_SIZE: Float = CHUNK_SIZE as Float / 8.;
+
+/// Fraction of concentration a cell shares with its 4 neighbors per second
+pub const PHEROMONE_DIFFUSION_RATE: Float = 0.5;
+
+/// Fraction of pheromone which evaporates per second
+pub const PHEROMONE_EVAPORATION_RATE: Float = 0.2;
+
+/// Cells with lower concentration are dropped
+const MIN_CONCENTRATION: Float = 0.001;
+
+/// What bug senses of pheromone field at its position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PheromoneInfo {
+    pub concentration: NoNeg<Float>,
+    /// Direction in which concentration grows (None if field is flat)
+    pub gradient_direction: Option<Angle<Float>>,
+}
+
+impl Default for PheromoneInfo {
+    fn default() -> Self {
+        Self {
+            concentration: noneg_float(0.),
+            gradient_direction: None,
+        }
+    }
+}
+
+/// Sparse grid of pheromone concentrations. Pheromone diffuses to neighbor cells and evaporates.
+/// Cells are ordered so that diffusion sums up concentrations in the same order on every run
+#[derive(Debug, Clone, Default)]
+pub struct PheromoneField {
+    cells: BTreeMap<(isize, isize), Float>,
+}
+
+impl PheromoneField {
+    fn cell_of(position: Point<Float>) -> (isize, isize) {
+        (
+            (position.x() / PHEROMONE_CELL_SIZE).floor() as isize,
+            (position.y() / PHEROMONE_CELL_SIZE).floor() as isize,
+        )
+    }
+
+    fn concentration_of(&self, cell: (isize, isize)) -> Float {
+        self.cells.get(&cell).copied().unwrap_or(0.)
+    }
+
+    pub fn deposit(&mut self, position: Point<Float>, amount: NoNeg<Float>) {
+        *self.cells.entry(Self::cell_of(position)).or_default() += amount.unwrap();
+    }
+
+    pub fn concentration_at(&self, position: Point<Float>) -> NoNeg<Float> {
+        NoNeg::wrap(self.concentration_of(Self::cell_of(position))).unwrap()
+    }
+
+    pub fn sense(&self, position: Point<Float>) -> PheromoneInfo {
+        let (x, y) = Self::cell_of(position);
+        let gradient: Vector<Float> = (
+            self.concentration_of((x + 1, y)) - self.concentration_of((x - 1, y)),
+            self.concentration_of((x, y + 1)) - self.concentration_of((x, y - 1)),
+        )
+            .into();
+        PheromoneInfo {
+            concentration: self.concentration_at(position),
+            gradient_direction: (*gradient.x() != 0. || *gradient.y() != 0.)
+                .then(|| gradient.angle()),
+        }
+    }
+
+    /// Indices (in units of `PHEROMONE_CELL_SIZE`) and concentrations of all non empty cells
+    pub fn cells(&self) -> impl Iterator<Item = ((isize, isize), Float)> + '_ {
+        self.cells.iter().map(|(cell, c)| (*cell, *c))
+    }
+
+    pub fn total(&self) -> Float {
+        self.cells.values().sum()
+    }
+
+    pub(crate) fn proceed(&mut self, dt: Duration) {
+        if self.cells.is_empty() {
+            return;
+        }
+        let dt = dt.as_secs_f64();
+        let shared = (PHEROMONE_DIFFUSION_RATE * dt).min(1.);
+        let remaining = (-PHEROMONE_EVAPORATION_RATE * dt).exp();
+
+        let mut next: BTreeMap<(isize, isize), Float> = BTreeMap::new();
+        for (&(x, y), &c) in &self.cells {
+            *next.entry((x, y)).or_default() += c * (1. - shared);
+            for neighbor in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                *next.entry(neighbor).or_default() += c * shared / 4.;
+            }
+        }
+        next.retain(|_, c| {
+            *c *= remaining;
+            *c >= MIN_CONCENTRATION
+        });
+        self.cells = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::math::{noneg_float, Point};
+
+    use super::{PheromoneField, PHEROMONE_CELL_SIZE};
+
+    #[test]
+    fn pheromone_diffuses_and_evaporates() {
+        let mut field = PheromoneField::default();
+        let origin: Point<_> = (1., 1.).into();
+        field.deposit(origin, noneg_float(10.));
+
+        field.proceed(Duration::from_millis(100));
+        let total = field.total();
+        assert!(total < 10. && total > 9.5, "total: {}", total);
+        assert!(field.concentration_at(origin).unwrap() < 10.);
+
+        // gradient of a neighbor points back to the source
+        let neighbor: Point<_> = (1. + PHEROMONE_CELL_SIZE, 1.).into();
+        let info = field.sense(neighbor);
+        assert!(info.concentration.unwrap() > 0.);
+        let direction = info.gradient_direction.unwrap().degrees();
+        assert!((direction - 180.).abs() < 1e-9, "direction: {}", direction);
+
+        for _ in 0..1000 {
+            field.proceed(Duration::from_millis(100));
+        }
+        assert_eq!(field.cells().count(), 0);
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet};
+
+use chromosome::Chromosome;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::Float;
+
+/// Max number of individuals kept in hall of fame
+pub const HALL_OF_FAME_SIZE: usize = 16;
+
+/// Max number of mutation events stored per bug. The rest is dropped
+pub const MAX_MUTATIONS_PER_BUG: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MutationCause {
+    Reproduction,
+    Irradiation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MutationEvent {
+    pub gene_index: usize,
+    pub old_value: Float,
+    pub new_value: Float,
+    pub cause: MutationCause,
+    /// Environment iteration when mutation happened
+    pub iteration: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Node {
+    parent_id: Option<usize>,
+    chromosome: Chromosome<Float>,
+    offspring_count: usize,
+    alive: bool,
+    /// Number of stored nodes which have this one as a parent
+    children_count: usize,
+    #[serde(default)]
+    mutations: Vec<MutationEvent>,
+    /// Environment iteration when individual was born
+    #[serde(default)]
+    birth_iteration: usize,
+    /// Environment iteration when individual died (None if it is alive)
+    #[serde(default)]
+    death_iteration: Option<usize>,
+}
+
+impl Node {
+    /// Store differences between current and new genome and replace the current one
+    fn apply_mutations(
+        &mut self,
+        chromosome: &Chromosome<Float>,
+        cause: MutationCause,
+        iteration: usize,
+    ) {
+        for (gene_index, (old_value, new_value)) in self
+            .chromosome
+            .genes
+            .iter()
+            .zip(chromosome.genes.iter())
+            .enumerate()
+        {
+            if old_value != new_value && self.mutations.len() < MAX_MUTATIONS_PER_BUG {
+                self.mutations.push(MutationEvent {
+                    gene_index,
+                    old_value: *old_value,
+                    new_value: *new_value,
+                    cause,
+                    iteration,
+                });
+            }
+        }
+        self.chromosome = chromosome.clone();
+    }
+}
+
+/// One step of line of descent
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LineageEntry {
+    pub bug_id: usize,
+    pub generation: usize,
+    pub offspring_count: usize,
+    pub chromosome: Chromosome<Float>,
+    /// Mutations which distinguish this genome from the previous entry
+    pub mutations: Vec<MutationEvent>,
+}
+
+/// Family tree of bugs. Only ancestors of living bugs and of hall of fame members are kept,
+/// all other branches are pruned as soon as they die out
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct Phylogeny {
+    nodes: HashMap<usize, Node>,
+    /// Ids of individuals with the largest number of offspring (sorted, best first)
+    hall_of_fame: Vec<usize>,
+}
+
+impl Phylogeny {
+    pub(crate) fn record_birth(
+        &mut self,
+        id: usize,
+        parent_id: Option<usize>,
+        chromosome: &Chromosome<Float>,
+        iteration: usize,
+    ) {
+        let parent_id = parent_id.filter(|parent_id| self.nodes.contains_key(parent_id));
+        let mut node = Node {
+            parent_id,
+            chromosome: chromosome.clone(),
+            offspring_count: 0,
+            alive: true,
+            children_count: 0,
+            mutations: Default::default(),
+            birth_iteration: iteration,
+            death_iteration: None,
+        };
+        if let Some(parent_id) = parent_id {
+            let parent = self.nodes.get_mut(&parent_id).unwrap();
+            parent.children_count += 1;
+            parent.offspring_count += 1;
+            node.chromosome = parent.chromosome.clone();
+            node.apply_mutations(chromosome, MutationCause::Reproduction, iteration);
+            self.update_hall_of_fame(parent_id);
+        }
+        self.nodes.insert(id, node);
+    }
+
+    /// Record mutations of genome of living bug
+    pub(crate) fn record_mutations(
+        &mut self,
+        id: usize,
+        chromosome: &Chromosome<Float>,
+        cause: MutationCause,
+        iteration: usize,
+    ) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.apply_mutations(chromosome, cause, iteration);
+        }
+    }
+
+    pub(crate) fn record_death(&mut self, id: usize, iteration: usize) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.alive = false;
+            node.death_iteration = Some(iteration);
+            self.prune(id);
+        }
+    }
+
+    pub fn hall_of_fame(&self) -> &[usize] {
+        &self.hall_of_fame
+    }
+
+    pub fn chromosome(&self, id: usize) -> Option<&Chromosome<Float>> {
+        self.nodes.get(&id).map(|node| &node.chromosome)
+    }
+
+    pub fn offspring_count(&self, id: usize) -> Option<usize> {
+        self.nodes.get(&id).map(|node| node.offspring_count)
+    }
+
+    /// None if individual is unknown or its parent is
+    pub fn parent_id(&self, id: usize) -> Option<usize> {
+        self.nodes.get(&id)?.parent_id
+    }
+
+    pub fn birth_iteration(&self, id: usize) -> Option<usize> {
+        self.nodes.get(&id).map(|node| node.birth_iteration)
+    }
+
+    /// None if individual is unknown or still alive
+    pub fn death_iteration(&self, id: usize) -> Option<usize> {
+        self.nodes.get(&id)?.death_iteration
+    }
+
+    /// Ids of known ancestors of individual with `id` starting from its parent
+    pub fn ancestors(&self, id: usize) -> Vec<usize> {
+        let mut result: Vec<usize> = Default::default();
+        let mut next = self.parent_id(id);
+        while let Some(id) = next {
+            result.push(id);
+            next = self.parent_id(id);
+        }
+        result
+    }
+
+    /// Ids of known descendants of individual with `id` (children of pruned branches are lost),
+    /// closer generations first
+    pub fn descendants(&self, id: usize) -> Vec<usize> {
+        let mut children: HashMap<usize, Vec<usize>> = Default::default();
+        for (child_id, node) in &self.nodes {
+            if let Some(parent_id) = node.parent_id {
+                children.entry(parent_id).or_default().push(*child_id);
+            }
+        }
+        let mut result: Vec<usize> = Default::default();
+        let mut generation = vec![id];
+        while !generation.is_empty() {
+            let mut next: Vec<usize> = generation
+                .iter()
+                .filter_map(|id| children.get(id))
+                .flatten()
+                .copied()
+                .collect();
+            next.sort();
+            result.extend(&next);
+            generation = next;
+        }
+        result
+    }
+
+    /// Youngest common ancestor of both individuals. Individual counts as its own ancestor, so
+    /// for parent and child it is the parent
+    pub fn most_recent_common_ancestor(&self, a: usize, b: usize) -> Option<usize> {
+        if !self.nodes.contains_key(&a) || !self.nodes.contains_key(&b) {
+            return None;
+        }
+        let line_of_a: HashSet<usize> = std::iter::once(a).chain(self.ancestors(a)).collect();
+        std::iter::once(b)
+            .chain(self.ancestors(b))
+            .find(|id| line_of_a.contains(id))
+    }
+
+    /// Chain of genomes from the oldest known ancestor to the individual with `id` (inclusive)
+    pub fn line_of_descent(&self, id: usize) -> Vec<LineageEntry> {
+        let mut result: Vec<LineageEntry> = Default::default();
+        let mut next = Some(id);
+        while let Some((id, node)) = next.and_then(|id| self.nodes.get(&id).map(|n| (id, n))) {
+            result.push(LineageEntry {
+                bug_id: id,
+                generation: 0,
+                offspring_count: node.offspring_count,
+                chromosome: node.chromosome.clone(),
+                mutations: node.mutations.clone(),
+            });
+            next = node.parent_id;
+        }
+        result.reverse();
+        for (generation, entry) in result.iter_mut().enumerate() {
+            entry.generation = generation;
+        }
+        result
+    }
+
+    /// Line of descent of the most successful individual of the hall of fame
+    pub fn champion_line_of_descent(&self) -> Vec<LineageEntry> {
+        self.hall_of_fame
+            .first()
+            .map(|id| self.line_of_descent(*id))
+            .unwrap_or_default()
+    }
+
+    /// Ids of all stored individuals (living ones and ancestors which are still kept)
+    pub(crate) fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.nodes.keys().copied()
+    }
+
+    /// Replace every id (including parent references) with `new_id(id)`
+    pub(crate) fn renumber<F: Fn(usize) -> usize>(&mut self, new_id: F) {
+        self.nodes = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(|(id, mut node)| {
+                node.parent_id = node.parent_id.map(&new_id);
+                (new_id(id), node)
+            })
+            .collect();
+        for id in &mut self.hall_of_fame {
+            *id = new_id(*id);
+        }
+    }
+
+    /// Add individuals of another phylogeny (whose ids must not collide with ones of this
+    /// phylogeny) shifting their iterations with `iteration`. Hall of fame keeps the best of both
+    pub(crate) fn merge<F: Fn(usize) -> usize>(&mut self, other: Phylogeny, iteration: F) {
+        self.nodes
+            .extend(other.nodes.into_iter().map(|(id, mut node)| {
+                node.birth_iteration = iteration(node.birth_iteration);
+                node.death_iteration = node.death_iteration.map(&iteration);
+                for mutation in &mut node.mutations {
+                    mutation.iteration = iteration(mutation.iteration);
+                }
+                (id, node)
+            }));
+        self.hall_of_fame.extend(other.hall_of_fame);
+        let nodes = &self.nodes;
+        self.hall_of_fame
+            .sort_by(|a, b| nodes[b].offspring_count.cmp(&nodes[a].offspring_count));
+        let dropped = self
+            .hall_of_fame
+            .split_off(self.hall_of_fame.len().min(HALL_OF_FAME_SIZE));
+        for id in dropped {
+            self.prune(id);
+        }
+    }
+
+    fn is_pinned(&self, id: usize) -> bool {
+        self.hall_of_fame.contains(&id)
+    }
+
+    fn update_hall_of_fame(&mut self, id: usize) {
+        let offspring_count = self.nodes[&id].offspring_count;
+        if !self.is_pinned(id) {
+            if self.hall_of_fame.len() >= HALL_OF_FAME_SIZE {
+                let worst = *self.hall_of_fame.last().unwrap();
+                if self.nodes[&worst].offspring_count >= offspring_count {
+                    return;
+                }
+                self.hall_of_fame.pop();
+                self.prune(worst);
+            }
+            self.hall_of_fame.push(id);
+        }
+        let nodes = &self.nodes;
+        self.hall_of_fame
+            .sort_by(|a, b| nodes[b].offspring_count.cmp(&nodes[a].offspring_count));
+    }
+
+    /// Remove node and all its ancestors which are not needed anymore
+    fn prune(&mut self, id: usize) {
+        let mut next = Some(id);
+        while let Some(id) = next {
+            let removable = self
+                .nodes
+                .get(&id)
+                .map(|node| !node.alive && node.children_count == 0)
+                .unwrap_or(false);
+
+            if !removable || self.is_pinned(id) {
+                break;
+            }
+
+            next = self.nodes.remove(&id).unwrap().parent_id;
+            if let Some(parent_id) = next {
+                self.nodes.get_mut(&parent_id).unwrap().children_count -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chromosome::Chromosome;
+
+    use super::{MutationCause, Phylogeny};
+
+    fn chromosome(gene: f64) -> Chromosome<f64> {
+        Chromosome { genes: vec![gene] }
+    }
+
+    #[test]
+    fn extinct_branches_are_pruned() {
+        let mut phylogeny = Phylogeny::default();
+        phylogeny.record_birth(0, None, &chromosome(0.), 0);
+        phylogeny.record_birth(1, Some(0), &chromosome(1.), 0);
+        phylogeny.record_birth(2, Some(1), &chromosome(2.), 0);
+        phylogeny.record_birth(3, Some(0), &chromosome(3.), 0);
+
+        phylogeny.record_death(0, 0);
+        phylogeny.record_death(1, 0);
+        assert_eq!(
+            phylogeny
+                .line_of_descent(2)
+                .iter()
+                .map(|x| x.bug_id)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+
+        phylogeny.record_death(3, 0);
+        // 0 is still in hall of fame, because it had 2 children
+        assert_eq!(phylogeny.champion_line_of_descent().len(), 1);
+        assert_eq!(phylogeny.offspring_count(3), None);
+        assert_eq!(phylogeny.offspring_count(0), Some(2));
+    }
+
+    #[test]
+    fn mutations_are_recorded_with_cause() {
+        let mut phylogeny = Phylogeny::default();
+        phylogeny.record_birth(0, None, &chromosome(0.), 0);
+        phylogeny.record_mutations(0, &chromosome(1.), MutationCause::Irradiation, 1);
+        phylogeny.record_birth(1, Some(0), &chromosome(2.), 2);
+
+        let lineage = phylogeny.line_of_descent(1);
+        assert_eq!(lineage[0].mutations.len(), 1);
+        assert_eq!(lineage[0].mutations[0].cause, MutationCause::Irradiation);
+        assert_eq!(lineage[1].mutations.len(), 1);
+        assert_eq!(lineage[1].mutations[0].cause, MutationCause::Reproduction);
+        assert_eq!(lineage[1].mutations[0].old_value, 1.);
+        assert_eq!(lineage[1].mutations[0].new_value, 2.);
+        assert_eq!(lineage[1].mutations[0].iteration, 2);
+    }
+
+    #[test]
+    fn lineage_queries() {
+        let mut phylogeny = Phylogeny::default();
+        phylogeny.record_birth(0, None, &chromosome(0.), 0);
+        phylogeny.record_birth(1, Some(0), &chromosome(1.), 10);
+        phylogeny.record_birth(2, Some(0), &chromosome(2.), 20);
+        phylogeny.record_birth(3, Some(1), &chromosome(3.), 30);
+        phylogeny.record_birth(4, None, &chromosome(4.), 40);
+        phylogeny.record_death(1, 50);
+
+        assert_eq!(phylogeny.birth_iteration(3), Some(30));
+        assert_eq!(phylogeny.death_iteration(1), Some(50));
+        assert_eq!(phylogeny.death_iteration(3), None);
+        assert_eq!(phylogeny.ancestors(3), vec![1, 0]);
+        assert_eq!(phylogeny.descendants(0), vec![1, 2, 3]);
+        assert_eq!(phylogeny.most_recent_common_ancestor(3, 2), Some(0));
+        assert_eq!(phylogeny.most_recent_common_ancestor(3, 1), Some(1));
+        assert_eq!(phylogeny.most_recent_common_ancestor(3, 4), None);
+    }
+}
@@ -0,0 +1,399 @@
+use std::{f64::consts::PI, time::Duration};
+
+use chromosome::Chromosome;
+use rand::{distributions::uniform::SampleRange, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chunk::Position,
+    environment::{EnvironmentRequest, FoodCreateInfo},
+    math::{noneg_float, AbsAsNoNeg as _, Angle, Complex, NoNeg, Point},
+    nutrient::Nutrients,
+    time_point::TimePoint,
+    utils::{self, Float},
+};
+
+pub const PLANT_GENOME_LEN: usize = 3;
+
+/// Energy per second which chunk (256x256) provides. Shared equally between all plants in that chunk
+pub const PLANT_CHUNK_FERTILITY: NoNeg<Float> = noneg_float(1.);
+
+/// Energy plant accumulates before dropping a fruit or a seed
+const PLANT_FRUIT_ENERGY: NoNeg<Float> = noneg_float(4.);
+
+/// Fruits are mostly carbohydrates
+const PLANT_FRUIT_NUTRIENTS: Nutrients = Nutrients {
+    protein: 0.1,
+    carbs: 0.8,
+    fat: 0.1,
+};
+
+/// Probability that accumulated energy is spent on a seed instead of a fruit
+const PLANT_SEED_PROBABILITY: Float = 0.1;
+
+/// Energy per second spent to sustain one unit of growth rate or toxin
+const PLANT_UPKEEP: NoNeg<Float> = noneg_float(0.01);
+
+const PLANT_MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24);
+
+const SEED_DISPERSAL_RADIUS_MUL: NoNeg<Float> = noneg_float(100.);
+
+/// Fruits fall within this radius around the plant
+pub(crate) const PLANT_FRUIT_SCATTER_RADIUS: NoNeg<Float> = noneg_float(16.);
+
+/// Share of energy taken by toxin of a fruit from its eater which returns to the plant (stands for
+/// fruits and seeds spat out near it by poisoned eaters)
+const PLANT_TOXIN_PAYBACK: NoNeg<Float> = noneg_float(0.5);
+
+/// Stationary producer agent. Much cheaper than a bug: no brain and a genome of 3 genes:
+/// 0 - growth rate, 1 - seed dispersal radius, 2 - toxin
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Plant<T> {
+    id: usize,
+    chromosome: Chromosome<Float>,
+    position: Point<Float>,
+    energy_level: NoNeg<Float>,
+    birth_instant: T,
+    /// Time elapsed since the previous update of plant in a sleeping chunk (see
+    /// `ChunkSleepConfig`)
+    #[serde(default, skip_serializing_if = "Duration::is_zero")]
+    skipped_time: Duration,
+}
+
+impl<T> Position for Plant<T> {
+    fn position(&self) -> Point<Float> {
+        self.position
+    }
+}
+
+impl<T> Position for &Plant<T> {
+    fn position(&self) -> Point<Float> {
+        self.position
+    }
+}
+
+impl<T> Plant<T> {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: usize) {
+        self.id = id
+    }
+
+    pub(crate) fn skipped_time_mut(&mut self) -> &mut Duration {
+        &mut self.skipped_time
+    }
+
+    /// Replace time points with ones of another clock (see `Environment::merge`)
+    pub(crate) fn rebase_time<F: Fn(&T) -> T>(&mut self, rebase: F) {
+        self.birth_instant = rebase(&self.birth_instant);
+    }
+
+    pub fn chromosome(&self) -> &Chromosome<Float> {
+        &self.chromosome
+    }
+
+    pub fn position(&self) -> Point<Float> {
+        self.position
+    }
+
+    pub fn energy_level(&self) -> NoNeg<Float> {
+        self.energy_level
+    }
+
+    pub fn growth_rate(&self) -> NoNeg<Float> {
+        self.chromosome.genes[0].abs_as_noneg()
+    }
+
+    pub fn seed_dispersal_radius(&self) -> NoNeg<Float> {
+        self.chromosome.genes[1].abs_as_noneg() * SEED_DISPERSAL_RADIUS_MUL
+    }
+
+    /// Share of fruit energy which is harmful for the eater. In range 0..=1. Part of the harm
+    /// returns to the plant (see `toxin_harmed_eater`)
+    pub fn toxin(&self) -> NoNeg<Float> {
+        self.chromosome.genes[2].abs().min(1.).abs_as_noneg()
+    }
+
+    /// Called when toxin of a fruit of this plant took `harm` energy from its eater
+    pub(crate) fn toxin_harmed_eater(&mut self, harm: NoNeg<Float>) {
+        self.energy_level += harm * PLANT_TOXIN_PAYBACK;
+    }
+
+    pub(crate) fn new(
+        next_id: &mut usize,
+        chromosome: Chromosome<Float>,
+        position: Point<Float>,
+        energy_level: NoNeg<Float>,
+        now: T,
+    ) -> Self {
+        assert_eq!(chromosome.genes.len(), PLANT_GENOME_LEN);
+        *next_id += 1;
+        Self {
+            id: *next_id - 1,
+            chromosome,
+            position,
+            energy_level,
+            birth_instant: now,
+            skipped_time: Duration::ZERO,
+        }
+    }
+
+    /// `fertility` - energy per second available to this plant
+    pub(crate) fn proceed<R: RngCore>(
+        &mut self,
+        now: &T,
+        fertility: NoNeg<Float>,
+        dt: Duration,
+        rng: &mut R,
+    ) -> Vec<EnvironmentRequest>
+    where
+        T: TimePoint,
+    {
+        let mut requests: Vec<EnvironmentRequest> = Default::default();
+
+        if now.duration_since(&self.birth_instant) > PLANT_MAX_AGE {
+            requests.push(EnvironmentRequest::Suicide);
+            return requests;
+        }
+
+        let dt = NoNeg::wrap(dt.as_secs_f64()).unwrap();
+        let growth_rate = self.growth_rate();
+        let toxin = self.toxin();
+
+        // growth saturates, so fast growers waste fertility
+        self.energy_level += fertility * growth_rate / (growth_rate + noneg_float(1.)) * dt;
+
+        if utils::drain_energy(
+            &mut self.energy_level,
+            (growth_rate + toxin) * PLANT_UPKEEP * dt,
+        ) {
+            requests.push(EnvironmentRequest::Suicide);
+            return requests;
+        }
+
+        while self.energy_level >= PLANT_FRUIT_ENERGY {
+            self.energy_level = NoNeg::wrap(self.energy_level - PLANT_FRUIT_ENERGY).unwrap();
+            if rng.gen_bool(PLANT_SEED_PROBABILITY) {
+                requests.push(EnvironmentRequest::PlantSeed {
+                    chromosome: self.chromosome.clone().mutated(|_, _| 0.01..0.8, 0.1, rng),
+                    position: self.random_point_around(self.seed_dispersal_radius(), rng),
+                    energy_level: PLANT_FRUIT_ENERGY,
+                });
+            } else {
+                requests.push(EnvironmentRequest::PlaceFood(FoodCreateInfo {
+                    position: self.random_point_around(PLANT_FRUIT_SCATTER_RADIUS, rng),
+                    energy: PLANT_FRUIT_ENERGY,
+                    toxicity: toxin,
+                    nutrients: PLANT_FRUIT_NUTRIENTS,
+                }));
+            }
+        }
+
+        requests
+    }
+
+    fn random_point_around<R: RngCore>(&self, radius: NoNeg<Float>, rng: &mut R) -> Point<Float> {
+        let offset: Point<Float> = Complex::from_polar(
+            rng.gen_range(0. ..=radius.unwrap()),
+            Angle::from_radians(rng.gen_range(0. ..(PI * 2.))),
+        )
+        .into_cartesian();
+        (
+            *self.position.x() + *offset.x(),
+            *self.position.y() + *offset.y(),
+        )
+            .into()
+    }
+}
+
+pub struct PlantCreateInfo {
+    pub chromosome: Chromosome<Float>,
+    pub position: Point<Float>,
+}
+
+impl PlantCreateInfo {
+    pub(crate) fn generate<R: RngCore, RR: SampleRange<Float> + Clone>(
+        rng: &mut R,
+        g_range: RR,
+        x_range: RR,
+        y_range: RR,
+    ) -> Self {
+        Self {
+            chromosome: Chromosome::new_random(PLANT_GENOME_LEN, g_range, rng),
+            position: (rng.gen_range(x_range), rng.gen_range(y_range)).into(),
+        }
+    }
+
+    pub fn generate_vec<R: RngCore, RR: SampleRange<Float> + Clone>(
+        rng: &mut R,
+        g_range: RR,
+        x_range: RR,
+        y_range: RR,
+        count: usize,
+    ) -> Vec<Self> {
+        (0..count)
+            .map(|_| Self::generate(rng, g_range.clone(), x_range.clone(), y_range.clone()))
+            .collect()
+    }
+
+    pub(crate) fn create<T>(self, next_id: &mut usize, now: T) -> Plant<T> {
+        Plant::new(
+            next_id,
+            self.chromosome,
+            self.position,
+            PLANT_FRUIT_ENERGY,
+            now,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chromosome::Chromosome;
+    use rand::SeedableRng as _;
+    use rand_pcg::Pcg64;
+
+    use super::{
+        Plant, PlantCreateInfo, PLANT_FRUIT_ENERGY, PLANT_FRUIT_SCATTER_RADIUS, PLANT_UPKEEP,
+    };
+    use crate::{
+        environment::{Environment, EnvironmentRequest},
+        math::{noneg_float, NoNeg},
+        time_point::StaticTimePoint,
+        utils::Float,
+    };
+
+    /// Plant at origin with genes: growth rate, seed dispersal radius, toxin
+    fn plant(genes: [Float; 3], energy_level: NoNeg<Float>) -> Plant<StaticTimePoint> {
+        Plant::new(
+            &mut 0,
+            Chromosome {
+                genes: genes.to_vec(),
+            },
+            (0., 0.).into(),
+            energy_level,
+            StaticTimePoint::default(),
+        )
+    }
+
+    fn proceed(plant: &mut Plant<StaticTimePoint>, fertility: Float) -> Vec<EnvironmentRequest> {
+        plant.proceed(
+            &StaticTimePoint::default(),
+            NoNeg::wrap(fertility).unwrap(),
+            Duration::from_secs(1),
+            &mut Pcg64::from_seed([0; 32]),
+        )
+    }
+
+    #[test]
+    fn growth_saturates_and_pays_upkeep() {
+        let mut slow = plant([1., 0., 0.], noneg_float(0.));
+        let mut fast = plant([3., 0., 0.], noneg_float(0.));
+        assert!(proceed(&mut slow, 1.).is_empty());
+        assert!(proceed(&mut fast, 1.).is_empty());
+
+        let upkeep = PLANT_UPKEEP.unwrap();
+        assert!((slow.energy_level().unwrap() - (0.5 - upkeep)).abs() < 1e-9);
+        assert!((fast.energy_level().unwrap() - (0.75 - 3. * upkeep)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn toxin_drains_energy_and_starves_plant() {
+        let mut harmless = plant([1., 0., 0.], noneg_float(1.));
+        let mut toxic = plant([1., 0., 1.], noneg_float(1.));
+        proceed(&mut harmless, 1.);
+        proceed(&mut toxic, 1.);
+        assert!(
+            (harmless.energy_level().unwrap()
+                - toxic.energy_level().unwrap()
+                - PLANT_UPKEEP.unwrap())
+            .abs()
+                < 1e-9
+        );
+
+        // nothing grows without fertility, so upkeep of toxin kills the plant
+        let mut starving = plant([1., 0., 1.], noneg_float(0.));
+        assert!(matches!(
+            proceed(&mut starving, 0.)[..],
+            [EnvironmentRequest::Suicide]
+        ));
+    }
+
+    #[test]
+    fn accumulated_energy_is_spent_on_fruits_and_seeds() {
+        let drops = 1000;
+        // a bit more than needed for upkeep of toxin
+        let mut plant = plant(
+            [0., 0.5, 0.25],
+            PLANT_FRUIT_ENERGY * NoNeg::wrap(drops as Float).unwrap() + noneg_float(0.01),
+        );
+        let radius = plant.seed_dispersal_radius().unwrap();
+        let requests = proceed(&mut plant, 0.);
+        assert_eq!(requests.len(), drops);
+
+        let mut seeds = 0;
+        let mut fruit_positions = vec![];
+        for request in requests {
+            match request {
+                EnvironmentRequest::PlaceFood(food) => {
+                    assert!(
+                        (food.position - plant.position()).len()
+                            <= PLANT_FRUIT_SCATTER_RADIUS.unwrap() + 1e-9
+                    );
+                    fruit_positions.push(food.position);
+                    assert_eq!(food.energy, PLANT_FRUIT_ENERGY);
+                    assert_eq!(food.toxicity, noneg_float(0.25));
+                }
+                EnvironmentRequest::PlantSeed {
+                    position,
+                    energy_level,
+                    ..
+                } => {
+                    seeds += 1;
+                    assert!((position - plant.position()).len() <= radius + 1e-9);
+                    assert_eq!(energy_level, PLANT_FRUIT_ENERGY);
+                }
+                _ => panic!("unexpected request"),
+            }
+        }
+        // PLANT_SEED_PROBABILITY of drops are seeds
+        assert!((50..150).contains(&seeds), "seeds: {}", seeds);
+        // fruits are scattered instead of being stacked
+        assert!(fruit_positions
+            .iter()
+            .any(|position| *position != fruit_positions[0]));
+    }
+
+    #[test]
+    fn chunk_fertility_is_shared_between_its_plants() {
+        let plant = |x, y| PlantCreateInfo {
+            chromosome: Chromosome {
+                genes: vec![1., 0., 0.],
+            },
+            position: (x, y).into(),
+        };
+        let mut environment = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        environment.add_plants(vec![plant(10., 10.), plant(20., 20.), plant(1000., 1000.)]);
+        environment.proceed(Duration::from_secs(1), &mut Pcg64::from_seed([0; 32]));
+
+        // each plant drops its initial fruit, so only energy gained during the second is left.
+        // Seeds of dropped fruits (if any) are ignored
+        let upkeep = PLANT_UPKEEP.unwrap();
+        for plant in environment.plants().filter(|plant| plant.id() < 3) {
+            let expected = if *plant.position().x() < 500. {
+                0.25 - upkeep
+            } else {
+                0.5 - upkeep
+            };
+            assert!(
+                (plant.energy_level().unwrap() - expected).abs() < 1e-9,
+                "{:?}",
+                plant.position()
+            );
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+use crate::{
+    bug::Bug,
+    chunk::{ChunkedVec, Position},
+    environment::{Environment, Food, CHUNK_SIZE},
+    math::{noneg_float, NoNeg, Point, Rect},
+    plant::Plant,
+    utils::Float,
+};
+
+/// Region to which query is restricted
+#[derive(Debug, Clone)]
+pub enum QueryArea {
+    Circle(Point<Float>, NoNeg<Float>),
+    Rect(Rect<Float>),
+}
+
+impl QueryArea {
+    /// Circle which contains the whole area (used to select chunks to traverse)
+    fn bounding_circle(&self) -> (Point<Float>, NoNeg<Float>) {
+        match self {
+            QueryArea::Circle(center, radius) => (*center, *radius),
+            QueryArea::Rect(rect) => {
+                let (w, h) = (*rect.w(), *rect.h());
+                let center: Point<Float> = (rect.x() + w / 2., rect.y() + h / 2.).into();
+                let radius = (w * w + h * h).sqrt() / 2.;
+                (center, NoNeg::wrap(radius).unwrap())
+            }
+        }
+    }
+
+    pub fn contains(&self, point: Point<Float>) -> bool {
+        match self {
+            QueryArea::Circle(center, radius) => (*center - point).len() < radius.unwrap(),
+            QueryArea::Rect(rect) => {
+                *point.x() >= rect.left()
+                    && *point.x() <= rect.right()
+                    && *point.y() >= rect.top()
+                    && *point.y() <= rect.bottom()
+            }
+        }
+    }
+}
+
+type Predicate<'a, E> = Box<dyn Fn(&E) -> bool + 'a>;
+
+/// Entry point of entity queries. See `Environment::query`
+pub struct Query<'a, T> {
+    environment: &'a Environment<T>,
+}
+
+impl<'a, T> Query<'a, T> {
+    pub(crate) fn new(environment: &'a Environment<T>) -> Self {
+        Self { environment }
+    }
+
+    /// Bugs which are currently borrowed mutably are skipped
+    pub fn bugs(self) -> EntityQuery<'a, Rc<RefCell<Bug<T>>>, Ref<'a, Bug<T>>> {
+        EntityQuery::new(self.environment.bugs_chunked(), |bug| bug.try_borrow().ok())
+    }
+
+    pub fn food(self) -> EntityQuery<'a, Food, &'a Food> {
+        EntityQuery::new(self.environment.food_chunked(), Some)
+    }
+
+    pub fn plants(self) -> EntityQuery<'a, Plant<T>, &'a Plant<T>> {
+        EntityQuery::new(self.environment.plants_chunked(), Some)
+    }
+}
+
+/// Composable filter over one kind of entities. Area restriction is resolved through chunks,
+/// so only entities of chunks intersecting the area are visited
+pub struct EntityQuery<'a, S, E> {
+    items: &'a ChunkedVec<S, CHUNK_SIZE, CHUNK_SIZE>,
+    map: fn(&'a S) -> Option<E>,
+    position: fn(&E) -> Point<Float>,
+    area: Option<QueryArea>,
+    filters: Vec<Predicate<'a, E>>,
+}
+
+impl<'a, S, E> EntityQuery<'a, S, E> {
+    fn new(items: &'a ChunkedVec<S, CHUNK_SIZE, CHUNK_SIZE>, map: fn(&'a S) -> Option<E>) -> Self
+    where
+        E: Position,
+    {
+        Self {
+            items,
+            map,
+            position: E::position,
+            area: None,
+            filters: Default::default(),
+        }
+    }
+
+    /// Keep entities closer than `radius` to `center`
+    pub fn in_circle(self, center: Point<Float>, radius: NoNeg<Float>) -> Self {
+        self.in_area(QueryArea::Circle(center, radius))
+    }
+
+    /// Keep entities inside `rect` (borders included)
+    pub fn in_rect(self, rect: Rect<Float>) -> Self {
+        self.in_area(QueryArea::Rect(rect))
+    }
+
+    /// Restrict query to `area`. Only the last area is applied
+    pub fn in_area(mut self, area: QueryArea) -> Self {
+        self.area = Some(area);
+        self
+    }
+
+    /// Keep entities satisfying predicate. Predicates are applied in order of addition
+    pub fn with<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&E) -> bool + 'a,
+    {
+        self.filters.push(Box::new(predicate));
+        self
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = E> + 'a
+    where
+        S: 'a,
+        E: 'a,
+    {
+        let candidates: Box<dyn Iterator<Item = &'a S> + 'a> = match &self.area {
+            Some(area) => {
+                let (center, radius) = area.bounding_circle();
+                // positions are rounded when items are assigned to chunks
+                Box::new(self.items.iter_in_range(center, radius + noneg_float(1.)))
+            }
+            None => Box::new(self.items.iter()),
+        };
+        let Self {
+            map,
+            position,
+            area,
+            filters,
+            ..
+        } = self;
+        candidates
+            .filter_map(map)
+            .filter(move |entity| {
+                area.as_ref()
+                    .map(|area| area.contains(position(entity)))
+                    .unwrap_or(true)
+            })
+            .filter(move |entity| filters.iter().all(|predicate| predicate(entity)))
+    }
+
+    pub fn count(self) -> usize
+    where
+        S: 'a,
+        E: 'a,
+    {
+        self.iter().count()
+    }
+}
+
+impl<'a, T: 'a> EntityQuery<'a, Rc<RefCell<Bug<T>>>, Ref<'a, Bug<T>>> {
+    /// Keep bugs which have tag named `name`
+    pub fn tagged(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.with(move |bug| bug.has_tag(&name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        env_presets,
+        math::{noneg_float, Point, Rect},
+        time_point::StaticTimePoint,
+    };
+
+    #[test]
+    fn query_matches_bespoke_filter() {
+        let environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        let center: Point<_> = (0., 0.).into();
+        let radius = 300.;
+
+        let expected = environment
+            .food()
+            .filter(|food| (food.position() - center).len() < radius)
+            .filter(|food| food.radius().unwrap() > 1.)
+            .count();
+        assert!(expected > 0);
+        assert_eq!(
+            environment
+                .query()
+                .food()
+                .in_circle(center, noneg_float(radius))
+                .with(|food| food.radius().unwrap() > 1.)
+                .count(),
+            expected
+        );
+
+        let rect: Rect<_> = (-100., -50., 200., 100.).into();
+        let expected = environment
+            .bugs()
+            .filter(|bug| {
+                let (x, y) = (*bug.position().x(), *bug.position().y());
+                (-100. ..=100.).contains(&x) && (-50. ..=50.).contains(&y)
+            })
+            .count();
+        assert_eq!(environment.query().bugs().in_rect(rect).count(), expected);
+        assert_eq!(environment.query().bugs().count(), environment.bugs_count());
+    }
+}
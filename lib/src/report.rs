@@ -0,0 +1,337 @@
+use std::{fmt::Write as _, time::Duration};
+
+use crate::{
+    chromo_utils::{BODY_GENES, BODY_GENE_NAMES},
+    environment::Environment,
+    time_point::TimePoint,
+    utils::{pretty_duration, Float},
+};
+
+/// Default number of samples kept by the time-lapse before it is thinned out
+pub const TIMELAPSE_CAPACITY: usize = 512;
+
+const CHART_WIDTH: Float = 800.;
+const CHART_HEIGHT: Float = 200.;
+const CHART_PADDING: Float = 40.;
+const PALETTE: [&str; 8] = [
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6", "#9a6324",
+];
+
+/// State of the population at one moment of simulation
+#[derive(Debug, Clone)]
+pub struct TimelapseSample {
+    pub iteration: usize,
+    /// Time since creation of the environment
+    pub sim_time: Duration,
+    pub population: usize,
+    pub food_count: usize,
+    pub species_count: usize,
+    /// Mean of each expressed body gene over living bugs (see `BODY_GENE_NAMES`)
+    pub body_gene_means: Vec<Float>,
+}
+
+/// Population samples taken every `period` iterations. When capacity is reached every other
+/// sample is dropped and period is doubled, so the whole run is covered with bounded memory
+#[derive(Debug, Clone)]
+pub struct Timelapse {
+    samples: Vec<TimelapseSample>,
+    period: usize,
+    capacity: usize,
+}
+
+impl Timelapse {
+    pub fn new(period: usize, capacity: usize) -> Self {
+        Self {
+            samples: Default::default(),
+            period: period.max(1),
+            capacity: capacity.max(2),
+        }
+    }
+
+    pub fn samples(&self) -> &[TimelapseSample] {
+        &self.samples
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Take a sample if current iteration falls on the sampling period
+    pub fn record<T: TimePoint>(&mut self, environment: &Environment<T>) {
+        if environment.iteration().rem_euclid(self.period) == 0 {
+            self.record_now(environment);
+        }
+    }
+
+    /// Take a sample regardless of the sampling period (e.g. the final state of the run)
+    pub fn record_now<T: TimePoint>(&mut self, environment: &Environment<T>) {
+        if self
+            .samples
+            .last()
+            .is_some_and(|sample| sample.iteration == environment.iteration())
+        {
+            return;
+        }
+
+        if self.samples.len() >= self.capacity {
+            let mut index = 0;
+            self.samples.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.period *= 2;
+        }
+
+        let mut sums = vec![0.; BODY_GENE_NAMES.len()];
+        let mut population = 0;
+        for bug in environment.bugs() {
+            let genes = &bug.chromosome().genes;
+            for (i, sum) in sums.iter_mut().enumerate() {
+                *sum += genes.get(BODY_GENES.start + i).copied().unwrap_or(0.);
+            }
+            population += 1;
+        }
+
+        self.samples.push(TimelapseSample {
+            iteration: environment.iteration(),
+            sim_time: environment
+                .now()
+                .duration_since(environment.creation_time()),
+            population,
+            food_count: environment.food_count(),
+            species_count: environment.species().len(),
+            body_gene_means: sums
+                .into_iter()
+                .map(|sum| {
+                    if population > 0 {
+                        sum / population as Float
+                    } else {
+                        0.
+                    }
+                })
+                .collect(),
+        });
+    }
+}
+
+fn svg_chart(title: &str, series: &[(&str, Vec<(Float, Float)>)]) -> String {
+    let points = series.iter().flat_map(|(_, points)| points.iter());
+    let (min_x, max_x, min_y, max_y) = points.fold(
+        (Float::MAX, Float::MIN, Float::MAX, Float::MIN),
+        |(min_x, max_x, min_y, max_y), (x, y)| {
+            (min_x.min(*x), max_x.max(*x), min_y.min(*y), max_y.max(*y))
+        },
+    );
+
+    let mut result = format!("<h3>{}</h3>\n", title);
+    if min_x > max_x {
+        result.push_str("<p>No data</p>\n");
+        return result;
+    }
+
+    let scale = |value: Float, min: Float, max: Float, len: Float| {
+        if max > min {
+            (value - min) / (max - min) * len
+        } else {
+            len / 2.
+        }
+    };
+    let inner_width = CHART_WIDTH - 2. * CHART_PADDING;
+    let inner_height = CHART_HEIGHT - 2. * CHART_PADDING;
+
+    writeln!(
+        result,
+        r#"<svg width="{w}" height="{h}" viewBox="0 0 {w} {h}" xmlns="http://www.w3.org/2000/svg">"#,
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT
+    )
+    .unwrap();
+    writeln!(
+        result,
+        r##"<rect x="{p}" y="{p}" width="{iw}" height="{ih}" fill="none" stroke="#999"/>"##,
+        p = CHART_PADDING,
+        iw = inner_width,
+        ih = inner_height
+    )
+    .unwrap();
+    writeln!(
+        result,
+        r#"<text x="2" y="{}" font-size="10">{:.3}</text><text x="2" y="{}" font-size="10">{:.3}</text>"#,
+        CHART_PADDING,
+        max_y,
+        CHART_HEIGHT - CHART_PADDING,
+        min_y
+    )
+    .unwrap();
+    writeln!(
+        result,
+        r#"<text x="{}" y="{}" font-size="10">{}</text><text x="{}" y="{}" font-size="10" text-anchor="end">{}</text>"#,
+        CHART_PADDING,
+        CHART_HEIGHT - CHART_PADDING / 2.,
+        pretty_duration(Duration::from_secs_f64(min_x)),
+        CHART_WIDTH - CHART_PADDING,
+        CHART_HEIGHT - CHART_PADDING / 2.,
+        pretty_duration(Duration::from_secs_f64(max_x))
+    )
+    .unwrap();
+
+    for (i, (name, points)) in series.iter().enumerate() {
+        let color = PALETTE[i % PALETTE.len()];
+        let polyline: Vec<String> = points
+            .iter()
+            .map(|(x, y)| {
+                format!(
+                    "{:.1},{:.1}",
+                    CHART_PADDING + scale(*x, min_x, max_x, inner_width),
+                    CHART_HEIGHT - CHART_PADDING - scale(*y, min_y, max_y, inner_height)
+                )
+            })
+            .collect();
+        writeln!(
+            result,
+            r#"<polyline fill="none" stroke="{}" stroke-width="1.5" points="{}"/>"#,
+            color,
+            polyline.join(" ")
+        )
+        .unwrap();
+        writeln!(
+            result,
+            r#"<text x="{}" y="{}" font-size="10" fill="{}">{}</text>"#,
+            CHART_PADDING + 90. * i as Float,
+            CHART_PADDING - 8.,
+            color,
+            name
+        )
+        .unwrap();
+    }
+    result.push_str("</svg>\n");
+    result
+}
+
+/// Self-contained html document with population, gene means and species curves of the run and
+/// genomes of the hall of fame
+pub fn html_report<T: TimePoint>(
+    title: &str,
+    timelapse: &Timelapse,
+    environment: &Environment<T>,
+) -> String {
+    let samples = timelapse.samples();
+    let curve = |f: &dyn Fn(&TimelapseSample) -> Float| -> Vec<(Float, Float)> {
+        samples
+            .iter()
+            .map(|sample| (sample.sim_time.as_secs_f64(), f(sample)))
+            .collect()
+    };
+
+    let mut result = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body style=\"font-family: sans-serif\">\n<h1>{title}</h1>\n"
+    );
+    writeln!(
+        result,
+        "<p>Iteration: {}, simulated time: {}, population: {}, food: {}, samples: {} (every {} iterations)</p>",
+        environment.iteration(),
+        environment.clock(),
+        environment.bugs_count(),
+        environment.food_count(),
+        samples.len(),
+        timelapse.period()
+    )
+    .unwrap();
+
+    result.push_str(&svg_chart(
+        "Population",
+        &[
+            ("bugs", curve(&|sample| sample.population as Float)),
+            ("food", curve(&|sample| sample.food_count as Float)),
+        ],
+    ));
+    let gene_series: Vec<_> = BODY_GENE_NAMES
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (*name, curve(&|sample| sample.body_gene_means[i])))
+        .collect();
+    result.push_str(&svg_chart("Body gene means", &gene_series));
+    result.push_str(&svg_chart(
+        "Species",
+        &[("species", curve(&|sample| sample.species_count as Float))],
+    ));
+
+    result.push_str("<h3>Top genomes</h3>\n<table border=\"1\" cellspacing=\"0\" cellpadding=\"3\">\n<tr><th>bug id</th><th>offspring</th>");
+    for name in BODY_GENE_NAMES {
+        write!(result, "<th>{}</th>", name).unwrap();
+    }
+    result.push_str("</tr>\n");
+    let phylogeny = environment.phylogeny();
+    for id in phylogeny.hall_of_fame() {
+        write!(
+            result,
+            "<tr><td>{}</td><td>{}</td>",
+            id,
+            phylogeny.offspring_count(*id).unwrap_or(0)
+        )
+        .unwrap();
+        let genes = phylogeny
+            .chromosome(*id)
+            .map(|chromosome| chromosome.genes.as_slice())
+            .unwrap_or_default();
+        for i in 0..BODY_GENE_NAMES.len() {
+            match genes.get(BODY_GENES.start + i) {
+                Some(gene) => write!(result, "<td>{:.3}</td>", gene).unwrap(),
+                None => result.push_str("<td></td>"),
+            }
+        }
+        result.push_str("</tr>\n");
+    }
+    result.push_str("</table>\n");
+
+    result.push_str("<h3>Food sources</h3>\n<table border=\"1\" cellspacing=\"0\" cellpadding=\"3\">\n<tr><th>position</th><th>food spawned</th><th>energy injected</th><th>food eaten</th><th>energy eaten</th><th>recent share</th></tr>\n");
+    for (food_source, report) in environment
+        .food_sources()
+        .zip(environment.stats().food_source_report())
+    {
+        writeln!(
+            result,
+            "<tr><td>({:.0}, {:.0})</td><td>{}</td><td>{:.1}</td><td>{}</td><td>{:.1}</td><td>{:.1}%</td></tr>",
+            food_source.position().x(),
+            food_source.position().y(),
+            report.totals.food_spawned,
+            report.totals.energy_injected,
+            report.totals.food_eaten,
+            report.totals.energy_eaten,
+            report.recent_share * 100.
+        )
+        .unwrap();
+    }
+    result.push_str("</table>\n</body>\n</html>\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{env_presets, time_point::StaticTimePoint};
+
+    use super::{html_report, Timelapse};
+
+    #[test]
+    fn timelapse_is_thinned_out_and_rendered() {
+        let mut environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        let mut timelapse = Timelapse::new(1, 4);
+        for _ in 0..10 {
+            let _ = environment.proceed(Duration::from_millis(100));
+            timelapse.record(&environment);
+        }
+        assert!(timelapse.samples().len() <= 4);
+        assert_eq!(timelapse.period(), 4);
+        assert!(timelapse
+            .samples()
+            .windows(2)
+            .all(|pair| pair[0].iteration < pair[1].iteration));
+
+        let report = html_report("test", &timelapse, &environment);
+        assert_eq!(report.matches("<svg").count(), 3);
+        assert!(report.contains("Top genomes"));
+    }
+}
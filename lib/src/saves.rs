@@ -0,0 +1,200 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+const SAVE_EXTENSION: &str = "json";
+const REPORTS_DIR: &str = "reports";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// Slot names are file names without extension, so they can not be empty or contain separators
+    InvalidName(String),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "io error: {}", err),
+            SaveError::Json(err) => write!(f, "json error: {}", err),
+            SaveError::InvalidName(name) => write!(f, "invalid save slot name: {:?}", name),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SaveSlot {
+    pub name: String,
+    pub path: PathBuf,
+    pub modified: SystemTime,
+}
+
+/// Directory of named save slots. Each slot is one json file
+#[derive(Debug, Clone)]
+pub struct SaveDir {
+    path: PathBuf,
+}
+
+impl SaveDir {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// `saves` directory next to the executable
+    pub fn default_path() -> PathBuf {
+        let exe_path = std::env::current_exe().unwrap();
+        exe_path.parent().unwrap().join("saves")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn slot_path(&self, name: &str) -> Result<PathBuf, SaveError> {
+        if name.is_empty() || name.starts_with('.') || name.contains(std::path::is_separator) {
+            return Err(SaveError::InvalidName(name.to_string()));
+        }
+        Ok(self.path.join(format!("{}.{}", name, SAVE_EXTENSION)))
+    }
+
+    /// All slots (newest first). Missing directory is treated as empty
+    pub fn slots(&self) -> Result<Vec<SaveSlot>, SaveError> {
+        let entries = match std::fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut result: Vec<SaveSlot> = Default::default();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|x| x.to_str()) != Some(SAVE_EXTENSION) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|x| x.to_str()) else {
+                continue;
+            };
+            result.push(SaveSlot {
+                name: name.to_string(),
+                modified: std::fs::metadata(&path)?.modified()?,
+                path: path.clone(),
+            });
+        }
+        result.sort_by_key(|slot| std::cmp::Reverse(slot.modified));
+        Ok(result)
+    }
+
+    pub fn save<T: Serialize>(&self, name: &str, value: &T) -> Result<SaveSlot, SaveError> {
+        let path = self.slot_path(name)?;
+        std::fs::create_dir_all(&self.path)?;
+        std::fs::write(&path, serde_json::to_string_pretty(value)?)?;
+        Ok(SaveSlot {
+            name: name.to_string(),
+            modified: std::fs::metadata(&path)?.modified()?,
+            path,
+        })
+    }
+
+    pub fn load<T: DeserializeOwned>(&self, name: &str) -> Result<T, SaveError> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(
+            self.slot_path(name)?,
+        )?)?)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), SaveError> {
+        Ok(std::fs::remove_file(self.slot_path(name)?)?)
+    }
+
+    /// `reports` subdirectory. Reports are kept out of slots so they are not listed as saves
+    pub fn reports_path(&self) -> PathBuf {
+        self.path.join(REPORTS_DIR)
+    }
+
+    /// Write json report named `name` into `reports_path()`
+    pub fn save_report<T: Serialize>(&self, name: &str, value: &T) -> Result<PathBuf, SaveError> {
+        let path = self.slot_path(name)?;
+        let path = self.reports_path().join(path.file_name().unwrap());
+        std::fs::create_dir_all(self.reports_path())?;
+        std::fs::write(&path, serde_json::to_string_pretty(value)?)?;
+        Ok(path)
+    }
+
+    /// Copy slot into the first free slot named `<name>_<n>`
+    pub fn duplicate(&self, name: &str) -> Result<SaveSlot, SaveError> {
+        let source = self.slot_path(name)?;
+        let new_name = self.free_name(name);
+        let path = self.slot_path(&new_name)?;
+        std::fs::copy(source, &path)?;
+        Ok(SaveSlot {
+            name: new_name,
+            modified: std::fs::metadata(&path)?.modified()?,
+            path,
+        })
+    }
+
+    /// First name of form `<prefix>_<n>` which is not taken by any slot
+    pub fn free_name(&self, prefix: &str) -> String {
+        (1..)
+            .map(|n| format!("{}_{}", prefix, n))
+            .find(|name| {
+                self.slot_path(name)
+                    .map(|path| !path.exists())
+                    .unwrap_or(false)
+            })
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SaveDir, SaveError};
+
+    #[test]
+    fn slots_can_be_saved_duplicated_and_deleted() {
+        let path = std::env::temp_dir().join(format!("bugs_saves_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let saves = SaveDir::new(path.clone());
+        assert!(saves.slots().unwrap().is_empty());
+
+        saves.save("first", &vec![1, 2, 3]).unwrap();
+        let copy = saves.duplicate("first").unwrap();
+        assert_eq!(copy.name, "first_1");
+        assert_eq!(saves.load::<Vec<i32>>("first_1").unwrap(), vec![1, 2, 3]);
+        assert_eq!(saves.slots().unwrap().len(), 2);
+
+        let report = saves.save_report("first_report", &vec![4, 5]).unwrap();
+        assert!(report.starts_with(saves.reports_path()));
+        assert_eq!(saves.slots().unwrap().len(), 2);
+
+        saves.delete("first").unwrap();
+        let names: Vec<_> = saves.slots().unwrap().into_iter().map(|x| x.name).collect();
+        assert_eq!(names, vec!["first_1"]);
+
+        assert!(matches!(
+            saves.save("../escape", &0),
+            Err(SaveError::InvalidName(_))
+        ));
+
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
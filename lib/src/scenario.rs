@@ -0,0 +1,209 @@
+use std::{fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    sim_clock::SimClock,
+    species::{genetic_distance, SPECIES_DISTANCE_THRESHOLD},
+    utils::Float,
+};
+
+/// Condition of scenario evaluated after each iteration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Goal {
+    /// Population reaches `population` bugs
+    Population { population: usize },
+    /// Bugs of a species which lived when scenario started are still alive `days` simulated
+    /// days later
+    SpeciesSurvives { days: u64 },
+    /// Total energy of all bugs reaches `energy`
+    TotalEnergy { energy: Float },
+    /// All bugs are dead
+    Extinction,
+}
+
+impl Display for Goal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Goal::Population { population } => write!(f, "population reaches {}", population),
+            Goal::SpeciesSurvives { days } => write!(f, "a species survives {} days", days),
+            Goal::TotalEnergy { energy } => write!(f, "total energy reaches {:.1}", energy),
+            Goal::Extinction => write!(f, "extinction"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScenarioOutcome {
+    Victory,
+    Defeat,
+}
+
+/// How and when scenario was completed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    pub outcome: ScenarioOutcome,
+    /// Goal which completed scenario
+    pub goal: Goal,
+    /// Simulated time since creation of environment
+    pub sim_time: Duration,
+}
+
+impl Display for ScenarioResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} ({}) after {:.0} s",
+            self.outcome,
+            self.goal,
+            self.sim_time.as_secs_f64()
+        )
+    }
+}
+
+/// Living bug as seen by scenario
+pub(crate) struct ScenarioBug<'a> {
+    pub energy: Float,
+    pub genes: &'a [Float],
+}
+
+/// Victory and defeat conditions of a run. Scenario is completed by the first goal met (defeat
+/// goals are checked first) and is not evaluated afterwards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub victory: Vec<Goal>,
+    pub defeat: Vec<Goal>,
+    /// Simulated time when scenario was evaluated for the first time
+    started: Option<Duration>,
+    /// Representatives of species living when scenario started (see `Goal::SpeciesSurvives`)
+    founders: Vec<Vec<Float>>,
+    result: Option<ScenarioResult>,
+}
+
+impl Scenario {
+    pub fn new(name: String, victory: Vec<Goal>, defeat: Vec<Goal>) -> Self {
+        Self {
+            name,
+            victory,
+            defeat,
+            started: None,
+            founders: Default::default(),
+            result: None,
+        }
+    }
+
+    /// None while scenario is in progress
+    pub fn result(&self) -> Option<&ScenarioResult> {
+        self.result.as_ref()
+    }
+
+    fn met(&self, goal: &Goal, clock: &SimClock, bugs: &[ScenarioBug]) -> bool {
+        match goal {
+            Goal::Population { population } => bugs.len() >= *population,
+            Goal::SpeciesSurvives { days } => {
+                let survived = clock
+                    .elapsed()
+                    .saturating_sub(self.started.unwrap_or_default());
+                survived >= clock.day_length() * (*days as u32)
+                    && bugs.iter().any(|bug| {
+                        self.founders.iter().any(|founder| {
+                            genetic_distance(founder, bug.genes) < SPECIES_DISTANCE_THRESHOLD
+                        })
+                    })
+            }
+            Goal::TotalEnergy { energy } => {
+                bugs.iter().map(|bug| bug.energy).sum::<Float>() >= *energy
+            }
+            Goal::Extinction => bugs.is_empty(),
+        }
+    }
+
+    /// Evaluate goals. Returns result if scenario has just been completed
+    pub(crate) fn proceed(
+        &mut self,
+        clock: SimClock,
+        bugs: &[ScenarioBug],
+    ) -> Option<ScenarioResult> {
+        if self.result.is_some() {
+            return None;
+        }
+        if self.started.is_none() {
+            self.started = Some(clock.elapsed());
+            for bug in bugs {
+                if !self.founders.iter().any(|founder| {
+                    genetic_distance(founder, bug.genes) < SPECIES_DISTANCE_THRESHOLD
+                }) {
+                    self.founders.push(bug.genes.to_vec());
+                }
+            }
+        }
+        let defeat = self
+            .defeat
+            .iter()
+            .find(|goal| self.met(goal, &clock, bugs))
+            .map(|goal| (ScenarioOutcome::Defeat, goal));
+        let victory = || {
+            self.victory
+                .iter()
+                .find(|goal| self.met(goal, &clock, bugs))
+                .map(|goal| (ScenarioOutcome::Victory, goal))
+        };
+        let (outcome, goal) = defeat.or_else(victory)?;
+        self.result = Some(ScenarioResult {
+            outcome,
+            goal: goal.clone(),
+            sim_time: clock.elapsed(),
+        });
+        self.result.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{sim_clock::SimClock, utils::Float};
+
+    use super::{Goal, Scenario, ScenarioBug, ScenarioOutcome};
+
+    fn bugs(n: usize, genes: &[Float]) -> Vec<ScenarioBug> {
+        (0..n).map(|_| ScenarioBug { energy: 1., genes }).collect()
+    }
+
+    #[test]
+    fn scenario_completes_once_by_first_met_goal() {
+        let day = Duration::from_secs(100);
+        let clock = |secs| SimClock::new(Duration::from_secs(secs), day);
+        let genes = [vec![0., 0.], vec![5., 5.]];
+        let mut scenario = Scenario::new(
+            "survival".to_string(),
+            vec![
+                Goal::SpeciesSurvives { days: 2 },
+                Goal::TotalEnergy { energy: 100. },
+            ],
+            vec![Goal::Extinction],
+        );
+
+        assert_eq!(scenario.proceed(clock(0), &bugs(2, &genes[0])), None);
+        // only descendants of founders count
+        assert_eq!(scenario.proceed(clock(250), &bugs(2, &genes[1])), None);
+        let result = scenario.proceed(clock(260), &bugs(1, &genes[0])).unwrap();
+        assert_eq!(result.outcome, ScenarioOutcome::Victory);
+        assert_eq!(result.goal, Goal::SpeciesSurvives { days: 2 });
+        assert_eq!(scenario.proceed(clock(270), &[]), None);
+        assert_eq!(scenario.result(), Some(&result));
+
+        let energy = || {
+            Scenario::new(
+                "energy".to_string(),
+                vec![Goal::TotalEnergy { energy: 10. }],
+                vec![Goal::Extinction],
+            )
+        };
+        let result = energy().proceed(clock(0), &bugs(10, &genes[0])).unwrap();
+        assert_eq!(result.outcome, ScenarioOutcome::Victory);
+        let result = energy().proceed(clock(0), &[]).unwrap();
+        assert_eq!(result.outcome, ScenarioOutcome::Defeat);
+    }
+}
@@ -0,0 +1,87 @@
+use std::{f64::consts::PI, fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::Float;
+
+/// Max amplitude of abundance. Food is never stopped completely, so spawn interval stays finite
+pub const MAX_SEASON_AMPLITUDE: Float = 0.95;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Display for Season {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Season::Spring => write!(f, "spring"),
+            Season::Summer => write!(f, "summer"),
+            Season::Autumn => write!(f, "autumn"),
+            Season::Winter => write!(f, "winter"),
+        }
+    }
+}
+
+/// Year of environment. Food sources spawn food more often and with more energy in summer and
+/// less in winter. Year starts with spring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeasonClock {
+    pub year_length: Duration,
+    /// Deviation of abundance from 1 in the middle of summer and winter (in range 0..1)
+    pub amplitude: Float,
+}
+
+impl SeasonClock {
+    /// Part of the current year passed after `elapsed` time since creation of environment
+    pub fn phase(&self, elapsed: Duration) -> Float {
+        elapsed
+            .as_secs_f64()
+            .rem_euclid(self.year_length.as_secs_f64())
+            / self.year_length.as_secs_f64()
+    }
+
+    pub fn season(&self, elapsed: Duration) -> Season {
+        match (self.phase(elapsed) * 4.) as usize {
+            0 => Season::Spring,
+            1 => Season::Summer,
+            2 => Season::Autumn,
+            _ => Season::Winter,
+        }
+    }
+
+    /// Multiplier of food production: the highest in the middle of summer, the lowest in the
+    /// middle of winter
+    pub fn abundance(&self, elapsed: Duration) -> Float {
+        let amplitude = self.amplitude.clamp(0., MAX_SEASON_AMPLITUDE);
+        1. + amplitude * (2. * PI * (self.phase(elapsed) - 0.375)).cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Season, SeasonClock};
+
+    #[test]
+    fn food_is_abundant_in_summer_and_scarce_in_winter() {
+        let clock = SeasonClock {
+            year_length: Duration::from_secs(400),
+            amplitude: 0.5,
+        };
+        let at = |secs| Duration::from_secs(secs);
+        assert_eq!(clock.season(at(0)), Season::Spring);
+        assert_eq!(clock.season(at(150)), Season::Summer);
+        assert_eq!(clock.season(at(250)), Season::Autumn);
+        assert_eq!(clock.season(at(350)), Season::Winter);
+        assert_eq!(clock.season(at(400 + 150)), Season::Summer);
+
+        assert!((clock.abundance(at(150)) - 1.5).abs() < 1e-9);
+        assert!((clock.abundance(at(350)) - 0.5).abs() < 1e-9);
+        assert!(clock.abundance(at(0)) > 0.5 && clock.abundance(at(0)) < 1.5);
+    }
+}
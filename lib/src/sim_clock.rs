@@ -0,0 +1,85 @@
+use std::{fmt::Display, time::Duration};
+
+use crate::utils::Float;
+
+pub const DEFAULT_DAY_LENGTH: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Simulated time since creation of environment split into days of configurable length.
+/// Time of day is shown on a 24 hour clock face regardless of the day length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimClock {
+    elapsed: Duration,
+    day_length: Duration,
+}
+
+impl SimClock {
+    pub fn new(elapsed: Duration, day_length: Duration) -> Self {
+        Self {
+            elapsed,
+            day_length,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn day_length(&self) -> Duration {
+        self.day_length
+    }
+
+    /// Number of the current day (the first day is 1)
+    pub fn day(&self) -> u64 {
+        (self.elapsed.as_nanos() / self.day_length.as_nanos()) as u64 + 1
+    }
+
+    /// Time passed since the start of the current day
+    pub fn time_of_day(&self) -> Duration {
+        Duration::from_nanos((self.elapsed.as_nanos() % self.day_length.as_nanos()) as u64)
+    }
+
+    /// Part of the current day passed (in range 0..1)
+    pub fn day_phase(&self) -> Float {
+        self.time_of_day().div_duration_f64(self.day_length)
+    }
+}
+
+impl Display for SimClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let minutes = (self.day_phase() * 24. * 60.) as u64;
+        write!(
+            f,
+            "day {}, {:02}:{:02}",
+            self.day(),
+            minutes / 60,
+            minutes % 60
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{SimClock, DEFAULT_DAY_LENGTH};
+
+    #[test]
+    fn clock_is_formatted_in_days() {
+        let clock = SimClock::new(
+            Duration::from_secs(11 * 24 * 60 * 60 + 3 * 60 * 60 + 15 * 60 + 59),
+            DEFAULT_DAY_LENGTH,
+        );
+        assert_eq!(clock.day(), 12);
+        assert_eq!(clock.to_string(), "day 12, 03:15");
+
+        // ten minutes long days: noon of the third day
+        let clock = SimClock::new(Duration::from_secs(25 * 60), Duration::from_secs(10 * 60));
+        assert_eq!(clock.to_string(), "day 3, 12:00");
+        assert_eq!(clock.day_phase(), 0.5);
+
+        assert_eq!(
+            SimClock::new(Duration::ZERO, DEFAULT_DAY_LENGTH).to_string(),
+            "day 1, 00:00"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use chromosome::Chromosome;
+
+use crate::{
+    environment::{Environment, FoodId},
+    math::{Angle, NoNeg, Point},
+    nutrient::Nutrients,
+    phylogeny::Phylogeny,
+    sim_clock::SimClock,
+    species::{self, Species, SPECIES_DISTANCE_THRESHOLD},
+    time_point::TimePoint,
+    utils::{Color, Float},
+};
+
+/// Bug at the moment of freezing
+#[derive(Debug, Clone)]
+pub struct BugSnapshot {
+    pub id: usize,
+    pub chromosome: Chromosome<Float>,
+    pub position: Point<Float>,
+    pub rotation: Angle<Float>,
+    pub size: NoNeg<Float>,
+    pub color: Color,
+    pub energy_level: NoNeg<Float>,
+    pub energy_capacity: NoNeg<Float>,
+    /// Relative to max age (0..=1)
+    pub age: NoNeg<Float>,
+    pub consumed_energy: NoNeg<Float>,
+    pub offspring_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FoodSnapshot {
+    pub id: FoodId,
+    pub position: Point<Float>,
+    pub energy: NoNeg<Float>,
+    pub nutrients: Nutrients,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlantSnapshot {
+    pub id: usize,
+    pub position: Point<Float>,
+    pub energy_level: NoNeg<Float>,
+}
+
+struct Frozen {
+    iteration: usize,
+    clock: SimClock,
+    bugs: Vec<BugSnapshot>,
+    food: Vec<FoodSnapshot>,
+    plants: Vec<PlantSnapshot>,
+    phylogeny: Phylogeny,
+}
+
+/// Immutable copy of entities made by `Environment::freeze`. Clones share the same data, and it
+/// can be sent to other threads, so analysis does not have to run inside the tick while
+/// environment keeps proceeding
+#[derive(Clone)]
+pub struct EnvironmentSnapshot {
+    frozen: Arc<Frozen>,
+}
+
+impl EnvironmentSnapshot {
+    pub(crate) fn of<T>(environment: &Environment<T>) -> Self
+    where
+        T: TimePoint + Clone,
+    {
+        let now = environment.now();
+        Self {
+            frozen: Arc::new(Frozen {
+                iteration: environment.iteration(),
+                clock: environment.clock(),
+                bugs: environment
+                    .bugs()
+                    .map(|bug| BugSnapshot {
+                        id: bug.id(),
+                        chromosome: bug.chromosome().clone(),
+                        position: bug.position(),
+                        rotation: bug.rotation(),
+                        size: bug.size(),
+                        color: bug.color().clone(),
+                        energy_level: bug.energy_level(),
+                        energy_capacity: bug.energy_capacity(),
+                        age: bug.age(now.clone()),
+                        consumed_energy: bug.consumed_energy(),
+                        offspring_count: bug.offspring_count(),
+                    })
+                    .collect(),
+                food: environment
+                    .food()
+                    .map(|food| FoodSnapshot {
+                        id: food.id(),
+                        position: food.position(),
+                        energy: food.energy(),
+                        nutrients: *food.nutrients(),
+                    })
+                    .collect(),
+                plants: environment
+                    .plants()
+                    .map(|plant| PlantSnapshot {
+                        id: plant.id(),
+                        position: plant.position(),
+                        energy_level: plant.energy_level(),
+                    })
+                    .collect(),
+                phylogeny: environment.phylogeny().clone(),
+            }),
+        }
+    }
+
+    /// Iteration of environment at the moment of freezing
+    pub fn iteration(&self) -> usize {
+        self.frozen.iteration
+    }
+
+    pub fn clock(&self) -> SimClock {
+        self.frozen.clock
+    }
+
+    pub fn bugs(&self) -> &[BugSnapshot] {
+        &self.frozen.bugs
+    }
+
+    pub fn food(&self) -> &[FoodSnapshot] {
+        &self.frozen.food
+    }
+
+    pub fn plants(&self) -> &[PlantSnapshot] {
+        &self.frozen.plants
+    }
+
+    pub fn phylogeny(&self) -> &Phylogeny {
+        &self.frozen.phylogeny
+    }
+
+    /// Bugs clustered by genetic similarity (the same as `Environment::species` at the moment
+    /// of freezing)
+    pub fn species(&self) -> Vec<Species> {
+        species::cluster(
+            self.frozen.bugs.iter().map(|bug| (bug.id, &bug.chromosome)),
+            SPECIES_DISTANCE_THRESHOLD,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use crate::{env_presets, time_point::StaticTimePoint};
+
+    #[test]
+    fn snapshot_is_analyzed_while_environment_proceeds() {
+        let mut environment = env_presets::plant_meadow(StaticTimePoint::default(), [0; 32]);
+        let _ = environment.proceed(Duration::from_secs(60));
+        let snapshot = environment.freeze();
+        let species_count = environment.species().len();
+        let ids: Vec<usize> = environment.bugs().map(|bug| bug.id()).collect();
+
+        let analysis = {
+            let snapshot = snapshot.clone();
+            thread::spawn(move || snapshot.species().len())
+        };
+        for _ in 0..10 {
+            let _ = environment.proceed(Duration::from_secs(60));
+        }
+
+        assert_eq!(analysis.join().unwrap(), species_count);
+        assert_eq!(snapshot.iteration(), 1);
+        assert_eq!(
+            snapshot.bugs().iter().map(|bug| bug.id).collect::<Vec<_>>(),
+            ids
+        );
+    }
+}
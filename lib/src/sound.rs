@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chunk::{ChunkedVec, Position},
+    environment::CHUNK_SIZE,
+    math::{noneg_float, Angle, NoNeg, Point},
+    utils::Float,
+};
+
+/// Sound is not heard further than this distance from its source
+pub const SOUND_RANGE: NoNeg<Float> = noneg_float(CHUNK_SIZE as Float);
+
+/// Time during which emitted pulse can be heard
+pub const SOUND_LIFETIME: Duration = Duration::from_millis(500);
+
+/// Sound pulse emitted by a bug
+#[derive(Debug, Clone)]
+pub struct Sound {
+    pub emitter_id: usize,
+    pub position: Point<Float>,
+    /// In range 0..=1 (determined by genes of emitter)
+    pub frequency: Float,
+    /// Intensity at the source (in range 0..=1)
+    pub loudness: NoNeg<Float>,
+    pub age: Duration,
+}
+
+impl Sound {
+    /// Intensity heard at `position`. Decreases linearly with distance and age
+    pub fn intensity_at(&self, position: Point<Float>) -> NoNeg<Float> {
+        let distance = (position - self.position).len();
+        let attenuation = (1. - distance / SOUND_RANGE.unwrap()).max(0.)
+            * (1. - self.age.as_secs_f64() / SOUND_LIFETIME.as_secs_f64()).max(0.);
+        self.loudness * NoNeg::wrap(attenuation).unwrap()
+    }
+}
+
+impl Position for Sound {
+    fn position(&self) -> Point<Float> {
+        self.position
+    }
+}
+
+/// What bug hears at its position: the loudest sound within `SOUND_RANGE`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HearingInfo {
+    pub intensity: NoNeg<Float>,
+    /// Direction to the source (None if nothing is heard)
+    pub direction: Option<Angle<Float>>,
+    /// Frequency of the loudest sound (0 if nothing is heard)
+    pub frequency: Float,
+}
+
+impl Default for HearingInfo {
+    fn default() -> Self {
+        Self {
+            intensity: noneg_float(0.),
+            direction: None,
+            frequency: 0.,
+        }
+    }
+}
+
+/// Sounds of the last `SOUND_LIFETIME` stored by chunks, so hearing visits only nearby ones
+#[derive(Default)]
+pub(crate) struct SoundField {
+    sounds: ChunkedVec<Sound, CHUNK_SIZE, CHUNK_SIZE>,
+}
+
+impl SoundField {
+    pub(crate) fn emit(&mut self, sound: Sound) {
+        self.sounds.push(sound)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Sound> {
+        self.sounds.iter()
+    }
+
+    /// Loudest sound heard at `position` (own sounds of `listener_id` are not heard)
+    pub(crate) fn hear(&self, position: Point<Float>, listener_id: usize) -> HearingInfo {
+        self.sounds
+            .iter_in_range(position, SOUND_RANGE + noneg_float(1.))
+            .filter(|sound| sound.emitter_id != listener_id)
+            .map(|sound| (sound, sound.intensity_at(position)))
+            .filter(|(_, intensity)| *intensity > noneg_float(0.))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(sound, intensity)| HearingInfo {
+                intensity,
+                direction: Some((sound.position - position).angle()),
+                frequency: sound.frequency,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Age sounds and drop the ones which faded out
+    pub(crate) fn proceed(&mut self, dt: Duration) {
+        if self.sounds.len() == 0 {
+            return;
+        }
+        self.sounds = self
+            .sounds
+            .iter()
+            .filter(|sound| sound.age + dt < SOUND_LIFETIME)
+            .map(|sound| Sound {
+                age: sound.age + dt,
+                ..sound.clone()
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::math::{noneg_float, Point};
+
+    use super::{Sound, SoundField, SOUND_LIFETIME, SOUND_RANGE};
+
+    #[test]
+    fn loudest_sound_of_others_is_heard() {
+        let mut field = SoundField::default();
+        let listener: Point<_> = (0., 0.).into();
+        let sound = |emitter_id, x: f64, loudness| Sound {
+            emitter_id,
+            position: (x, 0.).into(),
+            frequency: emitter_id as f64 / 10.,
+            loudness: noneg_float(loudness),
+            age: Duration::ZERO,
+        };
+        field.emit(sound(0, 0., 1.));
+        field.emit(sound(1, 10., 0.5));
+        field.emit(sound(2, -20., 1.));
+        field.emit(sound(3, SOUND_RANGE.unwrap() * 3., 1.));
+
+        // own sound is ignored, sound 2 is louder at listener than closer but quieter sound 1
+        let info = field.hear(listener, 0);
+        assert_eq!(info.frequency, 0.2);
+        assert!((info.direction.unwrap().degrees() - 180.).abs() < 1e-9);
+        assert!(info.intensity.unwrap() > 0.5 && info.intensity.unwrap() < 1.);
+
+        field.proceed(SOUND_LIFETIME);
+        assert_eq!(field.iter().count(), 0);
+        assert!(field.hear(listener, 0).direction.is_none());
+    }
+}
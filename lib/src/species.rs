@@ -0,0 +1,75 @@
+use chromosome::Chromosome;
+
+use crate::utils::Float;
+
+/// Max root mean square difference of genes between a genome and the representative of its species
+pub const SPECIES_DISTANCE_THRESHOLD: Float = 0.5;
+
+/// Group of similar genomes
+#[derive(Debug, Clone)]
+pub struct Species {
+    /// Genes of the first member (new members are compared against them)
+    pub representative: Vec<Float>,
+    /// Ids of members
+    pub members: Vec<usize>,
+}
+
+/// Root mean square difference of genes (genes missing in a shorter genome are not compared)
+pub fn genetic_distance(a: &[Float], b: &[Float]) -> Float {
+    let len = a.len().min(b.len());
+    if len == 0 {
+        return 0.;
+    }
+    let sum: Float = a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum();
+    (sum / len as Float).sqrt()
+}
+
+/// Greedy leader clustering: each genome joins the first species whose representative is closer
+/// than `threshold`, otherwise it founds a new species
+pub fn cluster<'a>(
+    genomes: impl Iterator<Item = (usize, &'a Chromosome<Float>)>,
+    threshold: Float,
+) -> Vec<Species> {
+    let mut result: Vec<Species> = Default::default();
+    for (id, chromosome) in genomes {
+        match result.iter_mut().find(|species| {
+            genetic_distance(&species.representative, &chromosome.genes) < threshold
+        }) {
+            Some(species) => species.members.push(id),
+            None => result.push(Species {
+                representative: chromosome.genes.clone(),
+                members: vec![id],
+            }),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use chromosome::Chromosome;
+
+    use super::cluster;
+
+    #[test]
+    fn similar_genomes_share_species() {
+        let genomes = [
+            Chromosome {
+                genes: vec![0., 0.],
+            },
+            Chromosome {
+                genes: vec![0.1, 0.],
+            },
+            Chromosome {
+                genes: vec![5., 5.],
+            },
+            Chromosome {
+                genes: vec![0., 0.2],
+            },
+        ];
+        let species = cluster(genomes.iter().enumerate(), 0.5);
+        assert_eq!(species.len(), 2);
+        assert_eq!(species[0].members, vec![0, 1, 3]);
+        assert_eq!(species[1].members, vec![2]);
+    }
+}
@@ -0,0 +1,205 @@
+use std::{collections::VecDeque, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{food_source::FoodSourceStats, utils::Float};
+
+/// Number of most recent deaths used for analysis
+pub const DEATH_WINDOW: usize = 1024;
+
+/// Number of most recent iterations over which recent contribution of food sources is measured
+pub const FOOD_SOURCE_WINDOW: usize = 1024;
+
+struct DeathRecord {
+    genes: Vec<Float>,
+    lifespan: Duration,
+    offspring_count: usize,
+}
+
+/// How strongly value of each gene is correlated with fitness proxies.
+/// Coefficients are Pearson correlations in range -1..=1 (0 if gene or proxy is constant)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelectionReport {
+    pub sample_size: usize,
+    pub lifespan: Vec<Float>,
+    pub offspring_count: Vec<Float>,
+}
+
+/// Position of a living bug in the efficiency leaderboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub bug_id: usize,
+    pub consumed_energy: Float,
+    pub offspring_count: usize,
+    /// Offspring per unit of consumed energy
+    pub efficiency: Float,
+}
+
+/// Contribution of one food source (in order of `Environment::food_sources`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoodSourceReport {
+    pub totals: FoodSourceStats,
+    /// Energy eaten from the source during the last `FOOD_SOURCE_WINDOW` iterations
+    pub recent_energy_eaten: Float,
+    /// Part of energy eaten from all sources during the same iterations (in range 0..=1)
+    pub recent_share: Float,
+}
+
+/// Work done in one chunk during one iteration
+#[derive(Debug, Clone, Default)]
+pub struct ChunkLoad {
+    /// Nearest food and nearest bug searches started from the chunk
+    pub sensing_queries: usize,
+    /// Environment requests produced by bugs, plants and food sources of the chunk
+    pub requests: usize,
+}
+
+impl ChunkLoad {
+    pub fn total(&self) -> usize {
+        self.sensing_queries + self.requests
+    }
+}
+
+/// Wall-clock time spent in each phase of one iteration
+#[derive(Debug, Clone, Default)]
+pub struct TickTimings {
+    /// Searching for nearest food, bugs and other inputs of brains
+    pub sense: Duration,
+    /// Evaluating brains
+    pub brain: Duration,
+    /// The rest of bug update (movement and energy exchange)
+    pub movement: Duration,
+    /// Proceeding food sources and plants and applying all requests
+    pub requests: Duration,
+    /// Reordering bugs in chunks
+    pub shuffle: Duration,
+}
+
+impl TickTimings {
+    pub fn total(&self) -> Duration {
+        self.sense + self.brain + self.movement + self.requests + self.shuffle
+    }
+}
+
+/// Collects statistics about the population while simulation is running
+#[derive(Default)]
+pub struct StatsCollector {
+    deaths: VecDeque<DeathRecord>,
+    food_sources: VecDeque<Vec<FoodSourceStats>>,
+}
+
+fn correlation(
+    xs: impl Iterator<Item = Float> + Clone,
+    ys: impl Iterator<Item = Float> + Clone,
+) -> Float {
+    let n = xs.clone().count() as Float;
+    if n < 2. {
+        return 0.;
+    }
+    let mean_x = xs.clone().sum::<Float>() / n;
+    let mean_y = ys.clone().sum::<Float>() / n;
+    let (cov, var_x, var_y) = xs
+        .zip(ys)
+        .fold((0., 0., 0.), |(cov, var_x, var_y), (x, y)| {
+            let (dx, dy) = (x - mean_x, y - mean_y);
+            (cov + dx * dy, var_x + dx * dx, var_y + dy * dy)
+        });
+    if var_x == 0. || var_y == 0. {
+        0.
+    } else {
+        cov / (var_x * var_y).sqrt()
+    }
+}
+
+impl StatsCollector {
+    pub(crate) fn record_death(
+        &mut self,
+        genes: &[Float],
+        lifespan: Duration,
+        offspring_count: usize,
+    ) {
+        if self.deaths.len() >= DEATH_WINDOW {
+            self.deaths.pop_front();
+        }
+        self.deaths.push_back(DeathRecord {
+            genes: genes.to_vec(),
+            lifespan,
+            offspring_count,
+        });
+    }
+
+    /// Snapshot of totals of all food sources taken after an iteration
+    pub(crate) fn record_food_sources(&mut self, stats: Vec<FoodSourceStats>) {
+        if self.food_sources.len() >= FOOD_SOURCE_WINDOW {
+            self.food_sources.pop_front();
+        }
+        self.food_sources.push_back(stats);
+    }
+
+    /// Snapshots are matched by index of source, so they are dropped when a source is removed
+    pub(crate) fn forget_food_sources(&mut self) {
+        self.food_sources.clear();
+    }
+
+    pub fn food_source_report(&self) -> Vec<FoodSourceReport> {
+        let (Some(oldest), Some(latest)) = (self.food_sources.front(), self.food_sources.back())
+        else {
+            return Default::default();
+        };
+        let recent: Vec<Float> = latest
+            .iter()
+            .enumerate()
+            .map(|(i, stats)| {
+                stats.energy_eaten - oldest.get(i).map(|x| x.energy_eaten).unwrap_or(0.)
+            })
+            .collect();
+        let recent_total: Float = recent.iter().sum();
+        latest
+            .iter()
+            .zip(recent)
+            .map(|(totals, recent_energy_eaten)| FoodSourceReport {
+                totals: totals.clone(),
+                recent_energy_eaten,
+                recent_share: if recent_total > 0. {
+                    recent_energy_eaten / recent_total
+                } else {
+                    0.
+                },
+            })
+            .collect()
+    }
+
+    pub fn deaths_count(&self) -> usize {
+        self.deaths.len()
+    }
+
+    /// Per-gene selection coefficients over the window of recent deaths
+    pub fn selection_report(&self) -> SelectionReport {
+        let genes_count = self
+            .deaths
+            .iter()
+            .map(|record| record.genes.len())
+            .min()
+            .unwrap_or(0);
+
+        let gene_values = |i: usize| self.deaths.iter().map(move |record| record.genes[i]);
+        let lifespans = self
+            .deaths
+            .iter()
+            .map(|record| record.lifespan.as_secs_f64());
+        let offspring_counts = self
+            .deaths
+            .iter()
+            .map(|record| record.offspring_count as Float);
+
+        SelectionReport {
+            sample_size: self.deaths.len(),
+            lifespan: (0..genes_count)
+                .map(|i| correlation(gene_values(i), lifespans.clone()))
+                .collect(),
+            offspring_count: (0..genes_count)
+                .map(|i| correlation(gene_values(i), offspring_counts.clone()))
+                .collect(),
+        }
+    }
+}
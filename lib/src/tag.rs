@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::Color;
+
+/// Label attached to bugs by experimenter to mark treatment groups (e.g. "cohort-A").
+/// Simulation itself never looks at tags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    /// Offspring get the tag too, so group can be followed through generations
+    pub inherited: bool,
+    /// Bugs with this tag are drawn with that color instead of genetic one
+    pub color: Option<Color>,
+}
+
+impl Tag {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            inherited: false,
+            color: None,
+        }
+    }
+
+    pub fn inherited(mut self) -> Self {
+        self.inherited = true;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
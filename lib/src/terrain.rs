@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{noneg_float, NoNeg, Point},
+    utils::Float,
+};
+
+/// Extra movement cost per unit of slope (climbing 1 unit up per 1 unit of distance costs
+/// `1 + SLOPE_MOVEMENT_COST` times more)
+pub const SLOPE_MOVEMENT_COST: Float = 2.;
+
+/// Going downhill is cheaper, but never cheaper than this part of the flat ground cost
+pub const MIN_DOWNHILL_MOVEMENT_COST: Float = 0.25;
+
+/// Number of noise layers, each next one has twice smaller features and twice lower amplitude
+const OCTAVES: usize = 3;
+
+/// Heightmap generated from value noise. Elevation is in range `0..max_elevation`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Terrain {
+    pub seed: u64,
+    /// Distance between neighbouring hills (size of the largest features)
+    pub feature_size: NoNeg<Float>,
+    pub max_elevation: NoNeg<Float>,
+}
+
+/// Pseudo random value in range 0..1 attached to lattice node
+fn lattice_value(seed: u64, x: i64, y: i64) -> Float {
+    let mut h = seed
+        ^ (x as u64).wrapping_mul(0x9e3779b97f4a7c15)
+        ^ (y as u64).wrapping_mul(0xc2b2ae3d27d4eb4f);
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d049bb133111eb);
+    h ^= h >> 31;
+    (h >> 11) as Float / (1u64 << 53) as Float
+}
+
+fn smoothstep(t: Float) -> Float {
+    t * t * (3. - 2. * t)
+}
+
+/// Value noise in range 0..1 with features of size 1
+fn value_noise(seed: u64, x: Float, y: Float) -> Float {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (smoothstep(x - x0), smoothstep(y - y0));
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let top = lattice_value(seed, x0, y0) * (1. - tx) + lattice_value(seed, x0 + 1, y0) * tx;
+    let bottom =
+        lattice_value(seed, x0, y0 + 1) * (1. - tx) + lattice_value(seed, x0 + 1, y0 + 1) * tx;
+    top * (1. - ty) + bottom * ty
+}
+
+/// Sum of `OCTAVES` layers of value noise in range 0..1. The largest features are of
+/// `feature_size`
+pub(crate) fn fractal_noise(seed: u64, point: Point<Float>, feature_size: Float) -> Float {
+    let (mut sum, mut norm, mut amplitude, mut frequency) = (0., 0., 1., 1.);
+    for octave in 0..OCTAVES {
+        let (x, y) = (
+            point.x() * frequency / feature_size,
+            point.y() * frequency / feature_size,
+        );
+        sum += amplitude * value_noise(seed.wrapping_add(octave as u64), x, y);
+        norm += amplitude;
+        amplitude /= 2.;
+        frequency *= 2.;
+    }
+    sum / norm
+}
+
+impl Terrain {
+    pub fn elevation(&self, point: Point<Float>) -> Float {
+        fractal_noise(self.seed, point, self.feature_size.unwrap()) * self.max_elevation.unwrap()
+    }
+
+    /// Multiplier of movement energy cost on the way from `from` to `to`
+    pub fn movement_cost_multiplier(&self, from: Point<Float>, to: Point<Float>) -> NoNeg<Float> {
+        let distance = (to - from).len();
+        if distance <= 0. {
+            return noneg_float(1.);
+        }
+        let slope = (self.elevation(to) - self.elevation(from)) / distance;
+        NoNeg::wrap((1. + SLOPE_MOVEMENT_COST * slope).max(MIN_DOWNHILL_MOVEMENT_COST)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::noneg_float;
+
+    use super::{Terrain, MIN_DOWNHILL_MOVEMENT_COST};
+
+    #[test]
+    fn climbing_costs_more_than_descending() {
+        let terrain = Terrain {
+            seed: 7,
+            feature_size: noneg_float(500.),
+            max_elevation: noneg_float(100.),
+        };
+        for i in 0..100 {
+            let elevation = terrain.elevation((i as f64 * 37., i as f64 * -11.).into());
+            assert!((0. ..100.).contains(&elevation));
+        }
+
+        // find a step going uphill
+        let (from, to) = (0..1000)
+            .map(|i| {
+                let from = (i as f64 * 10., 0.).into();
+                let to = (i as f64 * 10. + 10., 0.).into();
+                (from, to)
+            })
+            .find(|(from, to)| terrain.elevation(*to) > terrain.elevation(*from) + 0.1)
+            .unwrap();
+        assert!(terrain.movement_cost_multiplier(from, to) > noneg_float(1.));
+        let downhill = terrain.movement_cost_multiplier(to, from);
+        assert!(downhill < noneg_float(1.));
+        assert!(downhill >= noneg_float(MIN_DOWNHILL_MOVEMENT_COST));
+        assert_eq!(
+            terrain.movement_cost_multiplier(from, from),
+            noneg_float(1.)
+        );
+    }
+}
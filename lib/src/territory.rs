@@ -0,0 +1,136 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{
+    environment::CHUNK_SIZE,
+    math::{noneg_float, NoNeg, Point},
+    utils::{Color, Float},
+};
+
+/// Width and height of territory cell (one cell per chunk)
+pub const TERRITORY_CELL_SIZE: Float = CHUNK_SIZE as Float;
+
+/// Marker strength deposited per second by bug staying in a cell
+pub const TERRITORY_MARKING_RATE: Float = 1.;
+
+/// Fraction of marker strength which fades per second
+pub const TERRITORY_FADE_RATE: Float = 0.02;
+
+/// Markers with lower strength are dropped
+const MIN_STRENGTH: Float = 0.01;
+
+/// Claim of a single bug on a cell
+#[derive(Debug, Clone)]
+pub struct TerritoryMarker {
+    pub owner_id: usize,
+    /// Color of owner (used to tint the cell)
+    pub color: Color,
+    pub strength: Float,
+}
+
+/// Sparse grid of territory markers, at most one owner per cell. Marking a foreign cell first
+/// wears off the marker of its owner and claims the cell only when it is gone
+#[derive(Debug, Clone, Default)]
+pub struct TerritoryMap {
+    cells: BTreeMap<(isize, isize), TerritoryMarker>,
+}
+
+impl TerritoryMap {
+    fn cell_of(position: Point<Float>) -> (isize, isize) {
+        (
+            (position.x() / TERRITORY_CELL_SIZE).floor() as isize,
+            (position.y() / TERRITORY_CELL_SIZE).floor() as isize,
+        )
+    }
+
+    pub(crate) fn mark(
+        &mut self,
+        position: Point<Float>,
+        owner_id: usize,
+        color: &Color,
+        amount: NoNeg<Float>,
+    ) {
+        let amount = amount.unwrap();
+        let marker = self
+            .cells
+            .entry(Self::cell_of(position))
+            .or_insert_with(|| TerritoryMarker {
+                owner_id,
+                color: color.clone(),
+                strength: 0.,
+            });
+        if marker.owner_id == owner_id {
+            marker.strength += amount;
+        } else if marker.strength > amount {
+            marker.strength -= amount;
+        } else {
+            *marker = TerritoryMarker {
+                owner_id,
+                color: color.clone(),
+                strength: amount - marker.strength,
+            };
+        }
+    }
+
+    pub fn marker_at(&self, position: Point<Float>) -> Option<&TerritoryMarker> {
+        self.cells.get(&Self::cell_of(position))
+    }
+
+    /// Strength of marker at `position` unless it belongs to the bug with `id`
+    pub fn foreign_strength(&self, position: Point<Float>, id: usize) -> NoNeg<Float> {
+        self.marker_at(position)
+            .filter(|marker| marker.owner_id != id)
+            .map(|marker| NoNeg::wrap(marker.strength).unwrap())
+            .unwrap_or(noneg_float(0.))
+    }
+
+    /// Indices (in units of `TERRITORY_CELL_SIZE`) and markers of all claimed cells
+    pub fn cells(&self) -> impl Iterator<Item = ((isize, isize), &TerritoryMarker)> + '_ {
+        self.cells.iter().map(|(cell, marker)| (*cell, marker))
+    }
+
+    pub(crate) fn proceed(&mut self, dt: Duration) {
+        let remaining = (-TERRITORY_FADE_RATE * dt.as_secs_f64()).exp();
+        self.cells.retain(|_, marker| {
+            marker.strength *= remaining;
+            marker.strength >= MIN_STRENGTH
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        math::{noneg_float, Point},
+        utils::Color,
+    };
+
+    use super::TerritoryMap;
+
+    #[test]
+    fn intruder_wears_off_foreign_marker_before_claiming_cell() {
+        let color = Color {
+            a: 1.,
+            r: 1.,
+            g: 0.,
+            b: 0.,
+        };
+        let position: Point<_> = (1., 1.).into();
+        let mut map = TerritoryMap::default();
+        map.mark(position, 0, &color, noneg_float(3.));
+        assert_eq!(map.foreign_strength(position, 0), noneg_float(0.));
+        assert_eq!(map.foreign_strength(position, 1), noneg_float(3.));
+
+        map.mark(position, 1, &color, noneg_float(2.));
+        assert_eq!(map.marker_at(position).unwrap().owner_id, 0);
+        map.mark(position, 1, &color, noneg_float(2.));
+        assert_eq!(map.marker_at(position).unwrap().owner_id, 1);
+        assert_eq!(map.foreign_strength(position, 0), noneg_float(1.));
+
+        for _ in 0..1000 {
+            map.proceed(Duration::from_millis(500));
+        }
+        assert_eq!(map.cells().count(), 0);
+    }
+}
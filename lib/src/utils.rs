@@ -2,6 +2,7 @@ use std::{
     error::Error,
     fmt::{Debug, Display},
     ops::RangeBounds,
+    sync::OnceLock,
     time::Duration,
 };
 
@@ -91,6 +92,121 @@ pub fn pretty_duration(duration: Duration) -> String {
     }
 }
 
+/// Decimal separator of numbers produced by `pretty_number` and `pretty_percent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberLocale {
+    pub decimal_separator: char,
+}
+
+impl Default for NumberLocale {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+        }
+    }
+}
+
+/// Languages which separate decimals with comma
+const DECIMAL_COMMA_LANGUAGES: [&str; 14] = [
+    "de", "fr", "es", "it", "pt", "ru", "uk", "pl", "nl", "sv", "fi", "cs", "tr", "da",
+];
+
+impl NumberLocale {
+    /// Locale from `LC_ALL`, `LC_NUMERIC` or `LANG` (e.g. `de_DE.UTF-8`). Default if none is set
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .map(|value| Self::from_name(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        let language = name.split(['_', '.', '-']).next().unwrap_or_default();
+        if DECIMAL_COMMA_LANGUAGES.contains(&language) {
+            Self {
+                decimal_separator: ',',
+            }
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Locale of the process (detected once)
+    pub fn current() -> Self {
+        static CURRENT: OnceLock<NumberLocale> = OnceLock::new();
+        *CURRENT.get_or_init(Self::from_env)
+    }
+
+    fn localize(&self, formatted: String) -> String {
+        if self.decimal_separator == '.' {
+            formatted
+        } else {
+            formatted.replace('.', &self.decimal_separator.to_string())
+        }
+    }
+
+    /// Compact number with at most 3 significant digits before unit suffix:
+    /// 5.68, 12.3, 346, 12.3k, 4.57M, 7.8G
+    pub fn number(&self, value: Float) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let mut scaled = value;
+        let mut units = ["", "k", "M", "G", "T"].into_iter().peekable();
+        while let Some(unit) = units.next() {
+            // round first so that 999.7 becomes 1k instead of 1000
+            let precision = if scaled.abs() < 10. {
+                2
+            } else if scaled.abs() < 100. {
+                1
+            } else {
+                0
+            };
+            let factor = (10 as Float).powi(precision);
+            // avoid "-0"
+            let rounded = (scaled * factor).round() / factor + 0.;
+            if rounded.abs() < 1000. || units.peek().is_none() {
+                let formatted = format!("{:.*}", precision as usize, rounded);
+                // 1.50 -> 1.5, 2.00 -> 2
+                let formatted = if formatted.contains('.') {
+                    formatted
+                        .trim_end_matches('0')
+                        .trim_end_matches('.')
+                        .to_string()
+                } else {
+                    formatted
+                };
+                return self.localize(formatted + unit);
+            }
+            scaled /= 1000.;
+        }
+        unreachable!()
+    }
+
+    /// `ratio` 0.453 as 45% (small ratios keep one decimal: 4.5%)
+    pub fn percent(&self, ratio: Float) -> String {
+        let percent = ratio * 100.;
+        let formatted = if percent.abs() < 10. && percent.fract() != 0. {
+            format!("{:.1}%", percent)
+        } else {
+            format!("{:.0}%", percent)
+        };
+        self.localize(formatted)
+    }
+}
+
+/// Compact number in locale of the process (see `NumberLocale::number`)
+pub fn pretty_number(value: Float) -> String {
+    NumberLocale::current().number(value)
+}
+
+/// Percent in locale of the process (see `NumberLocale::percent`)
+pub fn pretty_percent(ratio: Float) -> String {
+    NumberLocale::current().percent(ratio)
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct RequiredToBeInRangeError<T, R> {
     value: T,
@@ -144,3 +260,35 @@ impl<T: PartialOrd, const SIZE: usize> RequiredToBeInRange for [T; SIZE] {
         Ok(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::NumberLocale;
+
+    #[test]
+    fn numbers_are_compact() {
+        let locale = NumberLocale::default();
+        assert_eq!(locale.number(0.), "0");
+        assert_eq!(locale.number(5.678), "5.68");
+        assert_eq!(locale.number(1.5), "1.5");
+        assert_eq!(locale.number(12.345), "12.3");
+        assert_eq!(locale.number(345.6), "346");
+        assert_eq!(locale.number(12345.678), "12.3k");
+        assert_eq!(locale.number(999.7), "1k");
+        assert_eq!(locale.number(-4567890.), "-4.57M");
+        assert_eq!(locale.percent(0.453), "45%");
+        assert_eq!(locale.percent(0.045), "4.5%");
+    }
+
+    #[test]
+    fn decimal_separator_follows_locale() {
+        let locale = NumberLocale::from_name("de_DE.UTF-8");
+        assert_eq!(locale.number(12345.678), "12,3k");
+        assert_eq!(locale.percent(0.045), "4,5%");
+        assert_eq!(
+            NumberLocale::from_name("en_US.UTF-8"),
+            NumberLocale::default()
+        );
+        assert_eq!(NumberLocale::from_name("C"), NumberLocale::default());
+    }
+}
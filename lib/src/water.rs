@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{NoNeg, Point},
+    utils::Float,
+};
+
+/// Moving through water costs this many times more energy to a bug which can not swim
+pub const WATER_MOVEMENT_COST: Float = 4.;
+
+/// Distance to shore at which shore proximity sense drops to a half
+pub const SHORE_SENSE_DISTANCE: Float = 100.;
+
+/// Area covered by water
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WaterRegion {
+    Circle {
+        center: Point<Float>,
+        radius: NoNeg<Float>,
+    },
+    /// Vertices in order of traversal (polygon is closed implicitly)
+    Polygon { vertices: Vec<Point<Float>> },
+}
+
+/// Distance from `point` to segment `a`-`b`
+fn distance_to_segment(point: Point<Float>, a: Point<Float>, b: Point<Float>) -> Float {
+    let (ab, ap) = (b - a, point - a);
+    let len_sqr = ab.len_sqr();
+    let t = if len_sqr > 0. {
+        ((ap.x() * ab.x() + ap.y() * ab.y()) / len_sqr).clamp(0., 1.)
+    } else {
+        0.
+    };
+    let (dx, dy) = (ap.x() - ab.x() * t, ap.y() - ab.y() * t);
+    (dx * dx + dy * dy).sqrt()
+}
+
+impl WaterRegion {
+    fn edges(vertices: &[Point<Float>]) -> impl Iterator<Item = (Point<Float>, Point<Float>)> + '_ {
+        vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+    }
+
+    pub fn contains(&self, point: Point<Float>) -> bool {
+        match self {
+            WaterRegion::Circle { center, radius } => (point - *center).len() < radius.unwrap(),
+            WaterRegion::Polygon { vertices } => {
+                // even-odd rule: count edges crossed by horizontal ray going right from the point
+                let (x, y) = (*point.x(), *point.y());
+                Self::edges(vertices)
+                    .filter(|(a, b)| (*a.y() > y) != (*b.y() > y))
+                    .filter(|(a, b)| x < a.x() + (y - a.y()) / (b.y() - a.y()) * (b.x() - a.x()))
+                    .count()
+                    % 2
+                    == 1
+            }
+        }
+    }
+
+    /// Distance from `point` to border of the region (from either side)
+    pub fn distance_to_border(&self, point: Point<Float>) -> NoNeg<Float> {
+        let distance = match self {
+            WaterRegion::Circle { center, radius } => {
+                ((point - *center).len() - radius.unwrap()).abs()
+            }
+            WaterRegion::Polygon { vertices } => Self::edges(vertices)
+                .map(|(a, b)| distance_to_segment(point, a, b))
+                .fold(Float::INFINITY, Float::min),
+        };
+        NoNeg::wrap(distance).unwrap()
+    }
+}
+
+/// What bug senses about water at its position
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WaterInfo {
+    pub in_water: bool,
+    /// Distance to the nearest border of water (None if there is no water in environment)
+    pub shore_distance: Option<NoNeg<Float>>,
+}
+
+impl WaterInfo {
+    pub(crate) fn sense(regions: &[WaterRegion], position: Point<Float>) -> Self {
+        Self {
+            in_water: regions.iter().any(|region| region.contains(position)),
+            shore_distance: regions
+                .iter()
+                .map(|region| region.distance_to_border(position))
+                .min_by(|a, b| a.partial_cmp(b).unwrap()),
+        }
+    }
+
+    /// 1 at the shore, decreasing to 0 further from it (0 if there is no water)
+    pub fn shore_proximity(&self) -> Float {
+        self.shore_distance
+            .map(|distance| SHORE_SENSE_DISTANCE / (distance.unwrap() + SHORE_SENSE_DISTANCE))
+            .unwrap_or(0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::noneg_float;
+
+    use super::{WaterInfo, WaterRegion};
+
+    #[test]
+    fn water_regions_are_sensed() {
+        let regions = [
+            WaterRegion::Circle {
+                center: (100., 0.).into(),
+                radius: noneg_float(10.),
+            },
+            WaterRegion::Polygon {
+                vertices: vec![
+                    (0., 0.).into(),
+                    (40., 0.).into(),
+                    (40., 40.).into(),
+                    (20., 10.).into(),
+                    (0., 40.).into(),
+                ],
+            },
+        ];
+
+        let info = WaterInfo::sense(&regions, (5., 5.).into());
+        assert!(info.in_water);
+        assert_eq!(info.shore_distance, Some(noneg_float(5.)));
+
+        // inside the notch of concave polygon
+        let info = WaterInfo::sense(&regions, (20., 30.).into());
+        assert!(!info.in_water);
+
+        let info = WaterInfo::sense(&regions, (104., 0.).into());
+        assert!(info.in_water);
+        assert_eq!(info.shore_distance, Some(noneg_float(6.)));
+        assert!(info.shore_proximity() > 0.9);
+
+        let info = WaterInfo::sense(&[], (0., 0.).into());
+        assert!(!info.in_water && info.shore_distance.is_none());
+        assert_eq!(info.shore_proximity(), 0.);
+    }
+}
@@ -0,0 +1,225 @@
+use bugs_lib::{env_presets, environment::SeededEnvironment, time_point::StaticTimePoint};
+use rand_seeder::Seeder;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Seeds and iteration counts of simulations run by the harness
+const MATRIX: [(&str, usize); 4] = [("ci-0", 100), ("ci-1", 100), ("ci-2", 300), ("ci-3", 1000)];
+
+/// Iterations at which saves are round-tripped
+const ROUND_TRIP_EVERY: usize = 50;
+
+const DT: Duration = Duration::from_millis(1000 / 30);
+
+/// Runs fixed matrix of short deterministic simulations and checks that they are reproducible,
+/// that saves round-trip losslessly and that final states match golden hashes
+#[derive(clap::Parser)]
+pub(crate) struct CiCommand {
+    /// Json file with golden hashes (`<seed>:<iterations>` -> hash). Golden check is skipped if
+    /// not provided
+    #[arg(long)]
+    golden: Option<PathBuf>,
+    /// Write hashes of this run into golden file instead of checking them
+    #[arg(long)]
+    bless: bool,
+    /// Directory of fuzz inputs (e.g. `fuzz/corpus/limited_resource_environment`). Each file is
+    /// used as seed of a short simulation whose save is round-tripped
+    #[arg(long)]
+    corpus: Option<PathBuf>,
+}
+
+#[derive(Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum CheckStatus {
+    Pass,
+    Fail,
+    Skipped,
+}
+
+#[derive(Serialize)]
+struct CheckResult {
+    name: String,
+    status: CheckStatus,
+    detail: String,
+}
+
+/// Printed to stdout as json when harness finishes
+#[derive(Serialize)]
+struct CiSummary {
+    passed: bool,
+    checks: Vec<CheckResult>,
+}
+
+/// FNV-1a hash of serialized state. Stable across platforms and toolchains unlike `DefaultHasher`
+fn state_hash(environment: &SeededEnvironment<StaticTimePoint>) -> String {
+    let json = serde_json::to_string(environment).unwrap();
+    let hash = json.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Saves are lossless if loaded save serializes back to the same json
+fn round_trip(environment: &SeededEnvironment<StaticTimePoint>) -> Result<(), String> {
+    let json = serde_json::to_string(environment).map_err(|err| err.to_string())?;
+    let mut loaded: SeededEnvironment<StaticTimePoint> =
+        serde_json::from_str(&json).map_err(|err| err.to_string())?;
+    let reserialized = serde_json::to_string(&loaded).map_err(|err| err.to_string())?;
+    if reserialized != json {
+        return Err(format!(
+            "save differs after loading ({} vs {} bytes)",
+            json.len(),
+            reserialized.len()
+        ));
+    }
+    // loaded save must remain simulatable
+    let _ = loaded.proceed(DT);
+    Ok(())
+}
+
+fn round_trip_check(name: String, environment: &SeededEnvironment<StaticTimePoint>) -> CheckResult {
+    let result = round_trip(environment);
+    CheckResult {
+        name,
+        status: if result.is_ok() {
+            CheckStatus::Pass
+        } else {
+            CheckStatus::Fail
+        },
+        detail: result.err().unwrap_or_default(),
+    }
+}
+
+/// Simulate `iterations` iterations from `seed` returning hash of the final state
+fn run(seed: &str, iterations: usize, round_trips: &mut Vec<CheckResult>) -> String {
+    let mut environment = env_presets::less_food_further_from_center(
+        StaticTimePoint::default(),
+        Seeder::from(seed).make_seed(),
+    );
+    for _ in 0..iterations {
+        let _ = environment.proceed(DT);
+        if environment.iteration().rem_euclid(ROUND_TRIP_EVERY) == 0 {
+            round_trips.push(round_trip_check(
+                format!("round_trip/{}@{}", seed, environment.iteration()),
+                &environment,
+            ));
+        }
+    }
+    state_hash(&environment)
+}
+
+/// Round-trip save of a short simulation seeded by each file of the corpus
+fn run_corpus(corpus: &Path) -> Vec<CheckResult> {
+    let mut paths: Vec<_> = match std::fs::read_dir(corpus) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect(),
+        Err(err) => {
+            return vec![CheckResult {
+                name: "corpus".to_string(),
+                status: CheckStatus::Fail,
+                detail: format!("can not read {:?}: {}", corpus, err),
+            }]
+        }
+    };
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = format!(
+                "corpus/{}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            );
+            let data = std::fs::read(&path).unwrap_or_default();
+            let mut environment = env_presets::less_food_further_from_center(
+                StaticTimePoint::default(),
+                Seeder::from(data.as_slice()).make_seed(),
+            );
+            for _ in 0..ROUND_TRIP_EVERY {
+                let _ = environment.proceed(DT);
+            }
+            round_trip_check(name, &environment)
+        })
+        .collect()
+}
+
+/// Run harness, print json summary and return whether all checks passed
+pub(crate) fn run_ci(command: CiCommand) -> bool {
+    let golden: Option<BTreeMap<String, String>> = match &command.golden {
+        Some(path) if !command.bless => Some(
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap_or_default())
+                .unwrap_or_default(),
+        ),
+        _ => None,
+    };
+
+    let mut checks: Vec<CheckResult> = Default::default();
+    let mut hashes: BTreeMap<String, String> = Default::default();
+    for (seed, iterations) in MATRIX {
+        let key = format!("{}:{}", seed, iterations);
+        eprintln!("Running {}", key);
+        let started = std::time::Instant::now();
+
+        let mut round_trips = Default::default();
+        let hash = run(seed, iterations, &mut round_trips);
+        let repeated_hash = run(seed, iterations, &mut Default::default());
+        checks.push(CheckResult {
+            name: format!("determinism/{}", key),
+            status: if hash == repeated_hash {
+                CheckStatus::Pass
+            } else {
+                CheckStatus::Fail
+            },
+            detail: format!("{} vs {}", hash, repeated_hash),
+        });
+        checks.append(&mut round_trips);
+
+        checks.push(match golden.as_ref().map(|golden| golden.get(&key)) {
+            None => CheckResult {
+                name: format!("golden/{}", key),
+                status: CheckStatus::Skipped,
+                detail: "no golden file".to_string(),
+            },
+            Some(None) => CheckResult {
+                name: format!("golden/{}", key),
+                status: CheckStatus::Fail,
+                detail: format!("no golden hash (got {})", hash),
+            },
+            Some(Some(expected)) => CheckResult {
+                name: format!("golden/{}", key),
+                status: if *expected == hash {
+                    CheckStatus::Pass
+                } else {
+                    CheckStatus::Fail
+                },
+                detail: format!("expected {}, got {}", expected, hash),
+            },
+        });
+        eprintln!("Finished {} in {:?}", key, started.elapsed());
+        hashes.insert(key, hash);
+    }
+
+    if let Some(corpus) = &command.corpus {
+        eprintln!("Running corpus {:?}", corpus);
+        checks.append(&mut run_corpus(corpus));
+    }
+
+    if command.bless {
+        if let Some(path) = &command.golden {
+            std::fs::write(path, serde_json::to_string_pretty(&hashes).unwrap()).unwrap();
+            eprintln!("Golden hashes written into: {:?}", path);
+        }
+    }
+
+    let summary = CiSummary {
+        passed: checks.iter().all(|check| check.status != CheckStatus::Fail),
+        checks,
+    };
+    println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+    summary.passed
+}
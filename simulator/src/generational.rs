@@ -0,0 +1,81 @@
+use bugs_lib::{
+    generational::{GenerationalConfig, GenerationalDriver, Selection},
+    saves::SaveDir,
+    utils::pretty_number,
+};
+use rand_seeder::Seeder;
+use std::{path::PathBuf, time::Duration};
+
+use crate::parse_duration;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum SelectionKind {
+    Tournament,
+    Roulette,
+}
+
+/// Evolves bugs in synchronized generations instead of continuous ecosystem: each individual
+/// lives through a fixed episode alone, and the next generation is bred from the fittest
+#[derive(clap::Parser)]
+pub(crate) struct GenerationalCommand {
+    #[arg(short, long)]
+    seed: String,
+    /// Number of generations to run
+    #[arg(short, long, default_value = "50")]
+    generations: usize,
+    /// Individuals in each generation
+    #[arg(long, default_value = "64")]
+    population: usize,
+    /// Length of episode in seconds of simulated time
+    #[arg(long, value_parser = parse_duration, default_value = "300")]
+    episode: Duration,
+    /// Best individuals copied into the next generation unchanged
+    #[arg(long, default_value = "4")]
+    elite: usize,
+    #[arg(long, value_enum, default_value = "tournament")]
+    selection: SelectionKind,
+    /// Number of individuals competing in each tournament
+    #[arg(long, default_value = "4")]
+    tournament_size: usize,
+    /// Directory for saves (`saves` next to the executable by default)
+    #[arg(long)]
+    save_dir: Option<PathBuf>,
+}
+
+pub(crate) fn run_generational(command: GenerationalCommand) {
+    println!("Run generational evolution with seed: {}", command.seed);
+    let config = GenerationalConfig {
+        population: command.population,
+        episode: command.episode,
+        elite: command.elite,
+        selection: match command.selection {
+            SelectionKind::Tournament => Selection::Tournament {
+                size: command.tournament_size,
+            },
+            SelectionKind::Roulette => Selection::Roulette,
+        },
+        ..Default::default()
+    };
+    println!("Config: {:?}", config);
+    let mut driver = GenerationalDriver::new(config, Seeder::from(command.seed).make_seed());
+
+    let mut last = None;
+    for _ in 0..command.generations {
+        let (summary, scored) = driver.proceed();
+        println!(
+            "Generation {}, best fitness: {}, mean fitness: {}",
+            summary.generation,
+            pretty_number(summary.best_fitness),
+            pretty_number(summary.mean_fitness)
+        );
+        last = Some(scored);
+    }
+
+    if let Some(scored) = last {
+        let save_dir = SaveDir::new(command.save_dir.unwrap_or_else(SaveDir::default_path));
+        let slot = save_dir
+            .save(&save_dir.free_name("generational"), &scored)
+            .unwrap();
+        println!("Saving scored last generation into: {:?}", slot.path);
+    }
+}
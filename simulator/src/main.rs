@@ -1,11 +1,22 @@
+mod ci;
+mod generational;
+
 use bugs_lib::{
+    curriculum::{Curriculum, CurriculumRule, Difficulty},
+    env_config::EnvConfig,
     env_presets,
-    environment::SeededEnvironment,
-    time_point::{StaticTimePoint, TimePoint as _},
-    utils::{pretty_duration, Float},
+    environment::{BrainLogPolicy, SeededEnvironment, StopConditions, TickOutcome},
+    events::EnvironmentEvent,
+    report::{html_report, Timelapse, TIMELAPSE_CAPACITY},
+    saves::SaveDir,
+    scenario::{Goal, Scenario},
+    time_point::{StaticTimePoint, TimePoint},
+    utils::{pretty_duration, pretty_number, Float},
 };
 use chrono::{DateTime, Utc};
-use clap::{ArgAction, Parser};
+use ci::{run_ci, CiCommand};
+use clap::{ArgAction, Args as ClapArgs, Parser};
+use generational::{run_generational, GenerationalCommand};
 use memory_stats::memory_stats;
 use rand_seeder::Seeder;
 use serde::Serialize;
@@ -20,23 +31,134 @@ use std::{
 enum Args {
     New(NewCommand),
     Load(LoadCommand),
+    Ci(CiCommand),
+    Compact(CompactCommand),
+    Generational(GenerationalCommand),
 }
 
 fn parse_duration(arg: &str) -> Result<Duration, ParseIntError> {
     Ok(Duration::from_secs(arg.parse()?))
 }
 
+/// Conditions which stop simulation in addition to extinction
+#[derive(ClapArgs)]
+struct StopArgs {
+    /// Stop when population exceeds this number
+    #[arg(long)]
+    max_population: Option<usize>,
+    /// Stop when simulated time exceeds this number of seconds
+    #[arg(long, value_parser = parse_duration)]
+    max_sim_time: Option<Duration>,
+    /// Stop when number of species drops below this number
+    #[arg(long)]
+    min_species: Option<usize>,
+    /// Report suspicious dynamics: bugs breeding without moving, energy gained from nowhere,
+    /// bugs lost far away and NaN in state
+    #[arg(long)]
+    detect_anomalies: bool,
+    /// Stop when anomaly detector flags suspicious dynamics (implies `--detect-anomalies`)
+    #[arg(long)]
+    stop_on_anomaly: bool,
+}
+
+impl From<StopArgs> for StopConditions {
+    fn from(value: StopArgs) -> Self {
+        Self {
+            extinction: true,
+            max_population: value.max_population,
+            max_sim_time: value.max_sim_time,
+            min_species_count: value.min_species,
+            anomaly: value.stop_on_anomaly,
+        }
+    }
+}
+
+/// Goals of scenario. Simulation stops with victory when any of them is met and with defeat on
+/// extinction
+#[derive(ClapArgs)]
+struct ScenarioArgs {
+    /// Win when population reaches this number
+    #[arg(long)]
+    goal_population: Option<usize>,
+    /// Win when a species living at start survives this number of simulated days
+    #[arg(long)]
+    goal_survival_days: Option<u64>,
+    /// Win when total energy of bugs reaches this value
+    #[arg(long)]
+    goal_total_energy: Option<Float>,
+}
+
+impl ScenarioArgs {
+    fn scenario(&self) -> Option<Scenario> {
+        let victory: Vec<Goal> = [
+            self.goal_population
+                .map(|population| Goal::Population { population }),
+            self.goal_survival_days
+                .map(|days| Goal::SpeciesSurvives { days }),
+            self.goal_total_energy
+                .map(|energy| Goal::TotalEnergy { energy }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!victory.is_empty())
+            .then(|| Scenario::new("cli".to_string(), victory, vec![Goal::Extinction]))
+    }
+}
+
 /// Generates simulation environment using provided seed
 #[derive(Parser)]
 struct NewCommand {
     #[arg(short, long)]
     seed: String,
+    /// Toml file with environment definition (food sources, initial bugs, world options).
+    /// Builtin preset is used by default
+    #[arg(long)]
+    env_config: Option<PathBuf>,
     /// Timeout in seconds. Simulation will stop after reaching this time limit
     #[arg(short, long, value_parser = parse_duration)]
     timeout: Option<Duration>,
     /// If true, continuously checks memory in another thread and panics if it reaches maximum
     #[arg(long, action = ArgAction::Set, default_value = "true")]
     check_memory_usage: bool,
+    /// Directory for saves and reports (`saves` next to the executable by default)
+    #[arg(long)]
+    save_dir: Option<PathBuf>,
+    #[command(flatten)]
+    stop: StopArgs,
+    /// Tighten environment over time (less food, higher metabolism)
+    #[arg(long, value_enum)]
+    curriculum: Option<CurriculumKind>,
+    #[command(flatten)]
+    scenario: ScenarioArgs,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum CurriculumKind {
+    /// Harden every hour of simulated time
+    Schedule,
+    /// Harden while population stays large and ease when it shrinks
+    Adaptive,
+}
+
+/// Population which adaptive curriculum keeps environment hard enough for
+const CURRICULUM_TARGET_POPULATION: usize = 500;
+
+/// Level change made by curriculum at once
+const CURRICULUM_STEP: Float = 0.05;
+
+impl From<CurriculumKind> for Curriculum {
+    fn from(value: CurriculumKind) -> Self {
+        let period = Duration::from_secs(60 * 60);
+        let rule = match value {
+            CurriculumKind::Schedule => CurriculumRule::Schedule { period },
+            CurriculumKind::Adaptive => CurriculumRule::Adaptive {
+                period,
+                target_population: CURRICULUM_TARGET_POPULATION,
+            },
+        };
+        Curriculum::new(rule, Difficulty::default(), CURRICULUM_STEP)
+    }
 }
 
 /// Loads simulation environment from json save file
@@ -49,35 +171,152 @@ struct LoadCommand {
     /// If true, continuously checks memory in another thread and panics if it reaches maximum
     #[arg(long, action = ArgAction::Set, default_value = "true")]
     check_memory_usage: bool,
+    /// Directory for saves and reports (`saves` next to the executable by default)
+    #[arg(long)]
+    save_dir: Option<PathBuf>,
+    #[command(flatten)]
+    stop: StopArgs,
+}
+
+/// Renumbers bugs and food of json save contiguously. Lineage references are rewritten, so
+/// compacted save continues the same simulation
+#[derive(Parser)]
+struct CompactCommand {
+    file: PathBuf,
+    /// Where to write compacted save (the input file is overwritten by default)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
 }
 
-fn save<T: Serialize>(environment: &SeededEnvironment<T>) {
-    let exe_path = std::env::current_exe().unwrap();
-    let exe_dir = exe_path.parent().unwrap();
+fn compact(command: CompactCommand) {
+    let mut environment: SeededEnvironment<StaticTimePoint> =
+        serde_json::from_str(&std::fs::read_to_string(&command.file).unwrap()).unwrap();
+    let id_map = environment.compact_ids();
+    let output = command.output.unwrap_or(command.file);
+    std::fs::write(&output, serde_json::to_string(&environment).unwrap()).unwrap();
+    println!(
+        "Renumbered {} bugs (max id {} -> {}) into: {:?}",
+        id_map.len(),
+        id_map.keys().last().copied().unwrap_or_default(),
+        id_map.len().saturating_sub(1),
+        output
+    );
+}
+
+/// Exit code of `ci` when any check fails
+const CI_FAILED_EXIT_CODE: i32 = 1;
+
+/// Exit code of `load` when save contains no living bugs
+const EXTINCT_EXIT_CODE: i32 = 2;
+
+/// Exit code of `new` when environment config can not be loaded
+const INVALID_ENV_CONFIG_EXIT_CODE: i32 = 3;
+
+/// Printed instead of running simulation when there is nothing to simulate
+#[derive(Serialize)]
+struct ExtinctionReport {
+    status: &'static str,
+    iteration: usize,
+    sim_time_secs: Float,
+    extinct_since_secs: Option<Float>,
+    food_count: usize,
+    plants_count: usize,
+    hall_of_fame: Vec<usize>,
+}
+
+impl ExtinctionReport {
+    fn new(environment: &SeededEnvironment<StaticTimePoint>) -> Self {
+        let start = environment.creation_time();
+        Self {
+            status: "extinct",
+            iteration: environment.iteration(),
+            sim_time_secs: environment.now().duration_since(start).as_secs_f64(),
+            extinct_since_secs: environment
+                .extinct_since()
+                .map(|time| time.duration_since(start).as_secs_f64()),
+            food_count: environment.food_count(),
+            plants_count: environment.plants_count(),
+            hall_of_fame: environment.phylogeny().hall_of_fame().to_vec(),
+        }
+    }
+}
+
+/// Iterations between two time-lapse samples (doubled each time the time-lapse is full)
+const TIMELAPSE_PERIOD: usize = 30;
+
+/// Write html report of the whole run next to the saves
+fn save_report<T: TimePoint>(
+    save_dir: &SaveDir,
+    timelapse: &mut Timelapse,
+    environment: &SeededEnvironment<T>,
+) {
+    timelapse.record_now(environment);
+    let now: DateTime<Utc> = SystemTime::now().into();
+    let title = format!("Simulation report {}", now.format("%d.%m.%Y %H:%M:%S"));
+    let path = save_dir
+        .path()
+        .join(format!("report_{}.html", now.format("%d.%m.%Y_%H-%M-%S")));
+    std::fs::create_dir_all(save_dir.path()).unwrap();
+    std::fs::write(&path, html_report(&title, timelapse, environment)).unwrap();
+    println!("Saving report into: {:?}", path);
+}
+
+fn save<T: Serialize>(save_dir: &SaveDir, environment: &SeededEnvironment<T>) {
     let now: DateTime<Utc> = SystemTime::now().into();
-    let save_path = exe_dir.join(format!("save_{}.json", now.format("%d.%m.%Y_%H:%M:%S")));
-    println!("Saving into: {:?}", save_path);
-    std::fs::write(
-        &save_path,
-        serde_json::to_string_pretty(&environment).unwrap(),
-    )
-    .unwrap();
+    let slot = save_dir
+        .save(
+            &format!("save_{}", now.format("%d.%m.%Y_%H-%M-%S")),
+            environment,
+        )
+        .unwrap();
+    println!("Saving into: {:?}", slot.path);
+
+    let report = save_dir
+        .save_report(
+            &format!("selection_report_{}", now.format("%d.%m.%Y_%H-%M-%S")),
+            &environment.stats().selection_report(),
+        )
+        .unwrap();
+    println!("Saving selection report into: {:?}", report);
 }
 
 fn main() {
     let args = Args::parse();
     let the_beginning_of_times = StaticTimePoint::default();
 
-    let (mut environment, timeout, check_memory_usage) = match args {
+    let (mut environment, timeout, check_memory_usage, save_dir, stop) = match args {
         Args::New(command) => {
             println!("Run simulation with seed: {}", command.seed);
+            let seed = Seeder::from(command.seed).make_seed();
+            let mut environment = match &command.env_config {
+                Some(path) => {
+                    println!("Environment config: {:?}", path);
+                    match EnvConfig::load(path) {
+                        Ok(env_config) => env_config.build(the_beginning_of_times.clone(), seed),
+                        Err(err) => {
+                            eprintln!("Failed to load environment config {:?}: {}", path, err);
+                            std::process::exit(INVALID_ENV_CONFIG_EXIT_CODE);
+                        }
+                    }
+                }
+                None => {
+                    env_presets::less_food_further_from_center(the_beginning_of_times.clone(), seed)
+                }
+            };
+            if let Some(curriculum) = command.curriculum {
+                println!("Curriculum: {:?}", curriculum);
+                environment.set_curriculum(Some(curriculum.into()));
+            }
+            if let Some(scenario) = command.scenario.scenario() {
+                println!("Scenario goals: {:?}", scenario.victory);
+                environment.set_scenario(Some(scenario));
+            }
             (
-                env_presets::less_food_further_from_center(
-                    the_beginning_of_times.clone(),
-                    Seeder::from(command.seed).make_seed(),
-                ),
+                environment,
                 command.timeout,
                 command.check_memory_usage,
+                command.save_dir,
+                command.stop,
             )
         }
         Args::Load(command) => {
@@ -86,14 +325,45 @@ fn main() {
                 serde_json::from_str(&std::fs::read_to_string(command.file).unwrap()).unwrap(),
                 command.timeout,
                 command.check_memory_usage,
+                command.save_dir,
+                command.stop,
             )
         }
+        Args::Ci(command) => {
+            if !run_ci(command) {
+                std::process::exit(CI_FAILED_EXIT_CODE);
+            }
+            return;
+        }
+        Args::Compact(command) => {
+            compact(command);
+            return;
+        }
+        Args::Generational(command) => {
+            run_generational(command);
+            return;
+        }
     };
 
-    println!(
-        "First bug genes: {:?}",
-        environment.bugs().next().unwrap().chromosome().genes
-    );
+    match environment.bugs().next() {
+        Some(bug) => println!("First bug genes: {:?}", bug.chromosome().genes),
+        None => {
+            let report = ExtinctionReport::new(&environment);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            std::process::exit(EXTINCT_EXIT_CODE);
+        }
+    }
+
+    if stop.detect_anomalies || stop.stop_on_anomaly {
+        environment.set_anomaly_detection(Some(Default::default()));
+    }
+    environment.set_stop_conditions(stop.into());
+    // Nobody looks at brain logs in headless mode
+    environment.set_brain_log_policy(BrainLogPolicy::Pinned(Default::default()));
+    println!("Stop conditions: {:?}", environment.stop_conditions());
+
+    let save_dir = SaveDir::new(save_dir.unwrap_or_else(SaveDir::default_path));
+    println!("Save dir: {:?}", save_dir.path());
 
     if let Some(timeout) = timeout {
         println!("Timeout is set to: {}", pretty_duration(timeout));
@@ -101,6 +371,17 @@ fn main() {
 
     println!("Check memory usage: {}", check_memory_usage);
 
+    let _ = environment.subscribe(|event| match event {
+        EnvironmentEvent::AnomalyDetected(anomaly) => println!("Anomaly detected: {}", anomaly),
+        EnvironmentEvent::CatastropheStruck(catastrophe) => {
+            println!("Catastrophe struck: {:?}", catastrophe)
+        }
+        EnvironmentEvent::PopulationLow { population } => {
+            println!("Population is low: {}", population)
+        }
+        _ => {}
+    });
+
     if check_memory_usage {
         std::thread::spawn(|| loop {
             if let Some(usage) = memory_stats() {
@@ -127,8 +408,19 @@ fn main() {
     let mut last_cycle_instant = real_simulation_start_time.clone();
     let mut last_log_instant = real_simulation_start_time.clone();
     let mut last_save_instant = real_simulation_start_time.clone();
-    while environment.bugs_count() > 0 {
-        environment.proceed(sim_dt);
+    let mut timelapse = Timelapse::new(TIMELAPSE_PERIOD, TIMELAPSE_CAPACITY);
+    timelapse.record_now(&environment);
+    loop {
+        let outcome = environment.proceed(sim_dt);
+        if let TickOutcome::Stopped(reason) = outcome {
+            if let Some(result) = environment.scenario_result() {
+                println!("Scenario completed: {}", result);
+            }
+            println!("Simulation stopped: {:?}", reason);
+            save(&save_dir, &environment);
+            break;
+        }
+        timelapse.record(&environment);
         let now = Instant::now();
         let real_dt = now - last_cycle_instant;
         last_cycle_instant = now;
@@ -136,33 +428,34 @@ fn main() {
 
         if now - last_log_instant > Duration::from_secs(5) {
             println!(
-                "Iteration {}, time: {}, population: {}, food: {}, time_speed: {:.2}, performance: {:.2}",
+                "Iteration {}, time: {}, population: {}, food: {}, time_speed: {}, performance: {}",
                 environment.iteration(),
-                pretty_duration(environment.now().duration_since(&the_beginning_of_times)),
-                environment.bugs_count(),
-                environment.food_count(),
-                time_speed,
-                environment.bugs_count() as Float * time_speed
+                environment.clock(),
+                pretty_number(environment.bugs_count() as Float),
+                pretty_number(environment.food_count() as Float),
+                pretty_number(time_speed),
+                pretty_number(environment.bugs_count() as Float * time_speed)
             );
             last_log_instant = now
         }
 
         if now - last_save_instant > Duration::from_secs(60 * 5) {
-            save(&environment);
+            save(&save_dir, &environment);
             last_save_instant = now
         }
 
         if let Some(timeout) = timeout {
             if now - real_simulation_start_time > timeout {
-                save(&environment);
+                save(&save_dir, &environment);
                 break;
             }
         }
 
         if let Ok(_) = ctrl_c_rx.try_recv() {
             println!("\n");
-            save(&environment);
+            save(&save_dir, &environment);
             break;
         }
     }
+    save_report(&save_dir, &mut timelapse, &environment);
 }
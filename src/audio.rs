@@ -0,0 +1,173 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use bugs_lib::events::EnvironmentEvent;
+
+/// Events older than that are not counted in event rate
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+/// Event rate (per second) at which cue is played at full volume
+const FULL_VOLUME_RATE: f32 = 64.;
+/// Volume of a cue for a single event relative to full volume
+const MIN_GAIN: f32 = 0.2;
+/// The same cue is not repeated more often than that, bursts of events are merged into one cue
+const MIN_CUE_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Sound played for a kind of events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cue {
+    Birth,
+    Death,
+    Nuke,
+    ExtinctionWarning,
+    Anomaly,
+    ScenarioCompleted,
+}
+
+impl Cue {
+    const COUNT: usize = 6;
+
+    /// None for events which are too frequent to be heard
+    fn from_event(event: &EnvironmentEvent) -> Option<Self> {
+        match event {
+            EnvironmentEvent::BugBorn { .. } => Some(Self::Birth),
+            EnvironmentEvent::BugDied { .. } => Some(Self::Death),
+            EnvironmentEvent::FoodEaten { .. } | EnvironmentEvent::FoodSpawned { .. } => None,
+            EnvironmentEvent::AreaIrradiated { .. } => Some(Self::Nuke),
+            EnvironmentEvent::PopulationLow { .. } => Some(Self::ExtinctionWarning),
+            EnvironmentEvent::AnomalyDetected(_) => Some(Self::Anomaly),
+            EnvironmentEvent::CatastropheStruck(_) => Some(Self::Nuke),
+            EnvironmentEvent::ScenarioCompleted(_) => Some(Self::ScenarioCompleted),
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Frequency (Hz), duration and loudness of the tone relative to other cues
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    fn tone(self) -> (f32, Duration, f32) {
+        match self {
+            Self::Birth => (880., Duration::from_millis(40), 0.3),
+            Self::Death => (220., Duration::from_millis(60), 0.3),
+            Self::Nuke => (110., Duration::from_millis(300), 0.6),
+            Self::ExtinctionWarning => (440., Duration::from_millis(500), 1.),
+            Self::Anomaly => (1320., Duration::from_millis(200), 0.8),
+            Self::ScenarioCompleted => (660., Duration::from_millis(800), 1.),
+        }
+    }
+}
+
+/// Plays subtle sounds for simulation events. Cue volume grows with the rate of events of its
+/// kind. Without `audio` feature events are counted but nothing is played
+pub(crate) struct AudioFeedback {
+    volume: f32,
+    muted: bool,
+    /// Instants of recent events per cue (oldest first)
+    recent: [VecDeque<Instant>; Cue::COUNT],
+    last_played: [Option<Instant>; Cue::COUNT],
+    /// Stream is kept alive while sounds are played through its handle. None if there is no
+    /// output device or sound was never unmuted
+    #[cfg(feature = "audio")]
+    output: Option<(rodio::OutputStream, rodio::OutputStreamHandle)>,
+}
+
+impl AudioFeedback {
+    /// `volume` in range 0..=1
+    pub(crate) fn new(volume: f32, muted: bool) -> Self {
+        let mut result = Self {
+            volume: volume.clamp(0., 1.),
+            muted: true,
+            recent: Default::default(),
+            last_played: Default::default(),
+            #[cfg(feature = "audio")]
+            output: None,
+        };
+        result.set_muted(muted);
+        result
+    }
+
+    pub(crate) fn muted(&self) -> bool {
+        self.muted
+    }
+
+    pub(crate) fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        #[cfg(feature = "audio")]
+        if !muted && self.output.is_none() {
+            match rodio::OutputStream::try_default() {
+                Ok(output) => self.output = Some(output),
+                Err(err) => eprintln!("Failed to open audio output: {}", err),
+            }
+        }
+    }
+
+    pub(crate) fn handle_events(&mut self, events: &[EnvironmentEvent], now: Instant) {
+        let mut happened = [false; Cue::COUNT];
+        for event in events {
+            let Some(cue) = Cue::from_event(event) else {
+                continue;
+            };
+            self.recent[cue.index()].push_back(now);
+            happened[cue.index()] = true;
+        }
+        for recent in &mut self.recent {
+            while recent
+                .front()
+                .is_some_and(|instant| now.duration_since(*instant) > RATE_WINDOW)
+            {
+                recent.pop_front();
+            }
+        }
+
+        if self.muted {
+            return;
+        }
+        for cue in [
+            Cue::Birth,
+            Cue::Death,
+            Cue::Nuke,
+            Cue::ExtinctionWarning,
+            Cue::Anomaly,
+            Cue::ScenarioCompleted,
+        ] {
+            let i = cue.index();
+            if !happened[i]
+                || self.last_played[i]
+                    .is_some_and(|instant| now.duration_since(instant) < MIN_CUE_INTERVAL)
+            {
+                continue;
+            }
+            self.last_played[i] = Some(now);
+            self.play(cue, self.gain(self.recent[i].len()));
+        }
+    }
+
+    /// Volume of a cue for `rate` events per `RATE_WINDOW`
+    fn gain(&self, rate: usize) -> f32 {
+        let gain = (1. + rate as f32).ln() / (1. + FULL_VOLUME_RATE).ln();
+        self.volume * gain.clamp(MIN_GAIN, 1.)
+    }
+
+    #[cfg(feature = "audio")]
+    fn play(&self, cue: Cue, gain: f32) {
+        use rodio::Source as _;
+
+        let Some((_, handle)) = &self.output else {
+            return;
+        };
+        let (frequency, duration, loudness) = cue.tone();
+        let source = rodio::source::SineWave::new(frequency)
+            .take_duration(duration)
+            .fade_in(duration / 4)
+            .amplify(gain * loudness);
+        if let Err(err) = handle.play_raw(source) {
+            eprintln!("Failed to play sound: {}", err);
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    fn play(&self, _cue: Cue, _gain: f32) {}
+}
@@ -0,0 +1,168 @@
+use std::{
+    fs::File,
+    io::{self, BufRead as _, BufReader, BufWriter, Write as _},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use bugs_lib::utils::Float;
+use serde::{Deserialize, Serialize};
+
+use crate::Tool;
+
+/// Pointer action as reported by `pointer-event` callback of main window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum PointerEventKind {
+    Up,
+    Down,
+    Move,
+    /// Pointer left environment canvas
+    Cancel,
+}
+
+impl PointerEventKind {
+    pub(crate) fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Self::Up),
+            1 => Some(Self::Down),
+            2 => Some(Self::Move),
+            3 => Some(Self::Cancel),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum PointerButton {
+    Left,
+    Right,
+}
+
+impl PointerButton {
+    pub(crate) fn from_code(code: i32) -> Self {
+        if code == 1 {
+            Self::Right
+        } else {
+            Self::Left
+        }
+    }
+}
+
+/// Interaction with GUI as seen by `State`. Coordinates are in pixels of environment canvas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) enum InputEvent {
+    Pointer {
+        kind: PointerEventKind,
+        button: PointerButton,
+        x: Float,
+        y: Float,
+    },
+    Scroll {
+        x: Float,
+        y: Float,
+        delta_y: Float,
+        shift: bool,
+        control: bool,
+    },
+    Key {
+        text: String,
+    },
+    ToolClicked {
+        tool: Tool,
+    },
+    /// Simulation timer fired `dt` after the previous time
+    Tick {
+        dt: Duration,
+    },
+}
+
+/// Input with everything needed to handle it the same way again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RecordedInput {
+    /// Time since recording started
+    pub(crate) at: Duration,
+    /// Size of environment canvas
+    pub(crate) viewport: (Float, Float),
+    pub(crate) event: InputEvent,
+}
+
+/// Writes inputs into a file as JSON lines, so recording survives a crash
+pub(crate) struct InputRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl InputRecorder {
+    pub(crate) fn create(path: &Path, start: Instant) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            start,
+        })
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        event: &InputEvent,
+        now: Instant,
+        viewport: (Float, Float),
+    ) -> io::Result<()> {
+        let input = RecordedInput {
+            at: now.saturating_duration_since(self.start),
+            viewport,
+            event: event.clone(),
+        };
+        serde_json::to_writer(&mut self.writer, &input)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+}
+
+/// Read inputs written by `InputRecorder`
+pub(crate) fn read_recording(path: &Path) -> io::Result<Vec<RecordedInput>> {
+    BufReader::new(File::open(path)?)
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{read_recording, InputEvent, InputRecorder, RecordedInput};
+
+    #[test]
+    fn recorded_inputs_are_read_back() {
+        let path = std::env::temp_dir().join("bugs_recorded_inputs_test.jsonl");
+        let start = Instant::now();
+        let events = [
+            InputEvent::Key {
+                text: " ".to_string(),
+            },
+            InputEvent::Tick {
+                dt: Duration::from_millis(33),
+            },
+        ];
+        let mut recorder = InputRecorder::create(&path, start).unwrap();
+        for (i, event) in events.iter().enumerate() {
+            recorder
+                .record(event, start + Duration::from_secs(i as u64), (800., 600.))
+                .unwrap();
+        }
+
+        assert_eq!(
+            read_recording(&path).unwrap(),
+            events
+                .into_iter()
+                .enumerate()
+                .map(|(i, event)| RecordedInput {
+                    at: Duration::from_secs(i as u64),
+                    viewport: (800., 600.),
+                    event,
+                })
+                .collect::<Vec<_>>()
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,32 +1,64 @@
 #![deny(unused_imports)]
 
 use app_utils::color_to_slint_rgba_color;
+use audio::AudioFeedback;
+use bugs_lib::brain::{MAX_HIDDEN_SIZE, OUTPUT_SIZE};
+use bugs_lib::config::ConfigWatcher;
+use bugs_lib::env_config::EnvConfig;
 use bugs_lib::env_presets;
-use bugs_lib::environment::SeededEnvironment;
-use bugs_lib::math::{noneg_float, Angle, NoNeg, Point};
+use bugs_lib::environment::{
+    BrainLogPolicy, BugHandle, ChunkSleepConfig, FoodSourceCreateInfo, SeededEnvironment,
+    StopConditions, TickOutcome, MAX_INTERACTION_RADIUS,
+};
+use bugs_lib::events::EnvironmentEvent;
+use bugs_lib::food_source::FoodSourceShape;
+use bugs_lib::island::Migration;
+use bugs_lib::math::{noneg_float, Angle, NoNeg, Point, Rect};
+use bugs_lib::phylogeny::LineageEntry;
+use bugs_lib::saves::SaveDir;
+use bugs_lib::scenario::{Goal, Scenario};
+use bugs_lib::stats::SelectionReport;
+use bugs_lib::tag::Tag;
 use bugs_lib::time_point::{StaticTimePoint, TimePoint as _};
-use bugs_lib::utils::{pretty_duration, Color, Float};
+use bugs_lib::utils::{pretty_duration, pretty_number, pretty_percent, Color, Float};
 use clap::Parser;
+use input::{
+    read_recording, InputEvent, InputRecorder, PointerButton, PointerEventKind, RecordedInput,
+};
 use rand::Rng;
-use render::{BrainRenderModel, Camera, ChunksDisplayMode, EnvironmentRenderModel};
-use slint::{CloseRequestResponse, ComponentHandle, PlatformError, Timer, TimerMode};
-use std::cell::RefCell;
+use render::{
+    population_bounds, BrainRenderModel, BudgetHistory, BugAnimations, Camera, CameraAnimator,
+    CameraPose, DebugOverlay, DemoDirector, EnvironmentRenderModel, FrameBudget,
+};
+use serde::{Deserialize, Serialize};
+use slint::{
+    CloseRequestResponse, ComponentHandle, PlatformError, RenderingState, Timer, TimerMode,
+};
+use std::cell::{Cell, RefCell};
 use std::path::PathBuf;
 use std::rc::Rc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
 mod app_utils;
+mod audio;
+mod input;
 mod render;
 
 slint::slint! {
-    export { MainWindow, BugInfo, EnvInfo, DisplayTool } from "src/main.slint";
+    export {
+        MainWindow, BugInfo, EnvInfo, DisplayTool, LeaderboardEntry, DisplayDebugOverlay,
+        DebugOverlayInfo, SaveSlotInfo, SaveSlotAction, ExtinctionInfo, ExtinctionAction,
+        FoodSourceEditorInfo, Format
+    } from "src/main.slint";
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 enum Tool {
     Nuke,
     Food,
     SpawnBug,
+    FoodSource,
+    Tag,
     None,
 }
 
@@ -36,6 +68,8 @@ impl From<Tool> for DisplayTool {
             Tool::Nuke => Self::Nuke,
             Tool::Food => Self::Food,
             Tool::SpawnBug => Self::SpawnBug,
+            Tool::FoodSource => Self::FoodSource,
+            Tool::Tag => Self::Tag,
             Tool::None => Self::None,
         }
     }
@@ -47,19 +81,126 @@ impl From<DisplayTool> for Tool {
             DisplayTool::Nuke => Self::Nuke,
             DisplayTool::Food => Self::Food,
             DisplayTool::SpawnBug => Self::SpawnBug,
+            DisplayTool::FoodSource => Self::FoodSource,
+            DisplayTool::Tag => Self::Tag,
             DisplayTool::None => Self::None,
         }
     }
 }
 
+impl From<DisplayDebugOverlay> for DebugOverlay {
+    fn from(value: DisplayDebugOverlay) -> Self {
+        match value {
+            DisplayDebugOverlay::FoodChunks => Self::FOOD_CHUNKS,
+            DisplayDebugOverlay::BugChunks => Self::BUG_CHUNKS,
+            DisplayDebugOverlay::VisionArcs => Self::VISION_ARCS,
+            DisplayDebugOverlay::VelocityVectors => Self::VELOCITY_VECTORS,
+            DisplayDebugOverlay::Ids => Self::IDS,
+            DisplayDebugOverlay::LoadHeatmap => Self::LOAD_HEATMAP,
+            DisplayDebugOverlay::Pheromones => Self::PHEROMONES,
+            DisplayDebugOverlay::Odor => Self::ODOR,
+            DisplayDebugOverlay::Territory => Self::TERRITORY,
+            DisplayDebugOverlay::Biomes => Self::BIOMES,
+            DisplayDebugOverlay::Light => Self::LIGHT,
+            DisplayDebugOverlay::Sounds => Self::SOUNDS,
+            DisplayDebugOverlay::StatusBars => Self::STATUS_BARS,
+            DisplayDebugOverlay::Elevation => Self::ELEVATION,
+            DisplayDebugOverlay::BudgetHud => Self::BUDGET_HUD,
+        }
+    }
+}
+
+impl From<DebugOverlay> for DebugOverlayInfo {
+    fn from(value: DebugOverlay) -> Self {
+        Self {
+            food_chunks: value.contains(DebugOverlay::FOOD_CHUNKS),
+            bug_chunks: value.contains(DebugOverlay::BUG_CHUNKS),
+            vision_arcs: value.contains(DebugOverlay::VISION_ARCS),
+            velocity_vectors: value.contains(DebugOverlay::VELOCITY_VECTORS),
+            ids: value.contains(DebugOverlay::IDS),
+            load_heatmap: value.contains(DebugOverlay::LOAD_HEATMAP),
+            pheromones: value.contains(DebugOverlay::PHEROMONES),
+            odor: value.contains(DebugOverlay::ODOR),
+            territory: value.contains(DebugOverlay::TERRITORY),
+            biomes: value.contains(DebugOverlay::BIOMES),
+            light: value.contains(DebugOverlay::LIGHT),
+            sounds: value.contains(DebugOverlay::SOUNDS),
+            status_bars: value.contains(DebugOverlay::STATUS_BARS),
+            elevation: value.contains(DebugOverlay::ELEVATION),
+            budget_hud: value.contains(DebugOverlay::BUDGET_HUD),
+        }
+    }
+}
+
 pub const NUKE_RADIUS: NoNeg<Float> = noneg_float(200.);
 
+/// How much magnifier zooms relative to the main camera
+pub const MAGNIFIER_ZOOM: Float = 4.;
+
+/// Width and height of world of `EnvPreset::BoundedArena`
+pub const BOUNDED_ARENA_SIZE: Float = 4000.;
+
+/// Number of bugs displayed in efficiency leaderboard
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// Selection report scans all recorded deaths, so it is recomputed once per that many iterations
+/// instead of on every frame
+const SELECTION_REPORT_PERIOD: usize = 100;
+
+/// Side of food source added by food source tool
+const NEW_FOOD_SOURCE_SIZE: Float = 500.;
+
+/// Bugs closer than that to pointer are tagged by tag tool
+const TAG_TOOL_RADIUS: NoNeg<Float> = noneg_float(30.);
+
+/// Tag attached by tag tool. It is inherited, so marked group can be followed through generations
+fn tool_tag() -> Tag {
+    Tag::new("marked").inherited().with_color(Color {
+        a: 1.,
+        r: 1.,
+        g: 0.,
+        b: 0.8,
+    })
+}
+
+/// Number of parasites released at once by `p` key
+const PARASITE_RELEASE_COUNT: usize = 16;
+
+/// How often config file is checked for changes
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of random inputs recorded when brain test vectors are exported
+pub const BRAIN_TEST_VECTORS_COUNT: usize = 64;
+
+/// Ancestors of the champion spawned one by one in a test arena
+struct LineageReplay {
+    lineage: Vec<LineageEntry>,
+    index: usize,
+    /// Main environment is put aside while arena is displayed
+    suspended_environment: SeededEnvironment<StaticTimePoint>,
+}
+
 struct State {
     environment: SeededEnvironment<StaticTimePoint>,
+    save_dir: SaveDir,
+    /// Name of slot which is written on quick save and on exit
+    save_slot: String,
+    /// Preset used to restart simulation after extinction
+    preset: EnvPreset,
+    lineage_replay: Option<LineageReplay>,
     camera: Camera,
+    /// Moves camera while demo mode is on
+    demo_director: Option<DemoDirector>,
+    camera_animator: CameraAnimator,
+    /// View saved with `b` and restored with `g`
+    camera_bookmark: Option<CameraPose>,
     environment_render_model: RefCell<EnvironmentRenderModel>,
+    magnifier_render_model: RefCell<EnvironmentRenderModel>,
+    magnifier_enabled: bool,
     brain_render_model: RefCell<BrainRenderModel>,
-    selected_bug_id: Option<usize>,
+    selected_bug: Option<BugHandle>,
+    /// Iteration at which displayed selection report was computed
+    selection_report_iteration: Option<usize>,
     time_speed: Float,
     pause: bool,
     selected_node: Option<(usize, usize)>,
@@ -67,7 +208,742 @@ struct State {
     active_tool: Tool,
     tool_action_point: Option<Point<Float>>,
     tool_action_active: bool,
-    chunks_display_mode: ChunksDisplayMode,
+    debug_overlay: DebugOverlay,
+    budget_history: BudgetHistory,
+    bug_animations: BugAnimations,
+    audio: AudioFeedback,
+    config_watcher: Option<ConfigWatcher>,
+    last_config_poll_instant: Instant,
+    /// How often simulation timer should fire
+    desired_tps: Float,
+    /// Size of environment canvas at the last frame
+    viewport: (Float, Float),
+    input_recorder: Option<InputRecorder>,
+    /// Last error which did not stop simulation (e.g. invalid config)
+    error_message: Option<String>,
+}
+
+/// What GUI has to update after `State` handled an input
+#[derive(Default)]
+struct InputResponse {
+    /// False if input was not used and can be handled by other elements
+    handled: bool,
+    /// New contents of food source editor
+    food_source_editor: Option<FoodSourceEditorInfo>,
+}
+
+impl InputResponse {
+    fn handled() -> Self {
+        Self {
+            handled: true,
+            ..Default::default()
+        }
+    }
+}
+
+impl State {
+    fn new(
+        environment: SeededEnvironment<StaticTimePoint>,
+        save_dir: SaveDir,
+        save_slot: String,
+        preset: EnvPreset,
+    ) -> Self {
+        Self {
+            environment,
+            save_dir,
+            save_slot,
+            preset,
+            lineage_replay: None,
+            selected_bug: None,
+            selection_report_iteration: None,
+            camera: Default::default(),
+            demo_director: None,
+            camera_animator: Default::default(),
+            camera_bookmark: None,
+            environment_render_model: Default::default(),
+            magnifier_render_model: Default::default(),
+            magnifier_enabled: false,
+            brain_render_model: Default::default(),
+            time_speed: 1.,
+            pause: true,
+            selected_node: None,
+            tps: 0.,
+            active_tool: Tool::None,
+            tool_action_point: None,
+            tool_action_active: false,
+            debug_overlay: DebugOverlay::NONE,
+            budget_history: Default::default(),
+            bug_animations: Default::default(),
+            audio: AudioFeedback::new(0., true),
+            config_watcher: None,
+            last_config_poll_instant: Instant::now(),
+            desired_tps: 30.,
+            viewport: (0., 0.),
+            input_recorder: None,
+            error_message: None,
+        }
+    }
+
+    fn main_environment(&self) -> &SeededEnvironment<StaticTimePoint> {
+        self.lineage_replay
+            .as_ref()
+            .map(|replay| &replay.suspended_environment)
+            .unwrap_or(&self.environment)
+    }
+
+    /// Apply config file if it changed since the last check. Lineage arena gets it as well
+    fn reload_config(&mut self) {
+        let Some(watcher) = &mut self.config_watcher else {
+            return;
+        };
+        match watcher.poll() {
+            Some(Ok(config)) => {
+                if let Some(replay) = &mut self.lineage_replay {
+                    replay.suspended_environment.apply_config(config.clone());
+                }
+                if self.environment.apply_config(config.clone()) {
+                    println!(
+                        "Config applied at iteration {}: {:?}",
+                        self.main_environment().iteration(),
+                        config
+                    );
+                }
+            }
+            Some(Err(err)) => {
+                // the current config stays applied
+                let message = format!("Failed to load config {:?}: {}", watcher.path(), err);
+                eprintln!("{}", message);
+                self.error_message = Some(message);
+            }
+            None => {}
+        }
+    }
+
+    fn save(&self) {
+        match self.save_dir.save(&self.save_slot, self.main_environment()) {
+            Ok(slot) => println!("Saved into: {:?}", slot.path),
+            Err(err) => eprintln!("Failed to save into slot {:?}: {}", self.save_slot, err),
+        }
+    }
+
+    fn handle_save_slot_action(&mut self, action: SaveSlotAction, name: &str) {
+        let result = match action {
+            SaveSlotAction::Save => {
+                self.save();
+                Ok(())
+            }
+            SaveSlotAction::SaveNew => {
+                self.save_slot = self.save_dir.free_name("save");
+                self.save();
+                Ok(())
+            }
+            SaveSlotAction::Load => self.save_dir.load(name).map(|environment| {
+                self.replace_environment(environment);
+                self.save_slot = name.to_string();
+            }),
+            SaveSlotAction::Duplicate => self.save_dir.duplicate(name).map(|_| ()),
+            SaveSlotAction::Delete => self.save_dir.delete(name),
+        };
+        if let Err(err) = result {
+            eprintln!("Save slot {:?} action {:?} failed: {}", name, action, err);
+        }
+    }
+
+    /// Replace simulated environment dropping all state bound to the old one
+    fn replace_environment(&mut self, mut environment: SeededEnvironment<StaticTimePoint>) {
+        environment.set_stop_conditions(stop_conditions());
+        environment.set_anomaly_detection(Some(Default::default()));
+        self.lineage_replay = None;
+        self.environment = environment;
+        self.selected_bug = None;
+        self.selection_report_iteration = None;
+    }
+
+    fn handle_extinction_action(&mut self, action: ExtinctionAction, center: Point<Float>) {
+        match action {
+            ExtinctionAction::SpawnHallOfFame => {
+                let count = self.environment.spawn_hall_of_fame(center);
+                println!("Spawned {} bugs from hall of fame", count);
+            }
+            ExtinctionAction::RestartPreset => {
+                self.replace_environment(create_environment(&self.preset));
+                // keep extinct world in its slot
+                self.save_slot = self.save_dir.free_name("save");
+            }
+        }
+    }
+
+    fn extinction_info(&self) -> ExtinctionInfo {
+        let environment = &self.environment;
+        ExtinctionInfo {
+            extinct: environment.bugs_count() == 0,
+            since: environment
+                .extinct_since()
+                .map(|time| environment.clock_at(time).to_string())
+                .unwrap_or_else(|| "unknown".to_string())
+                .into(),
+            hall_of_fame_size: environment.phylogeny().hall_of_fame().len() as i32,
+        }
+    }
+
+    fn save_slot_infos(&self) -> Vec<SaveSlotInfo> {
+        let now = SystemTime::now();
+        self.save_dir
+            .slots()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|slot| SaveSlotInfo {
+                current: slot.name == self.save_slot,
+                modified: format!(
+                    "{} ago",
+                    pretty_duration(now.duration_since(slot.modified).unwrap_or_default())
+                )
+                .into(),
+                name: slot.name.into(),
+            })
+            .collect()
+    }
+
+    fn spawn_lineage_arena(&mut self) {
+        if let Some(replay) = &self.lineage_replay {
+            let entry = &replay.lineage[replay.index];
+            println!(
+                "Lineage replay: generation {}/{} (bug id: {}, offspring: {})",
+                entry.generation + 1,
+                replay.lineage.len(),
+                entry.bug_id,
+                entry.offspring_count
+            );
+            self.environment = env_presets::test_arena(
+                StaticTimePoint::default(),
+                rand::thread_rng().gen(),
+                entry.chromosome.clone(),
+            );
+            self.selected_bug = None;
+            self.selection_report_iteration = None;
+        }
+    }
+
+    fn toggle_lineage_replay(&mut self, export_path: &PathBuf) {
+        if let Some(replay) = self.lineage_replay.take() {
+            self.environment = replay.suspended_environment;
+            self.selected_bug = None;
+            self.selection_report_iteration = None;
+        } else {
+            let lineage = self.environment.phylogeny().champion_line_of_descent();
+            if lineage.is_empty() {
+                return;
+            }
+            println!("Exporting lineage into: {:?}", export_path);
+            std::fs::write(export_path, serde_json::to_string_pretty(&lineage).unwrap()).unwrap();
+
+            let arena = env_presets::test_arena(
+                StaticTimePoint::default(),
+                rand::thread_rng().gen(),
+                lineage[0].chromosome.clone(),
+            );
+            self.lineage_replay = Some(LineageReplay {
+                lineage,
+                index: 0,
+                suspended_environment: std::mem::replace(&mut self.environment, arena),
+            });
+            self.spawn_lineage_arena();
+        }
+    }
+
+    /// New selection report if the displayed one was computed `SELECTION_REPORT_PERIOD` or more
+    /// iterations ago
+    fn outdated_selection_report(&mut self) -> Option<SelectionReport> {
+        let iteration = self.environment.iteration();
+        if let Some(computed_at) = self.selection_report_iteration {
+            if computed_at <= iteration && iteration < computed_at + SELECTION_REPORT_PERIOD {
+                return None;
+            }
+        }
+        self.selection_report_iteration = Some(iteration);
+        Some(self.environment.stats().selection_report())
+    }
+
+    /// Selected bug. None if it has died since it was selected
+    fn alive_selected_bug(&self) -> Option<BugHandle> {
+        self.selected_bug
+            .filter(|handle| self.environment.resolve(*handle).is_some())
+    }
+
+    /// Id of selected bug. None if it has died since it was selected
+    fn selected_bug_id(&self) -> Option<usize> {
+        self.alive_selected_bug().map(|handle| handle.id())
+    }
+
+    /// Width of hidden layer of brain of selected bug
+    fn selected_hidden_size(&self) -> usize {
+        self.selected_bug
+            .and_then(|handle| self.environment.resolve(handle))
+            .map(|bug| bug.brain().hidden_size())
+            .unwrap_or(MAX_HIDDEN_SIZE)
+    }
+
+    /// Write responses of selected bug brain to random inputs (see `BrainTestVector`)
+    fn export_brain_test_vectors(&self) {
+        let Some(bug) = self
+            .selected_bug
+            .and_then(|handle| self.environment.resolve(handle))
+        else {
+            return;
+        };
+        let vectors = bug
+            .brain()
+            .random_test_vectors(BRAIN_TEST_VECTORS_COUNT, &mut rand::thread_rng());
+        let path = self
+            .save_dir
+            .path()
+            .join(format!("brain_test_vectors_{}.json", bug.id()));
+        println!("Exporting brain test vectors into: {:?}", path);
+        std::fs::create_dir_all(self.save_dir.path()).unwrap();
+        std::fs::write(path, serde_json::to_string_pretty(&vectors).unwrap()).unwrap();
+    }
+
+    fn food_source_editor_info(&self, index: usize) -> FoodSourceEditorInfo {
+        let Some(food_source) = self.environment.food_sources().nth(index) else {
+            return Default::default();
+        };
+        let info = food_source.create_info();
+        let (circle, width, height) = match info.shape {
+            FoodSourceShape::Rect { size } => (false, *size.w(), *size.h()),
+            FoodSourceShape::Circle { radius } => (true, radius.unwrap(), radius.unwrap()),
+        };
+        FoodSourceEditorInfo {
+            selected: true,
+            index: index as i32,
+            circle,
+            x: info.position.x().to_string().into(),
+            y: info.position.y().to_string().into(),
+            width: width.to_string().into(),
+            height: height.to_string().into(),
+            energy_min: info.energy_range.start.to_string().into(),
+            energy_max: info.energy_range.end.to_string().into(),
+            spawn_interval_ms: info.spawn_interval.as_millis().to_string().into(),
+            aquatic: info.aquatic,
+        }
+    }
+
+    /// Select food source under `point` or add a new one there if there is none
+    fn select_food_source(&mut self, point: Point<Float>) -> FoodSourceEditorInfo {
+        let index = match self.environment.food_source_at(point) {
+            Some(index) => index,
+            None => self.environment.add_food_source(FoodSourceCreateInfo {
+                position: point,
+                shape: FoodSourceShape::Rect {
+                    size: (NEW_FOOD_SOURCE_SIZE, NEW_FOOD_SOURCE_SIZE).into(),
+                },
+                energy_range: (0. ..1.).into(),
+                spawn_interval: Duration::from_secs(1),
+                aquatic: false,
+                nutrients: Default::default(),
+            }),
+        };
+        self.food_source_editor_info(index)
+    }
+
+    fn apply_food_source_edit(&mut self, info: &FoodSourceEditorInfo) -> Result<(), String> {
+        let number = |name: &str, text: &str| -> Result<Float, String> {
+            text.trim()
+                .parse::<Float>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| format!("{} is not a number", name))
+        };
+        let positive = |name: &str, text: &str| -> Result<Float, String> {
+            Some(number(name, text)?)
+                .filter(|value| *value > 0.)
+                .ok_or_else(|| format!("{} must be positive", name))
+        };
+        let energy_min = number("min energy", &info.energy_min)?;
+        let energy_max = number("max energy", &info.energy_max)?;
+        if energy_min < 0. || energy_min >= energy_max {
+            return Err("energy range must be non-negative and non-empty".to_string());
+        }
+        let create_info = FoodSourceCreateInfo {
+            position: (number("x", &info.x)?, number("y", &info.y)?).into(),
+            shape: if info.circle {
+                FoodSourceShape::Circle {
+                    radius: noneg_float(positive("radius", &info.width)?),
+                }
+            } else {
+                FoodSourceShape::Rect {
+                    size: (
+                        positive("width", &info.width)?,
+                        positive("height", &info.height)?,
+                    )
+                        .into(),
+                }
+            },
+            energy_range: (energy_min..energy_max).into(),
+            spawn_interval: Duration::from_secs_f64(
+                positive("spawn interval", &info.spawn_interval_ms)? / 1000.,
+            ),
+            aquatic: info.aquatic,
+            // not editable yet, so the current profile is kept
+            nutrients: self
+                .environment
+                .food_sources()
+                .nth(info.index as usize)
+                .map(|food_source| *food_source.nutrients())
+                .unwrap_or_default(),
+        };
+        if self
+            .environment
+            .edit_food_source(info.index as usize, create_info)
+        {
+            Ok(())
+        } else {
+            Err(format!("no food source {}", info.index))
+        }
+    }
+
+    fn step_lineage_replay(&mut self, forward: bool) {
+        if let Some(replay) = &mut self.lineage_replay {
+            replay.index = if forward {
+                (replay.index + 1).min(replay.lineage.len() - 1)
+            } else {
+                replay.index.saturating_sub(1)
+            };
+            self.spawn_lineage_arena();
+        }
+    }
+
+    /// Handle `event` which happened at `now`. Every input of GUI goes through here, so it can
+    /// be recorded and replayed
+    fn handle_input(&mut self, event: &InputEvent, now: Instant) -> InputResponse {
+        if let Some(recorder) = &mut self.input_recorder {
+            if let Err(err) = recorder.record(event, now, self.viewport) {
+                eprintln!("Failed to record input: {}", err);
+                self.input_recorder = None;
+            }
+        }
+        match event {
+            InputEvent::Pointer { kind, button, x, y } => {
+                self.handle_pointer_event(*kind, *button, (*x, *y).into())
+            }
+            InputEvent::Scroll {
+                x,
+                y,
+                delta_y,
+                shift,
+                control,
+            } => {
+                self.handle_scroll((*x, *y).into(), *delta_y, *shift, *control);
+                InputResponse::handled()
+            }
+            InputEvent::Key { text } => self.handle_key(text, now),
+            InputEvent::ToolClicked { tool } => {
+                self.active_tool = *tool;
+                InputResponse::handled()
+            }
+            InputEvent::Tick { dt } => {
+                self.tick(*dt);
+                InputResponse::handled()
+            }
+        }
+    }
+
+    /// Handle inputs again as if they happened since `start`. A frame is handled after each
+    /// input instead of being rendered
+    fn replay(&mut self, inputs: &[RecordedInput], start: Instant) {
+        for input in inputs {
+            let now = start + input.at;
+            self.viewport = input.viewport;
+            self.handle_input(&input.event, now);
+            self.handle_frame(now, input.viewport);
+        }
+    }
+
+    /// Advance simulation by `dt` of real time (multiplied by time speed) unless paused
+    fn tick(&mut self, dt: Duration) {
+        if self.pause {
+            self.tps = 0.;
+            return;
+        }
+        if self.tool_action_active {
+            if let Some(tool_action_point) = self.tool_action_point {
+                match self.active_tool {
+                    Tool::Nuke => self
+                        .environment
+                        .irradiate_area(tool_action_point, NUKE_RADIUS),
+                    Tool::Food => self.environment.add_food(tool_action_point),
+                    Tool::SpawnBug => self.environment.add_bug(tool_action_point),
+                    Tool::Tag => {
+                        self.environment.tag_bugs_in_circle(
+                            tool_action_point,
+                            TAG_TOOL_RADIUS,
+                            tool_tag(),
+                        );
+                    }
+                    // food sources are selected on click instead
+                    Tool::FoodSource | Tool::None => {}
+                }
+            }
+        }
+
+        if let TickOutcome::Stopped(reason) = self.environment.proceed(dt.mul_f64(self.time_speed))
+        {
+            println!("Simulation stopped: {:?}", reason);
+            self.pause = true;
+        }
+        self.tps = 1. / dt.as_secs_f64();
+    }
+
+    /// Update everything which changes with frames but is not drawn directly: animations,
+    /// sounds and camera
+    fn handle_frame(&mut self, now: Instant, viewport: (Float, Float)) {
+        self.viewport = viewport;
+        let events = self.environment.drain_events();
+        for event in &events {
+            match event {
+                EnvironmentEvent::AnomalyDetected(anomaly) => {
+                    println!("Anomaly detected: {}", anomaly)
+                }
+                EnvironmentEvent::CatastropheStruck(catastrophe) => {
+                    println!("Catastrophe struck: {:?}", catastrophe)
+                }
+                EnvironmentEvent::ScenarioCompleted(result) => {
+                    println!("Scenario completed: {}", result)
+                }
+                _ => {}
+            }
+        }
+        self.bug_animations.handle_events(&events, now);
+        self.bug_animations.proceed(now);
+        self.audio.handle_events(&events, now);
+
+        let (width, height) = viewport;
+        if let Some(director) = &mut self.demo_director {
+            director.handle_events(&events, self.environment.iteration());
+            self.camera = director.camera(&self.environment, now, width, height);
+        } else {
+            self.camera_animator
+                .proceed(&mut self.camera, &self.environment, now, width, height);
+        }
+    }
+
+    /// What camera shows in environment canvas now
+    fn current_pose(&self) -> Option<CameraPose> {
+        CameraPose::of(&self.camera, self.viewport.0, self.viewport.1)
+    }
+
+    /// `position` is in pixels of environment canvas
+    fn handle_pointer_event(
+        &mut self,
+        kind: PointerEventKind,
+        button: PointerButton,
+        position: Point<Float>,
+    ) -> InputResponse {
+        let mut response = InputResponse::handled();
+        let point: Point<_> = &(!&self.camera.transformation()).unwrap() * &position;
+
+        match kind {
+            PointerEventKind::Up => {
+                if button == PointerButton::Left && self.active_tool == Tool::FoodSource {
+                    response.food_source_editor = Some(self.select_food_source(point));
+                    self.tool_action_active = false
+                } else if button == PointerButton::Left {
+                    struct BugInfo {
+                        id: usize,
+                        position: Point<Float>,
+                        eat_range: NoNeg<Float>,
+                    }
+
+                    let nearest_bug = self
+                        .environment
+                        .query()
+                        .bugs()
+                        .in_circle(point, MAX_INTERACTION_RADIUS)
+                        .iter()
+                        .min_by(|a, b| {
+                            (point - a.position())
+                                .len()
+                                .partial_cmp(&(point - b.position()).len())
+                                .unwrap()
+                        })
+                        .map(|bug| BugInfo {
+                            id: bug.id(),
+                            position: bug.position(),
+                            eat_range: bug.eat_range(),
+                        });
+
+                    if let Some(nearest_bug) = nearest_bug {
+                        self.selected_bug = if (point - nearest_bug.position).len()
+                            < nearest_bug.eat_range.unwrap()
+                        {
+                            self.environment.bug_handle(nearest_bug.id)
+                        } else {
+                            None
+                        };
+                    }
+                    self.tool_action_active = false
+                } else {
+                    self.active_tool = Tool::None;
+                }
+            }
+            PointerEventKind::Down => {
+                if button == PointerButton::Left {
+                    self.tool_action_active = true
+                }
+            }
+            PointerEventKind::Move => self.tool_action_point = Some(point),
+            PointerEventKind::Cancel => {
+                self.tool_action_point = None;
+                self.tool_action_active = false
+            }
+        }
+        response
+    }
+
+    /// `position` is in pixels of environment canvas
+    fn handle_scroll(
+        &mut self,
+        position: Point<Float>,
+        delta_y: Float,
+        shift: bool,
+        control: bool,
+    ) {
+        // manual camera control ends demo mode and animations
+        self.demo_director = None;
+        self.camera_animator.stop();
+
+        let default_deltas_per_step: Float = 120.;
+
+        let angle_delta_to_scale_division = |angle_delta: Float| {
+            let base: Float = 1.2;
+
+            base.powf(angle_delta / default_deltas_per_step)
+        };
+
+        let angle_delta_to_translation_delta = |angle_delta: Float| {
+            let velocity: Float = 10.; // px per step
+            return velocity * angle_delta / default_deltas_per_step;
+        };
+
+        if control {
+            // zoom
+            self.camera.concat_scale_centered(
+                angle_delta_to_scale_division(delta_y),
+                position,
+                position,
+            );
+        } else if shift {
+            // scroll horizontally
+            self.camera
+                .add_translation((angle_delta_to_translation_delta(delta_y), 0.).into());
+        } else {
+            // scroll vertically
+            self.camera
+                .add_translation((0., angle_delta_to_translation_delta(delta_y)).into());
+        }
+    }
+
+    fn handle_key(&mut self, text: &str, now: Instant) -> InputResponse {
+        let f1 = [0xEF, 0x9C, 0x84];
+        let f2 = [0xEF, 0x9C, 0x85];
+
+        if let Ok(lvl) = text.parse::<u32>() {
+            self.time_speed = (2_u32).pow(lvl) as f64;
+            self.desired_tps = match lvl {
+                9 => 240.,
+                8 => 120.,
+                7 => 60.,
+                _ => 30.,
+            };
+        } else if text.as_bytes() == f1 {
+            self.debug_overlay = self.debug_overlay.rotated_chunks();
+        } else if text.as_bytes() == f2 {
+            self.environment.collect_unused_chunks();
+        } else if text == "q" {
+            self.save();
+        } else if text == "l" {
+            let export_path = self.save_dir.path().join("lineage.json");
+            self.toggle_lineage_replay(&export_path);
+        } else if text == "v" {
+            self.export_brain_test_vectors();
+        } else if text == "[" {
+            self.step_lineage_replay(false);
+        } else if text == "]" {
+            self.step_lineage_replay(true);
+        } else if text == "," || text == "." {
+            let count = self.environment.island_count();
+            let step = if text == "," { count - 1 } else { 1 };
+            let index = (self.environment.viewed_island() + step) % count;
+            if self.environment.view_island(index) {
+                self.selected_bug = None;
+            }
+        } else if text == "m" {
+            self.magnifier_enabled = !self.magnifier_enabled;
+        } else if text == "u" {
+            let muted = self.audio.muted();
+            self.audio.set_muted(!muted);
+        } else if text == "t" {
+            self.demo_director = match self.demo_director {
+                Some(_) => None,
+                // start flying from what is on the screen now
+                None => self.current_pose().map(DemoDirector::new),
+            };
+        } else if text == "h" {
+            let (width, height) = self.viewport;
+            if let (Some(from), Some(bounds)) =
+                (self.current_pose(), population_bounds(&self.environment))
+            {
+                let to = CameraPose::fitting(&bounds, width, height);
+                self.camera_animator.animate(from, to, now);
+            }
+        } else if text == "b" {
+            self.camera_bookmark = self.current_pose();
+        } else if text == "g" {
+            if let (Some(from), Some(to)) = (self.current_pose(), self.camera_bookmark) {
+                self.camera_animator.animate(from, to, now);
+            }
+        } else if text == "c" {
+            match (
+                self.camera_animator.followed_bug(),
+                self.alive_selected_bug(),
+            ) {
+                (None, Some(bug)) => self.camera_animator.follow(bug),
+                _ => self.camera_animator.stop(),
+            }
+        } else if text == "p" {
+            // release parasites in the middle of the screen
+            if let Some(pose) = self.current_pose() {
+                self.environment
+                    .add_parasites(pose.center, PARASITE_RELEASE_COUNT);
+            }
+        } else if text == "z" {
+            let config = match self.environment.chunk_sleep() {
+                Some(_) => None,
+                None => Some(ChunkSleepConfig::default()),
+            };
+            self.environment.set_chunk_sleep(config);
+        } else if text == " " {
+            self.pause = !self.pause;
+        } else if text == "w" {
+            let hidden_size = self.selected_hidden_size();
+            let (layer, i) = self.selected_node.get_or_insert((0, 0));
+            *i = (*i - 1) % layer_size(hidden_size, *layer);
+        } else if text == "a" {
+            let i = &mut self.selected_node.get_or_insert((0, 0)).0;
+            *i = (*i - 1) % 2;
+        } else if text == "s" {
+            let hidden_size = self.selected_hidden_size();
+            let (layer, i) = self.selected_node.get_or_insert((0, 0));
+            *i = (*i + 1) % layer_size(hidden_size, *layer);
+        } else if text == "d" {
+            let i = &mut self.selected_node.get_or_insert((0, 0)).0;
+            *i = (*i + 1) % 2;
+        } else if text == "f" {
+            self.selected_node = None;
+        } else {
+            return Default::default();
+        }
+        InputResponse::handled()
+    }
 }
 
 #[derive(Parser)]
@@ -82,77 +958,295 @@ enum Args {
 enum EnvPreset {
     NestedRects,
     Circle,
+    PlantMeadow,
+    Lakes,
+    Hills,
+    BoundedArena,
+    Biomes,
+    SunlitGlades,
+    Procedural,
+}
+
+/// Number of nodes in layer of brain which can be selected (0 - hidden, 1 - output)
+fn layer_size(hidden_size: usize, layer: usize) -> usize {
+    if layer == 0 {
+        hidden_size
+    } else {
+        OUTPUT_SIZE
+    }
+}
+
+/// Simulation is paused when population dies out
+fn stop_conditions() -> StopConditions {
+    StopConditions {
+        extinction: true,
+        ..Default::default()
+    }
+}
+
+fn env_canvas_size(window: &MainWindow) -> (Float, Float) {
+    (
+        window.get_requested_env_canvas_width() as Float,
+        window.get_requested_env_canvas_height() as Float,
+    )
 }
 
-/// Generates simulation environment from one of builtin presets
+fn create_environment(preset: &EnvPreset) -> SeededEnvironment<StaticTimePoint> {
+    match preset {
+        EnvPreset::NestedRects => env_presets::less_food_further_from_center(
+            StaticTimePoint::default(),
+            rand::thread_rng().gen(),
+        ),
+        EnvPreset::Circle => {
+            env_presets::one_big_circle(StaticTimePoint::default(), rand::thread_rng().gen())
+        }
+        EnvPreset::PlantMeadow => {
+            env_presets::plant_meadow(StaticTimePoint::default(), rand::thread_rng().gen())
+        }
+        EnvPreset::Lakes => {
+            env_presets::lakes(StaticTimePoint::default(), rand::thread_rng().gen())
+        }
+        EnvPreset::Hills => {
+            env_presets::hills(StaticTimePoint::default(), rand::thread_rng().gen())
+        }
+        EnvPreset::BoundedArena => env_presets::bounded_arena(
+            StaticTimePoint::default(),
+            rand::thread_rng().gen(),
+            BOUNDED_ARENA_SIZE,
+        ),
+        EnvPreset::Biomes => {
+            env_presets::biomes(StaticTimePoint::default(), rand::thread_rng().gen())
+        }
+        EnvPreset::SunlitGlades => {
+            env_presets::sunlit_glades(StaticTimePoint::default(), rand::thread_rng().gen())
+        }
+        EnvPreset::Procedural => env_presets::procedural(
+            StaticTimePoint::default(),
+            rand::thread_rng().gen(),
+            Default::default(),
+        ),
+    }
+}
+
+/// Generates simulation environment from one of builtin presets or from environment config
 #[derive(Parser)]
 struct NewCommand {
-    #[arg(short, long)]
+    /// Preset to generate environment from. With `--env-config` it is only used to restart
+    /// simulation if population dies out
+    #[arg(short, long, default_value = "nested-rects")]
     env_preset: EnvPreset,
+    /// Toml file with environment definition (food sources, initial bugs, world options)
+    #[arg(long)]
+    env_config: Option<PathBuf>,
+    /// Number of islands generated from the preset (switched with `,` and `.`)
+    #[arg(long, default_value_t = 1)]
+    islands: usize,
+    /// Simulated seconds between migrations of bugs between islands
+    #[arg(long, default_value_t = 600)]
+    migration_period: u64,
+    /// Number of random bugs leaving each island per migration (0 keeps islands isolated)
+    #[arg(long, default_value_t = 2)]
+    migrants: usize,
+    #[command(flatten)]
+    scenario: ScenarioArgs,
+    /// Directory with save slots (`saves` next to the executable by default)
+    #[arg(long)]
+    save_dir: Option<PathBuf>,
+    /// Slot to save into (first free `save_<n>` by default)
+    #[arg(long)]
+    slot: Option<String>,
+    #[command(flatten)]
+    config: ConfigArgs,
+    #[command(flatten)]
+    view: ViewArgs,
+}
+
+/// Goals of scenario. Simulation pauses with victory when any of them is met and with defeat on
+/// extinction
+#[derive(clap::Args)]
+struct ScenarioArgs {
+    /// Win when population reaches this number
+    #[arg(long)]
+    goal_population: Option<usize>,
+    /// Win when a species living at start survives this number of simulated days
+    #[arg(long)]
+    goal_survival_days: Option<u64>,
+    /// Win when total energy of bugs reaches this value
+    #[arg(long)]
+    goal_total_energy: Option<Float>,
+}
+
+impl ScenarioArgs {
+    fn scenario(&self) -> Option<Scenario> {
+        let victory: Vec<Goal> = [
+            self.goal_population
+                .map(|population| Goal::Population { population }),
+            self.goal_survival_days
+                .map(|days| Goal::SpeciesSurvives { days }),
+            self.goal_total_energy
+                .map(|energy| Goal::TotalEnergy { energy }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!victory.is_empty())
+            .then(|| Scenario::new("cli".to_string(), victory, vec![Goal::Extinction]))
+    }
+}
+
+#[derive(clap::Args)]
+struct ViewArgs {
+    /// Start in demo mode where camera flies between interesting events (toggled with `t`)
+    #[arg(long)]
+    demo: bool,
+    /// Play sounds for simulation events (toggled with `u`). Requires `audio` feature
+    #[arg(long)]
+    sound: bool,
+    /// Volume of event sounds in range 0..=1
+    #[arg(long, default_value_t = 0.5)]
+    volume: f32,
+    /// Write every input (clicks, keys, scrolls and simulation ticks) into this file
+    #[arg(long)]
+    record_input: Option<PathBuf>,
+    /// Replay inputs written with `--record-input` before showing the window. Load the same
+    /// save slot to get the same result
+    #[arg(long)]
+    replay_input: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct ConfigArgs {
+    /// Toml file with simulation parameters. It is reloaded while simulation runs
+    #[arg(long)]
+    config: Option<PathBuf>,
 }
 
-/// Loads simulation environment from json save file
+/// Loads simulation environment from save slot
 #[derive(Parser)]
 struct LoadCommand {
-    #[arg(short, long)]
-    save_file: Option<PathBuf>,
+    /// Directory with save slots (`saves` next to the executable by default)
+    #[arg(long)]
+    save_dir: Option<PathBuf>,
+    /// Slot to load (the most recent one by default)
+    #[arg(long)]
+    slot: Option<String>,
+    /// Preset used to restart simulation if population dies out
+    #[arg(short, long, default_value = "nested-rects")]
+    env_preset: EnvPreset,
+    #[command(flatten)]
+    config: ConfigArgs,
+    #[command(flatten)]
+    view: ViewArgs,
 }
 
 pub fn main() -> Result<(), PlatformError> {
-    let (save_path, environment) = match Args::parse() {
+    // errors which do not prevent simulation from starting are shown in the window
+    let mut startup_error: Option<String> = None;
+    let (save_dir, save_slot, preset, config, view, mut environment) = match Args::parse() {
         Args::New(command) => {
-            let exe_path = std::env::current_exe().unwrap();
-            let exe_dir = exe_path.parent().unwrap();
-            let save_path = exe_dir.join("save.json");
+            let save_dir = SaveDir::new(command.save_dir.unwrap_or_else(SaveDir::default_path));
+            let save_slot = command.slot.unwrap_or_else(|| save_dir.free_name("save"));
 
+            // preset is used if environment config can not be loaded
+            let env_config = command.env_config.as_ref().and_then(|path| {
+                EnvConfig::load(path)
+                    .map_err(|err| {
+                        startup_error = Some(format!(
+                            "Failed to load environment config {:?}: {}",
+                            path, err
+                        ))
+                    })
+                    .ok()
+            });
+            let create = || match &env_config {
+                Some(env_config) => env_config
+                    .clone()
+                    .build(StaticTimePoint::default(), rand::thread_rng().gen()),
+                None => create_environment(&command.env_preset),
+            };
+            let mut environment = create();
+            for _ in 1..command.islands {
+                environment.add_island(create().into_environment());
+            }
+            if command.islands > 1 && command.migrants > 0 {
+                environment.set_migration(Some(Migration::new(
+                    Duration::from_secs(command.migration_period),
+                    command.migrants,
+                )));
+            }
+            environment.set_scenario(command.scenario.scenario());
             (
-                save_path,
-                match command.env_preset {
-                    EnvPreset::NestedRects => env_presets::less_food_further_from_center(
-                        StaticTimePoint::default(),
-                        rand::thread_rng().gen(),
-                    ),
-                    EnvPreset::Circle => env_presets::one_big_circle(
-                        StaticTimePoint::default(),
-                        rand::thread_rng().gen(),
-                    ),
-                },
+                save_dir,
+                save_slot,
+                command.env_preset,
+                command.config,
+                command.view,
+                environment,
             )
         }
         Args::Load(command) => {
-            let save_path = command.save_file.unwrap_or_else(|| {
-                let exe_path = std::env::current_exe().unwrap();
-                let exe_dir = exe_path.parent().unwrap();
-                exe_dir.join("save.json")
+            let save_dir = SaveDir::new(command.save_dir.unwrap_or_else(SaveDir::default_path));
+            let save_slot = command.slot.unwrap_or_else(|| {
+                save_dir
+                    .slots()
+                    .unwrap()
+                    .first()
+                    .expect("No saves found")
+                    .name
+                    .clone()
             });
+            let environment = save_dir.load(&save_slot).unwrap();
             (
-                save_path.clone(),
-                serde_json::from_str(&std::fs::read_to_string(&save_path).unwrap()).unwrap(),
+                save_dir,
+                save_slot,
+                command.env_preset,
+                command.config,
+                command.view,
+                environment,
             )
         }
     };
 
-    println!(
-        "save_path: {:?}, (exist: {})",
-        save_path,
-        save_path.exists()
-    );
-
-    let state = Rc::new(RefCell::new(State {
-        environment,
-        selected_bug_id: None,
-        camera: Default::default(),
-        environment_render_model: Default::default(),
-        brain_render_model: Default::default(),
-        time_speed: 1.,
-        pause: true,
-        selected_node: None,
-        tps: 0.,
-        active_tool: Tool::None,
-        tool_action_point: None,
-        tool_action_active: false,
-        chunks_display_mode: ChunksDisplayMode::None,
-    }));
+    println!("save dir: {:?}, slot: {}", save_dir.path(), save_slot);
+
+    environment.set_stop_conditions(stop_conditions());
+    environment.set_anomaly_detection(Some(Default::default()));
+
+    let mut state = State::new(environment, save_dir, save_slot, preset);
+    if let Some(err) = startup_error {
+        eprintln!("{}", err);
+        state.error_message = Some(err);
+    }
+    if view.demo {
+        state.demo_director = Some(DemoDirector::new(CameraPose {
+            center: Point::origin(),
+            scale: 1.,
+        }));
+    }
+    state.audio = AudioFeedback::new(view.volume, !view.sound);
+    state.config_watcher = config.config.map(ConfigWatcher::new);
+    if let Some(path) = &view.replay_input {
+        match read_recording(path) {
+            Ok(inputs) => {
+                // replayed inputs end right now
+                let duration = inputs.last().map(|input| input.at).unwrap_or_default();
+                let start = Instant::now()
+                    .checked_sub(duration)
+                    .unwrap_or_else(Instant::now);
+                state.replay(&inputs, start);
+                println!("Replayed {} inputs from {:?}", inputs.len(), path);
+            }
+            Err(err) => eprintln!("Failed to replay inputs from {:?}: {}", path, err),
+        }
+    }
+    if let Some(path) = &view.record_input {
+        match InputRecorder::create(path, Instant::now()) {
+            Ok(recorder) => state.input_recorder = Some(recorder),
+            Err(err) => eprintln!("Failed to record inputs into {:?}: {}", path, err),
+        }
+    }
+    let desired_tps = state.desired_tps;
+    let state = Rc::new(RefCell::new(state));
 
     let (ctrl_c_tx, ctrl_c_rx) = std::sync::mpsc::channel();
     ctrlc::set_handler(move || {
@@ -169,33 +1263,18 @@ pub fn main() -> Result<(), PlatformError> {
         let weak_state = Rc::downgrade(&state);
         timer.start(
             TimerMode::Repeated,
-            std::time::Duration::from_millis(1000 / 30),
+            std::time::Duration::from_millis((1000. / desired_tps) as u64),
             move || {
                 let now = Instant::now();
                 let dt = now - last_tick_instant;
                 last_tick_instant = now;
                 let state = weak_state.upgrade().unwrap();
                 let mut state = state.borrow_mut();
-                if !state.pause {
-                    if state.tool_action_active {
-                        if let Some(tool_action_point) = state.tool_action_point {
-                            match state.active_tool {
-                                Tool::Nuke => state
-                                    .environment
-                                    .irradiate_area(tool_action_point, NUKE_RADIUS),
-                                Tool::Food => state.environment.add_food(tool_action_point),
-                                Tool::SpawnBug => state.environment.add_bug(tool_action_point),
-                                Tool::None => {}
-                            }
-                        }
-                    }
-
-                    let time_speed = state.time_speed;
-                    state.environment.proceed(dt.mul_f64(time_speed));
-                    state.tps = 1. / dt.as_secs_f64();
-                } else {
-                    state.tps = 0.;
+                if now - state.last_config_poll_instant > CONFIG_POLL_INTERVAL {
+                    state.last_config_poll_instant = now;
+                    state.reload_config();
                 }
+                state.handle_input(&InputEvent::Tick { dt }, now);
             },
         );
     }
@@ -208,114 +1287,150 @@ pub fn main() -> Result<(), PlatformError> {
 
     let main_window = MainWindow::new().unwrap();
 
+    main_window
+        .global::<Format>()
+        .on_number(|value| pretty_number(value as Float).into());
+    main_window
+        .global::<Format>()
+        .on_percent(|ratio| pretty_percent(ratio as Float).into());
+
     {
         let weak_state = Rc::downgrade(&state);
         main_window.on_tool_clicked(move |tool: DisplayTool| {
             let state = weak_state.upgrade().unwrap();
             let mut state = state.try_borrow_mut().unwrap();
-            state.active_tool = tool.into();
+            state.handle_input(
+                &InputEvent::ToolClicked { tool: tool.into() },
+                Instant::now(),
+            );
         })
     }
 
+    main_window.set_save_slots(state.borrow().save_slot_infos()[..].into());
+
     {
         let weak_state = Rc::downgrade(&state);
-        main_window.on_pointer_event(move |event_type, button, x: f32, y: f32| {
+        let weak_window = main_window.as_weak();
+        main_window.on_extinction_action(move |action| {
             let state = weak_state.upgrade().unwrap();
             let mut state = state.try_borrow_mut().unwrap();
+            let window = weak_window.upgrade().unwrap();
+            // spawn in the middle of the screen
+            let center = &(!&state.camera.transformation()).unwrap()
+                * &Point::from((
+                    window.get_requested_env_canvas_width() as Float / 2.,
+                    window.get_requested_env_canvas_height() as Float / 2.,
+                ));
+            state.handle_extinction_action(action, center);
+            window.set_save_slots(state.save_slot_infos()[..].into());
+        })
+    }
 
-            let point: Point<_> = &(!&state.camera.transformation()).unwrap()
-                * &Point::from((x as Float, y as Float));
-
-            if event_type == 0 {
-                if button == 0 {
-                    struct BugInfo {
-                        id: usize,
-                        position: Point<Float>,
-                        eat_range: NoNeg<Float>,
-                    }
+    {
+        let weak_state = Rc::downgrade(&state);
+        let weak_window = main_window.as_weak();
+        main_window.on_save_slot_action(move |action, name| {
+            let state = weak_state.upgrade().unwrap();
+            let mut state = state.try_borrow_mut().unwrap();
+            state.handle_save_slot_action(action, &name);
+            if let Some(window) = weak_window.upgrade() {
+                window.set_save_slots(state.save_slot_infos()[..].into());
+            }
+        })
+    }
 
-                    let nearest_bug = state
-                        .environment
-                        .bugs()
-                        .min_by(|a, b| {
-                            (point - a.position())
-                                .len()
-                                .partial_cmp(&(point - b.position()).len())
-                                .unwrap()
-                        })
-                        .map(|bug| BugInfo {
-                            id: bug.id(),
-                            position: bug.position(),
-                            eat_range: bug.eat_range(),
-                        });
+    {
+        let weak_state = Rc::downgrade(&state);
+        main_window.on_debug_overlay_toggled(move |overlay: DisplayDebugOverlay| {
+            let state = weak_state.upgrade().unwrap();
+            let mut state = state.try_borrow_mut().unwrap();
+            state.debug_overlay.toggle(overlay.into());
+        })
+    }
 
-                    if let Some(nearest_bug) = nearest_bug {
-                        state.selected_bug_id = if (point - nearest_bug.position).len()
-                            < nearest_bug.eat_range.unwrap()
-                        {
-                            Some(nearest_bug.id)
-                        } else {
-                            None
-                        };
+    {
+        let weak_state = Rc::downgrade(&state);
+        let weak_window = main_window.as_weak();
+        main_window.on_food_source_applied(move |info| {
+            let state = weak_state.upgrade().unwrap();
+            let mut state = state.try_borrow_mut().unwrap();
+            match state.apply_food_source_edit(&info) {
+                Ok(()) => {
+                    if let Some(window) = weak_window.upgrade() {
+                        window.set_food_source_editor(
+                            state.food_source_editor_info(info.index as usize),
+                        );
                     }
-                    state.tool_action_active = false
-                } else {
-                    state.active_tool = Tool::None;
-                }
-            } else if event_type == 1 {
-                if button == 0 {
-                    state.tool_action_active = true
                 }
-            } else if event_type == 2 {
-                state.tool_action_point = Some(point)
-            } else if event_type == 3 {
-                state.tool_action_point = None;
-                state.tool_action_active = false
+                Err(err) => eprintln!("Failed to edit food source: {}", err),
             }
-        });
+        })
     }
 
     {
         let weak_state = Rc::downgrade(&state);
-        main_window.on_scroll_event(move |pos_x, pos_y, _delta_x, delta_y, shift, control| {
-            let position = (pos_x as Float, pos_y as Float).into();
-
+        let weak_window = main_window.as_weak();
+        main_window.on_food_source_deleted(move |index| {
             let state = weak_state.upgrade().unwrap();
             let mut state = state.try_borrow_mut().unwrap();
+            state.environment.remove_food_source(index as usize);
+            if let Some(window) = weak_window.upgrade() {
+                window.set_food_source_editor(Default::default());
+            }
+        })
+    }
 
-            let default_deltas_per_step: Float = 120.;
-
-            let angle_delta_to_scale_division = |angle_delta: Float| {
-                let base: Float = 1.2;
-
-                base.powf(angle_delta / default_deltas_per_step)
-            };
+    {
+        let weak_window = main_window.as_weak();
+        main_window.on_food_source_deselected(move || {
+            if let Some(window) = weak_window.upgrade() {
+                window.set_food_source_editor(Default::default());
+            }
+        })
+    }
 
-            let angle_delta_to_translation_delta = |angle_delta: Float| {
-                let velocity: Float = 10.; // px per step
-                return velocity * angle_delta / default_deltas_per_step;
+    {
+        let weak_state = Rc::downgrade(&state);
+        let weak_window = main_window.as_weak();
+        main_window.on_pointer_event(move |kind, button, x: f32, y: f32| {
+            let Some(kind) = PointerEventKind::from_code(kind) else {
+                return;
             };
-
-            if control {
-                // zoom
-                state.camera.concat_scale_centered(
-                    angle_delta_to_scale_division(delta_y as Float),
-                    position,
-                    position,
-                );
-            } else if shift {
-                // scroll horizontally
-                state.camera.add_translation(
-                    (angle_delta_to_translation_delta(delta_y as Float), 0.).into(),
-                );
-            } else {
-                // scroll vertically
-                state.camera.add_translation(
-                    (0., angle_delta_to_translation_delta(delta_y as Float)).into(),
-                );
+            let state = weak_state.upgrade().unwrap();
+            let mut state = state.try_borrow_mut().unwrap();
+            let response = state.handle_input(
+                &InputEvent::Pointer {
+                    kind,
+                    button: PointerButton::from_code(button),
+                    x: x as Float,
+                    y: y as Float,
+                },
+                Instant::now(),
+            );
+            if let (Some(info), Some(window)) = (response.food_source_editor, weak_window.upgrade())
+            {
+                window.set_food_source_editor(info);
             }
+        });
+    }
 
-            true
+    {
+        let weak_state = Rc::downgrade(&state);
+        main_window.on_scroll_event(move |pos_x, pos_y, _delta_x, delta_y, shift, control| {
+            let state = weak_state.upgrade().unwrap();
+            let mut state = state.try_borrow_mut().unwrap();
+            state
+                .handle_input(
+                    &InputEvent::Scroll {
+                        x: pos_x as Float,
+                        y: pos_y as Float,
+                        delta_y: delta_y as Float,
+                        shift,
+                        control,
+                    },
+                    Instant::now(),
+                )
+                .handled
         });
     }
 
@@ -326,66 +1441,40 @@ pub fn main() -> Result<(), PlatformError> {
 
     {
         let weak_state = Rc::downgrade(&state);
-        let save_path = save_path.clone();
         main_window.on_key_release_event(move |text| {
             let state = weak_state.upgrade().unwrap();
             let mut state = state.try_borrow_mut().unwrap();
-
-            let f1 = [0xEF, 0x9C, 0x84];
-            let f2 = [0xEF, 0x9C, 0x85];
-
-            if let Ok(lvl) = text.parse::<u32>() {
-                state.time_speed = (2_u32).pow(lvl) as f64;
-                match lvl {
-                    9 => set_desired_tps(240.),
-                    8 => set_desired_tps(120.),
-                    7 => set_desired_tps(60.),
-                    _ => set_desired_tps(30.),
-                }
-                true
-            } else if text.as_str().as_bytes() == f1 {
-                state.chunks_display_mode = state.chunks_display_mode.clone().rotated();
-                true
-            } else if text.as_str().as_bytes() == f2 {
-                state.environment.collect_unused_chunks();
-                true
-            } else if text == "q" {
-                std::fs::write(
-                    &save_path,
-                    serde_json::to_string_pretty(&state.environment).unwrap(),
-                )
-                .unwrap();
-                true
-            } else if text == " " {
-                state.pause = !state.pause;
-                true
-            } else if text == "w" {
-                let i = &mut state.selected_node.get_or_insert((0, 0)).1;
-                *i = (*i - 1) % 8;
-                true
-            } else if text == "a" {
-                let i = &mut state.selected_node.get_or_insert((0, 0)).0;
-                *i = (*i - 1) % 2;
-                true
-            } else if text == "s" {
-                let i = &mut state.selected_node.get_or_insert((0, 0)).1;
-                *i = (*i + 1) % 8;
-                true
-            } else if text == "d" {
-                let i = &mut state.selected_node.get_or_insert((0, 0)).0;
-                *i = (*i + 1) % 2;
-                true
-            } else if text == "f" {
-                state.selected_node = None;
-                true
-            } else {
-                false
+            let desired_tps = state.desired_tps;
+            let response = state.handle_input(
+                &InputEvent::Key {
+                    text: text.to_string(),
+                },
+                Instant::now(),
+            );
+            if state.desired_tps != desired_tps {
+                set_desired_tps(state.desired_tps);
             }
+            response.handled
         });
     }
     main_window.invoke_init_focus();
 
     let mut prev_render_instant = Instant::now();
+    let mut last_render_cpu = Duration::ZERO;
+
+    let render_gpu_wait = Rc::new(Cell::new(Duration::ZERO));
+    {
+        let render_gpu_wait = render_gpu_wait.clone();
+        let mut rendering_start = Instant::now();
+        // not supported by software renderer, in which case the phase stays empty
+        let _ = main_window
+            .window()
+            .set_rendering_notifier(move |rendering_state, _| match rendering_state {
+                RenderingState::BeforeRendering => rendering_start = Instant::now(),
+                RenderingState::AfterRendering => render_gpu_wait.set(rendering_start.elapsed()),
+                _ => {}
+            });
+    }
 
     let render_timer = Timer::default();
 
@@ -397,7 +1486,6 @@ pub fn main() -> Result<(), PlatformError> {
 
         let weak_state = Rc::downgrade(&state);
         let weak_window = main_window.as_weak();
-        let save_path = save_path.clone();
         render_timer.start(TimerMode::Repeated, render_interval, move || {
             if let Some(window) = weak_window.upgrade() {
                 let now = Instant::now();
@@ -405,43 +1493,213 @@ pub fn main() -> Result<(), PlatformError> {
                 prev_render_instant = now;
 
                 let state = weak_state.upgrade().unwrap();
+                {
+                    let mut state = state.borrow_mut();
+                    let observed_region = (!&state.camera.transformation()).map(|inverted| {
+                        &inverted
+                            * &Rect::from((
+                                0.,
+                                0.,
+                                window.get_requested_env_canvas_width() as Float,
+                                window.get_requested_env_canvas_height() as Float,
+                            ))
+                    });
+                    state.environment.set_observed_region(observed_region);
+                    let tracked_bug = state
+                        .camera_animator
+                        .followed_bug()
+                        .or(state.alive_selected_bug());
+                    state.environment.set_tracked_bug(tracked_bug);
+                    // Brain of the selected bug is evaluated on demand, so logs are only needed
+                    // when velocity overlay shows desired directions of all bugs
+                    let brain_log_policy =
+                        if state.debug_overlay.contains(DebugOverlay::VELOCITY_VECTORS) {
+                            BrainLogPolicy::All
+                        } else {
+                            BrainLogPolicy::Pinned(Default::default())
+                        };
+                    state.environment.set_brain_log_policy(brain_log_policy);
+
+                    state.handle_frame(now, env_canvas_size(&window));
+
+                    let budget = FrameBudget {
+                        tick: if state.pause {
+                            Default::default()
+                        } else {
+                            state.environment.tick_timings()
+                        },
+                        render_cpu: last_render_cpu,
+                        render_gpu_wait: render_gpu_wait.get(),
+                    };
+                    state.budget_history.push(budget);
+
+                    if let Some(selection_report) = state.outdated_selection_report() {
+                        let coefficients = &selection_report.offspring_count;
+                        window.set_selection_sample_size(selection_report.sample_size as i32);
+                        // square grid with a cell per gene
+                        window.set_selection_columns(
+                            (coefficients.len() as f64).sqrt().ceil().max(1.) as i32,
+                        );
+                        window.set_selection_coefficients(
+                            coefficients
+                                .iter()
+                                .map(|x| *x as f32)
+                                .collect::<Vec<_>>()[..]
+                                .into(),
+                        );
+                    }
+                }
                 let state = state.borrow();
 
+                let selected_bug = state.alive_selected_bug();
+                let selected_bug_id = selected_bug.map(|handle| handle.id());
+                let selected_brain_log = selected_bug
+                    .and_then(|handle| state.environment.evaluate_bug_brain(handle));
+
                 let mut environment_render_model = state.environment_render_model.borrow_mut();
 
+                let render_start = Instant::now();
                 let texture = environment_render_model.render(
                     &state.environment,
                     &state.camera,
-                    &state.selected_bug_id,
+                    &selected_bug_id,
+                    selected_brain_log.as_ref(),
+                    &state.bug_animations,
                     state.active_tool,
                     state.tool_action_point,
                     state.tool_action_active,
-                    state.chunks_display_mode.clone(),
+                    state.debug_overlay,
+                    &state.budget_history,
                     window.get_requested_env_canvas_width() as u32,
                     window.get_requested_env_canvas_height() as u32,
                 );
+                last_render_cpu = render_start.elapsed();
                 window.set_env_canvas(texture);
+
+                match state.tool_action_point {
+                    Some(tool_action_point) if state.magnifier_enabled => {
+                        let transformation = state.camera.transformation();
+                        let magnifier_size = window.get_requested_magnifier_canvas_size() as u32;
+                        let magnifier_camera = Camera::centered_on(
+                            tool_action_point,
+                            *transformation.scale_x() * MAGNIFIER_ZOOM,
+                            magnifier_size as Float,
+                            magnifier_size as Float,
+                        );
+                        let texture = state.magnifier_render_model.borrow_mut().render(
+                            &state.environment,
+                            &magnifier_camera,
+                            &selected_bug_id,
+                            selected_brain_log.as_ref(),
+                            &state.bug_animations,
+                            Tool::None,
+                            None,
+                            false,
+                            DebugOverlay::NONE,
+                            &Default::default(),
+                            magnifier_size,
+                            magnifier_size,
+                        );
+                        let screen_point = &transformation * &tool_action_point;
+                        window.set_magnifier_canvas(texture);
+                        window.set_magnifier_x(*screen_point.x() as f32);
+                        window.set_magnifier_y(*screen_point.y() as f32);
+                        window.set_magnifier_visible(true);
+                    }
+                    _ => window.set_magnifier_visible(false),
+                }
+
                 window.set_env_info(EnvInfo {
-                    now: pretty_duration(
-                        state
-                            .environment
-                            .now()
-                            .duration_since(state.environment.creation_time()),
-                    )
-                    .into(),
+                    now: state.environment.clock().to_string().into(),
                     pause: state.pause,
                     time_speed: state.time_speed as f32,
                     bugs_count: state.environment.bugs_count() as i32,
                     food_count: state.environment.food_count() as i32,
+                    plants_count: state.environment.plants_count() as i32,
+                    island: if state.environment.island_count() > 1 {
+                        let stats = state.environment.island_stats();
+                        let viewed = state.environment.viewed_island();
+                        format!(
+                            "{}/{} (+{} / -{} migrants)",
+                            viewed + 1,
+                            stats.len(),
+                            stats[viewed].immigrants,
+                            stats[viewed].emigrants,
+                        )
+                    } else {
+                        Default::default()
+                    }
+                    .into(),
+                    season: state
+                        .environment
+                        .season()
+                        .map(|season| {
+                            format!(
+                                "{} (food {:.0}%)",
+                                season,
+                                state.environment.food_abundance() * 100.
+                            )
+                        })
+                        .unwrap_or_default()
+                        .into(),
+                    scenario: state
+                        .environment
+                        .scenario()
+                        .map(|scenario| match scenario.result() {
+                            Some(result) => format!("{}: {}", scenario.name, result),
+                            None => format!("{}: in progress", scenario.name),
+                        })
+                        .unwrap_or_default()
+                        .into(),
+                    hovered_food_source: state
+                        .tool_action_point
+                        .and_then(|point| {
+                            state
+                                .environment
+                                .food_sources()
+                                .zip(state.environment.stats().food_source_report())
+                                .filter(|(food_source, _)| food_source.contains(point))
+                                .last()
+                                .map(|(_, report)| {
+                                    format!(
+                                        "spawned {} ({:.0} energy), eaten {} ({:.0} energy), recent share {:.1}%",
+                                        report.totals.food_spawned,
+                                        report.totals.energy_injected,
+                                        report.totals.food_eaten,
+                                        report.totals.energy_eaten,
+                                        report.recent_share * 100.
+                                    )
+                                })
+                        })
+                        .unwrap_or_default()
+                        .into(),
                 });
                 window.set_fps(1. / dt.as_secs_f32());
                 window.set_tps(state.tps as f32);
 
+                window.set_leaderboard(
+                    state
+                        .environment
+                        .efficiency_leaderboard(LEADERBOARD_SIZE)
+                        .into_iter()
+                        .map(|entry| LeaderboardEntry {
+                            id: entry.bug_id as i32,
+                            consumed_energy: entry.consumed_energy as f32,
+                            offspring_count: entry.offspring_count as i32,
+                            efficiency: entry.efficiency as f32,
+                        })
+                        .collect::<Vec<_>>()[..]
+                        .into(),
+                );
+
                 window.set_active_tool(state.active_tool.into());
+                window.set_debug_overlay(state.debug_overlay.into());
+                window.set_extinction(state.extinction_info());
+                window.set_error_message(state.error_message.clone().unwrap_or_default().into());
 
                 if let Some(bug) = state
-                    .selected_bug_id
-                    .and_then(|id| state.environment.find_bug_by_id(id))
+                    .selected_bug
+                    .and_then(|handle| state.environment.resolve(handle))
                 {
                     window.set_selected_bug_info(BugInfo {
                         genes: bug
@@ -464,14 +1722,23 @@ pub fn main() -> Result<(), PlatformError> {
                         y: *bug.position().y() as f32,
                         heat_capacity: bug.heat_capacity().unwrap() as f32,
                         heat_level: bug.heat_level().unwrap() as f32,
+                        stomach_capacity: bug.stomach_capacity().unwrap() as f32,
+                        stomach_level: bug.stomach_level().unwrap() as f32,
+                        basal_metabolism: bug.basal_metabolism().unwrap() as f32,
+                        armor: bug.armor().unwrap() as f32,
+                        photosynthesis: bug.photosynthesis().unwrap() as f32,
+                        mutation_scale: bug.mutation_rate().scale as f32,
+                        mutation_probability: bug.mutation_rate().probability as f32,
                         vision_range: bug.vision_range().unwrap() as f32,
                         vision_arc: (bug.vision_half_arc().unwrap().degrees() * 2.) as f32,
+                        buried: bug.buried(),
                     });
 
-                    if let Some(brain_log) = bug.last_brain_log() {
+                    if let Some(brain_log) = &selected_brain_log {
                         let mut brain_render_model = state.brain_render_model.borrow_mut();
 
                         window.set_brain_canvas(brain_render_model.render(
+                            bug.id(),
                             bug.brain(),
                             brain_log,
                             state.selected_node,
@@ -551,12 +1818,8 @@ pub fn main() -> Result<(), PlatformError> {
                 window.window().request_redraw();
 
                 if let Ok(_) = ctrl_c_rx.try_recv() {
-                    println!("\nSaving into: {:?}...", &save_path);
-                    std::fs::write(
-                        &save_path,
-                        serde_json::to_string_pretty(&state.environment).unwrap(),
-                    )
-                    .unwrap();
+                    println!();
+                    state.save();
                     window.window().hide().unwrap();
                 }
             }
@@ -569,12 +1832,7 @@ pub fn main() -> Result<(), PlatformError> {
             .window()
             .on_close_requested(move || -> CloseRequestResponse {
                 let state = weak_state.upgrade().unwrap();
-                let state = state.borrow();
-                std::fs::write(
-                    &save_path,
-                    serde_json::to_string_pretty(&state.environment).unwrap(),
-                )
-                .unwrap();
+                state.borrow().save();
                 CloseRequestResponse::HideWindow
             });
     }
@@ -590,3 +1848,157 @@ pub fn main() -> Result<(), PlatformError> {
 
     main_window.run()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bugs_lib::{
+        env_presets, math::Point, saves::SaveDir, time_point::StaticTimePoint, utils::Float,
+    };
+
+    use crate::{
+        input::{InputEvent, PointerButton, PointerEventKind, RecordedInput},
+        EnvPreset, State, Tool,
+    };
+
+    const VIEWPORT: (Float, Float) = (800., 600.);
+
+    /// State of GUI without a window. Camera is not moved, so canvas pixels equal world units
+    fn headless_state() -> State {
+        State::new(
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]),
+            SaveDir::new(std::env::temp_dir().join("bugs_headless_gui_test")),
+            "test".to_string(),
+            EnvPreset::NestedRects,
+        )
+    }
+
+    /// Inputs one frame apart
+    fn inputs(events: Vec<InputEvent>) -> Vec<RecordedInput> {
+        events
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| RecordedInput {
+                at: Duration::from_millis(1000 / 30) * i as u32,
+                viewport: VIEWPORT,
+                event,
+            })
+            .collect()
+    }
+
+    fn pointer(
+        kind: PointerEventKind,
+        button: PointerButton,
+        (x, y): (Float, Float),
+    ) -> InputEvent {
+        InputEvent::Pointer { kind, button, x, y }
+    }
+
+    fn key(text: &str) -> InputEvent {
+        InputEvent::Key {
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn clicked_bug_is_selected_and_followed() {
+        let mut state = headless_state();
+        let (id, position) = {
+            let bug = state.environment.bugs().next().unwrap();
+            (bug.id(), bug.position())
+        };
+
+        state.replay(
+            &inputs(vec![
+                pointer(
+                    PointerEventKind::Up,
+                    PointerButton::Left,
+                    (*position.x(), *position.y()),
+                ),
+                key("c"),
+            ]),
+            Instant::now(),
+        );
+        assert_eq!(state.selected_bug_id(), Some(id));
+        assert_eq!(
+            state.camera_animator.followed_bug(),
+            state.environment.bug_handle(id)
+        );
+
+        state.replay(&inputs(vec![key("c")]), Instant::now());
+        assert_eq!(state.camera_animator.followed_bug(), None);
+    }
+
+    #[test]
+    fn tools_act_where_pointer_is() {
+        let mut state = headless_state();
+        let point = (100000., 100000.);
+        let food_sources_count = state.environment.food_sources().count();
+
+        state.replay(
+            &inputs(vec![
+                InputEvent::ToolClicked { tool: Tool::Food },
+                key(" "),
+                pointer(PointerEventKind::Move, PointerButton::Left, point),
+                pointer(PointerEventKind::Down, PointerButton::Left, point),
+                InputEvent::Tick {
+                    dt: Duration::from_millis(1000 / 30),
+                },
+                pointer(PointerEventKind::Up, PointerButton::Left, point),
+                InputEvent::ToolClicked {
+                    tool: Tool::FoodSource,
+                },
+                pointer(PointerEventKind::Up, PointerButton::Left, point),
+            ]),
+            Instant::now(),
+        );
+        assert!(state
+            .environment
+            .food()
+            .any(|food| (food.position() - Point::from(point)).len() < 1.));
+        assert_eq!(
+            state.environment.food_sources().count(),
+            food_sources_count + 1
+        );
+        assert_eq!(state.active_tool, Tool::FoodSource);
+
+        // right click drops the tool
+        state.replay(
+            &inputs(vec![pointer(
+                PointerEventKind::Up,
+                PointerButton::Right,
+                point,
+            )]),
+            Instant::now(),
+        );
+        assert_eq!(state.active_tool, Tool::None);
+    }
+
+    #[test]
+    fn camera_flies_back_to_bookmark() {
+        let mut state = headless_state();
+        let start = Instant::now();
+        state.replay(
+            &inputs(vec![
+                key("b"),
+                InputEvent::Scroll {
+                    x: 400.,
+                    y: 300.,
+                    delta_y: 360.,
+                    shift: false,
+                    control: true,
+                },
+                key("g"),
+            ]),
+            start,
+        );
+        let bookmark = state.camera_bookmark.unwrap();
+        assert!(state.current_pose().unwrap().scale > bookmark.scale);
+
+        state.handle_frame(start + Duration::from_secs(2), VIEWPORT);
+        let pose = state.current_pose().unwrap();
+        assert!((pose.scale - bookmark.scale).abs() < 1e-9);
+        assert!((pose.center - bookmark.center).len() < 1e-6);
+    }
+}
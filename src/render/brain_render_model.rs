@@ -1,33 +1,56 @@
-use bugs_lib::{brain::Brain, bug::BrainLog, utils::Float};
-use font_loader::system_fonts;
+use super::frame_arena::{load_font, FrameArena};
+use bugs_lib::{
+    brain::{
+        Brain, InputSlot, INPUT_SIZE, MEMORY_INPUT_START, MEMORY_OUTPUT_START, MEMORY_SIZE,
+        OUTPUT_SIZE,
+    },
+    bug::BrainLog,
+    perceptron::PerceptronLayer,
+    utils::Float,
+};
 use sdl2::{
     gfx::primitives::DrawRenderer as _,
     pixels::Color,
     rect::{Point, Rect},
     render::{Canvas, TextureQuery},
-    rwops::RWops,
     surface::Surface,
     ttf::Font,
 };
-use simple_neural_net::PerceptronLayer;
 use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
 
+/// Everything rendered brain image depends on
+#[derive(PartialEq)]
+struct RenderKey {
+    bug_id: usize,
+    log_generation: usize,
+    selected_node: Option<(usize, usize)>,
+    width: u32,
+    height: u32,
+}
+
 pub struct BrainRenderModel {
     buffer: SharedPixelBuffer<Rgba8Pixel>,
+    font: Option<Font<'static, 'static>>,
+    arena: FrameArena,
+    last_render: Option<(RenderKey, Image)>,
 }
 
 impl Default for BrainRenderModel {
     fn default() -> Self {
         Self {
             buffer: SharedPixelBuffer::new(0, 0),
+            font: None,
+            arena: Default::default(),
+            last_render: None,
         }
     }
 }
 
-fn draw_layer_activations<const SIZE: usize>(
+fn draw_layer_activations(
     canvas: &mut Canvas<Surface>,
     font: &Font,
-    layer: [Float; SIZE],
+    arena: &mut FrameArena,
+    layer: &[Float],
     max_width: usize,
     selected_node: Option<(usize, usize)>,
     layer_index: isize,
@@ -67,7 +90,7 @@ fn draw_layer_activations<const SIZE: usize>(
 
         let texture_creator = canvas.texture_creator();
         let surface = font
-            .render(&format!("{:.2}", a))
+            .render(arena.format(format_args!("{:.2}", a)))
             .blended(text_color)
             .map_err(|e| e.to_string())
             .unwrap();
@@ -142,10 +165,11 @@ fn draw_layer_text<const SIZE: usize>(
     }
 }
 
-fn draw_connections<const INPUT_SIZE: usize, const OUTPUT_SIZE: usize>(
+fn draw_connections(
     canvas: &mut Canvas<Surface>,
     font: &Font,
-    layer: &PerceptronLayer<Float, INPUT_SIZE, OUTPUT_SIZE>,
+    arena: &mut FrameArena,
+    layer: &PerceptronLayer,
     max_width: usize,
     selected_node: Option<(usize, usize)>,
     layer_index: usize,
@@ -162,12 +186,15 @@ fn draw_connections<const INPUT_SIZE: usize, const OUTPUT_SIZE: usize>(
         .unwrap()
         .abs();
 
+    let input_size = layer.input_size();
+    let output_size = layer.output_size();
+
     let connection_color = Color::RGB(165, 136, 171);
     let negative_connection_color = Color::RGB(227, 10, 125);
     let text_color = Color::RGB(47, 72, 88);
     let bias_text_color = Color::RGB(249, 248, 113);
 
-    for j in 0..OUTPUT_SIZE {
+    for j in 0..output_size {
         let selected = selected_node
             .map(|s| s.0 == layer_index && s.1 == j)
             .unwrap_or(true);
@@ -176,11 +203,11 @@ fn draw_connections<const INPUT_SIZE: usize, const OUTPUT_SIZE: usize>(
             continue;
         }
 
-        for i in 0..INPUT_SIZE {
+        for i in 0..input_size {
             let w = layer.perceptrons()[j].weights()[i];
 
-            let off_i = (max_width - INPUT_SIZE) / 2;
-            let off_j = (max_width - OUTPUT_SIZE) / 2;
+            let off_i = (max_width - input_size) / 2;
+            let off_j = (max_width - output_size) / 2;
             let point0 = (x0, (40 + 40 * (off_i + i)) as i32);
             let point1 = (x1, (40 + 40 * (off_j + j)) as i32);
 
@@ -213,7 +240,7 @@ fn draw_connections<const INPUT_SIZE: usize, const OUTPUT_SIZE: usize>(
 
                 let texture_creator = canvas.texture_creator();
                 let surface = font
-                    .render(&format!("{:.2}", w))
+                    .render(arena.format(format_args!("{:.2}", w)))
                     .blended(text_color)
                     .map_err(|e| e.to_string())
                     .unwrap();
@@ -230,15 +257,15 @@ fn draw_connections<const INPUT_SIZE: usize, const OUTPUT_SIZE: usize>(
         }
 
         if selected_node.is_some() {
-            let off_i = (max_width - INPUT_SIZE) / 2;
-            let off_j = (max_width - OUTPUT_SIZE) / 2;
-            let point0 = (x0, (40 + 40 * (off_i + INPUT_SIZE)) as i32);
+            let off_i = (max_width - input_size) / 2;
+            let off_j = (max_width - output_size) / 2;
+            let point0 = (x0, (40 + 40 * (off_i + input_size)) as i32);
             let point1 = (x1, (40 + 40 * (off_j + j)) as i32);
             let center = (Point::from(point0) + Point::from(point1)) / 2;
 
             let texture_creator = canvas.texture_creator();
             let surface = font
-                .render(&format!("{:.2}", layer.perceptrons()[j].bias()))
+                .render(arena.format(format_args!("{:.2}", layer.perceptrons()[j].bias())))
                 .blended(bias_text_color)
                 .map_err(|e| e.to_string())
                 .unwrap();
@@ -255,34 +282,85 @@ fn draw_connections<const INPUT_SIZE: usize, const OUTPUT_SIZE: usize>(
     }
 }
 
+/// Lines from memory outputs back to memory inputs.
+/// They go around the bottom of the net, so they do not cross regular connections
+fn draw_recurrent_connections(
+    canvas: &mut Canvas<Surface>,
+    max_width: usize,
+    selected_node: Option<(usize, usize)>,
+    x0: i32,
+    x1: i32,
+) {
+    let color = Color::RGB(0, 95, 115);
+    let off_i = (max_width - INPUT_SIZE) / 2;
+    let off_j = (max_width - OUTPUT_SIZE) / 2;
+    let bottom = (40 + 40 * max_width) as i32;
+    for k in 0..MEMORY_SIZE {
+        let i = MEMORY_INPUT_START + k;
+        let j = MEMORY_OUTPUT_START + k;
+        let selected = selected_node.map(|s| s.0 == 1 && s.1 == j).unwrap_or(true);
+        if !selected {
+            continue;
+        }
+
+        let input = (x0, (40 + 40 * (off_i + i)) as i32);
+        let output = (x1, (40 + 40 * (off_j + j)) as i32);
+        let margin = 20 + 8 * k as i32;
+        let y = bottom - 20 + margin;
+        let points = [
+            output,
+            (x1 + margin, output.1),
+            (x1 + margin, y),
+            (x0 - margin, y),
+            (x0 - margin, input.1),
+            input,
+        ];
+        for pair in points.windows(2) {
+            canvas
+                .line(
+                    pair[0].0 as i16,
+                    pair[0].1 as i16,
+                    pair[1].0 as i16,
+                    pair[1].1 as i16,
+                    color,
+                )
+                .unwrap();
+        }
+    }
+}
+
 impl BrainRenderModel {
+    /// Previous image is returned if neither log nor view have changed since last call
     pub fn render(
         &mut self,
+        bug_id: usize,
         brain: &Brain,
         log: &BrainLog,
         selected_node: Option<(usize, usize)>,
         requested_canvas_width: u32,
         requested_canvas_height: u32,
     ) -> Image {
+        let key = RenderKey {
+            bug_id,
+            log_generation: log.generation,
+            selected_node,
+            width: requested_canvas_width,
+            height: requested_canvas_height,
+        };
+        if let Some((last_key, image)) = &self.last_render {
+            if *last_key == key {
+                return image.clone();
+            }
+        }
+
         if self.buffer.width() != requested_canvas_width
             || self.buffer.height() != requested_canvas_height
         {
             self.buffer = SharedPixelBuffer::new(requested_canvas_width, requested_canvas_height);
         }
 
-        let mut property = system_fonts::FontPropertyBuilder::new().monospace().build();
-        let sysfonts = system_fonts::query_specific(&mut property);
-        let font_bytes = system_fonts::get(
-            &system_fonts::FontPropertyBuilder::new()
-                .family(sysfonts.first().unwrap())
-                .build(),
-        )
-        .unwrap();
-        let rwops = RWops::from_bytes(&font_bytes.0[..]).unwrap();
-
-        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string()).unwrap();
-
-        let font = ttf_context.load_font_from_rwops(rwops, 12).unwrap();
+        let font = &*self.font.get_or_insert_with(|| load_font(12));
+        let arena = &mut self.arena;
 
         let buffer_size = (self.buffer.width(), self.buffer.height());
         assert_eq!(
@@ -305,14 +383,15 @@ impl BrainRenderModel {
             canvas.set_draw_color(Color::RGB(255, 183, 195));
             canvas.clear();
 
-            let (a0, a1, a2) = log.activations;
+            let (a0, a1, a2) = &log.activations;
 
             let max_width = a0.len().max(a1.len()).max(a2.len());
 
-            draw_connections::<16, 8>(
+            draw_connections(
                 &mut canvas,
-                &font,
-                &brain.layers().0,
+                font,
+                arena,
+                brain.layers().0,
                 max_width,
                 selected_node,
                 0,
@@ -320,10 +399,11 @@ impl BrainRenderModel {
                 20 + 40 + 100,
             );
 
-            draw_connections::<8, 8>(
+            draw_connections(
                 &mut canvas,
-                &font,
-                &brain.layers().1,
+                font,
+                arena,
+                brain.layers().1,
                 max_width,
                 selected_node,
                 1,
@@ -331,9 +411,18 @@ impl BrainRenderModel {
                 20 + 40 + 200,
             );
 
+            draw_recurrent_connections(
+                &mut canvas,
+                max_width,
+                selected_node,
+                20 + 40,
+                20 + 40 + 200,
+            );
+
             draw_layer_activations(
                 &mut canvas,
-                &font,
+                font,
+                arena,
                 a0,
                 max_width,
                 selected_node,
@@ -343,7 +432,8 @@ impl BrainRenderModel {
 
             draw_layer_activations(
                 &mut canvas,
-                &font,
+                font,
+                arena,
                 a1,
                 max_width,
                 selected_node,
@@ -353,7 +443,8 @@ impl BrainRenderModel {
 
             draw_layer_activations(
                 &mut canvas,
-                &font,
+                font,
+                arena,
                 a2,
                 max_width,
                 selected_node,
@@ -363,11 +454,8 @@ impl BrainRenderModel {
 
             draw_layer_text(
                 &mut canvas,
-                &font,
-                [
-                    "E/C", "FP/", "R-F", "FS", "A", "BP/", "R-B", "a", "r", "g", "b", "BS", "B/C",
-                    "R0", "R1", "R2",
-                ],
+                font,
+                InputSlot::ALL.map(InputSlot::label),
                 max_width,
                 selected_node,
                 -1,
@@ -376,8 +464,11 @@ impl BrainRenderModel {
 
             draw_layer_text(
                 &mut canvas,
-                &font,
-                ["V", "R", "RV", "BR", "R0", "R1", "R2", "R3"],
+                font,
+                [
+                    "V", "R", "RV", "BR", "BU", "R1", "R2", "R3", "St", "Sg", "M0", "M1", "Ea",
+                    "At", "Ph", "Fd",
+                ],
                 max_width,
                 selected_node,
                 1,
@@ -386,6 +477,8 @@ impl BrainRenderModel {
 
             canvas.present();
         }
-        slint::Image::from_rgba8(self.buffer.clone())
+        let image = slint::Image::from_rgba8(self.buffer.clone());
+        self.last_render = Some((key, image.clone()));
+        image
     }
 }
@@ -0,0 +1,142 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bugs_lib::stats::TickTimings;
+use sdl2::{gfx::primitives::DrawRenderer, pixels::Color, render::Canvas, surface::Surface};
+
+use super::frame_arena::FrameArena;
+
+/// Number of most recent frames shown by HUD (one bar per frame)
+const BUDGET_HISTORY_LEN: usize = 120;
+/// Time which fills the whole height of HUD
+const BUDGET_HUD_SCALE: Duration = Duration::from_millis(50);
+/// Frame time at 30 fps, marked by a line across HUD
+const FRAME_BUDGET: Duration = Duration::from_millis(1000 / 30);
+const BUDGET_BAR_WIDTH: i16 = 2;
+const BUDGET_HUD_HEIGHT: i16 = 100;
+const BUDGET_HUD_MARGIN: i16 = 8;
+const BUDGET_HUD_BACKGROUND_COLOR: Color = Color::RGBA(0, 18, 25, 160);
+const FRAME_BUDGET_LINE_COLOR: Color = Color::RGB(255, 255, 255);
+
+/// Time spent on one rendered frame split into phases
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FrameBudget {
+    /// Phases of the last simulation tick
+    pub(crate) tick: TickTimings,
+    /// Drawing environment into pixel buffer
+    pub(crate) render_cpu: Duration,
+    /// Time window spent rendering and presenting the previous frame
+    pub(crate) render_gpu_wait: Duration,
+}
+
+impl FrameBudget {
+    /// Phases in the order they are stacked from the bottom of a bar
+    fn phases(&self) -> [(&'static str, Duration, Color); 7] {
+        [
+            ("sense", self.tick.sense, Color::RGB(0, 95, 115)),
+            ("brain", self.tick.brain, Color::RGB(10, 147, 150)),
+            ("move", self.tick.movement, Color::RGB(148, 210, 189)),
+            ("requests", self.tick.requests, Color::RGB(233, 216, 166)),
+            ("shuffle", self.tick.shuffle, Color::RGB(238, 155, 0)),
+            ("render cpu", self.render_cpu, Color::RGB(202, 103, 2)),
+            ("render gpu", self.render_gpu_wait, Color::RGB(174, 32, 18)),
+        ]
+    }
+}
+
+/// Budgets of the most recent frames
+#[derive(Default)]
+pub(crate) struct BudgetHistory {
+    frames: VecDeque<FrameBudget>,
+}
+
+impl BudgetHistory {
+    pub(crate) fn push(&mut self, budget: FrameBudget) {
+        if self.frames.len() >= BUDGET_HISTORY_LEN {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(budget);
+    }
+
+    /// Draw stacked bar of phases per frame (the latest is on the right) in the bottom left
+    /// corner with a legend of phases averaged over the history
+    pub(crate) fn draw(
+        &self,
+        canvas: &mut Canvas<Surface>,
+        font: &sdl2::ttf::Font,
+        arena: &mut FrameArena,
+    ) {
+        let (_, canvas_height) = canvas.output_size().unwrap();
+        let left = BUDGET_HUD_MARGIN;
+        let bottom = canvas_height as i16 - BUDGET_HUD_MARGIN;
+        let top = bottom - BUDGET_HUD_HEIGHT;
+        let right = left + BUDGET_HISTORY_LEN as i16 * BUDGET_BAR_WIDTH;
+        let pixels = |duration: Duration| {
+            (duration.as_secs_f64() / BUDGET_HUD_SCALE.as_secs_f64() * BUDGET_HUD_HEIGHT as f64)
+                as i16
+        };
+
+        canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+        canvas
+            .box_(left, top, right, bottom, BUDGET_HUD_BACKGROUND_COLOR)
+            .unwrap();
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            let x = left + i as i16 * BUDGET_BAR_WIDTH;
+            let mut y = bottom;
+            for (_, duration, color) in frame.phases() {
+                let height = pixels(duration).min(y - top);
+                if height > 0 {
+                    canvas
+                        .box_(x, y - height, x + BUDGET_BAR_WIDTH - 1, y, color)
+                        .unwrap();
+                    y -= height;
+                }
+            }
+        }
+
+        let budget_y = bottom - pixels(FRAME_BUDGET);
+        canvas
+            .hline(left, right, budget_y, FRAME_BUDGET_LINE_COLOR)
+            .unwrap();
+
+        let frame_count = self.frames.len().max(1) as u32;
+        let legend = FrameBudget::default().phases();
+        let mut legend_y = bottom as i32 - legend.len() as i32 * font.height();
+        for (i, (name, _, color)) in legend.into_iter().enumerate() {
+            let average = self
+                .frames
+                .iter()
+                .map(|frame| frame.phases()[i].1)
+                .sum::<Duration>()
+                / frame_count;
+            let surface = font
+                .render(arena.format(format_args!(
+                    "{:<10} {:>6.2}ms",
+                    name,
+                    average.as_secs_f64() * 1000.
+                )))
+                .blended(color)
+                .map_err(|e| e.to_string())
+                .unwrap();
+            let texture_creator = canvas.texture_creator();
+            let texture = texture_creator
+                .create_texture_from_surface(&surface)
+                .map_err(|e| e.to_string())
+                .unwrap();
+            let height = surface.height();
+            canvas
+                .copy(
+                    &texture,
+                    None,
+                    sdl2::rect::Rect::new(
+                        (right + BUDGET_HUD_MARGIN) as i32,
+                        legend_y,
+                        surface.width(),
+                        height,
+                    ),
+                )
+                .unwrap();
+            legend_y += height as i32;
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use bugs_lib::{
+    catastrophe::Catastrophe,
+    events::EnvironmentEvent,
+    math::{Angle, NoNeg, Point},
+    utils::{Color, Float},
+};
+use std::time::{Duration, Instant};
+
+/// Time during which dead bug fades out
+pub const DEATH_FADE_DURATION: Duration = Duration::from_millis(800);
+
+/// Max number of bugs fading out at once (mass deaths would clutter the screen otherwise)
+const MAX_FADING_BUGS: usize = 1024;
+
+/// Time during which struck catastrophe is highlighted
+pub const CATASTROPHE_FLASH_DURATION: Duration = Duration::from_secs(2);
+
+/// Part of saturation which bug loses by the end of its life
+pub const AGE_DESATURATION: Float = 0.7;
+
+pub(crate) struct FadingBug {
+    pub position: Point<Float>,
+    pub rotation: Angle<Float>,
+    pub size: NoNeg<Float>,
+    pub color: Color,
+    died: Instant,
+}
+
+/// Render side animation state fed by environment events. Shared by all render models
+#[derive(Default)]
+pub(crate) struct BugAnimations {
+    fading: Vec<FadingBug>,
+    catastrophes: Vec<(Catastrophe, Instant)>,
+}
+
+impl BugAnimations {
+    pub fn handle_events(&mut self, events: &[EnvironmentEvent], now: Instant) {
+        for event in events {
+            match event {
+                EnvironmentEvent::BugDied {
+                    position,
+                    rotation,
+                    size,
+                    color,
+                    ..
+                } => {
+                    if self.fading.len() < MAX_FADING_BUGS {
+                        self.fading.push(FadingBug {
+                            position: *position,
+                            rotation: *rotation,
+                            size: *size,
+                            color: color.clone(),
+                            died: now,
+                        })
+                    }
+                }
+                EnvironmentEvent::CatastropheStruck(catastrophe) => {
+                    self.catastrophes.push((catastrophe.clone(), now))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Forget bugs which have faded out completely
+    pub fn proceed(&mut self, now: Instant) {
+        self.fading
+            .retain(|bug| now.duration_since(bug.died) < DEATH_FADE_DURATION);
+        self.catastrophes
+            .retain(|(_, struck)| now.duration_since(*struck) < CATASTROPHE_FLASH_DURATION);
+    }
+
+    /// Dead bugs together with their opacity (from 1 down to 0)
+    pub fn fading(&self, now: Instant) -> impl Iterator<Item = (&FadingBug, Float)> {
+        self.fading.iter().map(move |bug| {
+            let progress =
+                now.duration_since(bug.died).as_secs_f64() / DEATH_FADE_DURATION.as_secs_f64();
+            (bug, (1. - progress).clamp(0., 1.))
+        })
+    }
+
+    /// Recently struck catastrophes together with opacity of their highlight (from 1 down to 0)
+    pub fn catastrophes(&self, now: Instant) -> impl Iterator<Item = (&Catastrophe, Float)> {
+        self.catastrophes.iter().map(move |(catastrophe, struck)| {
+            let progress = now.duration_since(*struck).as_secs_f64()
+                / CATASTROPHE_FLASH_DURATION.as_secs_f64();
+            (catastrophe, (1. - progress).clamp(0., 1.))
+        })
+    }
+}
+
+/// Color of bug of given `age` (in range 0..=1): old bugs look washed out
+pub(crate) fn aged_color(color: &Color, age: Float) -> Color {
+    let gray = 0.299 * color.r + 0.587 * color.g + 0.114 * color.b;
+    let saturation = 1. - AGE_DESATURATION * age.clamp(0., 1.);
+    Color {
+        a: color.a,
+        r: gray + (color.r - gray) * saturation,
+        g: gray + (color.g - gray) * saturation,
+        b: gray + (color.b - gray) * saturation,
+    }
+}
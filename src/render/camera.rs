@@ -22,6 +22,25 @@ impl Default for Camera {
 }
 
 impl Camera {
+    /// Camera which projects `center` (world coordinates) into the middle of a viewport of given size
+    pub(crate) fn centered_on(
+        center: Point<Float>,
+        scale: Float,
+        viewport_width: Float,
+        viewport_height: Float,
+    ) -> Self {
+        let mut result = Self::default();
+        result.set_scale(scale);
+        result.set_translation(
+            (
+                viewport_width / 2. - *center.x() * scale,
+                viewport_height / 2. - *center.y() * scale,
+            )
+                .into(),
+        );
+        result
+    }
+
     pub(crate) fn set_translation(&mut self, translation: Point<Float>) {
         self.translation = Matrix::translate(translation - Point::origin());
     }
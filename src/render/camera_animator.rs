@@ -0,0 +1,223 @@
+use std::time::{Duration, Instant};
+
+use bugs_lib::{
+    environment::{BugHandle, Environment},
+    math::{Point, Rect},
+    utils::Float,
+};
+
+use super::Camera;
+
+/// Default duration of transitions between two poses
+const CAMERA_TWEEN_DURATION: Duration = Duration::from_millis(600);
+/// Time in which followed bug is approached by ~63% of the remaining distance
+const FOLLOW_TIME_CONSTANT: Duration = Duration::from_millis(300);
+/// Space left around the fitted rect (fraction of its size on each side)
+const FIT_MARGIN: Float = 0.1;
+/// Fitted rect is never smaller than that, so single bug is not zoomed in infinitely
+const MIN_FIT_SIZE: Float = 512.;
+
+/// What camera looks at: world point in the middle of viewport and its zoom
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CameraPose {
+    pub(crate) center: Point<Float>,
+    pub(crate) scale: Float,
+}
+
+impl CameraPose {
+    /// Pose of `camera` showing viewport of given size. None if camera is degenerate
+    pub(crate) fn of(
+        camera: &Camera,
+        viewport_width: Float,
+        viewport_height: Float,
+    ) -> Option<Self> {
+        let transformation = camera.transformation();
+        let inverted = (!&transformation)?;
+        Some(Self {
+            center: &inverted * &Point::from((viewport_width / 2., viewport_height / 2.)),
+            scale: *transformation.scale_x(),
+        })
+    }
+
+    /// Pose which shows the whole `rect` (world coordinates) with a margin around it
+    pub(crate) fn fitting(
+        rect: &Rect<Float>,
+        viewport_width: Float,
+        viewport_height: Float,
+    ) -> Self {
+        let width = rect.w().max(MIN_FIT_SIZE) * (1. + FIT_MARGIN * 2.);
+        let height = rect.h().max(MIN_FIT_SIZE) * (1. + FIT_MARGIN * 2.);
+        Self {
+            center: rect.center(),
+            scale: (viewport_width / width).min(viewport_height / height),
+        }
+    }
+
+    pub(crate) fn camera(&self, viewport_width: Float, viewport_height: Float) -> Camera {
+        Camera::centered_on(self.center, self.scale, viewport_width, viewport_height)
+    }
+
+    /// Pose between `self` (t = 0) and `other` (t = 1). Scale is interpolated geometrically,
+    /// so zooming speed looks constant
+    pub(crate) fn interpolate(&self, other: &CameraPose, t: Float) -> CameraPose {
+        let t = t.clamp(0., 1.);
+        CameraPose {
+            center: (
+                self.center.x() + (other.center.x() - self.center.x()) * t,
+                self.center.y() + (other.center.y() - self.center.y()) * t,
+            )
+                .into(),
+            scale: self.scale * (other.scale / self.scale).powf(t),
+        }
+    }
+}
+
+/// Eases transitions in and out
+pub(crate) fn smoothstep(t: Float) -> Float {
+    let t = t.clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+/// Bounding rect of all living bugs
+pub(crate) fn population_bounds<T>(environment: &Environment<T>) -> Option<Rect<Float>> {
+    Rect::aabb_from_points(environment.bugs().map(|bug| bug.position()))
+}
+
+enum Motion {
+    Tween {
+        from: CameraPose,
+        to: CameraPose,
+        start: Instant,
+        duration: Duration,
+    },
+    Follow {
+        bug: BugHandle,
+    },
+}
+
+/// Moves camera smoothly instead of jumping. Updated on each render tick
+#[derive(Default)]
+pub(crate) struct CameraAnimator {
+    motion: Option<Motion>,
+    last_instant: Option<Instant>,
+}
+
+impl CameraAnimator {
+    /// Fly from `from` to `to` during `CAMERA_TWEEN_DURATION`
+    pub(crate) fn animate(&mut self, from: CameraPose, to: CameraPose, now: Instant) {
+        self.motion = Some(Motion::Tween {
+            from,
+            to,
+            start: now,
+            duration: CAMERA_TWEEN_DURATION,
+        });
+    }
+
+    /// Keep bug in the middle of viewport. Camera lags behind it with damping
+    pub(crate) fn follow(&mut self, bug: BugHandle) {
+        self.motion = Some(Motion::Follow { bug });
+    }
+
+    pub(crate) fn followed_bug(&self) -> Option<BugHandle> {
+        match &self.motion {
+            Some(Motion::Follow { bug }) => Some(*bug),
+            _ => None,
+        }
+    }
+
+    /// Stop any motion (e.g. when user moves camera manually)
+    pub(crate) fn stop(&mut self) {
+        self.motion = None;
+    }
+
+    /// Move `camera` to where it should be at `now`. Following stops when bug is gone
+    pub(crate) fn proceed<T>(
+        &mut self,
+        camera: &mut Camera,
+        environment: &Environment<T>,
+        now: Instant,
+        viewport_width: Float,
+        viewport_height: Float,
+    ) {
+        let dt = self
+            .last_instant
+            .replace(now)
+            .map(|last_instant| now.duration_since(last_instant))
+            .unwrap_or_default();
+        let Some(motion) = &self.motion else {
+            return;
+        };
+        let Some(current) = CameraPose::of(camera, viewport_width, viewport_height) else {
+            return;
+        };
+        let (pose, finished) = match motion {
+            Motion::Tween {
+                from,
+                to,
+                start,
+                duration,
+            } => {
+                let t = now.duration_since(*start).as_secs_f64() / duration.as_secs_f64();
+                (from.interpolate(to, smoothstep(t as Float)), t >= 1.)
+            }
+            Motion::Follow { bug } => match environment.resolve(*bug) {
+                Some(bug) => {
+                    let target = CameraPose {
+                        center: bug.position(),
+                        scale: current.scale,
+                    };
+                    let t = 1. - (-dt.as_secs_f64() / FOLLOW_TIME_CONSTANT.as_secs_f64()).exp();
+                    (current.interpolate(&target, t as Float), false)
+                }
+                None => (current, true),
+            },
+        };
+        *camera = pose.camera(viewport_width, viewport_height);
+        if finished {
+            self.motion = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{smoothstep, CameraPose};
+
+    #[test]
+    fn poses_are_interpolated_smoothly() {
+        let a = CameraPose {
+            center: (0., 0.).into(),
+            scale: 1.,
+        };
+        let b = CameraPose {
+            center: (100., -50.).into(),
+            scale: 4.,
+        };
+        let start = a.interpolate(&b, smoothstep(0.));
+        assert_eq!(
+            (*start.center.x(), *start.center.y(), start.scale),
+            (0., 0., 1.)
+        );
+        let end = a.interpolate(&b, smoothstep(2.));
+        assert_eq!(
+            (*end.center.x(), *end.center.y(), end.scale),
+            (100., -50., 4.)
+        );
+        let middle = a.interpolate(&b, smoothstep(0.5));
+        assert_eq!((*middle.center.x(), *middle.center.y()), (50., -25.));
+        assert!((middle.scale - 2.).abs() < 1e-9);
+        assert!(smoothstep(0.1) < 0.1 && smoothstep(0.9) > 0.9);
+    }
+
+    #[test]
+    fn pose_survives_round_trip_through_camera() {
+        let pose = CameraPose {
+            center: (120., -30.).into(),
+            scale: 0.5,
+        };
+        let restored = CameraPose::of(&pose.camera(800., 600.), 800., 600.).unwrap();
+        assert!((restored.center.x() - 120.).abs() < 1e-9);
+        assert!((restored.center.y() + 30.).abs() < 1e-9);
+        assert!((restored.scale - 0.5).abs() < 1e-9);
+    }
+}
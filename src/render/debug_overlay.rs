@@ -0,0 +1,70 @@
+use std::ops::BitOr;
+
+/// Set of debug overlays drawn on top of environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct DebugOverlay(u32);
+
+impl DebugOverlay {
+    pub(crate) const NONE: Self = Self(0);
+    /// Borders and occupants count of food chunks
+    pub(crate) const FOOD_CHUNKS: Self = Self(1 << 0);
+    /// Borders and occupants count of bug chunks
+    pub(crate) const BUG_CHUNKS: Self = Self(1 << 1);
+    /// Vision arcs of all visible bugs (selected bug always shows its arc)
+    pub(crate) const VISION_ARCS: Self = Self(1 << 2);
+    /// Velocity and desired direction of all visible bugs
+    pub(crate) const VELOCITY_VECTORS: Self = Self(1 << 3);
+    /// Ids of all visible bugs
+    pub(crate) const IDS: Self = Self(1 << 4);
+    /// Heatmap of sensing queries and requests per chunk
+    pub(crate) const LOAD_HEATMAP: Self = Self(1 << 5);
+    /// Concentration of pheromone field
+    pub(crate) const PHEROMONES: Self = Self(1 << 6);
+    /// Sound pulses spreading from their sources
+    pub(crate) const SOUNDS: Self = Self(1 << 7);
+    /// Energy and baby charge bars above visible bugs (only when zoomed in)
+    pub(crate) const STATUS_BARS: Self = Self(1 << 8);
+    /// Terrain shaded by elevation (darker is higher)
+    pub(crate) const ELEVATION: Self = Self(1 << 9);
+    /// Time spent in phases of simulation and rendering over recent frames
+    pub(crate) const BUDGET_HUD: Self = Self(1 << 10);
+    /// Intensity of food odor field
+    pub(crate) const ODOR: Self = Self(1 << 11);
+    /// Territory markers tinted with color of their owners
+    pub(crate) const TERRITORY: Self = Self(1 << 12);
+    /// Biomes of chunks (red - hazard, green - fertility, blue - friction)
+    pub(crate) const BIOMES: Self = Self(1 << 13);
+    /// Cached light intensity of chunks
+    pub(crate) const LIGHT: Self = Self(1 << 14);
+
+    pub(crate) fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub(crate) fn toggle(&mut self, other: Self) {
+        self.0 ^= other.0;
+    }
+
+    /// Cycle chunk overlays: food -> bugs -> both -> none. Other overlays are kept
+    pub(crate) fn rotated_chunks(self) -> Self {
+        let chunks = Self::FOOD_CHUNKS | Self::BUG_CHUNKS;
+        let next = match (
+            self.contains(Self::FOOD_CHUNKS),
+            self.contains(Self::BUG_CHUNKS),
+        ) {
+            (false, false) => Self::FOOD_CHUNKS,
+            (true, false) => Self::BUG_CHUNKS,
+            (false, true) => chunks,
+            (true, true) => Self::NONE,
+        };
+        Self(self.0 & !chunks.0) | next
+    }
+}
+
+impl BitOr for DebugOverlay {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
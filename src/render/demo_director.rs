@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+use bugs_lib::{
+    environment::Environment,
+    events::EnvironmentEvent,
+    interest::{InterestKind, InterestScorer},
+    utils::Float,
+};
+
+use super::{
+    camera_animator::{population_bounds, smoothstep, CameraPose},
+    Camera,
+};
+
+/// How long camera stays at one shot (including transition to it)
+const SHOT_DURATION: Duration = Duration::from_secs(8);
+/// How long camera flies from one shot to the next one
+const TRANSITION_DURATION: Duration = Duration::from_secs(2);
+/// Every that many shots the whole population is shown
+const OVERVIEW_PERIOD: usize = 4;
+/// Size of world area (along the shorter side of viewport) visible in close-up shots
+const CLOSE_UP_SPAN: Float = 512.;
+
+/// Drives camera in demo mode: flies between interesting regions of environment
+/// (see `InterestScorer`) and periodically shows the whole population
+pub(crate) struct DemoDirector {
+    scorer: InterestScorer,
+    from: CameraPose,
+    to: CameraPose,
+    /// None until the first shot is chosen
+    shot_start: Option<Instant>,
+    shot_count: usize,
+    last_kind: Option<InterestKind>,
+}
+
+impl DemoDirector {
+    /// Director which starts flying from `pose`
+    pub(crate) fn new(shot: CameraPose) -> Self {
+        Self {
+            scorer: Default::default(),
+            from: shot,
+            to: shot,
+            shot_start: None,
+            shot_count: 0,
+            last_kind: None,
+        }
+    }
+
+    pub(crate) fn handle_events(&mut self, events: &[EnvironmentEvent], iteration: usize) {
+        self.scorer.handle_events(events, iteration);
+    }
+
+    fn overview<T>(
+        environment: &Environment<T>,
+        viewport_size: (Float, Float),
+    ) -> Option<CameraPose> {
+        population_bounds(environment)
+            .map(|bounds| CameraPose::fitting(&bounds, viewport_size.0, viewport_size.1))
+    }
+
+    fn next_shot<T>(
+        &mut self,
+        environment: &Environment<T>,
+        viewport_size: (Float, Float),
+    ) -> Option<CameraPose> {
+        let regions = self.scorer.score(environment);
+        self.shot_count += 1;
+        if self.shot_count % OVERVIEW_PERIOD == 0 {
+            self.last_kind = None;
+            return Self::overview(environment, viewport_size);
+        }
+        // prefer a different kind of event than the one shown last time
+        let region = regions
+            .iter()
+            .find(|region| Some(region.kind) != self.last_kind)
+            .or(regions.first())?;
+        self.last_kind = Some(region.kind);
+        Some(CameraPose {
+            center: region.center,
+            scale: viewport_size.0.min(viewport_size.1) / CLOSE_UP_SPAN,
+        })
+    }
+
+    /// Camera for moment `now`. Chooses the next shot when the current one is over
+    pub(crate) fn camera<T>(
+        &mut self,
+        environment: &Environment<T>,
+        now: Instant,
+        viewport_width: Float,
+        viewport_height: Float,
+    ) -> Camera {
+        let shot_start = match self.shot_start {
+            Some(shot_start) if now.duration_since(shot_start) < SHOT_DURATION => shot_start,
+            _ => {
+                self.from = self.to;
+                if let Some(shot) = self.next_shot(environment, (viewport_width, viewport_height)) {
+                    self.to = shot;
+                }
+                *self.shot_start.insert(now)
+            }
+        };
+        let t = now.duration_since(shot_start).as_secs_f64() / TRANSITION_DURATION.as_secs_f64();
+        self.from
+            .interpolate(&self.to, smoothstep(t as Float))
+            .camera(viewport_width, viewport_height)
+    }
+}
@@ -1,54 +1,84 @@
-use super::Camera;
+use super::{
+    budget_hud::BudgetHistory,
+    bug_animations::{aged_color, BugAnimations},
+    frame_arena::{load_font, FrameArena},
+    Camera, DebugOverlay,
+};
 use crate::{
     app_utils::{color_to_sdl2_rgba_color, point_to_sdl2_point, rect_to_sdl2_rect},
     Tool, NUKE_RADIUS,
 };
 use bugs_lib::{
-    environment::Environment,
+    biome::BIOME_CELL_SIZE,
+    brain::{RetinaHit, SeenObject, RETINA_SECTORS},
+    bug::{BrainLog, Bug},
+    catastrophe::Catastrophe,
+    egg::EGG_RADIUS,
+    environment::{Environment, CHUNK_SIZE},
     food_source::FoodSourceShape,
-    math::{map_into_range, noneg_float, Complex, DeltaAngle, Point, Rect, Size},
+    light::LIGHT_CELL_SIZE,
+    math::{map_into_range, noneg_float, Angle, Complex, DeltaAngle, Point, Rect, Size},
+    odor::ODOR_CELL_SIZE,
+    parasite::PARASITE_RADIUS,
+    pheromone::PHEROMONE_CELL_SIZE,
     range::Range,
+    sound::{SOUND_LIFETIME, SOUND_RANGE},
+    territory::TERRITORY_CELL_SIZE,
+    time_point::TimePoint,
     utils::Float,
+    water::WaterRegion,
 };
-use font_loader::system_fonts;
 use sdl2::{
     gfx::primitives::DrawRenderer,
     pixels::Color,
     render::{Canvas, TextureQuery},
-    rwops::RWops,
     surface::Surface,
     ttf::Font,
 };
 use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
-use std::f64::consts::PI;
-
-#[derive(Debug, Clone)]
-pub(crate) enum ChunksDisplayMode {
-    FoodChunks,
-    BugChunks,
-    Both,
-    None,
-}
+use std::{collections::HashMap, f64::consts::PI, time::Instant};
 
-impl ChunksDisplayMode {
-    pub(crate) fn rotated(self) -> Self {
-        match self {
-            ChunksDisplayMode::FoodChunks => ChunksDisplayMode::BugChunks,
-            ChunksDisplayMode::BugChunks => ChunksDisplayMode::Both,
-            ChunksDisplayMode::Both => ChunksDisplayMode::None,
-            ChunksDisplayMode::None => ChunksDisplayMode::FoodChunks,
-        }
-    }
-}
+/// Status bars are drawn only when zoomed in at least that much (otherwise they clutter the view)
+const STATUS_BARS_MIN_SCALE: Float = 1.;
+/// Elevation overlay samples terrain once per square of that many pixels
+const ELEVATION_CELL_PIXELS: u32 = 16;
+const STATUS_BAR_WIDTH: Float = 24.;
+const STATUS_BAR_HEIGHT: Float = 3.;
+const STATUS_BAR_BACKGROUND_COLOR: Color = Color::RGBA(0, 18, 25, 160);
+const ENERGY_BAR_COLOR: Color = Color::RGB(238, 155, 0);
+const BABY_CHARGE_BAR_COLOR: Color = Color::RGB(10, 147, 150);
+/// When zoomed out more than that, food is merged into density blobs (one per cell) instead of
+/// drawing every piece which would take a few pixels anyway
+const FOOD_DENSITY_MAX_SCALE: Float = 0.25;
+/// Side of cells food is merged into (each chunk is split into 4x4 cells)
+const FOOD_DENSITY_CELL_SIZE: Float = CHUNK_SIZE as Float / 4.;
+/// Total energy of food in a cell at which its blob covers the whole cell and is opaque
+const FOOD_DENSITY_FULL_ENERGY: Float = 200.;
+const FOOD_COLOR: (u8, u8, u8) = (73, 54, 87);
+const EGG_COLOR: Color = Color::RGB(243, 233, 210);
+const PARASITE_COLOR: Color = Color::RGB(155, 34, 38);
+const ATTACHED_PARASITE_COLOR: Color = Color::RGB(229, 56, 59);
+/// Glow of bugs with nonzero signal (alpha is scaled by signal)
+const SIGNAL_GLOW_COLOR: (u8, u8, u8) = (186, 255, 201);
+const RETINA_BORDER_COLOR: Color = Color::RGBA(255, 183, 3, 96);
+const RETINA_FOOD_COLOR: Color = Color::RGB(0, 255, 0);
+const RETINA_BUG_COLOR: Color = Color::RGB(255, 0, 0);
 
 pub struct EnvironmentRenderModel {
     buffer: SharedPixelBuffer<Rgba8Pixel>,
+    font: Option<Font<'static, 'static>>,
+    arena: FrameArena,
+    /// Total energy of food per density cell. Kept between frames to reuse allocation
+    food_density: HashMap<(i64, i64), Float>,
 }
 
 impl Default for EnvironmentRenderModel {
     fn default() -> Self {
         Self {
             buffer: SharedPixelBuffer::new(0, 0),
+            font: None,
+            arena: Default::default(),
+            food_density: Default::default(),
         }
     }
 }
@@ -83,9 +113,176 @@ fn draw_centered_text(
     }
 }
 
+fn draw_ray(
+    canvas: &mut Canvas<Surface>,
+    origin: Point<Float>,
+    direction: Angle<Float>,
+    length: Float,
+    color: Color,
+) {
+    let ray = Complex::from_polar(length, direction);
+    canvas
+        .line(
+            *origin.x() as i16,
+            *origin.y() as i16,
+            (*origin.x() + *ray.real()) as i16,
+            (*origin.y() + *ray.imag()) as i16,
+            color,
+        )
+        .unwrap();
+}
+
+/// Borders of retina sectors and a ray along the middle of each sector to the nearest object
+/// seen in it
+fn draw_retina<T>(
+    canvas: &mut Canvas<Surface>,
+    bug: &Bug<T>,
+    retina: &[Option<RetinaHit>; RETINA_SECTORS],
+    position: Point<Float>,
+    scale: Float,
+) {
+    let range = bug.vision_range().unwrap() * scale;
+    let start = bug.rotation() - bug.vision_half_arc().unwrap();
+    let sector_width = bug.vision_half_arc().unwrap() * (2. / RETINA_SECTORS as Float);
+    for i in 1..RETINA_SECTORS {
+        draw_ray(
+            canvas,
+            position,
+            start + sector_width * i as Float,
+            range,
+            RETINA_BORDER_COLOR,
+        );
+    }
+    for (i, hit) in retina.iter().enumerate() {
+        if let Some(hit) = hit {
+            let direction = start + sector_width * (i as Float + 0.5);
+            let length = hit.dst.unwrap().min(bug.vision_range().unwrap()) * scale;
+            let color = match hit.object {
+                SeenObject::Food => RETINA_FOOD_COLOR,
+                SeenObject::Bug => RETINA_BUG_COLOR,
+            };
+            draw_ray(canvas, position, direction, length, color);
+            let end = Complex::from_polar(length, direction);
+            canvas
+                .filled_circle(
+                    (*position.x() + *end.real()) as i16,
+                    (*position.y() + *end.imag()) as i16,
+                    3,
+                    color,
+                )
+                .unwrap();
+        }
+    }
+}
+
+/// Horizontal bars filled by `fractions` (in range 0..=1) stacked above `position`
+fn draw_status_bars(
+    canvas: &mut Canvas<Surface>,
+    position: Point<Float>,
+    offset: Float,
+    fractions: &[(Float, Color)],
+) {
+    let left = *position.x() - STATUS_BAR_WIDTH / 2.;
+    for (i, (fraction, color)) in fractions.iter().enumerate() {
+        let top = *position.y() - offset - (i + 1) as Float * (STATUS_BAR_HEIGHT + 1.);
+        let bottom = top + STATUS_BAR_HEIGHT;
+        canvas
+            .box_(
+                left as i16,
+                top as i16,
+                (left + STATUS_BAR_WIDTH) as i16,
+                bottom as i16,
+                STATUS_BAR_BACKGROUND_COLOR,
+            )
+            .unwrap();
+        if *fraction > 0. {
+            canvas
+                .box_(
+                    left as i16,
+                    top as i16,
+                    (left + STATUS_BAR_WIDTH * fraction.clamp(0., 1.)) as i16,
+                    bottom as i16,
+                    *color,
+                )
+                .unwrap();
+        }
+    }
+}
+
+/// Direction bug is going to move in according to its last brain output
+/// Screen coordinates of vertices of triangle depicting bug
+fn bug_triangle(position: Point<Float>, rotation: Angle<Float>, size: Float) -> [(i16, i16); 3] {
+    let rotation = complexible::complex_numbers::ComplexNumber::from_polar(
+        1.,
+        complexible::complex_numbers::Angle::from_radians(rotation.radians()),
+    );
+    let pos =
+        complexible::complex_numbers::ComplexNumber::from_cartesian(*position.x(), *position.y());
+    [(4., 0.), (-1., -1.), (-1., 1.)].map(|(x, y)| {
+        let p = complexible::complex_numbers::ComplexNumber::from_cartesian(x * size, y * size)
+            .mul(&rotation)
+            .add(&pos);
+        (p.real() as i16, p.imag() as i16)
+    })
+}
+
+fn desired_direction<T>(bug: &Bug<T>, log: &BrainLog) -> Angle<Float> {
+    bug.rotation()
+        + log.output.relative_desired_rotation
+        + DeltaAngle::from_radians(if log.output.velocity > 0. { 0. } else { PI })
+}
+
+fn draw_vision_arc<T>(
+    canvas: &mut Canvas<Surface>,
+    bug: &Bug<T>,
+    position: Point<Float>,
+    scale: Float,
+) {
+    let range = bug.vision_range().unwrap() * scale;
+    if bug.vision_half_arc() == DeltaAngle::from_radians(noneg_float(PI)) {
+        canvas
+            .circle(
+                *position.x() as i16,
+                *position.y() as i16,
+                range as i16,
+                Color::RGB(255, 183, 3),
+            )
+            .unwrap();
+    } else {
+        let arc = Range {
+            start: bug.rotation() - bug.vision_half_arc().unwrap(),
+            end: bug.rotation() + bug.vision_half_arc().unwrap(),
+        };
+
+        canvas
+            .arc(
+                *position.x() as i16,
+                *position.y() as i16,
+                range as i16,
+                arc.start.degrees() as i16,
+                arc.end.degrees() as i16,
+                Color::RGB(255, 183, 3),
+            )
+            .unwrap();
+
+        for edge in [arc.start, arc.end] {
+            canvas
+                .line(
+                    *position.x() as i16,
+                    *position.y() as i16,
+                    (*position.x() + edge.cos() * range) as i16,
+                    (*position.y() + edge.sin() * range) as i16,
+                    Color::RGB(255, 183, 3),
+                )
+                .unwrap();
+        }
+    }
+}
+
 fn draw_chunk(
     canvas: &mut Canvas<Surface>,
     font: &Font,
+    arena: &mut FrameArena,
     rect: &Rect<Float>,
     ocupants_count: usize,
     color: Color,
@@ -126,7 +323,7 @@ fn draw_chunk(
             draw_centered_text(
                 canvas,
                 &font,
-                &format!("{}", ocupants_count),
+                arena.format(format_args!("{}", ocupants_count)),
                 rect.center(),
                 color,
             );
@@ -140,13 +337,19 @@ impl EnvironmentRenderModel {
         environment: &Environment<T>,
         camera: &Camera,
         selected_bug_id: &Option<usize>,
+        selected_brain_log: Option<&BrainLog>,
+        animations: &BugAnimations,
         active_tool: Tool,
         tool_action_point: Option<Point<Float>>,
         tool_action_active: bool,
-        chunks_display_mode: ChunksDisplayMode,
+        debug_overlay: DebugOverlay,
+        budget_history: &BudgetHistory,
         requested_canvas_width: u32,
         requested_canvas_height: u32,
-    ) -> Image {
+    ) -> Image
+    where
+        T: TimePoint + Clone,
+    {
         if self.buffer.width() != requested_canvas_width
             || self.buffer.height() != requested_canvas_height
         {
@@ -171,19 +374,8 @@ impl EnvironmentRenderModel {
 
             let mut canvas = surface.into_canvas().unwrap();
 
-            let mut property = system_fonts::FontPropertyBuilder::new().monospace().build();
-            let sysfonts = system_fonts::query_specific(&mut property);
-            let font_bytes = system_fonts::get(
-                &system_fonts::FontPropertyBuilder::new()
-                    .family(sysfonts.first().unwrap())
-                    .build(),
-            )
-            .unwrap();
-            let rwops = RWops::from_bytes(&font_bytes.0[..]).unwrap();
-
-            let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string()).unwrap();
-
-            let font = ttf_context.load_font_from_rwops(rwops, 16).unwrap();
+            let font = &*self.font.get_or_insert_with(|| load_font(16));
+            let arena = &mut self.arena;
 
             let transformation = camera.transformation();
 
@@ -191,6 +383,89 @@ impl EnvironmentRenderModel {
             canvas.clear();
             let scale = Float::max(*transformation.scale_x(), *transformation.scale_y());
 
+            if let (true, Some(terrain), Some(inverted)) = (
+                debug_overlay.contains(DebugOverlay::ELEVATION),
+                environment.terrain(),
+                !&transformation,
+            ) {
+                // higher ground is shaded darker
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                for x in (0..requested_canvas_width).step_by(ELEVATION_CELL_PIXELS as usize) {
+                    for y in (0..requested_canvas_height).step_by(ELEVATION_CELL_PIXELS as usize) {
+                        let center = Point::from((
+                            (x + ELEVATION_CELL_PIXELS / 2) as Float,
+                            (y + ELEVATION_CELL_PIXELS / 2) as Float,
+                        ));
+                        let height =
+                            terrain.elevation(&inverted * &center) / terrain.max_elevation.unwrap();
+                        canvas.set_draw_color(Color::RGBA(94, 60, 30, (160. * height) as u8));
+                        canvas
+                            .fill_rect(sdl2::rect::Rect::new(
+                                x as i32,
+                                y as i32,
+                                ELEVATION_CELL_PIXELS,
+                                ELEVATION_CELL_PIXELS,
+                            ))
+                            .unwrap();
+                    }
+                }
+            }
+
+            if let Some(bounds) = environment.bounds() {
+                let rect = &transformation * bounds;
+                canvas.set_draw_color(Color::RGB(90, 70, 50));
+                for inset in 0..3 {
+                    canvas
+                        .draw_rect(sdl2::rect::Rect::new(
+                            *rect.x() as i32 - inset,
+                            *rect.y() as i32 - inset,
+                            (*rect.w() as i32 + inset * 2).max(0) as u32,
+                            (*rect.h() as i32 + inset * 2).max(0) as u32,
+                        ))
+                        .unwrap();
+                }
+            }
+
+            let water_color = Color::RGB(120, 180, 240);
+            for region in environment.water() {
+                match region {
+                    WaterRegion::Circle { center, radius } => {
+                        let center = &transformation * center;
+                        canvas
+                            .filled_circle(
+                                *center.x() as i16,
+                                *center.y() as i16,
+                                (radius.unwrap() * scale) as i16,
+                                water_color,
+                            )
+                            .unwrap();
+                    }
+                    WaterRegion::Polygon { vertices } => {
+                        let (vx, vy) = arena.polygon(vertices.iter().map(|vertex| {
+                            let vertex = &transformation * vertex;
+                            (*vertex.x() as i16, *vertex.y() as i16)
+                        }));
+                        canvas.filled_polygon(vx, vy, water_color).unwrap();
+                    }
+                }
+            }
+
+            let light_color = Color::RGB(255, 230, 80);
+            for source in environment.light().sources() {
+                let center = &transformation * &source.position;
+                canvas
+                    .circle(
+                        *center.x() as i16,
+                        *center.y() as i16,
+                        (source.radius.unwrap() * scale) as i16,
+                        light_color,
+                    )
+                    .unwrap();
+                canvas
+                    .filled_circle(*center.x() as i16, *center.y() as i16, 6, light_color)
+                    .unwrap();
+            }
+
             canvas.set_draw_color(Color::RGB(0, 255, 87));
             for source in environment.food_sources() {
                 let position = &transformation * &source.position();
@@ -227,120 +502,335 @@ impl EnvironmentRenderModel {
             )
                 .into();
 
-            for food in environment.food() {
-                let position = &transformation * &food.position();
-                let size = &transformation
-                    * &Size::from((food.radius().unwrap() * 2., food.radius().unwrap() * 2.));
+            for plant in environment.plants() {
+                let position = &transformation * &plant.position();
+                let half_size = (3. * scale).max(1.);
+                let aabb = Rect::from_center(position, (half_size * 2., half_size * 2.).into());
+
+                if view_port_rect.contains(&aabb) || view_port_rect.instersects(&aabb) {
+                    // the more toxic plant is, the more purple it looks
+                    let toxin = plant.toxin().unwrap();
+                    canvas
+                        .box_(
+                            (*position.x() - half_size) as i16,
+                            (*position.y() - half_size) as i16,
+                            (*position.x() + half_size) as i16,
+                            (*position.y() + half_size) as i16,
+                            Color::RGB((34. + 120. * toxin) as u8, 139, (34. + 160. * toxin) as u8),
+                        )
+                        .unwrap();
+                }
+            }
 
-                let aabb = Rect::from_center(position, size);
+            for egg in environment.eggs() {
+                let position = &transformation * &egg.position();
+                let radius = (EGG_RADIUS.unwrap() * scale).max(1.);
+                let aabb = Rect::from_center(position, (radius * 2., radius * 2.).into());
 
                 if view_port_rect.contains(&aabb) || view_port_rect.instersects(&aabb) {
                     canvas
                         .filled_circle(
                             *position.x() as i16,
                             *position.y() as i16,
-                            (size.w().max(*size.h()) / 2.) as i16,
-                            Color::RGB(73, 54, 87),
+                            radius as i16,
+                            EGG_COLOR,
                         )
                         .unwrap();
                 }
             }
 
-            match chunks_display_mode {
-                ChunksDisplayMode::FoodChunks => {
-                    for (index, ocupants_count) in environment.food_chunks() {
-                        let rect = &transformation
-                            * &Rect::from((
-                                index.x() as Float * 256.,
-                                index.y() as Float * 256.,
-                                256.,
-                                256.,
-                            ));
-                        if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
-                            draw_chunk(
-                                &mut canvas,
-                                &font,
-                                &rect,
-                                ocupants_count,
-                                Color::RGB(255, 110, 162),
-                            )
-                        }
+            if scale < FOOD_DENSITY_MAX_SCALE {
+                let food_density = &mut self.food_density;
+                food_density.clear();
+                for food in environment.food() {
+                    let position = food.position();
+                    let cell = (
+                        (*position.x() / FOOD_DENSITY_CELL_SIZE).floor() as i64,
+                        (*position.y() / FOOD_DENSITY_CELL_SIZE).floor() as i64,
+                    );
+                    *food_density.entry(cell).or_default() += food.energy().unwrap();
+                }
+
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                for (&(x, y), &energy) in food_density.iter() {
+                    let rect = &transformation
+                        * &Rect::from((
+                            x as Float * FOOD_DENSITY_CELL_SIZE,
+                            y as Float * FOOD_DENSITY_CELL_SIZE,
+                            FOOD_DENSITY_CELL_SIZE,
+                            FOOD_DENSITY_CELL_SIZE,
+                        ));
+                    if !(view_port_rect.contains(&rect) || view_port_rect.instersects(&rect)) {
+                        continue;
                     }
+                    let fill = (energy / FOOD_DENSITY_FULL_ENERGY).min(1.);
+                    let center = rect.center();
+                    canvas
+                        .filled_circle(
+                            *center.x() as i16,
+                            *center.y() as i16,
+                            ((rect.w() / 2. * fill.sqrt()) as i16).max(1),
+                            Color::RGBA(
+                                FOOD_COLOR.0,
+                                FOOD_COLOR.1,
+                                FOOD_COLOR.2,
+                                (80. + 175. * fill) as u8,
+                            ),
+                        )
+                        .unwrap();
                 }
-                ChunksDisplayMode::BugChunks => {
-                    for (index, ocupants_count) in environment.bug_chunks() {
-                        let rect = &transformation
-                            * &Rect::from((
-                                index.x() as Float * 256.,
-                                index.y() as Float * 256.,
-                                256.,
-                                256.,
-                            ));
-                        if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
-                            draw_chunk(
-                                &mut canvas,
-                                &font,
-                                &rect,
-                                ocupants_count,
-                                Color::RGB(0, 0, 255),
+            } else {
+                for food in environment.food() {
+                    let position = &transformation * &food.position();
+                    let size = &transformation
+                        * &Size::from((food.radius().unwrap() * 2., food.radius().unwrap() * 2.));
+
+                    let aabb = Rect::from_center(position, size);
+
+                    if view_port_rect.contains(&aabb) || view_port_rect.instersects(&aabb) {
+                        canvas
+                            .filled_circle(
+                                *position.x() as i16,
+                                *position.y() as i16,
+                                (size.w().max(*size.h()) / 2.) as i16,
+                                Color::RGB(FOOD_COLOR.0, FOOD_COLOR.1, FOOD_COLOR.2),
                             )
-                        }
+                            .unwrap();
                     }
                 }
-                ChunksDisplayMode::Both => {
-                    for (index, ocupants_count) in environment.food_chunks() {
-                        let rect = &transformation
-                            * &Rect::from((
-                                index.x() as Float * 256.,
-                                index.y() as Float * 256.,
-                                256.,
-                                256.,
-                            ));
-                        if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
-                            draw_chunk(
-                                &mut canvas,
-                                &font,
-                                &rect,
-                                ocupants_count,
-                                Color::RGB(255, 110, 162),
+            }
+
+            let chunk_overlays = [
+                (DebugOverlay::FOOD_CHUNKS, Color::RGB(255, 110, 162)),
+                (DebugOverlay::BUG_CHUNKS, Color::RGB(0, 0, 255)),
+            ];
+            for (overlay, color) in chunk_overlays {
+                if !debug_overlay.contains(overlay) {
+                    continue;
+                }
+                let chunks = if overlay == DebugOverlay::FOOD_CHUNKS {
+                    environment.food_chunks()
+                } else {
+                    environment.bug_chunks()
+                };
+                for (index, ocupants_count) in chunks {
+                    let rect = &transformation
+                        * &Rect::from((
+                            index.x() as Float * 256.,
+                            index.y() as Float * 256.,
+                            256.,
+                            256.,
+                        ));
+                    if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
+                        draw_chunk(&mut canvas, font, arena, &rect, ocupants_count, color)
+                    }
+                }
+            }
+
+            if debug_overlay.contains(DebugOverlay::LOAD_HEATMAP) {
+                let chunk_load = environment.chunk_load();
+                let max_load = chunk_load
+                    .iter()
+                    .map(|(_, load)| load.total())
+                    .max()
+                    .unwrap_or(0)
+                    .max(1);
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                for (index, load) in chunk_load {
+                    let rect = &transformation
+                        * &Rect::from((
+                            index.x() as Float * 256.,
+                            index.y() as Float * 256.,
+                            256.,
+                            256.,
+                        ));
+                    if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
+                        let heat = load.total() as Float / max_load as Float;
+                        canvas.set_draw_color(Color::RGBA(
+                            255,
+                            (200. * (1. - heat)) as u8,
+                            0,
+                            (32. + 128. * heat) as u8,
+                        ));
+                        canvas.fill_rect(rect_to_sdl2_rect(&rect)).unwrap();
+                        draw_centered_text(
+                            &mut canvas,
+                            font,
+                            arena.format(format_args!(
+                                "{} / {}",
+                                load.sensing_queries, load.requests
+                            )),
+                            rect.center(),
+                            Color::RGB(0, 18, 25),
+                        );
+                    }
+                }
+            }
+
+            if debug_overlay.contains(DebugOverlay::PHEROMONES) {
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                for ((x, y), concentration) in environment.pheromones().cells() {
+                    let rect = &transformation
+                        * &Rect::from((
+                            x as Float * PHEROMONE_CELL_SIZE,
+                            y as Float * PHEROMONE_CELL_SIZE,
+                            PHEROMONE_CELL_SIZE,
+                            PHEROMONE_CELL_SIZE,
+                        ));
+                    if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
+                        let intensity = concentration / (concentration + 1.);
+                        canvas.set_draw_color(Color::RGBA(128, 0, 255, (200. * intensity) as u8));
+                        canvas.fill_rect(rect_to_sdl2_rect(&rect)).unwrap();
+                    }
+                }
+            }
+
+            if debug_overlay.contains(DebugOverlay::ODOR) {
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                for ((x, y), intensity) in environment.odor().cells() {
+                    let rect = &transformation
+                        * &Rect::from((
+                            x as Float * ODOR_CELL_SIZE,
+                            y as Float * ODOR_CELL_SIZE,
+                            ODOR_CELL_SIZE,
+                            ODOR_CELL_SIZE,
+                        ));
+                    if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
+                        let intensity = intensity / (intensity + 1.);
+                        canvas.set_draw_color(Color::RGBA(255, 128, 0, (160. * intensity) as u8));
+                        canvas.fill_rect(rect_to_sdl2_rect(&rect)).unwrap();
+                    }
+                }
+            }
+
+            if debug_overlay.contains(DebugOverlay::TERRITORY) {
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                for ((x, y), marker) in environment.territory().cells() {
+                    let rect = &transformation
+                        * &Rect::from((
+                            x as Float * TERRITORY_CELL_SIZE,
+                            y as Float * TERRITORY_CELL_SIZE,
+                            TERRITORY_CELL_SIZE,
+                            TERRITORY_CELL_SIZE,
+                        ));
+                    if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
+                        let strength = marker.strength / (marker.strength + 1.);
+                        let mut color = color_to_sdl2_rgba_color(&marker.color);
+                        color.a = (96. * strength) as u8;
+                        canvas.set_draw_color(color);
+                        canvas.fill_rect(rect_to_sdl2_rect(&rect)).unwrap();
+                    }
+                }
+            }
+
+            if debug_overlay.contains(DebugOverlay::BIOMES) {
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                for ((x, y), biome) in environment.biomes().cells() {
+                    let rect = &transformation
+                        * &Rect::from((
+                            x as Float * BIOME_CELL_SIZE,
+                            y as Float * BIOME_CELL_SIZE,
+                            BIOME_CELL_SIZE,
+                            BIOME_CELL_SIZE,
+                        ));
+                    if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
+                        let channel = |value: Float| (255. * value / (value + 1.)) as u8;
+                        canvas.set_draw_color(Color::RGBA(
+                            channel(biome.hazard.unwrap() * 100.),
+                            channel(biome.fertility.unwrap()),
+                            channel(biome.friction.unwrap()),
+                            96,
+                        ));
+                        canvas.fill_rect(rect_to_sdl2_rect(&rect)).unwrap();
+                    }
+                }
+            }
+
+            if debug_overlay.contains(DebugOverlay::LIGHT) {
+                canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                for ((x, y), intensity) in environment.light().cells() {
+                    let rect = &transformation
+                        * &Rect::from((
+                            x as Float * LIGHT_CELL_SIZE,
+                            y as Float * LIGHT_CELL_SIZE,
+                            LIGHT_CELL_SIZE,
+                            LIGHT_CELL_SIZE,
+                        ));
+                    if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
+                        let intensity = intensity / (intensity + 1.);
+                        canvas.set_draw_color(Color::RGBA(255, 230, 80, (160. * intensity) as u8));
+                        canvas.fill_rect(rect_to_sdl2_rect(&rect)).unwrap();
+                    }
+                }
+            }
+
+            if debug_overlay.contains(DebugOverlay::SOUNDS) {
+                // pulse is drawn as a ring spreading from the source, pitch defines its hue
+                for sound in environment.sounds() {
+                    let progress = sound.age.as_secs_f64() / SOUND_LIFETIME.as_secs_f64();
+                    let position = &transformation * &sound.position;
+                    let radius = SOUND_RANGE.unwrap() * progress * scale;
+                    let aabb = Rect::from_center(position, (radius * 2., radius * 2.).into());
+                    if view_port_rect.contains(&aabb) || view_port_rect.instersects(&aabb) {
+                        canvas
+                            .circle(
+                                *position.x() as i16,
+                                *position.y() as i16,
+                                radius as i16,
+                                Color::RGBA(
+                                    (255. * sound.frequency) as u8,
+                                    64,
+                                    (255. * (1. - sound.frequency)) as u8,
+                                    (255. * sound.loudness.unwrap() * (1. - progress)) as u8,
+                                ),
                             )
-                        }
+                            .unwrap();
                     }
-                    for (index, ocupants_count) in environment.bug_chunks() {
-                        let rect = &transformation
-                            * &Rect::from((
-                                index.x() as Float * 256.,
-                                index.y() as Float * 256.,
-                                256.,
-                                256.,
-                            ));
-                        if view_port_rect.contains(&rect) || view_port_rect.instersects(&rect) {
-                            draw_chunk(
-                                &mut canvas,
-                                &font,
-                                &rect,
-                                ocupants_count,
-                                Color::RGB(0, 0, 255),
+                }
+            }
+
+            canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+            for (catastrophe, opacity) in animations.catastrophes(Instant::now()) {
+                let alpha = (160. * opacity) as u8;
+                match catastrophe {
+                    Catastrophe::Meteor { center, radius } => {
+                        let center = &transformation * center;
+                        canvas
+                            .filled_circle(
+                                *center.x() as i16,
+                                *center.y() as i16,
+                                (radius.unwrap() * scale) as i16,
+                                Color::RGBA(255, 96, 0, alpha),
                             )
-                        }
+                            .unwrap();
+                    }
+                    Catastrophe::Famine { .. } => {
+                        canvas.set_draw_color(Color::RGBA(150, 110, 40, alpha / 2));
+                        canvas.fill_rect(None).unwrap();
+                    }
+                    Catastrophe::Plague { .. } => {
+                        canvas.set_draw_color(Color::RGBA(120, 160, 0, alpha / 2));
+                        canvas.fill_rect(None).unwrap();
                     }
                 }
-                ChunksDisplayMode::None => {}
+            }
+
+            for (bug, opacity) in animations.fading(Instant::now()) {
+                let position = &transformation * &bug.position;
+                let [p0, p1, p2] =
+                    bug_triangle(position, bug.rotation, 5. * scale * bug.size.unwrap());
+                let mut color = color_to_sdl2_rgba_color(&bug.color);
+                color.a = (color.a as Float * opacity) as u8;
+                canvas
+                    .filled_trigon(p0.0, p0.1, p1.0, p1.1, p2.0, p2.1, color)
+                    .unwrap();
             }
 
             canvas.set_draw_color(Color::RGB(255, 183, 195));
+            let now = environment.now().clone();
             for bug in environment.bugs() {
                 let position = &transformation * &bug.position();
 
-                let rotation = complexible::complex_numbers::ComplexNumber::from_polar(
-                    1.,
-                    complexible::complex_numbers::Angle::from_radians(bug.rotation().radians()),
-                );
-                let pos = complexible::complex_numbers::ComplexNumber::from_cartesian(
-                    *position.x(),
-                    *position.y(),
-                );
-
                 let radius = bug.eat_range().unwrap() * scale;
 
                 let size = 5. * scale * bug.size().unwrap();
@@ -351,94 +841,155 @@ impl EnvironmentRenderModel {
                     || view_port_rect.instersects(&aabb)
                     || Some(bug.id()) == *selected_bug_id
                 {
-                    let p0 = complexible::complex_numbers::ComplexNumber::from_cartesian(
-                        4. * size,
-                        0. * size,
-                    );
-                    let p1 = complexible::complex_numbers::ComplexNumber::from_cartesian(
-                        -1. * size,
-                        -1. * size,
-                    );
-                    let p2 = complexible::complex_numbers::ComplexNumber::from_cartesian(
-                        -1. * size,
-                        1. * size,
-                    );
+                    let [p0, p1, p2] = bug_triangle(position, bug.rotation(), size);
+                    let age = bug.age(now.clone()).unwrap();
 
-                    let pp0 = p0.mul(&rotation).add(&pos);
-                    let pp1 = p1.mul(&rotation).add(&pos);
-                    let pp2 = p2.mul(&rotation).add(&pos);
+                    let signal = bug.signal().unwrap();
+                    if signal > 0. {
+                        let alpha = (signal * 255.) as u8;
+                        let (r, g, b) = SIGNAL_GLOW_COLOR;
+                        canvas
+                            .filled_circle(
+                                *position.x() as i16,
+                                *position.y() as i16,
+                                (2. * size) as i16,
+                                Color::RGBA(r, g, b, alpha / 3),
+                            )
+                            .unwrap();
+                        canvas
+                            .circle(
+                                *position.x() as i16,
+                                *position.y() as i16,
+                                (2. * size) as i16,
+                                Color::RGBA(r, g, b, alpha),
+                            )
+                            .unwrap();
+                    }
 
                     canvas
                         .filled_trigon(
-                            pp0.real() as i16,
-                            pp0.imag() as i16,
-                            pp1.real() as i16,
-                            pp1.imag() as i16,
-                            pp2.real() as i16,
-                            pp2.imag() as i16,
-                            color_to_sdl2_rgba_color(bug.color()),
+                            p0.0,
+                            p0.1,
+                            p1.0,
+                            p1.1,
+                            p2.0,
+                            p2.1,
+                            color_to_sdl2_rgba_color(&aged_color(
+                                bug.tag_color().unwrap_or(bug.color()),
+                                age,
+                            )),
                         )
                         .unwrap();
 
                     canvas
                         .trigon(
-                            pp0.real() as i16,
-                            pp0.imag() as i16,
-                            pp1.real() as i16,
-                            pp1.imag() as i16,
-                            pp2.real() as i16,
-                            pp2.imag() as i16,
+                            p0.0,
+                            p0.1,
+                            p1.0,
+                            p1.1,
+                            p2.0,
+                            p2.1,
                             Color::RGB(255, 183, 195),
                         )
                         .unwrap();
 
-                    if &Some(bug.id()) == selected_bug_id {
+                    if bug.buried() {
+                        // mound of soil above buried bug
+                        canvas
+                            .filled_circle(
+                                *position.x() as i16,
+                                *position.y() as i16,
+                                (4. * size) as i16,
+                                Color::RGBA(121, 85, 61, 160),
+                            )
+                            .unwrap();
+                    }
+
+                    if debug_overlay.contains(DebugOverlay::VISION_ARCS) {
+                        draw_vision_arc(&mut canvas, &bug, position, scale);
+                    }
+
+                    if debug_overlay.contains(DebugOverlay::VELOCITY_VECTORS) {
                         if let Some(log) = bug.last_brain_log() {
-                            {
-                                let rl = Complex::from_polar(radius, bug.rotation());
-                                canvas
-                                    .line(
-                                        *position.x() as i16,
-                                        *position.y() as i16,
-                                        *position.x() as i16 + *rl.real() as i16,
-                                        *position.y() as i16 + *rl.imag() as i16,
-                                        Color::RGB(255, 0, 0),
-                                    )
-                                    .unwrap();
-                            }
+                            // one second of movement
+                            draw_ray(
+                                &mut canvas,
+                                position,
+                                bug.rotation(),
+                                log.output.velocity * scale,
+                                Color::RGB(0, 95, 115),
+                            );
+                            draw_ray(
+                                &mut canvas,
+                                position,
+                                desired_direction(&bug, log),
+                                radius,
+                                Color::RGB(255, 183, 195),
+                            );
+                        }
+                    }
+
+                    if debug_overlay.contains(DebugOverlay::STATUS_BARS)
+                        && scale >= STATUS_BARS_MIN_SCALE
+                    {
+                        draw_status_bars(
+                            &mut canvas,
+                            position,
+                            radius,
+                            &[
+                                (
+                                    bug.energy_level().unwrap() / bug.energy_capacity().unwrap(),
+                                    ENERGY_BAR_COLOR,
+                                ),
+                                (
+                                    bug.baby_charge_level().unwrap()
+                                        / bug.baby_charge_capacity().unwrap(),
+                                    BABY_CHARGE_BAR_COLOR,
+                                ),
+                            ],
+                        );
+                    }
+
+                    if debug_overlay.contains(DebugOverlay::IDS) {
+                        draw_centered_text(
+                            &mut canvas,
+                            font,
+                            arena.format(format_args!("{}", bug.id())),
+                            (*position.x(), *position.y() + radius + 10.).into(),
+                            Color::RGB(0, 18, 25),
+                        );
+                    }
+
+                    if &Some(bug.id()) == selected_bug_id {
+                        if let Some(log) = selected_brain_log {
+                            draw_ray(
+                                &mut canvas,
+                                position,
+                                bug.rotation(),
+                                radius,
+                                Color::RGB(255, 0, 0),
+                            );
 
                             if let Some(nearest_food) = &log.input.nearest_food {
-                                let rl = Complex::from_polar(radius, nearest_food.direction);
-                                canvas
-                                    .line(
-                                        *position.x() as i16,
-                                        *position.y() as i16,
-                                        *position.x() as i16 + *rl.real() as i16,
-                                        *position.y() as i16 + *rl.imag() as i16,
-                                        Color::RGB(0, 255, 0),
-                                    )
-                                    .unwrap();
+                                draw_ray(
+                                    &mut canvas,
+                                    position,
+                                    nearest_food.direction,
+                                    radius,
+                                    Color::RGB(0, 255, 0),
+                                );
                             }
 
-                            {
-                                let rl =
-                                    Complex::from_polar(
-                                        radius,
-                                        bug.rotation()
-                                            + log.output.relative_desired_rotation
-                                            + DeltaAngle::from_radians(
-                                                if log.output.velocity > 0. { 0. } else { PI },
-                                            ),
-                                    );
-                                canvas
-                                    .line(
-                                        *position.x() as i16,
-                                        *position.y() as i16,
-                                        *position.x() as i16 + *rl.real() as i16,
-                                        *position.y() as i16 + *rl.imag() as i16,
-                                        Color::RGB(255, 183, 195),
-                                    )
-                                    .unwrap();
+                            draw_ray(
+                                &mut canvas,
+                                position,
+                                desired_direction(&bug, log),
+                                radius,
+                                Color::RGB(255, 183, 195),
+                            );
+
+                            if let Some(retina) = &log.input.retina {
+                                draw_retina(&mut canvas, &bug, retina, position, scale);
                             }
                         }
 
@@ -451,61 +1002,15 @@ impl EnvironmentRenderModel {
                             )
                             .unwrap();
 
+                        if !debug_overlay.contains(DebugOverlay::VISION_ARCS) {
+                            draw_vision_arc(&mut canvas, &bug, position, scale);
+                        }
+
                         let arc = Range {
                             start: bug.rotation() - bug.vision_half_arc().unwrap(),
                             end: bug.rotation() + bug.vision_half_arc().unwrap(),
                         };
 
-                        if bug.vision_half_arc() == DeltaAngle::from_radians(noneg_float(PI)) {
-                            canvas
-                                .circle(
-                                    *position.x() as i16,
-                                    *position.y() as i16,
-                                    (bug.vision_range().unwrap() * scale) as i16,
-                                    Color::RGB(255, 183, 3),
-                                )
-                                .unwrap();
-                        } else {
-                            canvas
-                                .arc(
-                                    *position.x() as i16,
-                                    *position.y() as i16,
-                                    (bug.vision_range().unwrap() * scale) as i16,
-                                    arc.start.degrees() as i16,
-                                    arc.end.degrees() as i16,
-                                    Color::RGB(255, 183, 3),
-                                )
-                                .unwrap();
-
-                            canvas
-                                .line(
-                                    *position.x() as i16,
-                                    *position.y() as i16,
-                                    (*position.x()
-                                        + arc.start.cos() * bug.vision_range().unwrap() * scale)
-                                        as i16,
-                                    (*position.y()
-                                        + arc.start.sin() * bug.vision_range().unwrap() * scale)
-                                        as i16,
-                                    Color::RGB(255, 183, 3),
-                                )
-                                .unwrap();
-
-                            canvas
-                                .line(
-                                    *position.x() as i16,
-                                    *position.y() as i16,
-                                    (*position.x()
-                                        + arc.end.cos() * bug.vision_range().unwrap() * scale)
-                                        as i16,
-                                    (*position.y()
-                                        + arc.end.sin() * bug.vision_range().unwrap() * scale)
-                                        as i16,
-                                    Color::RGB(255, 183, 3),
-                                )
-                                .unwrap();
-                        }
-
                         if let Some(tool_action_point) = tool_action_point {
                             let yes = if bug.vision_half_arc()
                                 == DeltaAngle::from_radians(noneg_float(PI))
@@ -534,23 +1039,25 @@ impl EnvironmentRenderModel {
                         }
 
                         let chunks_info: Option<(Box<dyn Iterator<Item = (isize, isize)>>, Color)> =
-                            match chunks_display_mode {
-                                ChunksDisplayMode::FoodChunks => Some((
+                            match (
+                                debug_overlay.contains(DebugOverlay::FOOD_CHUNKS),
+                                debug_overlay.contains(DebugOverlay::BUG_CHUNKS),
+                            ) {
+                                (true, false) => Some((
                                     Box::new(environment.food_chunks_circular_traverse_iter(
                                         bug.position(),
                                         bug.vision_range(),
                                     )),
                                     Color::RGB(255, 0, 0),
                                 )),
-                                ChunksDisplayMode::BugChunks => Some((
+                                (false, true) => Some((
                                     Box::new(environment.bug_chunks_circular_traverse_iter(
                                         bug.position(),
                                         bug.vision_range(),
                                     )),
                                     Color::RGB(255, 255, 0),
                                 )),
-                                ChunksDisplayMode::Both => None,
-                                ChunksDisplayMode::None => None,
+                                _ => None,
                             };
 
                         if let Some((chunks_iter, chunks_color)) = chunks_info {
@@ -567,7 +1074,7 @@ impl EnvironmentRenderModel {
 
                                 let texture_creator = canvas.texture_creator();
                                 let surface = font
-                                    .render(&format!("{}", i))
+                                    .render(arena.format(format_args!("{}", i)))
                                     .blended(chunks_color)
                                     .map_err(|e| e.to_string())
                                     .unwrap();
@@ -586,6 +1093,27 @@ impl EnvironmentRenderModel {
                 }
             }
 
+            for parasite in environment.parasites() {
+                let position = &transformation * &parasite.position();
+                let radius = (PARASITE_RADIUS.unwrap() * scale).max(1.);
+                let aabb = Rect::from_center(position, (radius * 2., radius * 2.).into());
+
+                if view_port_rect.contains(&aabb) || view_port_rect.instersects(&aabb) {
+                    canvas
+                        .filled_circle(
+                            *position.x() as i16,
+                            *position.y() as i16,
+                            radius as i16,
+                            if parasite.host_id().is_some() {
+                                ATTACHED_PARASITE_COLOR
+                            } else {
+                                PARASITE_COLOR
+                            },
+                        )
+                        .unwrap();
+                }
+            }
+
             if let Some(tool_action_point) = tool_action_point {
                 let tool_action_point = &transformation * &tool_action_point;
                 if active_tool == Tool::Nuke {
@@ -624,6 +1152,10 @@ impl EnvironmentRenderModel {
                 }
             }
 
+            if debug_overlay.contains(DebugOverlay::BUDGET_HUD) {
+                budget_history.draw(&mut canvas, font, arena);
+            }
+
             canvas.present();
         }
         slint::Image::from_rgba8(self.buffer.clone())
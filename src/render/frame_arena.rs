@@ -0,0 +1,168 @@
+use font_loader::system_fonts;
+use sdl2::{
+    rwops::RWops,
+    ttf::{Font, Sdl2TtfContext},
+};
+use std::fmt::{self, Write as _};
+
+fn monospace_font_bytes() -> Vec<u8> {
+    let mut property = system_fonts::FontPropertyBuilder::new().monospace().build();
+    let sysfonts = system_fonts::query_specific(&mut property);
+    system_fonts::get(
+        &system_fonts::FontPropertyBuilder::new()
+            .family(sysfonts.first().unwrap())
+            .build(),
+    )
+    .unwrap()
+    .0
+}
+
+thread_local! {
+    /// Ttf context and font data shared by all render models. They live until the end of the
+    /// program, so they are leaked once per thread instead of once per loaded font
+    static FONT_SOURCE: (&'static Sdl2TtfContext, &'static [u8]) = (
+        Box::leak(Box::new(
+            sdl2::ttf::init().map_err(|e| e.to_string()).unwrap(),
+        )),
+        Box::leak(monospace_font_bytes().into_boxed_slice()),
+    );
+}
+
+/// Load system monospace font
+pub(crate) fn load_font(point_size: u16) -> Font<'static, 'static> {
+    FONT_SOURCE.with(|&(ttf_context, font_bytes)| {
+        ttf_context
+            .load_font_from_rwops(RWops::from_bytes(font_bytes).unwrap(), point_size)
+            .unwrap()
+    })
+}
+
+/// Scratch memory of render model which is cleared instead of reallocated on each frame
+#[derive(Default)]
+pub(crate) struct FrameArena {
+    text: String,
+    vertices_x: Vec<i16>,
+    vertices_y: Vec<i16>,
+}
+
+impl FrameArena {
+    /// Format into reused text buffer
+    pub(crate) fn format(&mut self, args: fmt::Arguments) -> &str {
+        self.text.clear();
+        self.text.write_fmt(args).unwrap();
+        &self.text
+    }
+
+    /// Split vertices into reused coordinate buffers (as taken by `DrawRenderer::filled_polygon`)
+    pub(crate) fn polygon<I>(&mut self, vertices: I) -> (&[i16], &[i16])
+    where
+        I: IntoIterator<Item = (i16, i16)>,
+    {
+        self.vertices_x.clear();
+        self.vertices_y.clear();
+        for (x, y) in vertices {
+            self.vertices_x.push(x);
+            self.vertices_y.push(y);
+        }
+        (&self.vertices_x, &self.vertices_y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+
+    use bugs_lib::{env_presets, environment::Environment, time_point::StaticTimePoint};
+
+    use crate::{
+        render::{BugAnimations, Camera, DebugOverlay, EnvironmentRenderModel},
+        Tool,
+    };
+
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn count_allocations(f: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.with(|count| count.get());
+        f();
+        ALLOCATIONS.with(|count| count.get()) - before
+    }
+
+    /// Upper bound of allocations of a frame after buffers have grown. Only wrappers of canvas
+    /// around pixel buffer are allocated, nothing is allocated per drawn object
+    const MAX_STEADY_FRAME_ALLOCATIONS: usize = 8;
+
+    /// Allocations made by each of a few frames rendered one after another
+    fn frame_allocations(
+        environment: &Environment<StaticTimePoint>,
+        debug_overlay: DebugOverlay,
+    ) -> Vec<usize> {
+        let camera = Camera::default();
+        let mut render_model = EnvironmentRenderModel::default();
+        let mut render = || {
+            render_model.render(
+                environment,
+                &camera,
+                &None,
+                None,
+                &BugAnimations::default(),
+                Tool::None,
+                None,
+                false,
+                debug_overlay,
+                &Default::default(),
+                320,
+                240,
+            );
+        };
+        (0..4).map(|_| count_allocations(&mut render)).collect()
+    }
+
+    #[test]
+    fn environment_render_reuses_frame_resources() {
+        let environment =
+            env_presets::less_food_further_from_center(StaticTimePoint::default(), [0; 32]);
+        let frames = frame_allocations(
+            &environment,
+            DebugOverlay::FOOD_CHUNKS | DebugOverlay::BUG_CHUNKS,
+        );
+        // first frame loads font and grows buffers
+        assert!(frames[1] < frames[0]);
+        assert!(frames[2..].iter().all(|frame| *frame == frames[1]));
+    }
+
+    #[test]
+    fn steady_frames_do_not_allocate_per_drawn_object() {
+        let empty = Environment::new(StaticTimePoint::default(), vec![], vec![], vec![]);
+        // has food, bugs and water polygon
+        let lakes = env_presets::lakes(StaticTimePoint::default(), [0; 32]);
+
+        let fixed = frame_allocations(&empty, DebugOverlay::NONE)[3];
+        assert!(
+            fixed <= MAX_STEADY_FRAME_ALLOCATIONS,
+            "allocations per frame: {}",
+            fixed
+        );
+        assert_eq!(frame_allocations(&lakes, DebugOverlay::NONE)[3], fixed);
+    }
+}
@@ -1,7 +1,18 @@
 mod brain_render_model;
+mod budget_hud;
+mod bug_animations;
 mod camera;
+mod camera_animator;
+mod debug_overlay;
+mod demo_director;
 mod environment_render_model;
+mod frame_arena;
 
 pub(crate) use brain_render_model::*;
+pub(crate) use budget_hud::*;
+pub(crate) use bug_animations::*;
 pub(crate) use camera::*;
+pub(crate) use camera_animator::*;
+pub(crate) use debug_overlay::*;
+pub(crate) use demo_director::*;
 pub(crate) use environment_render_model::*;